@@ -29,6 +29,18 @@ pub trait CommandRunner: Send + Sync {
         stdin_content: &str,
         env_vars: &[(&str, &str)],
     ) -> Result<Output>;
+
+    /// Run a program with arguments and capture its output.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if spawning the process or waiting for output fails.
+    fn run_command(&self, program: &str, args: &[&str]) -> Result<Output> {
+        Command::new(program)
+            .args(args)
+            .output()
+            .with_context(|| format!("Failed to run {program}"))
+    }
 }
 
 /// Real implementation using [`std::process::Command`].
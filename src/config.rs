@@ -3,16 +3,23 @@
 //! Parses [preprocessor.validator] section including validator definitions.
 
 use std::collections::HashMap;
+use std::io::IsTerminal;
+use std::num::NonZeroUsize;
 use std::path::PathBuf;
+use std::time::Duration;
 
 use anyhow::Result;
 use tracing::debug;
 
 use crate::error::ValidatorError;
-use serde::Deserialize;
+use crate::parser::{
+    front_matter_validators, interpolate_env, parse_memory_size, split_front_matter,
+};
+use serde::{Deserialize, Serialize};
 
 /// Configuration for a single validator
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct ValidatorConfig {
     /// Docker image (e.g., "osquery/osquery:5.17.0-ubuntu22.04")
     pub container: String,
@@ -22,27 +29,442 @@ pub struct ValidatorConfig {
     /// If not set, defaults based on validator type
     #[serde(default)]
     pub exec_command: Option<String>,
+    /// Allow the container to reach the network (default: false).
+    /// When false the container is started with `--network none`, so examples that
+    /// accidentally depend on the internet fail locally instead of flaking in CI.
+    #[serde(default)]
+    pub allow_network: bool,
+    /// Override the image entrypoint (e.g. when the image's own entrypoint interferes).
+    /// The container is still kept alive by `sleep infinity`, which is passed to the
+    /// entrypoint as arguments, so the entrypoint must run its arguments.
+    #[serde(default)]
+    pub entrypoint: Option<String>,
+    /// Hard memory limit for the container, as Docker writes it (e.g. `"256m"`).
+    /// A query killed for exceeding it fails with E022; swap is disabled so the
+    /// limit can't be sidestepped.
+    #[serde(default)]
+    pub memory_limit: Option<String>,
+    /// Environment variables set in the container (e.g. `TZ`, `LANG`), visible
+    /// to SETUP, the query, hooks and CLEANUP
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// How container state carries between blocks (default: cumulative)
+    #[serde(default)]
+    pub state: StateMode,
+    /// Run each distinct SETUP script once per container, skipping it for later
+    /// blocks with an identical script (default: false). Only safe when nothing
+    /// (e.g. an `after_each` rollback) undoes the SETUP between blocks.
+    #[serde(default)]
+    pub cache_setup: bool,
+    /// Shell command run in the container before every block (e.g. snapshot state)
+    #[serde(default)]
+    pub before_each: Option<String>,
+    /// Shell command run in the container after every block, even a failing one
+    /// (e.g. roll back to the snapshot so blocks stay isolated)
+    #[serde(default)]
+    pub after_each: Option<String>,
+    /// Shell command run in the container when a block fails, before `after_each`;
+    /// its output is appended to the error (e.g. dump a table for debugging)
+    #[serde(default)]
+    pub on_failure: Option<String>,
+    /// Clean-up applied to block content before it runs (default: off)
+    #[serde(default)]
+    pub normalize: NormalizeMode,
+    /// Transforms applied in order to block content after `normalize`, before it
+    /// runs (e.g. `["strip_prompt", "interpolate_env"]`)
+    #[serde(default)]
+    pub pipeline: Vec<Transform>,
+    /// Seconds a block's query, and separately its SETUP, may run before it fails
+    /// (default: [`DEFAULT_TIMEOUT_SECS`]).
+    /// A block's `timeout=` attribute overrides this. A timed-out exec fails with
+    /// E023 and its container is discarded.
+    #[serde(default, alias = "timeout_secs")]
+    pub timeout: Option<u64>,
+    /// Times a failing or timed-out query is re-run before the block fails
+    /// (default: [`DEFAULT_RETRIES`]). A block's `retry=` attribute overrides this.
+    #[serde(default)]
+    pub retries: Option<u32>,
+    /// Version probe run once after the container starts; startup fails (E016)
+    /// if the command's output doesn't contain the expected string
+    #[serde(default)]
+    pub expect_version: Option<ExpectVersion>,
+    /// Message for failed validations, replacing the default layout. Placeholders:
+    /// `{chapter}`, `{validator}`, `{section}`, `{code}` (the block's code),
+    /// `{exit_code}` and `{stderr}` (the validator's and host checks' output)
+    #[serde(default)]
+    pub failure_message_template: Option<String>,
+    /// Container warnings that don't fail validation, matched by substring against
+    /// each stderr line (e.g. `"Cannot set unknown setting foo"`). Blocks can add
+    /// more with `allow_warning="..."`.
+    #[serde(default)]
+    pub allow_warnings: Vec<String>,
+    /// Evaluate `rows`, `contains`, `stderr_empty` and `json_length` assertions in
+    /// Rust instead of the validator script (default: false). Blocks using any other
+    /// assertion, or a plain `<!--EXPECT-->`, still run the script. Only for
+    /// validators whose output is JSON rows, like sqlite and osquery.
+    #[serde(default)]
+    pub native_assertions: bool,
+    /// Assertions applied to blocks that declare none of their own (no ASSERT,
+    /// ASSERT_FILE, ASSERT_TOML, `ASSERT:name` or `use=`), e.g.
+    /// `["rows >= 0", "valid_json"]`
+    #[serde(default)]
+    pub implicit_assertions: Vec<String>,
+}
+
+/// Expected tool version for a validator's container, e.g.
+/// `expect_version = { command = "sqlite3 --version", contains = "3.47" }`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExpectVersion {
+    /// Shell command run in the container that prints the tool version
+    pub command: String,
+    /// Substring the command's output (stdout and stderr) must contain
+    pub contains: String,
+}
+
+// Default exec commands for validators when not configured. Paths here (and
+// `/validate.sh`, `{db}`) are inside the container's own filesystem, so two
+// containers for the same validator never share them; only the fixtures mount
+// is shared with the host.
+pub(crate) const DEFAULT_EXEC_SQLITE: &str = "sqlite3 -json /tmp/test.db";
+pub(crate) const DEFAULT_EXEC_OSQUERY: &str = "osqueryi --json";
+pub(crate) const DEFAULT_EXEC_FALLBACK: &str = "cat";
+
+/// Query timeout used when neither the block nor its validator sets one
+pub const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// Query retries used when neither the block nor its validator sets them
+pub const DEFAULT_RETRIES: u32 = 0;
+
+/// How state in a validator's container carries from one block to the next.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StateMode {
+    /// One container per validator for the whole build. Blocks run in document
+    /// order, so each block sees everything earlier blocks (and their SETUP) did.
+    #[default]
+    Cumulative,
+    /// Every block gets a new container, so no state carries over between blocks.
+    Fresh,
+}
+
+/// How long validator containers live, for the whole book (`isolation`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Isolation {
+    /// Containers are shared by every chapter (each validator's `state` still applies)
+    #[default]
+    Shared,
+    /// Every chapter starts with new containers, discarded when it's done
+    PerChapter,
+    /// Every block gets a new container, like `state = "fresh"` on every validator
+    PerBlock,
+}
+
+/// Clean-up applied to block content before it is executed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NormalizeMode {
+    /// Run the content as written (apart from trimming the block's ends)
+    #[default]
+    Off,
+    /// Strip trailing whitespace from every line
+    Whitespace,
+    /// Strip trailing whitespace, then a single trailing `;`, for tools that
+    /// reject (or require the absence of) a statement terminator
+    Semicolon,
+}
+
+impl NormalizeMode {
+    /// Apply this normalization to block content
+    #[must_use]
+    pub fn apply(self, content: &str) -> String {
+        if self == Self::Off {
+            return content.to_owned();
+        }
+
+        let mut out = content
+            .lines()
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\n");
+        let trimmed_len = out.trim_end().len();
+        out.truncate(trimmed_len);
+
+        if self == Self::Semicolon && out.ends_with(';') {
+            out.pop();
+            let trimmed_len = out.trim_end().len();
+            out.truncate(trimmed_len);
+        }
+        out
+    }
+}
+
+/// A step in a validator's `pipeline`, rewriting block content before it runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transform {
+    /// Remove a leading prompt (`$ `, `>>> `, `... `, `> ` or `name> `, e.g.
+    /// `sqlite> `) from every line, so transcripts run as plain input
+    StripPrompt,
+    /// Substitute `${VAR}` from the environment; an undefined variable fails (E019)
+    InterpolateEnv,
+    /// Strip trailing whitespace and a single trailing `;`, like
+    /// `normalize = "semicolon"`
+    TrimTrailingSemicolon,
+    /// Remove the leading whitespace every non-blank line shares (like Python's
+    /// `textwrap.dedent`), for blocks indented inside list items
+    TrimCommonIndent,
+}
+
+impl Transform {
+    /// Apply this transform to block content
+    ///
+    /// # Errors
+    ///
+    /// Returns the name of the first undefined variable for `interpolate_env`.
+    pub fn apply(self, content: &str) -> Result<String, String> {
+        match self {
+            Self::StripPrompt => Ok(content
+                .lines()
+                .map(strip_prompt)
+                .collect::<Vec<_>>()
+                .join("\n")),
+            Self::InterpolateEnv => interpolate_env(content, |name| std::env::var(name).ok()),
+            Self::TrimTrailingSemicolon => Ok(NormalizeMode::Semicolon.apply(content)),
+            Self::TrimCommonIndent => Ok(trim_common_indent(content)),
+        }
+    }
+
+    /// Apply `pipeline` to `content`, in order
+    ///
+    /// # Errors
+    ///
+    /// Returns the name of the first undefined variable for `interpolate_env`.
+    pub fn apply_all(pipeline: &[Self], content: &str) -> Result<String, String> {
+        pipeline
+            .iter()
+            .try_fold(content.to_owned(), |content, transform| {
+                transform.apply(&content)
+            })
+    }
+}
+
+/// Remove the longest leading whitespace shared by every non-blank line.
+///
+/// Blank lines don't count towards the common indent and come out empty.
+fn trim_common_indent(content: &str) -> String {
+    let indent = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| &line[..line.len() - line.trim_start().len()])
+        .reduce(|common, indent| {
+            let shared: usize = common
+                .chars()
+                .zip(indent.chars())
+                .take_while(|(a, b)| a == b)
+                .map(|(c, _)| c.len_utf8())
+                .sum();
+            &common[..shared]
+        })
+        .unwrap_or_default();
+
+    content
+        .lines()
+        .map(|line| {
+            line.strip_prefix(indent)
+                .filter(|rest| !rest.trim().is_empty())
+        })
+        .map(Option::unwrap_or_default)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Strip a `$ `, `>>> `, `... `, `> ` or `name> ` prompt from the start of `line`
+fn strip_prompt(line: &str) -> &str {
+    if let Some(rest) = ["$ ", ">>> ", "... "]
+        .iter()
+        .find_map(|prompt| line.strip_prefix(prompt))
+    {
+        return rest;
+    }
+    match line.split_once("> ") {
+        Some((name, rest))
+            if name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') =>
+        {
+            rest
+        }
+        _ => line,
+    }
 }
 
 /// Main preprocessor configuration from book.toml
-#[derive(Debug, Clone, Deserialize, Default)]
+// Each bool is an independent top-level book.toml switch; grouping them would
+// change the config format (and `flatten` can't be combined with
+// `deny_unknown_fields`)
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
 pub struct Config {
     /// Map of validator name to config
     #[serde(default)]
     pub validators: HashMap<String, ValidatorConfig>,
-    /// Stop on first validation failure (default: true)
-    #[serde(default = "default_fail_fast")]
+    /// Stop on first validation failure (default: true). With `false` every
+    /// block is validated and all failures are returned together as
+    /// [`AggregatedErrors`](crate::error::AggregatedErrors)
+    #[serde(default = "default_fail_fast", alias = "fail-fast")]
     pub fail_fast: bool,
     /// Optional path to fixtures directory - mounted to /fixtures in containers.
     /// Path must be absolute. Relative paths are resolved from book root.
     #[serde(default)]
     pub fixtures_dir: Option<PathBuf>,
+    /// Value for the `mdbook-validator` Docker label on every started container
+    /// (e.g. the book title), so CI can filter and clean them up
+    #[serde(default)]
+    pub container_label: Option<String>,
+    /// Named assertion sets, referenced from blocks with `use=name`.
+    /// Each value is newline-separated assertions, as in an `<!--ASSERT-->` block.
+    #[serde(default)]
+    pub assertions: HashMap<String, String>,
+    /// Print `validated N/M blocks` to stderr as blocks finish
+    /// (default: on when stderr is a terminal)
+    #[serde(default)]
+    pub progress: Option<bool>,
+    /// Log nothing at info level for passing chapters, so green runs are silent
+    /// and only failures produce output
+    #[serde(default)]
+    pub quiet_success: bool,
+    /// Strip validation markers from the output (default: true). With `false`
+    /// chapters are validated but left exactly as written, for pipelines that
+    /// strip markers themselves
+    #[serde(default)]
+    pub strip: Option<bool>,
+    /// Before validating, remove containers left by earlier runs that carry this
+    /// book's `container_label` (e.g. after a crash). Needs `container_label`.
+    #[serde(default)]
+    pub clean_on_start: bool,
+    /// Replace a validator's container with a fresh one (re-running its setup)
+    /// after it has served this many blocks. Unset: containers are reused for
+    /// the whole run
+    #[serde(default)]
+    pub max_blocks_per_container: Option<NonZeroUsize>,
+    /// After a successful run, warn about validators that no block references
+    #[serde(default)]
+    pub warn_unused_validators: bool,
+    /// Pull each image with the `docker` CLI before starting it, so CLI-configured
+    /// credential helpers and proxies are used where the API pull fails
+    #[serde(default)]
+    pub pull_via_cli: bool,
+    /// When set, every validator's exec command (its `exec_command`, or the
+    /// default for its name) must be one of these, exactly as written, or the
+    /// config is rejected. Guards CI against commands slipped in through a PR.
+    #[serde(default)]
+    pub allowed_commands: Option<Vec<String>>,
+    /// Container lifecycle for the whole book: `shared`, `per-chapter` or `per-block`
+    #[serde(default)]
+    pub isolation: Isolation,
+    /// Check once that Docker is running before validating any block, failing
+    /// the build if it isn't. Unset: on whenever validators are configured
+    #[serde(default)]
+    pub require_docker: Option<bool>,
+    /// Write every block's result and a summary to this file as JSON after
+    /// each run, failing or not. Relative paths are resolved from book root.
+    #[serde(default)]
+    pub report_path: Option<PathBuf>,
+    /// Validator for each fence language (`{ sql = "sqlite" }`), used for
+    /// fences without `validator=`. A chapter's `<!--VALIDATOR_DEFAULT-->` takes
+    /// precedence
+    #[serde(default)]
+    pub default_validators: HashMap<String, String>,
+}
+
+// Not derived: `fail_fast` defaults to true, as when it's missing from book.toml
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            validators: HashMap::new(),
+            fail_fast: default_fail_fast(),
+            fixtures_dir: None,
+            container_label: None,
+            assertions: HashMap::new(),
+            progress: None,
+            quiet_success: false,
+            strip: None,
+            clean_on_start: false,
+            max_blocks_per_container: None,
+            warn_unused_validators: false,
+            pull_via_cli: false,
+            allowed_commands: None,
+            isolation: Isolation::default(),
+            require_docker: None,
+            report_path: None,
+            default_validators: HashMap::new(),
+        }
+    }
 }
 
+/// Key path of this preprocessor's table in book.toml
+const CONFIG_ROOT: &str = "preprocessor.validator";
+
+/// Keys mdBook itself reads from every `[preprocessor.*]` table
+const MDBOOK_KEYS: &[&str] = &["command", "before", "after", "renderers", "optional"];
+
 const fn default_fail_fast() -> bool {
     true
 }
 
+/// A [`ValidatorError::ConfigParse`] for a deserialization error under `root`,
+/// naming the failing key path and suggesting a fix for a mistyped key.
+fn config_parse_error(
+    root: &str,
+    error: &serde_path_to_error::Error<toml::de::Error>,
+) -> ValidatorError {
+    let path = match error.path().to_string().as_str() {
+        "." => root.to_owned(),
+        key_path => format!("{root}.{key_path}"),
+    };
+    let mut message = error.inner().to_string();
+    if let Some(field) = suggest_field(&message) {
+        message = format!("{message} (did you mean `{field}`?)");
+    }
+    ValidatorError::ConfigParse { path, message }
+}
+
+/// For an ``unknown field `x`, expected one of `a`, `b` `` error, the known field
+/// closest to `x`, if it's close enough to be a typo.
+fn suggest_field(message: &str) -> Option<&str> {
+    let rest = message.split_once("unknown field `")?.1;
+    // Backticks alternate: the unknown field, then each expected field
+    let mut quoted = rest.split('`');
+    let unknown = quoted.next()?;
+    let max_distance = (unknown.len() / 3).max(1);
+    quoted
+        .skip(1)
+        .step_by(2)
+        .map(|field| (edit_distance(unknown, field), field))
+        .filter(|(distance, _)| *distance <= max_distance)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, field)| field)
+}
+
+/// Levenshtein distance between `a` and `b`, in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut curr = Vec::with_capacity(prev.len());
+        curr.push(i + 1);
+        for ((cb, diag), up) in b.iter().zip(&prev).zip(prev.iter().skip(1)) {
+            let left = curr.last().copied().unwrap_or_default();
+            curr.push((diag + usize::from(ca != *cb)).min(up + 1).min(left + 1));
+        }
+        prev = curr;
+    }
+    prev.last().copied().unwrap_or_default()
+}
+
 impl Config {
     /// Parse config from mdBook preprocessor context.
     ///
@@ -50,11 +472,13 @@ impl Config {
     ///
     /// Returns error if the config section is missing or malformed.
     pub fn from_context(ctx: &mdbook_preprocessor::PreprocessorContext) -> Result<Self> {
-        // Use the new mdbook 0.5 config API to get preprocessor config
-        let config: Option<Config> = ctx.config.get("preprocessor.validator")?;
-        let config = config.ok_or_else(|| ValidatorError::Config {
+        // Use the new mdbook 0.5 config API to get the raw preprocessor table,
+        // then deserialize it ourselves so errors can name the offending key
+        let raw: Option<toml::Value> = ctx.config.get("preprocessor.validator")?;
+        let raw = raw.ok_or_else(|| ValidatorError::Config {
             message: "No [preprocessor.validator] section in book.toml".into(),
         })?;
+        let config = Self::from_value(raw)?;
 
         debug!(
             validators = config.validators.len(),
@@ -70,6 +494,77 @@ impl Config {
         Ok(config)
     }
 
+    /// Deserialize config from the raw `[preprocessor.validator]` table.
+    ///
+    /// mdBook's own keys (`command`, `before`, ...) are ignored; any other
+    /// unknown key is an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ValidatorError::ConfigParse`] naming the key path that failed,
+    /// e.g. `preprocessor.validator.validators.sqlite`. For an unknown key the
+    /// message suggests the closest known one.
+    pub fn from_value(mut value: toml::Value) -> Result<Self> {
+        if let Some(table) = value.as_table_mut() {
+            for key in MDBOOK_KEYS {
+                table.remove(*key);
+            }
+        }
+        let config: Self = serde_path_to_error::deserialize(value)
+            .map_err(|e| config_parse_error(CONFIG_ROOT, &e))?;
+        config.check_allowed_commands()?;
+        Ok(config)
+    }
+
+    /// With `allowed_commands` set, check that every validator's exec command is listed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ValidatorError::InvalidConfig`] for the first validator (by name)
+    /// whose command isn't allowed.
+    pub fn check_allowed_commands(&self) -> Result<()> {
+        let Some(allowed) = &self.allowed_commands else {
+            return Ok(());
+        };
+        let mut validators: Vec<_> = self.validators.iter().collect();
+        validators.sort_by_key(|(name, _)| *name);
+        for (name, validator) in validators {
+            let command = validator.resolved_exec_command(name);
+            if !allowed
+                .iter()
+                .any(|allowed| allowed.trim() == command.trim())
+            {
+                return Err(ValidatorError::InvalidConfig {
+                    name: name.clone(),
+                    reason: format!("exec command `{command}` is not in allowed_commands"),
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether to print block progress: the `progress` flag if set, otherwise
+    /// whether stderr is a terminal (and `quiet_success` is off).
+    #[must_use]
+    pub fn progress_enabled(&self) -> bool {
+        self.progress
+            .unwrap_or_else(|| !self.quiet_success && std::io::stderr().is_terminal())
+    }
+
+    /// Whether validated chapters have their markers stripped (`strip`, default on)
+    #[must_use]
+    pub fn strip_enabled(&self) -> bool {
+        self.strip.unwrap_or(true)
+    }
+
+    /// Whether to check Docker up front (`require_docker`, default on when any
+    /// validator is configured, since every validator runs in a container)
+    #[must_use]
+    pub fn require_docker_enabled(&self) -> bool {
+        self.require_docker.unwrap_or(!self.validators.is_empty())
+    }
+
     /// Get validator config by name.
     ///
     /// # Errors
@@ -83,6 +578,124 @@ impl Config {
             .into()
         })
     }
+
+    /// This config with the validators overridden by a chapter's front matter.
+    ///
+    /// `front_matter` is the YAML between the chapter's `---` lines. Each entry of
+    /// its `validator:` map replaces the given keys of the book's validator of that
+    /// name; a validator the book doesn't define must be given in full. Returns
+    /// the overridden validators' names with the config, or `None` if the front
+    /// matter has no top-level `validator` key. See
+    /// [`front_matter_validators`] for the YAML subset `validator:` may use.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ValidatorError::ConfigParse`] if `validator:` or a merged
+    /// validator is malformed.
+    pub fn with_front_matter(
+        &self,
+        front_matter: &str,
+        chapter: &str,
+    ) -> Result<Option<(Self, Vec<String>)>> {
+        let root = format!("front matter of '{chapter}'.validator");
+        let Some(overrides) = front_matter_validators(front_matter).map_err(|message| {
+            ValidatorError::ConfigParse {
+                path: root.clone(),
+                message,
+            }
+        })?
+        else {
+            return Ok(None);
+        };
+
+        let mut config = self.clone();
+        let mut names = Vec::new();
+        for (name, value) in overrides {
+            let merged = match (self.validators.get(&name), value) {
+                (Some(base), toml::Value::Table(keys)) => match toml::Value::try_from(base)? {
+                    toml::Value::Table(mut table) => {
+                        table.extend(keys);
+                        toml::Value::Table(table)
+                    }
+                    other => other,
+                },
+                (_, value) => value,
+            };
+            let validator = serde_path_to_error::deserialize(merged)
+                .map_err(|e| config_parse_error(&format!("{root}.{name}"), &e))?;
+            config.validators.insert(name.clone(), validator);
+            names.push(name);
+        }
+        names.sort();
+        config.check_allowed_commands()?;
+        Ok(Some((config, names)))
+    }
+
+    /// The config a chapter is validated with: this one, overridden by the
+    /// chapter's front matter if it has a `validator:` map.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ValidatorError::ConfigParse`] if the front matter's overrides
+    /// are malformed.
+    pub fn for_chapter(&self, content: &str, chapter: &str) -> Result<Self> {
+        let overridden = match split_front_matter(content) {
+            Some((front_matter, _)) => self.with_front_matter(front_matter, chapter)?,
+            None => None,
+        };
+        Ok(overridden.map_or_else(|| self.clone(), |(config, _)| config))
+    }
+
+    /// This config with its defaults filled in: each validator's `timeout` and
+    /// `retries`, and `strip`, as they take effect during a run.
+    #[must_use]
+    pub fn resolved(&self) -> Self {
+        let mut config = self.clone();
+        config.strip = Some(self.strip_enabled());
+        for validator in config.validators.values_mut() {
+            validator.timeout = Some(validator.effective_timeout(None).as_secs());
+            validator.retries = Some(validator.effective_retries(None));
+        }
+        config
+    }
+
+    /// Render as the body of a `[preprocessor.validator]` table, keys sorted.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the config can't be represented as TOML.
+    pub fn to_toml_string(&self) -> Result<String> {
+        // Through `toml::Value`, whose tables are sorted, so the output is stable
+        let value = toml::Value::try_from(self)?;
+        Ok(toml::to_string_pretty(&value)?)
+    }
+
+    /// Expand `use=` references into a single assertion list.
+    ///
+    /// Named sets come first, in the order referenced, followed by the block's
+    /// own `<!--ASSERT-->` content.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a referenced assertion set is not defined.
+    pub fn resolve_assertions(&self, uses: &[String], own: Option<&str>) -> Result<Option<String>> {
+        let mut parts = Vec::with_capacity(uses.len() + 1);
+        for name in uses {
+            let set = self
+                .assertions
+                .get(name)
+                .ok_or_else(|| ValidatorError::UnknownAssertionSet { name: name.clone() })?;
+            parts.push(set.trim());
+        }
+        parts.extend(own.map(str::trim));
+        parts.retain(|part| !part.is_empty());
+
+        if parts.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(parts.join("\n")))
+        }
+    }
 }
 
 impl ValidatorConfig {
@@ -90,7 +703,7 @@ impl ValidatorConfig {
     ///
     /// # Errors
     ///
-    /// Returns error if container or script are empty.
+    /// Returns error if container or script are empty, or timeout is zero.
     pub fn validate(&self, name: &str) -> Result<()> {
         if self.container.is_empty() {
             return Err(ValidatorError::InvalidConfig {
@@ -106,8 +719,64 @@ impl ValidatorConfig {
             }
             .into());
         }
+        self.memory_limit_bytes(name)?;
+        if self.timeout == Some(0) {
+            return Err(ValidatorError::InvalidConfig {
+                name: name.to_owned(),
+                reason: "timeout must be at least 1 second".into(),
+            }
+            .into());
+        }
         Ok(())
     }
+
+    /// This validator's `memory_limit` in bytes, if one is set.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ValidatorError::InvalidConfig`] if the limit is malformed.
+    pub fn memory_limit_bytes(&self, name: &str) -> Result<Option<u64>> {
+        self.memory_limit
+            .as_deref()
+            .map(parse_memory_size)
+            .transpose()
+            .map_err(|reason| {
+                ValidatorError::InvalidConfig {
+                    name: name.to_owned(),
+                    reason: format!("memory_limit: {reason}"),
+                }
+                .into()
+            })
+    }
+
+    /// The command blocks run through: `exec_command`, else the default for the
+    /// validator's name (`sqlite`, `osquery`, or `cat` for any other).
+    #[must_use]
+    pub fn resolved_exec_command(&self, name: &str) -> &str {
+        self.exec_command.as_deref().unwrap_or(match name {
+            "sqlite" => DEFAULT_EXEC_SQLITE,
+            "osquery" => DEFAULT_EXEC_OSQUERY,
+            _ => DEFAULT_EXEC_FALLBACK,
+        })
+    }
+
+    /// Query timeout for a block: the block's `timeout=`, else this validator's
+    /// `timeout`, else [`DEFAULT_TIMEOUT_SECS`].
+    #[must_use]
+    pub fn effective_timeout(&self, block_timeout: Option<u64>) -> Duration {
+        Duration::from_secs(
+            block_timeout
+                .or(self.timeout)
+                .unwrap_or(DEFAULT_TIMEOUT_SECS),
+        )
+    }
+
+    /// Query retries for a block: the block's `retry=`, else this validator's
+    /// `retries`, else [`DEFAULT_RETRIES`].
+    #[must_use]
+    pub fn effective_retries(&self, block_retry: Option<u32>) -> u32 {
+        block_retry.or(self.retries).unwrap_or(DEFAULT_RETRIES)
+    }
 }
 
 #[cfg(test)]
@@ -123,6 +792,7 @@ mod tests {
             container: "ubuntu:22.04".to_owned(),
             script: PathBuf::from("validators/validate.sh"),
             exec_command: None,
+            ..Default::default()
         };
         assert!(config.validate("test").is_ok());
     }
@@ -133,6 +803,7 @@ mod tests {
             container: String::new(),
             script: PathBuf::from("validators/validate.sh"),
             exec_command: None,
+            ..Default::default()
         };
         let err = config
             .validate("test")
@@ -151,6 +822,7 @@ mod tests {
             container: "ubuntu:22.04".to_owned(),
             script: PathBuf::new(),
             exec_command: None,
+            ..Default::default()
         };
         let err = config
             .validate("test")
@@ -169,6 +841,7 @@ mod tests {
             container: "ubuntu:22.04".to_owned(),
             script: PathBuf::from("validators/validate.sh"),
             exec_command: Some("sqlite3 -json /tmp/test.db".to_owned()),
+            ..Default::default()
         };
         assert!(config.validate("test").is_ok());
         assert_eq!(
@@ -177,6 +850,92 @@ mod tests {
         );
     }
 
+    #[test]
+    fn validator_config_zero_timeout_is_invalid() {
+        let config = ValidatorConfig {
+            container: "ubuntu:22.04".to_owned(),
+            script: PathBuf::from("validators/validate.sh"),
+            timeout: Some(0),
+            ..Default::default()
+        };
+        let err = config
+            .validate("test")
+            .unwrap_err()
+            .downcast::<ValidatorError>()
+            .expect("should be ValidatorError");
+        assert!(matches!(
+            err,
+            ValidatorError::InvalidConfig { reason, .. } if reason.contains("timeout")
+        ));
+    }
+
+    #[test]
+    fn validator_config_memory_limit_is_parsed() {
+        let config = ValidatorConfig {
+            container: "ubuntu:22.04".to_owned(),
+            script: PathBuf::from("validators/validate.sh"),
+            memory_limit: Some("64m".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.memory_limit_bytes("test").expect("valid limit"),
+            Some(64 * 1024 * 1024)
+        );
+
+        let invalid = ValidatorConfig {
+            memory_limit: Some("lots".to_owned()),
+            ..config
+        };
+        let err = invalid
+            .validate("test")
+            .unwrap_err()
+            .downcast::<ValidatorError>()
+            .expect("should be ValidatorError");
+        assert!(matches!(
+            err,
+            ValidatorError::InvalidConfig { reason, .. } if reason.contains("memory_limit")
+        ));
+    }
+
+    #[test]
+    fn effective_timeout_precedence() {
+        let unset = ValidatorConfig::default();
+        assert_eq!(
+            unset.effective_timeout(None),
+            Duration::from_secs(DEFAULT_TIMEOUT_SECS)
+        );
+
+        let config = ValidatorConfig {
+            timeout: Some(5),
+            ..Default::default()
+        };
+        assert_eq!(
+            config.effective_timeout(None),
+            Duration::from_secs(5),
+            "validator timeout applies to a block without its own"
+        );
+        assert_eq!(
+            config.effective_timeout(Some(30)),
+            Duration::from_secs(30),
+            "block timeout wins"
+        );
+    }
+
+    #[test]
+    fn effective_retries_precedence() {
+        assert_eq!(
+            ValidatorConfig::default().effective_retries(None),
+            DEFAULT_RETRIES
+        );
+
+        let config = ValidatorConfig {
+            retries: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(config.effective_retries(None), 2);
+        assert_eq!(config.effective_retries(Some(0)), 0, "block retry wins");
+    }
+
     // ==================== Config tests ====================
 
     #[test]
@@ -188,12 +947,14 @@ mod tests {
                 container: "keinos/sqlite3:3.47.2".to_owned(),
                 script: PathBuf::from("validators/validate-sqlite.sh"),
                 exec_command: None,
+                ..Default::default()
             },
         );
         let config = Config {
             validators,
             fail_fast: true,
             fixtures_dir: None,
+            ..Default::default()
         };
 
         let result = config.get_validator("sqlite");
@@ -222,6 +983,48 @@ mod tests {
         assert!(default_fail_fast());
     }
 
+    #[test]
+    fn config_default_matches_missing_keys() {
+        let parsed: Config = toml::from_str("").unwrap();
+        assert_eq!(Config::default().fail_fast, parsed.fail_fast);
+    }
+
+    #[test]
+    fn resolved_config_fills_defaults_and_front_matter() {
+        let config: Config = toml::from_str(
+            r#"
+            [validators.sqlite]
+            container = "keinos/sqlite3:3.47.2"
+            script = "validators/validate-sqlite.sh"
+            timeout = 5
+        "#,
+        )
+        .unwrap();
+        let chapter =
+            "---\nvalidator:\n  sqlite:\n    container: keinos/sqlite3:3.45.3\n---\n# Intro\n";
+
+        let resolved = config.for_chapter(chapter, "Intro").unwrap().resolved();
+        let text = resolved.to_toml_string().unwrap();
+
+        assert!(text.contains("fail_fast = true"), "{text}");
+        assert!(text.contains("strip = true"), "{text}");
+        assert!(
+            text.contains("timeout = 5"),
+            "inherited from the book: {text}"
+        );
+        assert!(
+            text.contains("retries = 0"),
+            "default made explicit: {text}"
+        );
+
+        let reparsed: Config = toml::from_str(&text).unwrap();
+        assert_eq!(reparsed.validators["sqlite"].timeout, Some(5));
+        assert_eq!(
+            reparsed.validators["sqlite"].container, "keinos/sqlite3:3.45.3",
+            "front matter override: {text}"
+        );
+    }
+
     // ==================== TOML parsing tests ====================
 
     #[test]
@@ -267,6 +1070,363 @@ mod tests {
         assert_eq!(custom.exec_command, Some("python3 -c".to_owned()));
     }
 
+    #[test]
+    fn config_parse_allow_network_defaults_to_false() {
+        let toml_str = r#"
+            [validators.sqlite]
+            container = "keinos/sqlite3:3.47.2"
+            script = "validators/validate-sqlite.sh"
+
+            [validators.fetcher]
+            container = "alpine:3"
+            script = "validators/validate-fetch.sh"
+            allow_network = true
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert!(!config.validators["sqlite"].allow_network);
+        assert!(config.validators["fetcher"].allow_network);
+    }
+
+    #[test]
+    fn config_parse_with_entrypoint() {
+        let toml_str = r#"
+            [validators.custom]
+            container = "osquery/osquery:5.17.0-ubuntu22.04"
+            script = "validators/validate-custom.sh"
+            entrypoint = "/usr/bin/env"
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        let custom = config.validators.get("custom").unwrap();
+        assert_eq!(custom.entrypoint, Some("/usr/bin/env".to_owned()));
+    }
+
+    #[test]
+    fn config_parse_with_each_hooks() {
+        let toml_str = r#"
+            [validators.sqlite]
+            container = "keinos/sqlite3:3.47.2"
+            script = "validators/validate-sqlite.sh"
+            before_each = "cp /tmp/test.db /tmp/test.db.bak"
+            after_each = "mv /tmp/test.db.bak /tmp/test.db"
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        let sqlite = config.validators.get("sqlite").unwrap();
+        assert_eq!(
+            sqlite.before_each.as_deref(),
+            Some("cp /tmp/test.db /tmp/test.db.bak")
+        );
+        assert_eq!(
+            sqlite.after_each.as_deref(),
+            Some("mv /tmp/test.db.bak /tmp/test.db")
+        );
+    }
+
+    #[test]
+    fn config_parse_assertion_library() {
+        let toml_str = r#"
+            [assertions]
+            valid_resp = "rows >= 1\ncontains \"id\""
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            config.assertions.get("valid_resp").map(String::as_str),
+            Some("rows >= 1\ncontains \"id\"")
+        );
+    }
+
+    #[test]
+    fn resolve_assertions_prepends_named_sets() {
+        let mut config = Config::default();
+        config
+            .assertions
+            .insert("non_empty".to_owned(), "rows >= 1\n".to_owned());
+        config
+            .assertions
+            .insert("has_id".to_owned(), "contains \"id\"".to_owned());
+
+        let uses = vec!["non_empty".to_owned(), "has_id".to_owned()];
+        let resolved = config
+            .resolve_assertions(&uses, Some("columns = 2\n"))
+            .unwrap();
+        assert_eq!(
+            resolved.as_deref(),
+            Some("rows >= 1\ncontains \"id\"\ncolumns = 2")
+        );
+    }
+
+    #[test]
+    fn resolve_assertions_unknown_set_errors() {
+        let config = Config::default();
+        let err = config
+            .resolve_assertions(&["missing".to_owned()], None)
+            .unwrap_err();
+        assert!(err.to_string().contains("[E014]"), "got: {err}");
+        assert!(err.to_string().contains("missing"), "got: {err}");
+    }
+
+    #[test]
+    fn config_parse_isolation() {
+        let config: Config = toml::from_str("").unwrap();
+        assert_eq!(config.isolation, Isolation::Shared);
+        let config: Config = toml::from_str("isolation = \"per-chapter\"").unwrap();
+        assert_eq!(config.isolation, Isolation::PerChapter);
+        let config: Config = toml::from_str("isolation = \"per-block\"").unwrap();
+        assert_eq!(config.isolation, Isolation::PerBlock);
+        assert!(toml::from_str::<Config>("isolation = \"per_block\"").is_err());
+    }
+
+    #[test]
+    fn config_parse_state_mode() {
+        let toml_str = r#"
+            [validators.sqlite]
+            container = "keinos/sqlite3:3.47.2"
+            script = "validators/validate-sqlite.sh"
+            state = "fresh"
+
+            [validators.osquery]
+            container = "osquery/osquery:5.17.0-ubuntu22.04"
+            script = "validators/validate-osquery.sh"
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.validators["sqlite"].state, StateMode::Fresh);
+        assert_eq!(config.validators["osquery"].state, StateMode::Cumulative);
+    }
+
+    #[test]
+    fn config_parse_normalize_mode() {
+        let toml_str = r#"
+            [validators.osquery]
+            container = "osquery/osquery:5.17.0-ubuntu22.04"
+            script = "validators/validate-osquery.sh"
+            normalize = "semicolon"
+
+            [validators.sqlite]
+            container = "keinos/sqlite3:3.47.2"
+            script = "validators/validate-sqlite.sh"
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            config.validators["osquery"].normalize,
+            NormalizeMode::Semicolon
+        );
+        assert_eq!(config.validators["sqlite"].normalize, NormalizeMode::Off);
+    }
+
+    #[test]
+    fn config_parse_timeout_and_retries() {
+        let toml_str = r#"
+            [validators.osquery]
+            container = "osquery/osquery:5.17.0-ubuntu22.04"
+            script = "validators/validate-osquery.sh"
+            timeout = 60
+            retries = 2
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.validators["osquery"].timeout, Some(60));
+        assert_eq!(config.validators["osquery"].retries, Some(2));
+    }
+
+    #[test]
+    fn config_parse_expect_version() {
+        let toml_str = r#"
+            [validators.sqlite]
+            container = "keinos/sqlite3:3.47.2"
+            script = "validators/validate-sqlite.sh"
+            expect_version = { command = "sqlite3 --version", contains = "3.47" }
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            config.validators["sqlite"].expect_version,
+            Some(ExpectVersion {
+                command: "sqlite3 --version".to_owned(),
+                contains: "3.47".to_owned(),
+            })
+        );
+    }
+
+    #[test]
+    fn transform_pipeline_applies_in_order() {
+        let content = "sqlite> SELECT 1;\n$ echo hi\n>>> print(1)\nplain > text";
+        assert_eq!(
+            Transform::StripPrompt.apply(content),
+            Ok("SELECT 1;\necho hi\nprint(1)\nplain > text".to_owned())
+        );
+        assert_eq!(
+            Transform::apply_all(
+                &[Transform::StripPrompt, Transform::TrimTrailingSemicolon],
+                "sqlite> SELECT 1;  "
+            ),
+            Ok("SELECT 1".to_owned())
+        );
+        assert_eq!(Transform::apply_all(&[], "a;"), Ok("a;".to_owned()));
+        assert_eq!(
+            Transform::InterpolateEnv.apply("${MDBOOK_VALIDATOR_SURELY_UNSET}"),
+            Err("MDBOOK_VALIDATOR_SURELY_UNSET".to_owned())
+        );
+    }
+
+    #[test]
+    fn trim_common_indent_removes_shared_whitespace() {
+        let content = "    def f():\n        return 1\n\n    f()";
+        assert_eq!(
+            Transform::TrimCommonIndent.apply(content),
+            Ok("def f():\n    return 1\n\nf()".to_owned())
+        );
+        // Whitespace-only lines don't limit the indent and are emptied
+        assert_eq!(
+            Transform::TrimCommonIndent.apply("\t\ta\n  \n\t\t\tb"),
+            Ok("a\n\n\tb".to_owned())
+        );
+        // Mixed tabs and spaces share no indent
+        assert_eq!(
+            Transform::TrimCommonIndent.apply("\ta\n    b"),
+            Ok("\ta\n    b".to_owned())
+        );
+        assert_eq!(
+            Transform::TrimCommonIndent.apply("a\n  b"),
+            Ok("a\n  b".to_owned())
+        );
+    }
+
+    #[test]
+    fn config_parse_pipeline() {
+        let toml_str = r#"
+            [validators.sqlite]
+            container = "keinos/sqlite3:3.47.2"
+            script = "validators/validate-sqlite.sh"
+            pipeline = ["strip_prompt", "trim_trailing_semicolon"]
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(
+            config.validators["sqlite"].pipeline,
+            [Transform::StripPrompt, Transform::TrimTrailingSemicolon]
+        );
+
+        let unknown = toml_str.replace("strip_prompt", "shout");
+        assert!(toml::from_str::<Config>(&unknown).is_err());
+    }
+
+    #[test]
+    fn normalize_mode_apply() {
+        let content = "SELECT 1  \nFROM t;  \n\n";
+        assert_eq!(NormalizeMode::Off.apply(content), content);
+        assert_eq!(
+            NormalizeMode::Whitespace.apply(content),
+            "SELECT 1\nFROM t;"
+        );
+        assert_eq!(NormalizeMode::Semicolon.apply(content), "SELECT 1\nFROM t");
+        // Only one terminator is removed, and only at the very end
+        assert_eq!(NormalizeMode::Semicolon.apply("a; b;;"), "a; b;");
+    }
+
+    #[test]
+    fn config_parse_container_label() {
+        let toml_str = r#"
+            container_label = "My Book"
+        "#;
+        let config: Config = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.container_label.as_deref(), Some("My Book"));
+    }
+
+    #[test]
+    fn config_progress_flag_overrides_terminal_detection() {
+        let config: Config = toml::from_str("progress = false").unwrap();
+        assert!(!config.progress_enabled());
+
+        let config: Config = toml::from_str("progress = true").unwrap();
+        assert!(config.progress_enabled());
+
+        assert_eq!(Config::default().progress, None);
+    }
+
+    #[test]
+    fn config_quiet_success_disables_default_progress() {
+        let config: Config = toml::from_str("quiet_success = true").unwrap();
+        assert!(config.quiet_success);
+        assert!(!config.progress_enabled());
+
+        let config: Config = toml::from_str("quiet_success = true\nprogress = true").unwrap();
+        assert!(config.progress_enabled());
+
+        assert!(!Config::default().quiet_success);
+    }
+
+    #[test]
+    fn config_strip_defaults_to_on() {
+        assert!(Config::default().strip_enabled());
+
+        let config: Config = toml::from_str("strip = false").unwrap();
+        assert!(!config.strip_enabled());
+    }
+
+    #[test]
+    fn config_parse_default_validators() {
+        assert!(Config::default().default_validators.is_empty());
+
+        let config: Config = toml::from_str(r#"default_validators = { sql = "sqlite" }"#).unwrap();
+        assert_eq!(
+            config.default_validators.get("sql").map(String::as_str),
+            Some("sqlite")
+        );
+    }
+
+    #[test]
+    fn config_require_docker_defaults_to_having_validators() {
+        assert!(!Config::default().require_docker_enabled());
+
+        let config: Config = toml::from_str(
+            r#"
+            [validators.sqlite]
+            container = "keinos/sqlite3:3.47.2"
+            script = "validators/validate-sqlite.sh"
+            "#,
+        )
+        .unwrap();
+        assert!(config.require_docker_enabled());
+
+        let config: Config = toml::from_str(
+            r#"
+            require_docker = false
+            [validators.sqlite]
+            container = "keinos/sqlite3:3.47.2"
+            script = "validators/validate-sqlite.sh"
+            "#,
+        )
+        .unwrap();
+        assert!(!config.require_docker_enabled());
+    }
+
+    #[test]
+    fn edit_distance_counts_single_character_edits() {
+        assert_eq!(edit_distance("container", "container"), 0);
+        assert_eq!(edit_distance("continer", "container"), 1);
+        assert_eq!(edit_distance("scirpt", "script"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn suggest_field_ignores_distant_names() {
+        let message = "unknown field `continer`, expected one of `container`, `script`";
+        assert_eq!(suggest_field(message), Some("container"));
+
+        let message = "unknown field `banana`, expected `container` or `script`";
+        assert_eq!(suggest_field(message), None);
+    }
+
+    #[test]
+    fn config_fail_fast_accepts_hyphenated_key() {
+        let config: Config = toml::from_str("fail-fast = false").unwrap();
+        assert!(!config.fail_fast);
+    }
+
+    #[test]
+    fn config_max_blocks_per_container_rejects_zero() {
+        let config: Config = toml::from_str("max_blocks_per_container = 50").unwrap();
+        assert_eq!(config.max_blocks_per_container, NonZeroUsize::new(50));
+
+        assert!(toml::from_str::<Config>("max_blocks_per_container = 0").is_err());
+    }
+
     #[test]
     fn config_parse_with_fixtures_dir() {
         let toml_str = r#"
@@ -288,4 +1448,63 @@ mod tests {
         assert!(config.validators.is_empty());
         assert!(config.fail_fast);
     }
+
+    // ==================== front matter override tests ====================
+
+    fn sqlite_book_config() -> Config {
+        toml::from_str(
+            r#"
+            [validators.sqlite]
+            container = "keinos/sqlite3:3.47.2"
+            script = "validators/validate-sqlite.sh"
+            timeout = 10
+        "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn with_front_matter_overrides_only_given_keys() {
+        let front_matter = "validator:\n  sqlite:\n    container: keinos/sqlite3:3.45\n";
+        let (config, overridden) = sqlite_book_config()
+            .with_front_matter(front_matter, "Intro")
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(overridden, ["sqlite"]);
+        let sqlite = config.get_validator("sqlite").unwrap();
+        assert_eq!(sqlite.container, "keinos/sqlite3:3.45");
+        assert_eq!(
+            sqlite.script,
+            PathBuf::from("validators/validate-sqlite.sh")
+        );
+        assert_eq!(sqlite.timeout, Some(10));
+    }
+
+    #[test]
+    fn with_front_matter_ignores_front_matter_without_validator_key() {
+        let config = sqlite_book_config();
+        assert!(config
+            .with_front_matter("title: Intro\n", "Intro")
+            .unwrap()
+            .is_none());
+        assert!(config
+            .with_front_matter("just some text", "Intro")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn with_front_matter_names_mistyped_key() {
+        let front_matter = "validator:\n  sqlite:\n    containr: keinos/sqlite3:3.45\n";
+        let err = sqlite_book_config()
+            .with_front_matter(front_matter, "Intro")
+            .unwrap_err();
+        let message = err.to_string();
+        assert!(
+            message.contains("front matter of 'Intro'.validator.sqlite"),
+            "{message}"
+        );
+        assert!(message.contains("did you mean `container`?"), "{message}");
+    }
 }
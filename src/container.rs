@@ -3,15 +3,22 @@
 //! Uses testcontainers async API to start containers and bollard
 //! for exec with environment variables.
 
-use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
 use tracing::{debug, trace};
 
 use anyhow::{Context, Result};
 
+use crate::command::CommandRunner;
 use crate::error::ValidatorError;
-use bollard::container::LogOutput;
+use bollard::container::{
+    ListContainersOptions, LogOutput, RemoveContainerOptions, UpdateContainerOptions,
+};
 use bollard::exec::{CreateExecOptions, StartExecOptions, StartExecResults};
 use futures_util::StreamExt;
+use sha2::{Digest, Sha256};
 use testcontainers::core::client::docker_client_instance;
 use testcontainers::{runners::AsyncRunner, ContainerAsync, GenericImage, ImageExt};
 
@@ -58,6 +65,24 @@ async fn collect_exec_output(
     })
 }
 
+/// Pull `image` with `docker pull`, so the Docker CLI's credential helpers and
+/// proxy settings apply. The image is then present locally when the container starts.
+///
+/// # Errors
+///
+/// Returns error if `docker` can't be run or the pull fails.
+pub fn pull_image_via_cli<R: CommandRunner>(runner: &R, image: &str) -> Result<()> {
+    debug!(image = %image, "Pulling image via docker CLI");
+    let output = runner.run_command("docker", &["pull", image])?;
+    if output.status.success() {
+        return Ok(());
+    }
+    Err(anyhow::anyhow!(
+        "`docker pull {image}` failed: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    ))
+}
+
 /// Result of executing a validator
 #[derive(Debug)]
 #[must_use]
@@ -70,6 +95,87 @@ pub struct ValidationResult {
     pub stderr: String,
 }
 
+/// Docker label key applied to containers when a label value is configured
+pub const CONTAINER_LABEL_KEY: &str = "mdbook-validator";
+
+/// Options for starting a raw validator container.
+///
+/// Defaults mirror the validator config defaults: no mount and no network access.
+#[derive(Debug, Clone, Default)]
+pub struct ContainerOptions {
+    /// Optional (`host_path`, `container_path`) bind mount
+    pub mount: Option<(PathBuf, String)>,
+    /// Allow network access. When false the container runs with `--network none`.
+    pub allow_network: bool,
+    /// Entrypoint override; `sleep infinity` is passed to it as arguments
+    pub entrypoint: Option<String>,
+    /// Value for the [`CONTAINER_LABEL_KEY`] label, so containers can be filtered
+    /// (`docker ps --filter label=mdbook-validator=<value>`)
+    pub label: Option<String>,
+    /// Hard memory limit in bytes, with swap disabled
+    pub memory_limit: Option<u64>,
+    /// Environment variables set on the container. Every exec inherits them;
+    /// variables an exec sets itself (like `exec_with_env`'s) take precedence.
+    pub env: HashMap<String, String>,
+}
+
+/// A parsed image reference: `name[:tag][@digest]`.
+///
+/// `name` may include a registry with a port (`localhost:5000/tool`), so the tag
+/// is only what follows the last `:` of the final path component.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageReference {
+    /// Repository name, including any registry (e.g. `keinos/sqlite3`)
+    pub name: String,
+    /// Tag, if given (e.g. `3.47.2`)
+    pub tag: Option<String>,
+    /// Content digest, if pinned (e.g. `sha256:abc...`)
+    pub digest: Option<String>,
+}
+
+impl ImageReference {
+    /// Parse an image reference such as `keinos/sqlite3:3.47.2` or
+    /// `keinos/sqlite3@sha256:...`.
+    #[must_use]
+    pub fn parse(image: &str) -> Self {
+        let (rest, digest) = match image.split_once('@') {
+            Some((rest, digest)) => (rest, Some(digest.to_owned())),
+            None => (image, None),
+        };
+        let last_slash = rest.rfind('/').map_or(0, |i| i + 1);
+        let (name, tag) = match rest.rfind(':') {
+            Some(colon) if colon >= last_slash => (
+                rest.get(..colon).unwrap_or(rest),
+                rest.get(colon + 1..).map(ToOwned::to_owned),
+            ),
+            _ => (rest, None),
+        };
+        Self {
+            name: name.to_owned(),
+            tag,
+            digest,
+        }
+    }
+
+    /// Name and tag for [`GenericImage::new`], which pulls `{name}:{tag}`.
+    ///
+    /// A digest is carried by splitting it at its algorithm, so `name@sha256:hex`
+    /// becomes (`name@sha256`, `hex`). Untagged images default to `latest`.
+    fn generic_image_parts(&self) -> (String, String) {
+        let name = match &self.tag {
+            Some(tag) if self.digest.is_some() => format!("{}:{tag}", self.name),
+            _ => self.name.clone(),
+        };
+        match self.digest.as_deref().and_then(|d| d.split_once(':')) {
+            Some((algorithm, hex)) => (format!("{name}@{algorithm}"), hex.to_owned()),
+            None => (
+                name,
+                self.tag.clone().unwrap_or_else(|| "latest".to_owned()),
+            ),
+        }
+    }
+}
+
 /// Manages validator container lifecycle
 ///
 /// Starts an Alpine container with a validator script copied in,
@@ -80,6 +186,8 @@ pub struct ValidatorContainer {
     container_id: String,
     /// Docker operations for exec calls (injected for testability)
     docker: Arc<dyn DockerOperations>,
+    /// SHA-256 of each SETUP script that has run successfully in this container
+    setups: Mutex<HashSet<[u8; 32]>>,
 }
 
 impl ValidatorContainer {
@@ -102,6 +210,7 @@ impl ValidatorContainer {
             _container: container,
             container_id,
             docker,
+            setups: Mutex::default(),
         }
     }
 
@@ -120,7 +229,7 @@ impl ValidatorContainer {
     /// Returns error if Docker is not running or container fails to start.
     pub async fn start_with_image(image: &str, validator_script: &[u8]) -> Result<Self> {
         debug!(image = %image, "Starting container");
-        let (name, tag) = image.rsplit_once(':').unwrap_or((image, "latest"));
+        let (name, tag) = ImageReference::parse(image).generic_image_parts();
 
         let container = GenericImage::new(name, tag)
             .with_copy_to("/validate.sh", validator_script.to_vec())
@@ -144,6 +253,7 @@ impl ValidatorContainer {
             _container: container,
             container_id,
             docker,
+            setups: Mutex::default(),
         })
     }
 
@@ -167,6 +277,9 @@ impl ValidatorContainer {
     /// - `VALIDATOR_ASSERTIONS`: Assertion rules (if present)
     /// - `VALIDATOR_EXPECT`: Expected output (if present)
     ///
+    /// These are merged with the container's own environment (the validator's
+    /// `env`); on a name clash the variables above win.
+    ///
     /// # Errors
     ///
     /// Returns error if exec creation or execution fails.
@@ -227,6 +340,94 @@ impl ValidatorContainer {
         &self.container_id
     }
 
+    /// Whether `script` has been recorded with [`Self::record_setup`]
+    #[must_use]
+    pub fn has_run_setup(&self, script: &str) -> bool {
+        self.setups
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .contains(&setup_hash(script))
+    }
+
+    /// Remember that the SETUP `script` ran successfully in this container
+    pub fn record_setup(&self, script: &str) {
+        self.setups
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(setup_hash(script));
+    }
+
+    /// Check whether the container is still running.
+    ///
+    /// Returns `false` if the container has exited (crash, OOM kill) or can no
+    /// longer be inspected (e.g. it was removed).
+    pub async fn is_running(&self) -> bool {
+        match self.docker.inspect_container(&self.container_id).await {
+            Ok(inspect) => inspect
+                .state
+                .and_then(|state| state.running)
+                .unwrap_or(false),
+            Err(e) => {
+                debug!(error = %e, "Container inspect failed");
+                false
+            }
+        }
+    }
+
+    /// Check whether the kernel OOM killer has fired in the container.
+    ///
+    /// Reads `State.OOMKilled`, which Docker sets for any OOM kill in the
+    /// container's cgroup, including an exec'd process while the container itself
+    /// keeps running. Returns `false` if the container cannot be inspected.
+    pub async fn was_oom_killed(&self) -> bool {
+        match self.docker.inspect_container(&self.container_id).await {
+            Ok(inspect) => inspect
+                .state
+                .and_then(|state| state.oom_killed)
+                .unwrap_or(false),
+            Err(e) => {
+                debug!(error = %e, "Container inspect failed");
+                false
+            }
+        }
+    }
+
+    /// Verify the container's image has repo digest `digest` (e.g. `sha256:abc...`).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ValidatorError::DigestMismatch`] if none of the image's repo digests
+    /// match, or an error if the container or image cannot be inspected.
+    pub async fn verify_digest(&self, digest: &str) -> Result<()> {
+        let inspect = self.docker.inspect_container(&self.container_id).await?;
+        let image_id = inspect
+            .image
+            .context("Container inspect did not report an image")?;
+        let repo_digests = self
+            .docker
+            .inspect_image(&image_id)
+            .await?
+            .repo_digests
+            .unwrap_or_default();
+        trace!(image = %image_id, repo_digests = ?repo_digests, "Image digests");
+
+        let suffix = format!("@{digest}");
+        if repo_digests.iter().any(|d| d.ends_with(&suffix)) {
+            debug!(digest = %digest, "Image digest verified");
+            return Ok(());
+        }
+        Err(ValidatorError::DigestMismatch {
+            image: image_id,
+            expected: digest.to_owned(),
+            actual: if repo_digests.is_empty() {
+                "no repo digests".to_owned()
+            } else {
+                repo_digests.join(", ")
+            },
+        }
+        .into())
+    }
+
     /// Execute a raw command in the container and return output.
     ///
     /// This is a lower-level method than `exec_with_env` that runs arbitrary
@@ -273,11 +474,55 @@ impl ValidatorContainer {
         collect_exec_output(self.docker.as_ref(), &exec_id, output).await
     }
 
+    /// Run a (possibly multi-line) script in the container with `interpreter`.
+    ///
+    /// The script is written to a temp file in the container via stdin, then run as
+    /// `interpreter <file>`, so it is never interpolated into a command line and
+    /// needs no quoting. The script's own stdin is empty. The temp file is removed
+    /// afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `interpreter` - Program that runs the script file (e.g., `"sh"`, `"bash"`)
+    /// * `script` - Script content
+    ///
+    /// # Errors
+    ///
+    /// Returns error if writing the script or executing it fails.
+    pub async fn exec_script(&self, interpreter: &str, script: &str) -> Result<ValidationResult> {
+        static NEXT_SCRIPT: AtomicU64 = AtomicU64::new(0);
+        let path = format!(
+            "/tmp/mdbook-validator-script-{}-{}",
+            std::process::id(),
+            NEXT_SCRIPT.fetch_add(1, Ordering::Relaxed)
+        );
+
+        // The path is passed as $0, not interpolated into the command
+        let write = self
+            .exec_with_stdin(&["sh", "-c", "cat > \"$0\"", &path], script)
+            .await?;
+        if write.exit_code != 0 {
+            return Err(ValidatorError::ContainerExec {
+                message: format!("Failed to write script to {path}: {}", write.stderr),
+            }
+            .into());
+        }
+
+        let result = self.exec_raw(&[interpreter, &path]).await;
+        if let Err(e) = self.exec_raw(&["rm", "-f", &path]).await {
+            debug!(path = %path, error = %e, "Failed to remove script file");
+        }
+        result
+    }
+
     /// Execute a command in the container with stdin content.
     ///
     /// This passes content via stdin instead of shell interpolation, eliminating
     /// shell injection risks from special characters in the content.
     ///
+    /// Stdin is written on a separate task while output is read, so a command that
+    /// fills its stdout pipe before draining stdin doesn't deadlock.
+    ///
     /// # Arguments
     ///
     /// * `cmd` - Command and arguments to execute (e.g., `&["cat"]`)
@@ -325,14 +570,23 @@ impl ValidatorContainer {
             .into());
         };
 
-        // Write stdin content and close to signal EOF
-        input
-            .write_all(stdin_content.as_bytes())
-            .await
-            .context("Failed to write to stdin")?;
-        input.shutdown().await.context("Failed to close stdin")?;
-
-        collect_exec_output(self.docker.as_ref(), &exec_id, output).await
+        // Write stdin content and close to signal EOF, concurrently with reading output
+        let stdin_bytes = stdin_content.as_bytes().to_vec();
+        let writer = tokio::spawn(async move {
+            input
+                .write_all(&stdin_bytes)
+                .await
+                .context("Failed to write to stdin")?;
+            input.shutdown().await.context("Failed to close stdin")
+        });
+
+        let result = collect_exec_output(self.docker.as_ref(), &exec_id, output).await;
+        if result.is_err() {
+            writer.abort();
+            return result;
+        }
+        writer.await.context("Stdin writer task failed")??;
+        result
     }
 
     /// Start a container without copying a validator script.
@@ -355,6 +609,7 @@ impl ValidatorContainer {
     ///
     /// This is for the new architecture where validators run on the host,
     /// and containers only provide the tool (sqlite3, osquery, etc.).
+    /// Network access is allowed; use [`Self::start_raw_with_options`] to disable it.
     ///
     /// # Arguments
     ///
@@ -368,26 +623,65 @@ impl ValidatorContainer {
         image: &str,
         mount: Option<(&std::path::Path, &str)>,
     ) -> Result<Self> {
-        use testcontainers::core::Mount;
+        let options = ContainerOptions {
+            mount: mount.map(|(host, container)| (host.to_path_buf(), container.to_owned())),
+            allow_network: true,
+            ..Default::default()
+        };
+        Self::start_raw_with_options(image, &options).await
+    }
 
-        debug!(image = %image, mount = ?mount.map(|(p, c)| (p.display().to_string(), c)), "Starting raw container");
-        let (name, tag) = image.rsplit_once(':').unwrap_or((image, "latest"));
+    /// Start a container configured by [`ContainerOptions`].
+    ///
+    /// The container runs `sleep infinity` so it stays up for exec calls.
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - Docker image in "name:tag" format
+    /// * `options` - Mount, network, entrypoint, label, and memory settings
+    ///
+    /// # Errors
+    ///
+    /// Returns error if Docker is not running or container fails to start.
+    pub async fn start_raw_with_options(image: &str, options: &ContainerOptions) -> Result<Self> {
+        use testcontainers::core::Mount;
 
-        let base_image = GenericImage::new(name, tag).with_cmd(["sleep", "infinity"]);
+        debug!(
+            image = %image,
+            mount = ?options.mount.as_ref().map(|(p, c)| (p.display().to_string(), c)),
+            allow_network = options.allow_network,
+            entrypoint = ?options.entrypoint,
+            label = ?options.label,
+            memory_limit = ?options.memory_limit,
+            env = ?options.env.keys().collect::<Vec<_>>(),
+            "Starting raw container"
+        );
+        let (name, tag) = ImageReference::parse(image).generic_image_parts();
+
+        let mut generic = GenericImage::new(name, tag);
+        if let Some(entrypoint) = &options.entrypoint {
+            generic = generic.with_entrypoint(entrypoint);
+        }
+        let mut request = generic.with_cmd(["sleep", "infinity"]);
 
-        let container = if let Some((host_path, container_path)) = mount {
+        if let Some((host_path, container_path)) = &options.mount {
             let host_str = host_path.to_string_lossy().to_string();
-            base_image
-                .with_mount(Mount::bind_mount(host_str, container_path))
-                .start()
-                .await
-                .context("Failed to start container with mount. Is Docker running?")?
-        } else {
-            base_image
-                .start()
-                .await
-                .context("Failed to start container. Is Docker running?")?
-        };
+            request = request.with_mount(Mount::bind_mount(host_str, container_path.as_str()));
+        }
+        if !options.allow_network {
+            request = request.with_network("none");
+        }
+        if let Some(label) = &options.label {
+            request = request.with_labels([(CONTAINER_LABEL_KEY, label.as_str())]);
+        }
+        for (name, value) in &options.env {
+            request = request.with_env_var(name, value);
+        }
+
+        let container = request
+            .start()
+            .await
+            .context("Failed to start container. Is Docker running?")?;
 
         let container_id = container.id().to_owned();
         // Show first 12 chars of container ID (like docker ps)
@@ -398,12 +692,145 @@ impl ValidatorContainer {
         let docker_client = docker_client_instance()
             .await
             .context("Failed to get Docker client")?;
+        if let Some(bytes) = options.memory_limit {
+            // Swap equal to memory means no swap, so exceeding the limit OOM-kills the exec
+            let bytes = i64::try_from(bytes).context("Memory limit is too large")?;
+            docker_client
+                .update_container(
+                    &container_id,
+                    UpdateContainerOptions::<String> {
+                        memory: Some(bytes),
+                        memory_swap: Some(bytes),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .context("Failed to set container memory limit")?;
+        }
         let docker: Arc<dyn DockerOperations> = Arc::new(BollardDocker::new(docker_client));
 
         Ok(Self {
             _container: container,
             container_id,
             docker,
+            setups: Mutex::default(),
+        })
+    }
+}
+
+fn setup_hash(script: &str) -> [u8; 32] {
+    Sha256::digest(script.as_bytes()).into()
+}
+
+/// A container carrying the [`CONTAINER_LABEL_KEY`] label
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabeledContainer {
+    /// Container ID
+    pub id: String,
+    /// Image the container was started from
+    pub image: String,
+    /// Value of the label (the configured `container_label`)
+    pub label: String,
+}
+
+/// List containers carrying the [`CONTAINER_LABEL_KEY`] label, stopped ones included.
+///
+/// With `label`, only containers whose label has that value are listed.
+///
+/// # Errors
+///
+/// Returns error if Docker is unreachable or the listing fails.
+pub async fn list_labeled_containers(label: Option<&str>) -> Result<Vec<LabeledContainer>> {
+    let docker = docker_client_instance()
+        .await
+        .context("Failed to get Docker client")?;
+    let filter = match label {
+        Some(value) => format!("{CONTAINER_LABEL_KEY}={value}"),
+        None => CONTAINER_LABEL_KEY.to_owned(),
+    };
+    let options = ListContainersOptions::<String> {
+        all: true,
+        filters: HashMap::from([("label".to_owned(), vec![filter])]),
+        ..Default::default()
+    };
+    let summaries = docker
+        .list_containers(Some(options))
+        .await
+        .context("Failed to list containers")?;
+
+    Ok(summaries
+        .into_iter()
+        .filter_map(|summary| {
+            Some(LabeledContainer {
+                label: summary
+                    .labels
+                    .and_then(|labels| labels.get(CONTAINER_LABEL_KEY).cloned())
+                    .unwrap_or_default(),
+                image: summary.image.unwrap_or_default(),
+                id: summary.id?,
+            })
         })
+        .collect())
+}
+
+/// Force-remove containers carrying the [`CONTAINER_LABEL_KEY`] label, e.g. ones
+/// left running by a crashed build. Returns the removed containers.
+///
+/// With `label`, only containers whose label has that value are removed.
+///
+/// # Errors
+///
+/// Returns error if Docker is unreachable or a container can't be removed.
+pub async fn remove_labeled_containers(label: Option<&str>) -> Result<Vec<LabeledContainer>> {
+    let containers = list_labeled_containers(label).await?;
+    let docker = docker_client_instance()
+        .await
+        .context("Failed to get Docker client")?;
+    for container in &containers {
+        debug!(container_id = %container.id, image = %container.image, "Removing labeled container");
+        docker
+            .remove_container(
+                &container.id,
+                Some(RemoveContainerOptions {
+                    force: true,
+                    ..Default::default()
+                }),
+            )
+            .await
+            .with_context(|| format!("Failed to remove container {}", container.id))?;
+    }
+    Ok(containers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(image: &str) -> (String, String) {
+        ImageReference::parse(image).generic_image_parts()
+    }
+
+    #[test]
+    fn generic_image_parts_keeps_registry_port_in_name() {
+        assert_eq!(
+            parts("myregistry.com:5000/image"),
+            ("myregistry.com:5000/image".to_owned(), "latest".to_owned())
+        );
+        assert_eq!(
+            parts("myregistry.com:5000/image:1.2"),
+            ("myregistry.com:5000/image".to_owned(), "1.2".to_owned())
+        );
+    }
+
+    #[test]
+    fn generic_image_parts_rebuilds_digest_reference() {
+        let (name, tag) = parts("img@sha256:abc123");
+        assert_eq!(format!("{name}:{tag}"), "img@sha256:abc123");
+
+        let (name, tag) = parts("registry:5000/img:1.0@sha256:abc123");
+        assert_eq!(
+            format!("{name}:{tag}"),
+            "registry:5000/img:1.0@sha256:abc123"
+        );
     }
 }
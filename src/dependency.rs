@@ -38,8 +38,7 @@ impl DependencyChecker for RealChecker {
         Command::new(cmd)
             .args(args)
             .output()
-            .map(|o| o.status.success())
-            .unwrap_or(false)
+            .is_ok_and(|o| o.status.success())
     }
 }
 
@@ -1,14 +1,15 @@
 //! Docker operations abstraction for testing.
 //!
 //! Provides a trait for Docker exec operations, enabling mocking in tests
-//! to cover error paths (`create_exec` failure, `start_exec` failure, `inspect_exec` failure).
+//! to cover error paths (`create_exec` failure, `start_exec` failure, `inspect_exec` failure),
+//! container state (a cached container that died), and image digests.
 
 use anyhow::Result;
 
 use crate::error::ValidatorError;
 use async_trait::async_trait;
 use bollard::exec::{CreateExecOptions, CreateExecResults, StartExecOptions, StartExecResults};
-use bollard::service::ExecInspectResponse;
+use bollard::service::{ContainerInspectResponse, ExecInspectResponse, ImageInspect};
 use bollard::Docker;
 
 /// Trait for Docker exec operations.
@@ -33,6 +34,12 @@ pub trait DockerOperations: Send + Sync {
 
     /// Inspect an exec instance to get exit code.
     async fn inspect_exec(&self, exec_id: &str) -> Result<ExecInspectResponse>;
+
+    /// Inspect a container to get its state.
+    async fn inspect_container(&self, container_id: &str) -> Result<ContainerInspectResponse>;
+
+    /// Inspect an image (by name or ID) to get its repo digests.
+    async fn inspect_image(&self, image: &str) -> Result<ImageInspect>;
 }
 
 /// Real implementation wrapping [`bollard::Docker`].
@@ -89,6 +96,27 @@ impl DockerOperations for BollardDocker {
             .into()
         })
     }
+
+    async fn inspect_container(&self, container_id: &str) -> Result<ContainerInspectResponse> {
+        self.inner
+            .inspect_container(container_id, None)
+            .await
+            .map_err(|e| {
+                ValidatorError::ContainerExec {
+                    message: format!("inspect_container failed: {e}"),
+                }
+                .into()
+            })
+    }
+
+    async fn inspect_image(&self, image: &str) -> Result<ImageInspect> {
+        self.inner.inspect_image(image).await.map_err(|e| {
+            ValidatorError::ContainerExec {
+                message: format!("inspect_image failed: {e}"),
+            }
+            .into()
+        })
+    }
 }
 
 #[cfg(test)]
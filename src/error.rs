@@ -1,8 +1,10 @@
 //! Structured error types for mdbook-validator.
 //!
-//! Each variant has an error code (E001-E010) for grep-ability
+//! Each variant has an error code (E001-E025) for grep-ability
 //! and structured fields for programmatic access.
 
+use std::fmt;
+
 use thiserror::Error;
 
 /// Errors that can occur during mdbook-validator operations.
@@ -54,10 +56,82 @@ pub enum ValidatorError {
     /// Mutually exclusive attributes (E011)
     #[error("[E011] 'hidden' and 'skip' are mutually exclusive")]
     MutuallyExclusiveAttributes,
+
+    /// `before_each` hook failed (E012)
+    #[error("[E012] before_each hook failed (exit {exit_code}): {message}")]
+    BeforeEachFailed { exit_code: i32, message: String },
+
+    /// `after_each` hook failed (E013)
+    #[error("[E013] after_each hook failed (exit {exit_code}): {message}")]
+    AfterEachFailed { exit_code: i32, message: String },
+
+    /// Unknown named assertion set (E014)
+    #[error("[E014] Unknown assertion set '{name}' (referenced with use=)")]
+    UnknownAssertionSet { name: String },
+
+    /// Config value could not be parsed (E015)
+    #[error("[E015] Invalid config at '{path}': {message}")]
+    ConfigParse { path: String, message: String },
+
+    /// Tool version in the container doesn't match `expect_version` (E016)
+    #[error("[E016] Tool version mismatch for '{name}': expected output of `{command}` to contain '{expected}', got: {actual}")]
+    VersionMismatch {
+        name: String,
+        command: String,
+        expected: String,
+        actual: String,
+    },
+
+    /// Running image doesn't have the digest pinned in `container` (E017)
+    #[error("[E017] Image digest mismatch for '{image}': expected {expected}, got: {actual}")]
+    DigestMismatch {
+        image: String,
+        expected: String,
+        actual: String,
+    },
+
+    /// `<!--ASSERT:name-->` with no matching `-- @name` section (E018)
+    #[error("[E018] ASSERT:{name} has no matching '-- @{name}' section in the block")]
+    UnknownSection { name: String },
+
+    /// `${VAR}` in assertions or EXPECT names an unset environment variable (E019)
+    #[error(
+        "[E019] Undefined environment variable '{name}' (referenced as ${{{name}}} in '{chapter}')"
+    )]
+    UndefinedVariable { name: String, chapter: String },
+
+    /// Stripped chapter failed its self-check (E020); indicates a stripping bug
+    #[error("[E020] Marker stripping produced invalid markdown: {reason}")]
+    StripInvariantViolation { reason: String },
+
+    /// `<!--OUTPUT:name-->` with no validated `capture=name` block in the chapter (E021)
+    #[error("[E021] OUTPUT:{name} in '{chapter}' has no validated block with capture={name}")]
+    UnknownCapture { name: String, chapter: String },
+
+    /// Query was killed for exceeding the validator's `memory_limit` (E022)
+    #[error("[E022] Out of memory (limit {limit}): {message}")]
+    OutOfMemory { limit: String, message: String },
+
+    /// SETUP or query exec ran past the block's timeout (E023)
+    #[error("[E023] {phase} timed out after {seconds}s (validator: {validator}): {message}")]
+    Timeout {
+        phase: String,
+        validator: String,
+        seconds: u64,
+        message: String,
+    },
+
+    /// `<!--CLEANUP-->` script failed (E024)
+    #[error("[E024] Cleanup script failed (exit {exit_code}): {message}")]
+    CleanupFailed { exit_code: i32, message: String },
+
+    /// Docker isn't running and `require_docker` is on (E025)
+    #[error("[E025] Docker is not available (`docker info` failed); start Docker, or set require_docker = false")]
+    DockerUnavailable,
 }
 
 impl ValidatorError {
-    /// Returns the error code (E001-E011) for this error variant.
+    /// Returns the error code (E001-E025) for this error variant.
     ///
     /// Error codes are stable and can be used for programmatic matching.
     #[must_use]
@@ -74,6 +148,463 @@ impl ValidatorError {
             Self::FixturesError { .. } => "E009",
             Self::ScriptNotFound { .. } => "E010",
             Self::MutuallyExclusiveAttributes => "E011",
+            Self::BeforeEachFailed { .. } => "E012",
+            Self::AfterEachFailed { .. } => "E013",
+            Self::UnknownAssertionSet { .. } => "E014",
+            Self::ConfigParse { .. } => "E015",
+            Self::VersionMismatch { .. } => "E016",
+            Self::DigestMismatch { .. } => "E017",
+            Self::UnknownSection { .. } => "E018",
+            Self::UndefinedVariable { .. } => "E019",
+            Self::StripInvariantViolation { .. } => "E020",
+            Self::UnknownCapture { .. } => "E021",
+            Self::OutOfMemory { .. } => "E022",
+            Self::Timeout { .. } => "E023",
+            Self::CleanupFailed { .. } => "E024",
+            Self::DockerUnavailable => "E025",
         }
     }
+
+    /// Append `extra` to the error's message, for variants that carry one.
+    ///
+    /// Returns `false` (leaving the error unchanged) for variants without a message.
+    pub fn append_message(&mut self, extra: &str) -> bool {
+        self.message_mut()
+            .map(|message| message.push_str(extra))
+            .is_some()
+    }
+
+    /// Convert into an [`anyhow::Error`] whose message starts with `context`.
+    ///
+    /// Unlike formatting the error into a new message, the result still
+    /// downcasts to `ValidatorError`. Variants with a message get `context`
+    /// prepended to it; the rest are wrapped with `context` as anyhow context.
+    #[must_use]
+    pub fn into_anyhow_with_context(mut self, context: impl fmt::Display) -> anyhow::Error {
+        if self.prepend_message(&format!("{context}: ")) {
+            return self.into();
+        }
+        let message = format!("{context}: {self}");
+        anyhow::Error::new(self).context(message)
+    }
+
+    /// Prepend `prefix` to the error's message, for variants that carry one.
+    fn prepend_message(&mut self, prefix: &str) -> bool {
+        self.message_mut()
+            .map(|message| message.insert_str(0, prefix))
+            .is_some()
+    }
+
+    /// The error's message, for variants that carry one.
+    fn message_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Self::Config { message }
+            | Self::ContainerStartup { message }
+            | Self::ContainerExec { message }
+            | Self::SetupFailed { message, .. }
+            | Self::QueryFailed { message, .. }
+            | Self::ValidationFailed { message, .. }
+            | Self::FixturesError { message }
+            | Self::BeforeEachFailed { message, .. }
+            | Self::AfterEachFailed { message, .. }
+            | Self::ConfigParse { message, .. }
+            | Self::OutOfMemory { message, .. }
+            | Self::Timeout { message, .. }
+            | Self::CleanupFailed { message, .. } => Some(message),
+            _ => None,
+        }
+    }
+}
+
+/// Every block failure of a run with `fail_fast = false`, in validation order.
+///
+/// Returned instead of the first failure when failures are accumulated. A failure
+/// outside any one block (e.g. invalid front matter) is an entry of its own. Each
+/// failure stays its own error, so typed ones still downcast to [`ValidatorError`].
+#[derive(Debug)]
+pub struct AggregatedErrors {
+    errors: Vec<anyhow::Error>,
+}
+
+impl AggregatedErrors {
+    /// Aggregate `errors`, in the order they occurred
+    #[must_use]
+    pub fn new(errors: Vec<anyhow::Error>) -> Self {
+        Self { errors }
+    }
+
+    /// All failures, in validation order
+    #[must_use]
+    pub fn errors(&self) -> &[anyhow::Error] {
+        &self.errors
+    }
+
+    /// Take the failures out, in validation order
+    #[must_use]
+    pub fn into_errors(self) -> Vec<anyhow::Error> {
+        self.errors
+    }
+
+    /// The failures that are [`ValidatorError`]s, in validation order
+    pub fn validator_errors(&self) -> impl Iterator<Item = &ValidatorError> {
+        self.errors.iter().filter_map(anyhow::Error::downcast_ref)
+    }
+}
+
+/// Each failure gets a numbered header line, so multi-line messages (code,
+/// stderr) stay visibly apart in a terminal
+impl fmt::Display for AggregatedErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.errors.len();
+        match total {
+            1 => write!(f, "1 block failed validation:")?,
+            n => write!(f, "{n} blocks failed validation:")?,
+        }
+        for (n, error) in self.errors.iter().enumerate() {
+            write!(f, "\n\n--- Failure {} of {total} ---\n{error:#}", n + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for AggregatedErrors {}
+
+/// Prefix `context` to an error's message, keeping a [`ValidatorError`] inside
+/// it downcastable (see [`ValidatorError::into_anyhow_with_context`]).
+#[must_use]
+pub fn with_context(error: anyhow::Error, context: impl fmt::Display) -> anyhow::Error {
+    match error.downcast::<ValidatorError>() {
+        Ok(validator_error) => validator_error.into_anyhow_with_context(context),
+        Err(error) => anyhow::anyhow!("{context}: {error}"),
+    }
+}
+
+/// Long-form explanations for each error code, shown by `mdbook-validator explain <code>`.
+///
+/// Keep in sync with [`ValidatorError::code`] and TROUBLESHOOTING.md.
+const EXPLANATIONS: &[(&str, &str)] = &[
+    (
+        "E001",
+        "E001: Configuration error
+
+The [preprocessor.validator] section of book.toml is missing or could not be parsed.
+
+Fix: make sure the section exists and is valid TOML:
+
+    [preprocessor.validator]
+    command = \"mdbook-validator\"",
+    ),
+    (
+        "E002",
+        "E002: Container startup failed
+
+The Docker container for a validator could not be started. Usually Docker is not
+running, or the image name/tag does not exist.
+
+Fix: check `docker info`, then try `docker pull <image>` with the `container` value
+from your validator config. Use a specific tag, never `:latest`.",
+    ),
+    (
+        "E003",
+        "E003: Container exec failed
+
+A command could not be executed inside a running validator container (the exec
+could not be created, started, or inspected).
+
+Fix: verify `exec_command` exists in the image:
+
+    docker run --rm keinos/sqlite3:3.47.2 sqlite3 --version",
+    ),
+    (
+        "E004",
+        "E004: Setup script failed
+
+The <!--SETUP--> content exited non-zero inside the container. SETUP is written
+to a temp file in the container and run with `sh`.
+
+Fix: run the SETUP content by hand in the container and fix the error, e.g.
+
+    <!--SETUP
+    sqlite3 /tmp/test.db 'CREATE TABLE users (id INTEGER);'
+    -->",
+    ),
+    (
+        "E005",
+        "E005: Query execution failed
+
+The visible block content (the query or script) exited non-zero in the container.
+
+Fix: correct the example itself; the error output from the tool is included in the
+message.",
+    ),
+    (
+        "E006",
+        "E006: Validation failed
+
+The container ran the block, but the host validator rejected the output: an
+<!--ASSERT--> rule or <!--EXPECT--> comparison did not hold.
+
+Fix: compare the expected and actual values in the message, then update either the
+example or the assertion:
+
+    <!--ASSERT
+    rows >= 1
+    contains \"alice\"
+    -->",
+    ),
+    (
+        "E007",
+        "E007: Unknown validator
+
+A block uses `validator=<name>` but no validator with that name is configured.
+
+Fix: add it to book.toml:
+
+    [preprocessor.validator.validators.sqlite]
+    container = \"keinos/sqlite3:3.47.2\"
+    script = \"validators/validate-sqlite.sh\"",
+    ),
+    (
+        "E008",
+        "E008: Invalid validator config
+
+A validator entry is missing a required value (`container` or `script` is empty).
+
+Fix: set both fields for the validator named in the message.",
+    ),
+    (
+        "E009",
+        "E009: Fixtures directory error
+
+`fixtures_dir` does not exist, is not a directory, or could not be resolved.
+Relative paths are resolved from the book root.
+
+Fix: create the directory or correct the path:
+
+    [preprocessor.validator]
+    fixtures_dir = \"fixtures\"",
+    ),
+    (
+        "E010",
+        "E010: Script not found
+
+The validator `script` path does not exist. Paths are relative to the book root.
+
+Fix: check the path, e.g. `ls validators/validate-sqlite.sh`.",
+    ),
+    (
+        "E011",
+        "E011: 'hidden' and 'skip' are mutually exclusive
+
+`hidden` validates a block and removes it from output; `skip` shows a block without
+validating it. Using both would hide a block that is never checked.
+
+Fix: remove one of the attributes:
+
+    ```sql validator=sqlite hidden     <- validated, not shown
+    ```sql validator=sqlite skip       <- shown, not validated",
+    ),
+    (
+        "E012",
+        "E012: before_each hook failed
+
+The validator's `before_each` command exited non-zero. It runs via `sh -c` in the
+container before every block of that validator.
+
+Fix: run the hook by hand in the container and fix it:
+
+    [preprocessor.validator.validators.sqlite]
+    before_each = \"cp /tmp/test.db /tmp/test.db.bak\"",
+    ),
+    (
+        "E013",
+        "E013: after_each hook failed
+
+The validator's `after_each` command exited non-zero. It runs via `sh -c` in the
+container after every block of that validator, even when the block failed.
+
+Fix: run the hook by hand in the container and fix it. A hook that restores state
+should tolerate a missing snapshot, e.g. `mv -f ... || true`.",
+    ),
+    (
+        "E014",
+        "E014: Unknown assertion set
+
+A block references `use=<name>` but no assertion set with that name is defined.
+
+Fix: define it in book.toml:
+
+    [preprocessor.validator.assertions]
+    valid_resp = \"rows >= 1\\ncontains \\\"id\\\"\"",
+    ),
+    (
+        "E015",
+        "E015: Invalid config
+
+A value in the [preprocessor.validator] section has the wrong type or a required
+key is missing. The message names the key path, e.g.
+`preprocessor.validator.validators.sqlite` for a validator missing `container`.
+
+Fix: correct the named key in book.toml:
+
+    [preprocessor.validator.validators.sqlite]
+    container = \"keinos/sqlite3:3.47.2\"
+    script = \"validators/validate-sqlite.sh\"",
+    ),
+    (
+        "E016",
+        "E016: Tool version mismatch
+
+The validator has `expect_version` set, and the output of its version command
+(run once when the container starts) doesn't contain the expected string. The
+image's tool version differs from what the book documents.
+
+Fix: pin the image tag that ships the documented version, or update
+`expect_version` (and the book) to the new version:
+
+    [preprocessor.validator.validators.sqlite]
+    container = \"keinos/sqlite3:3.47.2\"
+    expect_version = { command = \"sqlite3 --version\", contains = \"3.47\" }",
+    ),
+    (
+        "E017",
+        "E017: Image digest mismatch
+
+The validator's `container` is pinned by digest (`image@sha256:...`), but the
+started container's image has no matching repo digest. This usually means a
+local image with the same name shadows the pinned one, or the digest was
+copied incorrectly.
+
+Fix: check the digest Docker reports for the image and update book.toml:
+
+    docker image inspect --format '{{.RepoDigests}}' keinos/sqlite3:3.47.2",
+    ),
+    (
+        "E018",
+        "E018: Unknown section
+
+A block has an `<!--ASSERT:name-->` marker, but its code has no `-- @name`
+line starting a section with that name.
+
+Fix: label the statement the assertions are for:
+
+    -- @name
+    SELECT * FROM users;
+    <!--ASSERT:name
+    rows = 2
+    -->",
+    ),
+    (
+        "E019",
+        "E019: Undefined environment variable
+
+An assertion or EXPECT contains `${NAME}`, which is replaced with the value of
+the NAME environment variable before validation, but NAME is not set.
+
+Fix: export the variable before building:
+
+    EXPECTED_VERSION=1.2.3 mdbook build",
+    ),
+    (
+        "E020",
+        "E020: Marker stripping produced invalid markdown
+
+After validation, markers are stripped from each validator block and the result
+is checked: no <!--SETUP, <!--ASSERT or <!--EXPECT marker may remain, and no
+line of it may close the block's fence early. This usually means a marker is
+missing its closing `-->`, or a stripping bug.
+
+Fix: close every marker with `-->` on its own line:
+
+    <!--ASSERT
+    rows = 1
+    -->
+
+If the markers are well-formed, please report the chapter as a bug.",
+    ),
+    (
+        "E021",
+        "E021: Unknown capture
+
+A chapter contains `<!--OUTPUT:name-->`, which is replaced with the output of
+the block marked `capture=name`, but no block in the chapter captured that
+name. The capturing block may be missing, misspelled, or marked `skip`.
+
+Fix: add `capture=name` to the block whose output should be shown:
+
+    ```sql validator=sqlite hidden capture=name
+    SELECT * FROM users;
+    ```",
+    ),
+    (
+        "E022",
+        "E022: Out of memory
+
+The validator's container has a `memory_limit`, and the block's query was
+killed for exceeding it: it exited with SIGKILL (137) and Docker reported the
+container's `State.OOMKilled` flag. A SIGKILL without that flag, or in a block
+that expects exit code 137, is not reported as E022. A `max_memory` assertion
+in the block states the budget the example is expected to fit in.
+
+Fix: make the example use less memory, or raise the limit:
+
+    [preprocessor.validator.validators.sqlite]
+    memory_limit = \"512m\"",
+    ),
+    (
+        "E023",
+        "E023: Timed out
+
+A block's SETUP script or query ran longer than its timeout and was abandoned.
+The error names the phase. The container is discarded, since the exec may still
+be running in it, and the next block starts a new one.
+
+Fix: look for a query waiting on something that never comes (a locked
+database, a socket), or raise the timeout for the validator or the block:
+
+    [preprocessor.validator.validators.osquery]
+    timeout = 600
+
+    ```sql validator=osquery timeout=600
+    ```",
+    ),
+    (
+        "E024",
+        "E024: Cleanup script failed
+
+The <!--CLEANUP--> content exited non-zero inside the container. CLEANUP runs
+with `sh -c` after the block's query and assertions, whether or not they passed;
+a block that already failed reports its own error instead.
+
+Fix: run the CLEANUP content by hand in the container and fix the error, e.g.
+
+    <!--CLEANUP
+    rm -f /tmp/example.txt
+    -->",
+    ),
+    (
+        "E025",
+        "E025: Docker is not available
+
+The book has blocks to validate, but `docker info` failed, so no validator
+container could start. The check runs once, before any block is validated.
+
+Fix: start Docker Desktop or the Docker daemon and check `docker info` works.
+If Docker is reachable but the `docker` CLI isn't installed, turn the check off:
+
+    [preprocessor.validator]
+    require_docker = false",
+    ),
+];
+
+/// Returns the long-form explanation for an error code (e.g. `"E011"`).
+///
+/// Matching is case-insensitive. Returns `None` for unknown codes.
+#[must_use]
+pub fn explain(code: &str) -> Option<&'static str> {
+    let code = code.trim().to_ascii_uppercase();
+    EXPLANATIONS
+        .iter()
+        .find(|(known, _)| *known == code)
+        .map(|(_, text)| *text)
 }
@@ -0,0 +1,299 @@
+//! Block fingerprints for change tracking
+//!
+//! A fingerprint is a SHA-256 over everything in a block that affects its
+//! validation outcome, so tools can tell whether a block needs re-validating.
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+
+use crate::config::Config;
+use crate::parser::{BlockAttributes, ExtractedMarkers};
+
+/// Stable hex fingerprint of a block and everything its validation depends on.
+///
+/// Covers the code (including `@@` lines), SETUP, all assertion markers (including
+/// ASSERT_TOML), every EXPECT, EXPECT_REGEX and EXPECT-EXIT; the block's attributes
+/// except the display-only `desc=` and `caption=`; the contents of its `use=`
+/// assertion sets; the validator's config with defaults resolved (exec command,
+/// env, ...) and the bytes of its script, read from `book_root`; and the container
+/// image. Each field is length-prefixed and absent values are distinguished from
+/// empty ones, so distinct inputs can't collide by shifting text between fields.
+///
+/// # Errors
+///
+/// Returns error if the validator or a `use=` assertion set isn't in `config`, or
+/// the validator script can't be read.
+pub fn block_fingerprint(
+    validator: &str,
+    markers: &ExtractedMarkers,
+    attrs: &BlockAttributes,
+    config: &Config,
+    book_root: &Path,
+    image: &str,
+) -> Result<String> {
+    let resolved = config.resolved();
+    let validator_config = resolved.get_validator(validator)?;
+    // Through `toml::Value`, whose tables are sorted, so `env` hashes stably
+    let settings = toml::to_string(&toml::Value::try_from(validator_config)?)?;
+    let script_path = book_root.join(&validator_config.script);
+    let script = std::fs::read(&script_path).with_context(|| {
+        format!(
+            "Failed to read validator script '{}'",
+            script_path.display()
+        )
+    })?;
+    let script = format!("{:x}", Sha256::digest(&script));
+    let assertion_sets = config.resolve_assertions(&attrs.uses, None)?;
+
+    let mut hasher = Sha256::new();
+    // Bump when the canonical form changes so old fingerprints don't match
+    hasher.update(b"mdbook-validator-fingerprint-v6");
+
+    let mut field = |value: Option<&str>| match value {
+        Some(value) => {
+            hasher.update([1_u8]);
+            hasher.update((value.len() as u64).to_le_bytes());
+            hasher.update(value.as_bytes());
+        }
+        None => hasher.update([0_u8]),
+    };
+
+    field(Some(validator));
+    field(Some(image));
+    field(Some(&settings));
+    field(Some(validator_config.resolved_exec_command(validator)));
+    field(Some(&script));
+
+    field(Some(&markers.visible_content));
+    field(markers.setup.as_deref());
+    field(markers.cleanup.as_deref());
+    field(markers.assertions.as_deref());
+    field(markers.assert_file.as_deref());
+    field(markers.assert_toml.as_deref());
+    for (name, assertions) in &markers.section_assertions {
+        field(Some(name));
+        field(Some(assertions));
+    }
+    // Terminates the section list so a section can't be read as the next field
+    field(None);
+    for expect in &markers.expect {
+        field(Some(expect));
+    }
+    field(None);
+    field(markers.expect_regex.as_deref());
+    field(markers.expect_exit.as_deref());
+
+    field(Some(&attrs.language));
+    for flag in [attrs.skip, attrs.hidden, attrs.optional] {
+        field(flag.then_some(""));
+    }
+    for values in [
+        &attrs.uses,
+        &attrs.args,
+        &attrs.invalid,
+        &attrs.expect_ignore,
+        &attrs.allow_warnings,
+    ] {
+        for value in values {
+            field(Some(value));
+        }
+        field(None);
+    }
+    field(attrs.filter.as_deref());
+    field(attrs.session.as_deref());
+    field(attrs.timeout.map(|secs| secs.to_string()).as_deref());
+    field(attrs.retry.map(|retries| retries.to_string()).as_deref());
+    field(Some(&format!("{:?}", attrs.expect_mode)));
+    field(Some(&format!("{:?}", attrs.assert_target)));
+    field(attrs.cwd.as_deref());
+    field(attrs.capture.as_deref());
+    field(assertion_sets.as_deref());
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{extract_markers, parse_block_attributes};
+
+    const IMAGE: &str = "keinos/sqlite3:3.47.2";
+    const INFO: &str = "sql validator=sqlite use=base";
+    const BLOCK: &str = "<!--SETUP\nsqlite3 /tmp/test.db 'CREATE TABLE t(x);'\n-->\n@@SELECT 0;\nSELECT * FROM t;\n<!--ASSERT\nrows = 0\n-->\n<!--EXPECT\n[]\n-->";
+    const CONFIG: &str = r#"
+        [validators.sqlite]
+        container = "keinos/sqlite3:3.47.2"
+        script = "validate.sh"
+
+        [assertions]
+        base = "rows >= 0"
+    "#;
+
+    /// A book root holding the validator script
+    fn book_root() -> tempfile::TempDir {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("validate.sh"), "#!/bin/sh\nexit 0\n").unwrap();
+        dir
+    }
+
+    fn fingerprint(info: &str, block: &str, config: &str, root: &Path) -> String {
+        block_fingerprint(
+            "sqlite",
+            &extract_markers(block),
+            &parse_block_attributes(info),
+            &toml::from_str(config).unwrap(),
+            root,
+            IMAGE,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn fingerprint_is_stable() {
+        let root = book_root();
+        let first = fingerprint(INFO, BLOCK, CONFIG, root.path());
+        assert_eq!(first, fingerprint(INFO, BLOCK, CONFIG, root.path()));
+        assert_eq!(first.len(), 64);
+        assert!(first.chars().all(|c| c.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn fingerprint_changes_with_every_marker() {
+        let root = book_root();
+        let base = fingerprint(INFO, BLOCK, CONFIG, root.path());
+
+        let variants = [
+            BLOCK.replace("SELECT * FROM t;", "SELECT x FROM t;"),
+            BLOCK.replace("@@SELECT 0;", "@@SELECT 1;"),
+            BLOCK.replace("CREATE TABLE t(x)", "CREATE TABLE t(y)"),
+            BLOCK.replace("rows = 0", "rows = 1"),
+            BLOCK.replace("[]", "[{}]"),
+            format!("{BLOCK}\n<!--EXPECT\n[]\n-->"),
+            format!("{BLOCK}\n<!--EXPECT_REGEX\n.*\n-->"),
+            format!("{BLOCK}\n<!--ASSERT_FILE\nrules.txt\n-->"),
+            format!("{BLOCK}\n<!--ASSERT_TOML\ncontains = [\"x\"]\n-->"),
+            format!("{BLOCK}\n<!--ASSERT:q1\nrows = 1\n-->"),
+            format!("{BLOCK}\n<!--CLEANUP\nrm -f /tmp/test.db\n-->"),
+            format!("{BLOCK}\n<!--EXPECT-EXIT 1-->"),
+        ];
+        for variant in &variants {
+            assert_ne!(
+                base,
+                fingerprint(INFO, variant, CONFIG, root.path()),
+                "fingerprint should change for:\n{variant}"
+            );
+        }
+    }
+
+    #[test]
+    fn fingerprint_changes_with_every_attribute() {
+        let root = book_root();
+        let base = fingerprint(INFO, BLOCK, CONFIG, root.path());
+
+        let attributes = [
+            "filter=\".[0]\"",
+            "args=\"-bail\"",
+            "session=orders",
+            "timeout=5",
+            "retry=2",
+            "expect_ignore=id",
+            "expect_unordered",
+            "allow_warning=\"deprecated\"",
+            "assert_target=combined",
+            "cwd=/work",
+            "optional",
+            "skip",
+        ];
+        for attribute in attributes {
+            let info = format!("{INFO} {attribute}");
+            assert_ne!(
+                base,
+                fingerprint(&info, BLOCK, CONFIG, root.path()),
+                "fingerprint should change for: {attribute}"
+            );
+        }
+        // Display-only attributes don't affect validation
+        assert_eq!(
+            base,
+            fingerprint(
+                &format!("{INFO} desc=\"Users\""),
+                BLOCK,
+                CONFIG,
+                root.path()
+            )
+        );
+    }
+
+    #[test]
+    fn fingerprint_changes_with_validator_config_script_and_assertion_sets() {
+        let root = book_root();
+        let base = fingerprint(INFO, BLOCK, CONFIG, root.path());
+
+        let script = "script = \"validate.sh\"";
+        let configs = [
+            CONFIG.replace(
+                script,
+                &format!("{script}\nexec_command = \"sqlite3 -json {{db}}\""),
+            ),
+            CONFIG.replace(script, &format!("{script}\nenv = {{ TZ = \"UTC\" }}")),
+            CONFIG.replace(script, &format!("{script}\ntimeout = 5")),
+            CONFIG.replace("rows >= 0", "rows >= 1"),
+        ];
+        for config in &configs {
+            assert_ne!(
+                base,
+                fingerprint(INFO, BLOCK, config, root.path()),
+                "fingerprint should change for:\n{config}"
+            );
+        }
+
+        std::fs::write(root.path().join("validate.sh"), "#!/bin/sh\nexit 1\n").unwrap();
+        assert_ne!(base, fingerprint(INFO, BLOCK, CONFIG, root.path()));
+
+        assert_ne!(
+            base,
+            block_fingerprint(
+                "sqlite",
+                &extract_markers(BLOCK),
+                &parse_block_attributes(INFO),
+                &toml::from_str(CONFIG).unwrap(),
+                root.path(),
+                "keinos/sqlite3:3.46.0",
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn fingerprint_distinguishes_missing_from_empty_marker() {
+        let root = book_root();
+        let config: Config = toml::from_str(CONFIG).unwrap();
+        let attrs = parse_block_attributes(INFO);
+        let without = extract_markers("SELECT 1;");
+        let with_empty = ExtractedMarkers {
+            setup: Some(String::new()),
+            ..without.clone()
+        };
+        assert_ne!(
+            block_fingerprint("sqlite", &without, &attrs, &config, root.path(), IMAGE).unwrap(),
+            block_fingerprint("sqlite", &with_empty, &attrs, &config, root.path(), IMAGE).unwrap()
+        );
+    }
+
+    #[test]
+    fn fingerprint_rejects_unknown_assertion_set() {
+        let root = book_root();
+        let err = block_fingerprint(
+            "sqlite",
+            &extract_markers(BLOCK),
+            &parse_block_attributes("sql validator=sqlite use=missing"),
+            &toml::from_str(CONFIG).unwrap(),
+            root.path(),
+            IMAGE,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("missing"), "{err}");
+    }
+}
@@ -3,10 +3,29 @@
 //! Runs validator scripts on the host machine, enabling use of jq
 //! and other host tools for JSON parsing.
 
-use anyhow::Result;
+use std::borrow::Cow;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::Value;
 use tracing::{debug, trace};
 
 use crate::command::CommandRunner;
+use crate::parser::{parse_assertions, AssertTarget, Assertion};
+
+/// Placeholder in `<!--EXPECT-->` that matches any JSON value
+pub const EXPECT_WILDCARD: &str = "<*>";
+
+/// Assertion keywords evaluated on the host (see [`check_host_assertion`])
+const JSON_LENGTH: &str = "json_length";
+const CONTAINS_JSON: &str = "contains_json";
+const MAX_MEMORY: &str = "max_memory";
+const VALID_JSON: &str = "valid_json";
+const TABLE_COLUMNS: &str = "table_columns";
 
 /// Result of running a host validator
 #[derive(Debug)]
@@ -22,6 +41,9 @@ pub struct HostValidationResult {
 
 /// Run a validator script on the host with JSON input.
 ///
+/// When both `assertions` and `expect` are given, the script evaluates them
+/// independently: both must pass, and every failure is reported in `stderr`.
+///
 /// # Arguments
 ///
 /// * `runner` - Command runner for executing scripts (enables mocking)
@@ -29,7 +51,8 @@ pub struct HostValidationResult {
 /// * `json_input` - JSON output from container to validate
 /// * `assertions` - Optional assertion rules
 /// * `expect` - Optional expected output
-/// * `container_stderr` - Optional stderr output from container (for warning detection)
+/// * `container_stderr` - Optional stderr output from container (for warning detection),
+///   with allowed warnings already removed by [`filter_allowed_warnings`]
 ///
 /// # Errors
 ///
@@ -73,3 +96,1058 @@ pub fn run_validator<R: CommandRunner>(
         stderr,
     })
 }
+
+/// The container output a block's assertions run against, per its `assert_target=`.
+///
+/// `Combined` is stdout followed directly by stderr, so text a tool splits
+/// across the two streams can be matched as one.
+#[must_use]
+pub fn assertion_input<'a>(target: AssertTarget, stdout: &'a str, stderr: &'a str) -> Cow<'a, str> {
+    match target {
+        AssertTarget::Stdout => Cow::Borrowed(stdout),
+        AssertTarget::Combined => Cow::Owned(format!("{stdout}{stderr}")),
+    }
+}
+
+/// Drop container stderr lines containing any of the `allowed` warning patterns.
+///
+/// Validator scripts treat some container warnings as failures (e.g. osquery's
+/// `Cannot set unknown ...`); this lets expected, benign warnings through. Lines
+/// are matched by substring; other lines are kept as is.
+#[must_use]
+pub fn filter_allowed_warnings(stderr: &str, allowed: &[String]) -> String {
+    if allowed.is_empty() {
+        return stderr.to_owned();
+    }
+    stderr
+        .lines()
+        .filter(|line| {
+            let allow = allowed
+                .iter()
+                .find(|pattern| line.contains(pattern.as_str()));
+            if let Some(pattern) = allow {
+                debug!(line = %line, pattern = %pattern, "Ignoring allowed warning");
+            }
+            allow.is_none()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Apply a block's `filter="..."` jq expression to JSON output.
+///
+/// The filter's results are collected into an array, so `.[] | {id}` turns rows
+/// into rows of `{id}` and row-based assertions (`rows = N`) keep working.
+/// On success the compact filtered JSON is in `stdout`. An invalid expression,
+/// or one that fails on this output, produces a failing result (exit code 1)
+/// with jq's error in `stderr`.
+///
+/// # Errors
+///
+/// Returns error if `jq` cannot be run.
+pub fn apply_filter(filter: &str, output: &str) -> Result<HostValidationResult> {
+    trace!(filter = %filter, "Applying output filter");
+
+    let mut child = Command::new("jq")
+        .arg("-c")
+        .arg(format!("[{filter}\n]"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run jq for filter. Is jq installed?")?;
+
+    // Write stdin from a thread while reading stdout: jq starts printing before
+    // it has read all its input, and would block on a full stdout pipe while we
+    // blocked on a full stdin pipe
+    let writer = child.stdin.take().map(|mut stdin| {
+        let input = output.to_owned();
+        thread::spawn(move || stdin.write_all(input.as_bytes()))
+    });
+
+    let result = child.wait_with_output().context("Failed to wait for jq")?;
+
+    if let Some(writer) = writer {
+        let written = writer
+            .join()
+            .map_err(|_| anyhow::anyhow!("jq stdin writer panicked"))?;
+        if let Err(e) = written {
+            // jq exits early on a compile error; its exit code reports that below
+            if e.kind() != std::io::ErrorKind::BrokenPipe {
+                return Err(e).context("Failed to write output to jq");
+            }
+        }
+    }
+
+    if result.status.success() {
+        Ok(HostValidationResult {
+            exit_code: 0,
+            stdout: String::from_utf8_lossy(&result.stdout).trim().to_owned(),
+            stderr: String::new(),
+        })
+    } else {
+        Ok(failed(format!(
+            "Invalid filter \"{filter}\": {}",
+            String::from_utf8_lossy(&result.stderr).trim()
+        )))
+    }
+}
+
+/// Check output against an `<!--EXPECT_REGEX-->` pattern.
+///
+/// The pattern must match the entire output (surrounding whitespace is trimmed from both),
+/// so `\d+` matches `"42"` but not `"id 42"`. Evaluated natively, no validator script involved.
+///
+/// A non-matching output or an invalid pattern produces a failing result (exit code 1)
+/// with the pattern and actual output in `stderr`.
+pub fn check_expect_regex(pattern: &str, output: &str) -> HostValidationResult {
+    let pattern = pattern.trim();
+    let actual = output.trim();
+    trace!(pattern = %pattern, actual = %actual, "Checking EXPECT_REGEX");
+
+    let re = match Regex::new(&format!(r"\A(?:{pattern})\z")) {
+        Ok(re) => re,
+        Err(e) => return failed(format!("Invalid EXPECT_REGEX pattern: {e}")),
+    };
+
+    if re.is_match(actual) {
+        passed()
+    } else {
+        failed(format!(
+            "Output does not match EXPECT_REGEX:\n  Pattern: {pattern}\n  Actual:  {actual}"
+        ))
+    }
+}
+
+/// Check JSON output against an `<!--EXPECT-->` template containing `<*>` wildcards.
+///
+/// Each `<*>` (bare, or as the whole string `"<*>"`) matches any single JSON value.
+/// Everything else must match structurally: same object keys, same array lengths,
+/// equal scalars. Whitespace and key order do not matter.
+///
+/// A mismatch or unparseable template/output produces a failing result (exit code 1)
+/// with the template and actual output in `stderr`.
+pub fn check_expect_template(template: &str, output: &str) -> HostValidationResult {
+    check_expect_ignoring(template, output, &[])
+}
+
+/// Like [`check_expect_template`], but first removes every object key named in
+/// `ignore` (at any depth) from both the template and the output.
+///
+/// Used for `expect_ignore=` so volatile fields like timestamps or generated IDs
+/// don't break the comparison.
+pub fn check_expect_ignoring(
+    template: &str,
+    output: &str,
+    ignore: &[String],
+) -> HostValidationResult {
+    let template = template.trim();
+    let actual = output.trim();
+    trace!(template = %template, actual = %actual, ?ignore, "Checking EXPECT template");
+
+    compare_json(template, actual, ignore, |expected, found| {
+        if template_matches(&expected, &found) {
+            passed()
+        } else if ignore.is_empty() {
+            failed(format!(
+                "Output does not match EXPECT template:\n  Expected: {template}\n  Actual:   {actual}"
+            ))
+        } else {
+            failed(format!(
+                "Output does not match EXPECT template (ignoring {}):\n  Expected: {template}\n  Actual:   {actual}",
+                ignore.join(", ")
+            ))
+        }
+    })
+}
+
+/// Parse the trimmed `template` (with `<*>` wildcards) and `actual` output as
+/// JSON, remove the `ignore` keys from both, and pass them to `compare`.
+///
+/// Either side failing to parse is a failing result without calling `compare`.
+fn compare_json(
+    template: &str,
+    actual: &str,
+    ignore: &[String],
+    compare: impl FnOnce(Value, Value) -> HostValidationResult,
+) -> HostValidationResult {
+    let mut expected: Value = match serde_json::from_str(&quote_wildcards(template)) {
+        Ok(v) => v,
+        Err(e) => return failed(format!("Invalid EXPECT template: {e}")),
+    };
+    let mut actual_value: Value = match serde_json::from_str(actual) {
+        Ok(v) => v,
+        Err(e) => return failed(format!("Output is not valid JSON: {e}\n  Actual: {actual}")),
+    };
+    strip_fields(&mut expected, ignore);
+    strip_fields(&mut actual_value, ignore);
+    compare(expected, actual_value)
+}
+
+/// Like [`check_expect_ignoring`], with a line diff of the normalized forms on
+/// a mismatch.
+///
+/// Used for `expect_json`. Both sides are parsed as JSON, so object key order
+/// and whitespace never matter. The diff pretty-prints both values with keys
+/// sorted, so it shows only real differences rather than formatting.
+pub fn check_expect_json(template: &str, output: &str, ignore: &[String]) -> HostValidationResult {
+    let template = template.trim();
+    let actual = output.trim();
+    trace!(template = %template, actual = %actual, ?ignore, "Checking EXPECT as JSON");
+
+    compare_json(template, actual, ignore, |expected, found| {
+        if template_matches(&expected, &found) {
+            return passed();
+        }
+        failed(format!(
+            "Output does not match EXPECT (as JSON; - expected, + actual):\n{}",
+            line_diff(&normalize_json(&expected), &normalize_json(&found))
+        ))
+    })
+}
+
+/// Pretty-print `value` with object keys sorted
+fn normalize_json(value: &Value) -> String {
+    serde_json::to_string_pretty(&sort_keys(value)).unwrap_or_else(|_| value.to_string())
+}
+
+/// Copy of `value` with every object's keys inserted in sorted order, so it
+/// prints sorted even if another crate turns on serde_json's `preserve_order`
+fn sort_keys(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by_key(|(key, _)| *key);
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, v)| (key.clone(), sort_keys(v)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.iter().map(sort_keys).collect()),
+        other => other.clone(),
+    }
+}
+
+/// Lines shared at the start and end of `expected` and `actual` are kept as
+/// context (`  `); the differing lines between are listed as removed (`- `)
+/// then added (`+ `).
+fn line_diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let prefix = expected
+        .iter()
+        .zip(&actual)
+        .take_while(|(e, a)| e == a)
+        .count();
+    let suffix = expected
+        .iter()
+        .skip(prefix)
+        .rev()
+        .zip(actual.iter().skip(prefix).rev())
+        .take_while(|(e, a)| e == a)
+        .count();
+
+    let context = |lines: &[&str], skip: usize, take: usize| -> Vec<String> {
+        lines
+            .iter()
+            .skip(skip)
+            .take(take)
+            .map(|line| format!("    {line}"))
+            .collect()
+    };
+    let changed = |lines: &[&str], sign: char| -> Vec<String> {
+        let end = lines.len() - suffix;
+        lines
+            .iter()
+            .take(end)
+            .skip(prefix)
+            .map(|line| format!("  {sign} {line}"))
+            .collect()
+    };
+
+    let mut out = context(&expected, 0, prefix);
+    out.extend(changed(&expected, '-'));
+    out.extend(changed(&actual, '+'));
+    out.extend(context(&expected, expected.len() - suffix, suffix));
+    out.join("\n")
+}
+
+/// Like [`check_expect_ignoring`], but the template and output are JSON arrays
+/// compared as multisets: each expected row must match a distinct actual row, in
+/// any order.
+///
+/// Used for `expect_unordered`, so queries without `ORDER BY` don't flake. Rows
+/// are paired greedily, each expected row with the first unpaired actual row it
+/// matches (`<*>` wildcards included). A failure lists the expected rows with no
+/// match as missing and the unpaired actual rows as extra.
+pub fn check_expect_unordered(
+    template: &str,
+    output: &str,
+    ignore: &[String],
+) -> HostValidationResult {
+    let template = template.trim();
+    let actual = output.trim();
+    trace!(template = %template, actual = %actual, ?ignore, "Checking unordered EXPECT");
+
+    compare_json(template, actual, ignore, |expected, found| {
+        if let (Value::Array(expected_rows), Value::Array(found_rows)) = (expected, found) {
+            return unordered_rows(expected_rows, found_rows);
+        }
+        failed(format!(
+            "expect_unordered needs a JSON array on both sides:\n  Expected: {template}\n  Actual:   {actual}"
+        ))
+    })
+}
+
+/// Pair each expected row with a distinct matching actual row, as
+/// [`check_expect_unordered`] describes.
+fn unordered_rows(expected_rows: Vec<Value>, mut extra: Vec<Value>) -> HostValidationResult {
+    let mut missing = Vec::new();
+    for row in expected_rows {
+        match extra
+            .iter()
+            .position(|actual| template_matches(&row, actual))
+        {
+            Some(i) => {
+                extra.remove(i);
+            }
+            None => missing.push(row),
+        }
+    }
+    if missing.is_empty() && extra.is_empty() {
+        return passed();
+    }
+
+    let mut lines = vec!["Output does not match EXPECT (unordered):".to_owned()];
+    for (label, rows) in [("Missing", &missing), ("Extra", &extra)] {
+        if !rows.is_empty() {
+            lines.push(format!("  {label} rows:"));
+            lines.extend(rows.iter().map(|row| format!("    {row}")));
+        }
+    }
+    failed(lines.join("\n"))
+}
+
+/// Check output holding several JSON documents against one `<!--EXPECT-->` each,
+/// in order.
+///
+/// Tools print one document per statement that returns rows (sqlite's `-json`
+/// prints an array per result set), so the output is read as a stream of JSON
+/// values. Each is matched like [`check_expect_ignoring`]. A different number of
+/// documents than templates, or any mismatch, fails; mismatches name the EXPECT
+/// by position.
+pub fn check_expect_documents(
+    templates: &[String],
+    output: &str,
+    ignore: &[String],
+) -> HostValidationResult {
+    check_documents(templates, output, |template, document| {
+        check_expect_ignoring(template, document, ignore)
+    })
+}
+
+/// Like [`check_expect_documents`], but each document's rows are matched in any
+/// order, like [`check_expect_unordered`]. The documents themselves stay in order.
+pub fn check_expect_documents_unordered(
+    templates: &[String],
+    output: &str,
+    ignore: &[String],
+) -> HostValidationResult {
+    check_documents(templates, output, |template, document| {
+        check_expect_unordered(template, document, ignore)
+    })
+}
+
+/// Like [`check_expect_documents`], but each document is compared like
+/// [`check_expect_json`].
+pub fn check_expect_documents_json(
+    templates: &[String],
+    output: &str,
+    ignore: &[String],
+) -> HostValidationResult {
+    check_documents(templates, output, |template, document| {
+        check_expect_json(template, document, ignore)
+    })
+}
+
+/// Split `output` into JSON documents and check each against its template with `check`
+fn check_documents(
+    templates: &[String],
+    output: &str,
+    check: impl Fn(&str, &str) -> HostValidationResult,
+) -> HostValidationResult {
+    let actual = output.trim();
+    let documents = match serde_json::Deserializer::from_str(actual)
+        .into_iter::<Value>()
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(documents) => documents,
+        Err(e) => return failed(format!("Output is not valid JSON: {e}\n  Actual: {actual}")),
+    };
+    if documents.len() != templates.len() {
+        return failed(format!(
+            "Expected {} result sets (one per EXPECT), got {}:\n  Actual: {actual}",
+            templates.len(),
+            documents.len()
+        ));
+    }
+
+    let failures: Vec<String> = templates
+        .iter()
+        .zip(&documents)
+        .enumerate()
+        .filter_map(|(idx, (template, document))| {
+            let result = check(template, &document.to_string());
+            (result.exit_code != 0).then(|| format!("EXPECT #{}: {}", idx + 1, result.stderr))
+        })
+        .collect();
+    if failures.is_empty() {
+        passed()
+    } else {
+        failed(failures.join("\n"))
+    }
+}
+
+/// Remove every object key named in `fields`, recursively.
+fn strip_fields(value: &mut Value, fields: &[String]) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|key, _| !fields.contains(key));
+            map.values_mut().for_each(|v| strip_fields(v, fields));
+        }
+        Value::Array(items) => items.iter_mut().for_each(|v| strip_fields(v, fields)),
+        _ => {}
+    }
+}
+
+/// Replace bare `<*>` tokens (outside JSON strings) with the string `"<*>"`
+/// so the template parses as JSON.
+fn quote_wildcards(template: &str) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut rest = template;
+
+    while let Some(c) = rest.chars().next() {
+        if !in_string {
+            if let Some(after) = rest.strip_prefix(EXPECT_WILDCARD) {
+                out.push('"');
+                out.push_str(EXPECT_WILDCARD);
+                out.push('"');
+                rest = after;
+                continue;
+            }
+        }
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+        } else if c == '"' {
+            in_string = true;
+        }
+        out.push(c);
+        let mut chars = rest.chars();
+        chars.next();
+        rest = chars.as_str();
+    }
+
+    out
+}
+
+/// Split `<!--ASSERT-->` content into lines for the validator script and
+/// `json_length`/`contains_json`/`max_memory`/`valid_json`/`table_columns`
+/// assertions, which are evaluated on the host.
+///
+/// Returns `None` for the script part when no script assertions remain.
+#[must_use]
+pub fn split_host_assertions(assertions: &str) -> (Option<String>, Vec<String>) {
+    let (host, script): (Vec<&str>, Vec<&str>) = assertions
+        .lines()
+        .partition(|line| host_keyword(line).is_some());
+    let script = script.join("\n");
+    let script = if script.trim().is_empty() {
+        None
+    } else {
+        Some(script)
+    };
+    (script, host.into_iter().map(str::to_owned).collect())
+}
+
+/// The host keyword an assertion line (optionally negated with `!`) starts with, if any.
+fn host_keyword(line: &str) -> Option<&'static str> {
+    let line = line.trim();
+    let line = line.strip_prefix('!').map_or(line, str::trim_start);
+    [
+        JSON_LENGTH,
+        CONTAINS_JSON,
+        MAX_MEMORY,
+        VALID_JSON,
+        TABLE_COLUMNS,
+    ]
+    .into_iter()
+    .find(|keyword| {
+        line.strip_prefix(keyword)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+    })
+}
+
+/// Check a host assertion (from [`split_host_assertions`]) against the output.
+pub fn check_host_assertion(assertion: &str, output: &str) -> HostValidationResult {
+    match host_keyword(assertion) {
+        Some(CONTAINS_JSON) => check_contains_json(assertion, output),
+        Some(TABLE_COLUMNS) => check_table_columns(assertion, output),
+        // The budget is enforced by the container's memory limit: an exec that
+        // exceeds it is killed and fails before its output is checked
+        Some(MAX_MEMORY) => passed(),
+        Some(VALID_JSON) => check_valid_json(assertion, output),
+        _ => check_json_length(assertion, output),
+    }
+}
+
+/// Comparison operator of a count assertion
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum CompareOp {
+    /// `=`
+    #[serde(rename = "=")]
+    Eq,
+    /// `!=`
+    #[serde(rename = "!=")]
+    Ne,
+    /// `>=`
+    #[serde(rename = ">=")]
+    Ge,
+    /// `>`
+    #[serde(rename = ">")]
+    Gt,
+    /// `<=`
+    #[serde(rename = "<=")]
+    Le,
+    /// `<`
+    #[serde(rename = "<")]
+    Lt,
+}
+
+impl CompareOp {
+    /// Parse an operator symbol (`=`, `!=`, `>=`, `>`, `<=`, `<`)
+    #[must_use]
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        Some(match symbol {
+            "=" => Self::Eq,
+            "!=" => Self::Ne,
+            ">=" => Self::Ge,
+            ">" => Self::Gt,
+            "<=" => Self::Le,
+            "<" => Self::Lt,
+            _ => return None,
+        })
+    }
+
+    /// The operator's symbol
+    #[must_use]
+    pub const fn symbol(self) -> &'static str {
+        match self {
+            Self::Eq => "=",
+            Self::Ne => "!=",
+            Self::Ge => ">=",
+            Self::Gt => ">",
+            Self::Le => "<=",
+            Self::Lt => "<",
+        }
+    }
+
+    /// Whether `actual <op> expected` holds
+    #[must_use]
+    pub fn holds(self, actual: usize, expected: usize) -> bool {
+        match self {
+            Self::Eq => actual == expected,
+            Self::Ne => actual != expected,
+            Self::Ge => actual >= expected,
+            Self::Gt => actual > expected,
+            Self::Le => actual <= expected,
+            Self::Lt => actual < expected,
+        }
+    }
+}
+
+/// A count check in `<!--ASSERT_TOML-->`, e.g. `rows = { op = ">=", value = 1 }`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CountCheck {
+    /// How the actual count is compared to `value`
+    pub op: CompareOp,
+    /// Count to compare against
+    pub value: usize,
+}
+
+/// Structured assertions from an `<!--ASSERT_TOML-->` block, evaluated in Rust
+/// rather than by the validator script:
+///
+/// ```toml
+/// rows = { op = ">=", value = 1 }
+/// contains = ["alice", "bob"]
+/// not_contains = ["password"]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TomlAssertions {
+    /// Row count: the length of a JSON array, or the key count of an object.
+    /// Empty output (e.g. `sqlite3 -json` with no rows) counts as 0 rows.
+    #[serde(default)]
+    pub rows: Option<CountCheck>,
+    /// Strings that must all appear in the output
+    #[serde(default)]
+    pub contains: Vec<String>,
+    /// Strings that must not appear in the output
+    #[serde(default)]
+    pub not_contains: Vec<String>,
+}
+
+impl TomlAssertions {
+    /// Parse the body of an `<!--ASSERT_TOML-->` block.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the body is not valid TOML or has unknown keys or
+    /// wrongly typed values.
+    pub fn parse(body: &str) -> std::result::Result<Self, toml::de::Error> {
+        toml::from_str(body)
+    }
+
+    /// Check every assertion against the output, reporting all failures together.
+    pub fn check(&self, output: &str) -> HostValidationResult {
+        trace!(assertions = ?self, "Checking ASSERT_TOML");
+        let mut failures = Vec::new();
+
+        if let Some(rows) = self.rows {
+            let expected = format!("rows {} {}", rows.op.symbol(), rows.value);
+            let trimmed = output.trim();
+            let actual = if trimmed.is_empty() {
+                Ok(0)
+            } else {
+                match serde_json::from_str::<Value>(trimmed) {
+                    Ok(Value::Array(items)) => Ok(items.len()),
+                    Ok(Value::Object(fields)) => Ok(fields.len()),
+                    Ok(_) => Err("output is not a JSON array or object".to_owned()),
+                    Err(e) => Err(format!("output is not valid JSON: {e}")),
+                }
+            };
+            match actual {
+                Ok(actual) if rows.op.holds(actual, rows.value) => {}
+                Ok(actual) => failures.push(format!("Assertion failed: {expected}: got {actual}")),
+                Err(reason) => failures.push(format!("Assertion failed: {expected}: {reason}")),
+            }
+        }
+        for needle in &self.contains {
+            if !output.contains(needle.as_str()) {
+                failures.push(format!(
+                    "Assertion failed: contains \"{needle}\": not found"
+                ));
+            }
+        }
+        for needle in &self.not_contains {
+            if output.contains(needle.as_str()) {
+                failures.push(format!(
+                    "Assertion failed: not_contains \"{needle}\": found"
+                ));
+            }
+        }
+
+        if failures.is_empty() {
+            passed()
+        } else {
+            failed(failures.join("\n"))
+        }
+    }
+}
+
+/// Check a `json_length <op> N` assertion against JSON output.
+///
+/// The output is parsed with `serde_json`; the length is the element count of an
+/// array or the key count of an object, whatever the validator. Operators are
+/// `=`, `!=`, `>=`, `>`, `<=` and `<`. A leading `!` negates the assertion.
+///
+/// A failing comparison, malformed assertion, or output that is not a JSON array
+/// or object produces a failing result (exit code 1). Malformed assertions and
+/// unsuitable output fail even when negated.
+pub fn check_json_length(assertion: &str, output: &str) -> HostValidationResult {
+    let assertion = assertion.trim();
+    trace!(assertion = %assertion, "Checking json_length");
+
+    let (negated, body) = match assertion.strip_prefix('!') {
+        Some(rest) => (true, rest.trim_start()),
+        None => (false, assertion),
+    };
+    let mut parts = body.split_whitespace().skip(1);
+    let (Some(op), Some(expected), None) = (parts.next(), parts.next(), parts.next()) else {
+        return failed(format!(
+            "Assertion failed: {assertion}: expected `json_length <op> N`"
+        ));
+    };
+    let Ok(expected) = expected.parse::<usize>() else {
+        return failed(format!("Assertion failed: {assertion}: invalid integer"));
+    };
+
+    let actual = match serde_json::from_str::<Value>(output.trim()) {
+        Ok(Value::Array(items)) => items.len(),
+        Ok(Value::Object(fields)) => fields.len(),
+        Ok(_) => {
+            return failed(format!(
+                "Assertion failed: {assertion}: output is not a JSON array or object"
+            ))
+        }
+        Err(e) => {
+            return failed(format!(
+                "Assertion failed: {assertion}: output is not valid JSON: {e}"
+            ))
+        }
+    };
+
+    let Some(holds) = CompareOp::from_symbol(op).map(|op| op.holds(actual, expected)) else {
+        return failed(format!(
+            "Assertion failed: {assertion}: unknown operator '{op}'"
+        ));
+    };
+
+    match (holds, negated) {
+        (true, false) | (false, true) => passed(),
+        (false, false) => failed(format!("Assertion failed: {assertion}: got {actual}")),
+        (true, true) => failed(format!(
+            "Assertion failed: {assertion}: assertion holds but is negated"
+        )),
+    }
+}
+
+/// Check a `table_columns <op> N` assertion against fixed-width table output
+/// (e.g. `sqlite3 -column -header`), for tools that don't print JSON.
+///
+/// Blank lines and separators made only of `-`, `=`, `+`, `|` and `:` (header
+/// underlines, box borders) are skipped. The first remaining line is the header
+/// and the second the first data row, whose whitespace-separated fields are
+/// compared with `N`, using
+/// `=`, `!=`, `>=`, `>`, `<=` or `<`. A leading `!` negates the assertion.
+///
+/// A failing comparison, malformed assertion, or output without a data row
+/// produces a failing result (exit code 1). Malformed assertions and output
+/// without a data row fail even when negated.
+pub fn check_table_columns(assertion: &str, output: &str) -> HostValidationResult {
+    let assertion = assertion.trim();
+    trace!(assertion = %assertion, "Checking table_columns");
+
+    let (negated, body) = match assertion.strip_prefix('!') {
+        Some(rest) => (true, rest.trim_start()),
+        None => (false, assertion),
+    };
+    let mut parts = body.split_whitespace().skip(1);
+    let (Some(op), Some(expected), None) = (parts.next(), parts.next(), parts.next()) else {
+        return failed(format!(
+            "Assertion failed: {assertion}: expected `table_columns <op> N`"
+        ));
+    };
+    let Ok(expected) = expected.parse::<usize>() else {
+        return failed(format!("Assertion failed: {assertion}: invalid integer"));
+    };
+
+    let is_separator = |line: &str| {
+        line.chars()
+            .all(|c| c.is_whitespace() || matches!(c, '-' | '=' | '+' | '|' | ':'))
+    };
+    let Some(row) = output
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !is_separator(line))
+        .nth(1)
+    else {
+        return failed(format!(
+            "Assertion failed: {assertion}: output has no data row after the header"
+        ));
+    };
+    let actual = row.split_whitespace().count();
+
+    let Some(holds) = CompareOp::from_symbol(op).map(|op| op.holds(actual, expected)) else {
+        return failed(format!(
+            "Assertion failed: {assertion}: unknown operator '{op}'"
+        ));
+    };
+
+    match (holds, negated) {
+        (true, false) | (false, true) => passed(),
+        (false, false) => failed(format!(
+            "Assertion failed: {assertion}: got {actual} in row '{}'",
+            row.trim()
+        )),
+        (true, true) => failed(format!(
+            "Assertion failed: {assertion}: assertion holds but is negated"
+        )),
+    }
+}
+
+/// Check a `contains_json '<fragment>'` assertion against JSON output.
+///
+/// Passes when any row of the output contains the fragment: every key of the
+/// fragment is present with an equal value, with nested objects matched the same
+/// way. A single-object output counts as one row. The fragment may be wrapped in
+/// single quotes. A leading `!` negates the assertion.
+///
+/// A failing match, malformed fragment, or output that is not a JSON array or
+/// object produces a failing result (exit code 1) naming the fragment.
+pub fn check_contains_json(assertion: &str, output: &str) -> HostValidationResult {
+    let assertion = assertion.trim();
+    trace!(assertion = %assertion, "Checking contains_json");
+
+    let (negated, body) = match assertion.strip_prefix('!') {
+        Some(rest) => (true, rest.trim_start()),
+        None => (false, assertion),
+    };
+    let raw = body.strip_prefix(CONTAINS_JSON).unwrap_or(body).trim();
+    let raw = raw
+        .strip_prefix('\'')
+        .and_then(|rest| rest.strip_suffix('\''))
+        .unwrap_or(raw);
+    let fragment = match serde_json::from_str::<Value>(raw) {
+        Ok(fragment @ Value::Object(_)) => fragment,
+        Ok(_) => {
+            return failed(format!(
+                "Assertion failed: {assertion}: fragment must be a JSON object"
+            ))
+        }
+        Err(e) => {
+            return failed(format!(
+                "Assertion failed: {assertion}: fragment is not valid JSON: {e}"
+            ))
+        }
+    };
+
+    let holds = match serde_json::from_str::<Value>(output.trim()) {
+        Ok(Value::Array(rows)) => rows.iter().any(|row| json_subset(&fragment, row)),
+        Ok(row @ Value::Object(_)) => json_subset(&fragment, &row),
+        Ok(_) => {
+            return failed(format!(
+                "Assertion failed: {assertion}: output is not a JSON array or object"
+            ))
+        }
+        Err(e) => {
+            return failed(format!(
+                "Assertion failed: {assertion}: output is not valid JSON: {e}"
+            ))
+        }
+    };
+
+    match (holds, negated) {
+        (true, false) | (false, true) => passed(),
+        (false, false) => failed(format!(
+            "Assertion failed: {assertion}: no row contains {fragment}"
+        )),
+        (true, true) => failed(format!(
+            "Assertion failed: {assertion}: a row contains {fragment} but the assertion is negated"
+        )),
+    }
+}
+
+/// Check a `valid_json` assertion: the output must parse as a single JSON value.
+///
+/// Any well-formed value passes, whatever its content; empty output does not. A
+/// failure reports serde_json's error with its line and column. A leading `!`
+/// negates the assertion.
+pub fn check_valid_json(assertion: &str, output: &str) -> HostValidationResult {
+    let assertion = assertion.trim();
+    trace!(assertion = %assertion, "Checking valid_json");
+
+    let negated = assertion.starts_with('!');
+    match (serde_json::from_str::<Value>(output.trim()), negated) {
+        (Ok(_), false) | (Err(_), true) => passed(),
+        (Err(e), false) => failed(format!(
+            "Assertion failed: {assertion}: output is not valid JSON: {e}"
+        )),
+        (Ok(_), true) => failed(format!(
+            "Assertion failed: {assertion}: output is valid JSON but the assertion is negated"
+        )),
+    }
+}
+
+/// Evaluate a block's assertions in Rust, without the validator script or jq.
+///
+/// Supports `rows` (with or without a tolerance), `contains`, `stderr_empty` and
+/// `json_length`, each optionally negated, against JSON row output. As in the
+/// sqlite and osquery scripts, output that isn't valid JSON fails, `contains`
+/// searches string values, `stderr_empty` checks `container_stderr`, and
+/// evaluation stops at the first failing assertion.
+///
+/// Returns `None` if any assertion is malformed or not supported natively, so the
+/// caller can fall back to the validator script.
+#[must_use]
+pub fn check_native_assertions(
+    assertions: Option<&str>,
+    output: &str,
+    container_stderr: &str,
+) -> Option<HostValidationResult> {
+    let assertions = parse_assertions(assertions.unwrap_or_default()).ok()?;
+    if !assertions.iter().all(is_native) {
+        return None;
+    }
+    let Ok(json) = serde_json::from_str::<Value>(output.trim()) else {
+        return Some(failed("Invalid JSON output".to_owned()));
+    };
+
+    for assertion in &assertions {
+        let (negated, inner) = match assertion {
+            Assertion::Not(inner) => (true, inner.as_ref()),
+            _ => (false, assertion),
+        };
+        let reason = match (check_native(inner, &json, container_stderr), negated) {
+            (Verdict::Holds, false) | (Verdict::Fails(_), true) => continue,
+            (Verdict::Fails(reason) | Verdict::Invalid(reason), _) => reason,
+            (Verdict::Holds, true) => {
+                format!("Assertion failed: {assertion}: assertion holds but is negated")
+            }
+        };
+        trace!(assertion = %assertion, "Native assertion failed");
+        return Some(failed(reason));
+    }
+    Some(passed())
+}
+
+/// Whether [`check_native_assertions`] can evaluate an assertion
+fn is_native(assertion: &Assertion) -> bool {
+    match assertion {
+        Assertion::Rows { .. }
+        | Assertion::RowsWithin { .. }
+        | Assertion::Contains(_)
+        | Assertion::StderrEmpty
+        | Assertion::JsonLength { .. } => true,
+        // The scripts strip a single `!`, so `!!x` is unknown syntax
+        Assertion::Not(inner) => !matches!(inner.as_ref(), Assertion::Not(_)) && is_native(inner),
+        _ => false,
+    }
+}
+
+/// Outcome of one natively evaluated assertion, mirroring the scripts' exit
+/// statuses: holds (0), fails (1), or can't be evaluated (2, fails even when negated)
+enum Verdict {
+    Holds,
+    Fails(String),
+    Invalid(String),
+}
+
+fn check_native(assertion: &Assertion, output: &Value, container_stderr: &str) -> Verdict {
+    let verdict = |holds: bool, reason: String| {
+        if holds {
+            Verdict::Holds
+        } else {
+            Verdict::Fails(reason)
+        }
+    };
+    // `jq 'length'`: elements of an array, keys of an object
+    let length = match output {
+        Value::Array(items) => Some(items.len()),
+        Value::Object(fields) => Some(fields.len()),
+        Value::Null => Some(0),
+        _ => None,
+    };
+
+    match assertion {
+        Assertion::Rows { op, n } => match length {
+            Some(actual) => verdict(
+                op.holds(actual, *n),
+                format!("Assertion failed: {assertion}: got {actual}"),
+            ),
+            None => Verdict::Invalid(format!(
+                "Assertion failed: {assertion}: output is not a JSON array"
+            )),
+        },
+        Assertion::RowsWithin { n, tolerance } => match length {
+            Some(actual) => verdict(
+                actual.abs_diff(*n) <= *tolerance,
+                format!("Assertion failed: {assertion}: got {actual}"),
+            ),
+            None => Verdict::Invalid(format!(
+                "Assertion failed: {assertion}: output is not a JSON array"
+            )),
+        },
+        Assertion::Contains(needle) => verdict(
+            any_string_contains(output, needle),
+            format!("Assertion failed: {assertion}: not found in output"),
+        ),
+        Assertion::StderrEmpty => verdict(
+            container_stderr.trim().is_empty(),
+            format!(
+                "Assertion failed: stderr_empty: container stderr is not empty\nstderr: {container_stderr}"
+            ),
+        ),
+        Assertion::JsonLength { op, n } => match output {
+            Value::Array(_) | Value::Object(_) => {
+                let actual = length.unwrap_or_default();
+                verdict(
+                    op.holds(actual, *n),
+                    format!("Assertion failed: {assertion}: got {actual}"),
+                )
+            }
+            _ => Verdict::Fails(format!(
+                "Assertion failed: {assertion}: output is not a JSON array or object"
+            )),
+        },
+        _ => Verdict::Invalid(format!("Assertion failed: Unknown assertion syntax: {assertion}")),
+    }
+}
+
+/// Whether any string value in `value`, at any depth, contains `needle`
+/// (`any(.. | strings; contains($s))` in the scripts)
+fn any_string_contains(value: &Value, needle: &str) -> bool {
+    match value {
+        Value::String(s) => s.contains(needle),
+        Value::Array(items) => items.iter().any(|item| any_string_contains(item, needle)),
+        Value::Object(fields) => fields
+            .values()
+            .any(|field| any_string_contains(field, needle)),
+        _ => false,
+    }
+}
+
+/// Whether every key of `fragment` is in `actual` with a matching value.
+/// Nested objects match as subsets too; anything else must be equal.
+fn json_subset(fragment: &Value, actual: &Value) -> bool {
+    match (fragment, actual) {
+        (Value::Object(f), Value::Object(a)) => f
+            .iter()
+            .all(|(k, f)| a.get(k).is_some_and(|a| json_subset(f, a))),
+        _ => fragment == actual,
+    }
+}
+
+/// Structural match where the string `"<*>"` in `expected` matches anything.
+fn template_matches(expected: &Value, actual: &Value) -> bool {
+    match (expected, actual) {
+        (Value::String(s), _) if s == EXPECT_WILDCARD => true,
+        (Value::Array(e), Value::Array(a)) => {
+            e.len() == a.len() && e.iter().zip(a).all(|(e, a)| template_matches(e, a))
+        }
+        (Value::Object(e), Value::Object(a)) => {
+            e.len() == a.len()
+                && e.iter()
+                    .all(|(k, e)| a.get(k).is_some_and(|a| template_matches(e, a)))
+        }
+        _ => expected == actual,
+    }
+}
+
+/// A passing result with no output.
+fn passed() -> HostValidationResult {
+    HostValidationResult {
+        exit_code: 0,
+        stdout: String::new(),
+        stderr: String::new(),
+    }
+}
+
+/// A failing result (exit code 1) with the given message on stderr.
+fn failed(stderr: String) -> HostValidationResult {
+    HostValidationResult {
+        exit_code: 1,
+        stdout: String::new(),
+        stderr,
+    }
+}
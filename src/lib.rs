@@ -8,10 +8,18 @@ pub mod container;
 pub mod dependency;
 pub mod docker;
 pub mod error;
+pub mod fingerprint;
 pub mod host_validator;
 pub mod parser;
+pub mod pool;
 pub mod preprocessor;
+pub mod profile;
+pub mod progress;
+pub mod report;
+pub mod scaffold;
 pub mod transpiler;
+pub mod watch;
 
-pub use error::ValidatorError;
+pub use error::{AggregatedErrors, ValidatorError};
 pub use preprocessor::ValidatorPreprocessor;
+pub use report::ValidationOutcome;
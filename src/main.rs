@@ -5,12 +5,29 @@
 //! Implements the mdBook preprocessor protocol:
 //! - `mdbook-validator supports <renderer>` - check renderer support
 //! - `mdbook-validator` - read JSON from stdin, process, write to stdout
+//!
+//! Plus standalone subcommands:
+//! - `mdbook-validator explain <code>` - describe an error code (e.g. `E011`)
+//! - `mdbook-validator init [dir]` - scaffold a validator config and sample chapter
+//! - `mdbook-validator validate [dir] [--json]` - validate every chapter once
+//! - `mdbook-validator watch [dir]` - re-validate chapters as they change
+//! - `mdbook-validator clean [label]` - remove containers left by crashed runs
+//! - `mdbook-validator print-config [dir] [--json] [--chapter <file>]` - print the effective config
+//! - `mdbook-validator explain-block` - print the attributes and markers parsed from a block on stdin
 
 use std::io::{self, Read, Write};
+use std::path::Path;
 use std::process;
 
+use anyhow::Context;
 use mdbook_preprocessor::{parse_input, Preprocessor};
+use mdbook_validator::container::remove_labeled_containers;
 use mdbook_validator::dependency::{check_all, RealChecker};
+use mdbook_validator::error::explain;
+use mdbook_validator::parser::{extract_markers, parse_block_attributes};
+use mdbook_validator::report::Report;
+use mdbook_validator::scaffold::{self, ConfigAction, SAMPLE_CONFIG};
+use mdbook_validator::watch::{self, WatchSession};
 use mdbook_validator::ValidatorPreprocessor;
 use tracing_subscriber::EnvFilter;
 
@@ -39,6 +56,21 @@ fn init_logger() {
 fn main() {
     init_logger();
 
+    // Standalone subcommands that don't need Docker or jq
+    if std::env::args().nth(1).as_deref() == Some("explain") {
+        process::exit(run_explain(std::env::args().nth(2).as_deref()));
+    }
+    if std::env::args().nth(1).as_deref() == Some("init") {
+        process::exit(run_init(std::env::args().nth(2).as_deref()));
+    }
+    if std::env::args().nth(1).as_deref() == Some("print-config") {
+        let args: Vec<String> = std::env::args().skip(2).collect();
+        process::exit(run_print_config(&args));
+    }
+    if std::env::args().nth(1).as_deref() == Some("explain-block") {
+        process::exit(run_explain_block());
+    }
+
     // Check for required external dependencies and warn if missing
     let status = check_all(&RealChecker);
     if !status.jq_available {
@@ -54,6 +86,17 @@ fn main() {
         );
     }
 
+    if std::env::args().nth(1).as_deref() == Some("validate") {
+        let args: Vec<String> = std::env::args().skip(2).collect();
+        process::exit(run_validate(&args));
+    }
+    if std::env::args().nth(1).as_deref() == Some("watch") {
+        process::exit(run_watch(std::env::args().nth(2).as_deref()));
+    }
+    if std::env::args().nth(1).as_deref() == Some("clean") {
+        process::exit(run_clean(std::env::args().nth(2).as_deref()));
+    }
+
     let preprocessor = ValidatorPreprocessor::new();
 
     if let Some(sub_cmd) = std::env::args().nth(1) {
@@ -73,6 +116,287 @@ fn main() {
     }
 }
 
+/// Print the explanation for an error code. Returns the process exit code.
+fn run_explain(code: Option<&str>) -> i32 {
+    let Some(code) = code else {
+        tracing::error!("Usage: mdbook-validator explain <code> (e.g. E011)");
+        return 2;
+    };
+
+    if let Some(text) = explain(code) {
+        let _ = writeln!(io::stdout(), "{text}");
+        0
+    } else {
+        tracing::error!("Unknown error code '{code}'");
+        1
+    }
+}
+
+/// Scaffold a validator setup in `dir` (default: current directory). Returns the process exit code.
+fn run_init(dir: Option<&str>) -> i32 {
+    let book_root = Path::new(dir.unwrap_or("."));
+
+    let report = match scaffold::init(book_root) {
+        Ok(report) => report,
+        Err(e) => {
+            tracing::error!("init failed: {e:#}");
+            return 1;
+        }
+    };
+
+    let mut out = io::stdout().lock();
+    let _ = match report.config {
+        ConfigAction::Appended => writeln!(out, "updated  book.toml"),
+        ConfigAction::AlreadyConfigured => {
+            writeln!(
+                out,
+                "skipped  book.toml ([preprocessor.validator] already present)"
+            )
+        }
+        ConfigAction::NoBookToml => writeln!(
+            out,
+            "No book.toml found. Add this to your book.toml:\n\n{SAMPLE_CONFIG}"
+        ),
+    };
+    for path in &report.created {
+        let _ = writeln!(out, "created  {}", path.display());
+    }
+    for path in &report.skipped {
+        let _ = writeln!(out, "skipped  {} (already exists)", path.display());
+    }
+    if report
+        .created
+        .iter()
+        .any(|path| path.ends_with("validator-example.md"))
+    {
+        let _ = writeln!(
+            out,
+            "\nAdd the sample chapter to src/SUMMARY.md:\n\n- [Validated Examples](validator-example.md)"
+        );
+    }
+
+    0
+}
+
+/// Print the config of the book in `[dir]` (default: current directory) with
+/// defaults filled in, as TOML or with `--json` as JSON. With `--chapter <file>`
+/// (relative to the book root), that chapter's front matter overrides are
+/// applied. Returns the process exit code.
+fn run_print_config(args: &[String]) -> i32 {
+    let json = args.iter().any(|arg| arg == "--json");
+    let chapter = args
+        .iter()
+        .position(|arg| arg == "--chapter")
+        .and_then(|i| args.get(i + 1));
+    let book_root = Path::new(
+        args.iter()
+            .find(|arg| !arg.starts_with("--") && Some(*arg) != chapter)
+            .map_or(".", String::as_str),
+    );
+
+    match render_config(book_root, chapter.map(String::as_str), json) {
+        Ok(text) => {
+            let _ = writeln!(io::stdout(), "{}", text.trim_end());
+            0
+        }
+        Err(e) => {
+            tracing::error!("print-config failed: {e:#}");
+            1
+        }
+    }
+}
+
+/// The effective config of the book in `book_root`, rendered for `print-config`
+fn render_config(book_root: &Path, chapter: Option<&str>, json: bool) -> anyhow::Result<String> {
+    let mut config = watch::load_config(book_root)?;
+    if let Some(chapter) = chapter {
+        let path = book_root.join(chapter);
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        config = config.for_chapter(&content, chapter)?;
+    }
+    let config = config.resolved();
+    if json {
+        // Through `serde_json::Value`, whose maps are sorted, so the output is stable
+        Ok(serde_json::to_string_pretty(&serde_json::to_value(
+            &config,
+        )?)?)
+    } else {
+        config.to_toml_string()
+    }
+}
+
+/// Print what the parser extracts from the fenced block on stdin: its attributes,
+/// its markers, and the content that would be run. Returns the process exit code.
+fn run_explain_block() -> i32 {
+    let mut input = String::new();
+    if let Err(e) = io::stdin().read_to_string(&mut input) {
+        tracing::error!("Failed to read stdin: {e}");
+        return 1;
+    }
+    let Some((info, content)) = split_fenced_block(&input) else {
+        tracing::error!(
+            "Usage: mdbook-validator explain-block < block.md (expected a ``` or ~~~ fenced block)"
+        );
+        return 2;
+    };
+
+    let attrs = parse_block_attributes(info);
+    let markers = extract_markers(&content);
+    let _ = writeln!(
+        io::stdout(),
+        "attributes: {attrs:#?}\nmarkers: {markers:#?}\nvalidation_content: {:?}",
+        markers.validation_content()
+    );
+    0
+}
+
+/// The info string and content of the first fenced block in `input`. The block
+/// ends at a closing fence of the same characters, or at the end of the input.
+fn split_fenced_block(input: &str) -> Option<(&str, String)> {
+    let mut lines = input.lines().skip_while(|line| line.trim().is_empty());
+    let opening = lines.next()?.trim_start();
+    let info = opening.trim_start_matches(['`', '~']);
+    let fence = &opening[..opening.len() - info.len()];
+    if fence.len() < 3 {
+        return None;
+    }
+    let content: Vec<&str> = lines
+        .take_while(|line| !line.trim_start().starts_with(fence))
+        .collect();
+    Some((info.trim(), content.join("\n")))
+}
+
+/// Validate every chapter of the book in `[dir]` (default: current directory)
+/// once. Prints a line per chapter, or with `--json` each validated block's
+/// result as a JSON array. Returns the process exit code.
+fn run_validate(args: &[String]) -> i32 {
+    let json = args.iter().any(|arg| arg == "--json");
+    let book_root = Path::new(
+        args.iter()
+            .find(|arg| !arg.starts_with("--"))
+            .map_or(".", String::as_str),
+    );
+
+    let mut session = match WatchSession::load(book_root) {
+        Ok(session) => session,
+        Err(e) => {
+            tracing::error!("validate failed: {e:#}");
+            return 1;
+        }
+    };
+
+    let mut report = Report::default();
+    let mut failed = false;
+    let mut out = io::stdout().lock();
+    for path in watch::chapter_files(&session.src_dir()) {
+        let recorded = report.blocks().len();
+        let result = session.validate_file_with_report(&path, &mut report);
+        failed |= result.is_err();
+        let path = path.strip_prefix(book_root).unwrap_or(&path);
+        if json {
+            // A failure outside any block (e.g. an unreadable file) has no entry to carry it
+            if let Err(e) = &result {
+                if report
+                    .blocks()
+                    .iter()
+                    .skip(recorded)
+                    .all(|block| block.passed || block.skipped)
+                {
+                    tracing::error!("{}: {e:#}", path.display());
+                }
+            }
+        } else {
+            let _ = match &result {
+                Ok(()) => writeln!(out, "✓ {}", path.display()),
+                Err(e) => writeln!(out, "✗ {}\n  {e:#}", path.display()),
+            };
+        }
+    }
+
+    if json {
+        match serde_json::to_string_pretty(report.blocks()) {
+            Ok(text) => {
+                let _ = writeln!(out, "{text}");
+            }
+            Err(e) => {
+                tracing::error!("validate failed: {e}");
+                return 1;
+            }
+        }
+    }
+    i32::from(failed)
+}
+
+/// Watch the book in `dir` (default: current directory) and re-validate
+/// changed chapters. Returns the process exit code.
+fn run_watch(dir: Option<&str>) -> i32 {
+    let book_root = Path::new(dir.unwrap_or("."));
+
+    let mut session = match WatchSession::load(book_root) {
+        Ok(session) => session,
+        Err(e) => {
+            tracing::error!("watch failed: {e:#}");
+            return 1;
+        }
+    };
+
+    tracing::info!("Watching {} for changes", session.src_dir().display());
+    let result = watch::watch(&mut session, |path, result| {
+        let mut out = io::stdout().lock();
+        let path = path.strip_prefix(book_root).unwrap_or(path);
+        let _ = match result {
+            Ok(()) => writeln!(out, "✓ {}", path.display()),
+            Err(e) => writeln!(out, "✗ {}\n  {e:#}", path.display()),
+        };
+    });
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            tracing::error!("watch failed: {e:#}");
+            1
+        }
+    }
+}
+
+/// Remove validator containers left running, e.g. by a crashed build. With
+/// `label`, only containers with that `container_label`. Returns the process exit code.
+fn run_clean(label: Option<&str>) -> i32 {
+    let rt = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(rt) => rt,
+        Err(e) => {
+            tracing::error!("clean failed: Failed to create tokio runtime: {e}");
+            return 1;
+        }
+    };
+
+    match rt.block_on(remove_labeled_containers(label)) {
+        Ok(removed) => {
+            let mut out = io::stdout().lock();
+            for container in &removed {
+                let short_id: String = container.id.chars().take(12).collect();
+                let _ = writeln!(
+                    out,
+                    "removed  {short_id} {} ({})",
+                    container.image, container.label
+                );
+            }
+            if removed.is_empty() {
+                let _ = writeln!(out, "No labeled containers found");
+            }
+            0
+        }
+        Err(e) => {
+            tracing::error!("clean failed: {e:#}");
+            1
+        }
+    }
+}
+
 fn run_preprocessor(
     preprocessor: &ValidatorPreprocessor,
 ) -> Result<(), mdbook_preprocessor::errors::Error> {
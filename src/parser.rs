@@ -1,8 +1,344 @@
 //! Markdown parsing and code block extraction
 
+use std::fmt;
+
+use crate::host_validator::CompareOp;
+
+/// Prefix of a named-section assertion marker, `<!--ASSERT:name`
+pub(crate) const SECTION_ASSERT_PREFIX: &str = "<!--ASSERT:";
+
+/// Prefix of the line that starts a named section, `-- @name`
+const SECTION_PREFIX: &str = "-- @";
+
+/// Which container output a block's assertions and EXPECT are checked against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AssertTarget {
+    /// Standard output only
+    #[default]
+    Stdout,
+    /// Standard output followed by standard error, for tools that split their
+    /// output across both streams
+    Combined,
+}
+
+/// How a block's EXPECT is compared with its output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExpectMode {
+    /// As text with whitespace normalized, `<*>` wildcards allowed
+    #[default]
+    Exact,
+    /// `expect_unordered`: EXPECT rows may come in any order
+    Unordered,
+    /// `expect_json`: compared as JSON, ignoring key order and whitespace, with
+    /// a diff on mismatch
+    Json,
+}
+
+/// Attributes parsed from a fenced code block's info string.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockAttributes {
+    /// Language tag (first word of the info string)
+    pub language: String,
+    /// Validator name from `validator=`
+    pub validator: Option<String>,
+    /// `skip` attribute: show the block without validating it
+    pub skip: bool,
+    /// `hidden` attribute: validate the block but remove it from output
+    pub hidden: bool,
+    /// `optional` attribute: skip the block if its validator's container can't start
+    pub optional: bool,
+    /// Named assertion sets from `use=a,b`
+    pub uses: Vec<String>,
+    /// jq expression from `filter="..."`, applied to the output before assertions
+    pub filter: Option<String>,
+    /// Extra exec command arguments from `args="..."`, split on whitespace
+    pub args: Vec<String>,
+    /// Session name from `session=`; blocks in the same session share a `{db}` path
+    pub session: Option<String>,
+    /// Query timeout in seconds from `timeout=`, overriding the validator's
+    pub timeout: Option<u64>,
+    /// Query retry count from `retry=`, overriding the validator's `retries`
+    pub retry: Option<u32>,
+    /// Why a `timeout=` or `retry=` value was rejected; the block fails with E008
+    pub invalid: Vec<String>,
+    /// JSON keys from `expect_ignore=a,b`, removed before the EXPECT comparison
+    pub expect_ignore: Vec<String>,
+    /// EXPECT comparison, from `expect_unordered` or `expect_json`
+    pub expect_mode: ExpectMode,
+    /// Allowed container warnings from `allow_warning="..."` (repeatable)
+    pub allow_warnings: Vec<String>,
+    /// Name from `capture=`; the block's output is rendered wherever
+    /// `<!--OUTPUT:name-->` appears in the chapter
+    pub capture: Option<String>,
+    /// Output checked by assertions, from `assert_target=combined` (default: stdout)
+    pub assert_target: AssertTarget,
+    /// Container directory from `cwd=`, where the block's exec command runs
+    pub cwd: Option<String>,
+    /// Human-readable description from `desc="..."`, shown in reports and errors
+    pub description: Option<String>,
+    /// Caption from `caption="..."`, rendered as a blockquote above the block
+    pub caption: Option<String>,
+}
+
+/// Parses all attributes from a fenced code block's info string.
+///
+/// # Examples
+///
+/// - `"sql validator=sqlite use=valid_resp"` → validator `sqlite`, uses `["valid_resp"]`
+/// - `"sql validator=sqlite use=a,b skip"` → uses `["a", "b"]`, skip
+/// - `"sql validator=sqlite filter=\".[] | {id}\""` → filter `.[] | {id}`
+/// - `"sql validator=sqlite args=\"/tmp/other.db -bail\""` → args `["/tmp/other.db", "-bail"]`
+/// - `"sql validator=sqlite timeout=10 retry=2"` → timeout `10`, retry `2`
+/// - `"sql validator=sqlite timeout=0 retry=x"` → no timeout or retry, two `invalid` reasons
+/// - `"sql validator=sqlite expect_ignore=id,created_at"` → expect_ignore `["id", "created_at"]`
+/// - `"sql validator=sqlite expect_unordered"` → expect_mode `Unordered`
+/// - `"sql validator=sqlite expect_json"` → expect_mode `Json`
+/// - `"json validator=osquery-config allow_warning=\"unknown flag: foo\""` → allow_warnings `["unknown flag: foo"]`
+/// - `"sql validator=sqlite hidden capture=users"` → capture `users`
+/// - `"python validator=python assert_target=combined"` → assert_target `Combined`
+/// - `"sql validator=sqlite cwd=/fixtures/users"` → cwd `/fixtures/users`
+/// - `"sql validator=sqlite desc=\"Create users table\""` → description `Create users table`
+/// - `"sql validator=sqlite caption=\"Tested against SQLite\""` → caption `Tested against SQLite`
+#[must_use]
+pub fn parse_block_attributes(info: &str) -> BlockAttributes {
+    let tokens = tokenize_info_string(info);
+    let parts: Vec<&str> = tokens.iter().map(String::as_str).collect();
+
+    let language = parts.first().map_or(String::new(), |s| (*s).to_owned());
+
+    let validator = attr_value(&parts, "validator").map(ToOwned::to_owned);
+    let uses = attr_list(&parts, "use");
+    let filter = attr_value(&parts, "filter").map(ToOwned::to_owned);
+    let args = attr_values(&parts, "args")
+        .flat_map(str::split_whitespace)
+        .map(ToOwned::to_owned)
+        .collect();
+    let session = attr_value(&parts, "session").map(ToOwned::to_owned);
+    let mut invalid = Vec::new();
+    let timeout = attr_value(&parts, "timeout").and_then(|secs| match secs.parse() {
+        Ok(0) => {
+            invalid.push("timeout must be at least 1 second".to_owned());
+            None
+        }
+        Ok(secs) => Some(secs),
+        Err(_) => {
+            invalid.push(format!(
+                "timeout must be a whole number of seconds, got '{secs}'"
+            ));
+            None
+        }
+    });
+    let retry = attr_value(&parts, "retry").and_then(|n| {
+        n.parse()
+            .map_err(|_| invalid.push(format!("retry must be a whole number, got '{n}'")))
+            .ok()
+    });
+    let expect_ignore = attr_list(&parts, "expect_ignore");
+    let allow_warnings = attr_values(&parts, "allow_warning")
+        .filter(|pattern| !pattern.is_empty())
+        .map(ToOwned::to_owned)
+        .collect();
+    let capture = attr_value(&parts, "capture").map(ToOwned::to_owned);
+    let assert_target = match attr_value(&parts, "assert_target") {
+        Some("combined") => AssertTarget::Combined,
+        _ => AssertTarget::Stdout,
+    };
+    let cwd = attr_value(&parts, "cwd").map(ToOwned::to_owned);
+    let description = attr_value(&parts, "desc").map(|desc| desc.trim().to_owned());
+    let caption = attr_value(&parts, "caption").map(|caption| caption.trim().to_owned());
+
+    BlockAttributes {
+        language,
+        validator,
+        skip: parts.contains(&"skip"),
+        hidden: parts.contains(&"hidden"),
+        optional: parts.contains(&"optional"),
+        uses,
+        filter,
+        args,
+        session,
+        timeout,
+        retry,
+        invalid,
+        expect_ignore,
+        // `expect_unordered` wins if both are given
+        expect_mode: if parts.contains(&"expect_unordered") {
+            ExpectMode::Unordered
+        } else if parts.contains(&"expect_json") {
+            ExpectMode::Json
+        } else {
+            ExpectMode::Exact
+        },
+        allow_warnings,
+        capture,
+        assert_target,
+        cwd,
+        description,
+        caption,
+    }
+}
+
+/// Values of every `key=value` token, in order (for repeatable attributes)
+fn attr_values<'p, 'a>(parts: &'p [&'a str], key: &str) -> impl Iterator<Item = &'a str> + 'p {
+    let prefix = format!("{key}=");
+    parts
+        .iter()
+        .filter_map(move |part| part.strip_prefix(prefix.as_str()))
+}
+
+/// Value of the first `key=value` token, or `None` if it's missing or blank
+fn attr_value<'a>(parts: &[&'a str], key: &str) -> Option<&'a str> {
+    attr_values(parts, key)
+        .next()
+        .filter(|value| !value.trim().is_empty())
+}
+
+/// Items of every `key=a,b` token, trimmed, empty items dropped
+fn attr_list(parts: &[&str], key: &str) -> Vec<String> {
+    attr_values(parts, key)
+        .flat_map(|items| items.split(','))
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(ToOwned::to_owned)
+        .collect()
+}
+
+/// Splits an info string on whitespace, keeping double-quoted spans together.
+///
+/// Quotes are removed and `\"` inside quotes is an escaped quote, so
+/// `filter=".[] | {id}"` is the single token `filter=.[] | {id}`.
+fn tokenize_info_string(info: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut in_quotes = false;
+    let mut chars = info.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                in_token = true;
+            }
+            '\\' if in_quotes => {
+                if let Some(next) = chars.next() {
+                    if next != '"' {
+                        current.push('\\');
+                    }
+                    current.push(next);
+                }
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if in_token {
+                    tokens.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            c => {
+                current.push(c);
+                in_token = true;
+            }
+        }
+    }
+    if in_token {
+        tokens.push(current);
+    }
+
+    tokens
+}
+
+/// `key=value` info string attributes read only by mdbook-validator
+const INTERNAL_ATTRIBUTES: [&str; 14] = [
+    "validator",
+    "use",
+    "filter",
+    "args",
+    "session",
+    "timeout",
+    "retry",
+    "expect_ignore",
+    "allow_warning",
+    "capture",
+    "assert_target",
+    "cwd",
+    "desc",
+    "caption",
+];
+
+/// Bare info string flags read only by mdbook-validator
+const INTERNAL_FLAGS: [&str; 5] = [
+    "skip",
+    "hidden",
+    "optional",
+    "expect_unordered",
+    "expect_json",
+];
+
+/// Removes mdbook-validator's own attributes from an info string.
+///
+/// The language and every other attribute, such as mdBook's `editable`,
+/// `noplayground` or `ignore`, are kept as written, so later preprocessors and
+/// the renderer still see them.
+///
+/// # Examples
+///
+/// - `"rust editable validator=rust-exec"` → `"rust editable"`
+/// - `"sql validator=sqlite desc=\"Create users\" noplayground"` → `"sql noplayground"`
+#[must_use]
+pub fn strip_internal_attributes(info: &str) -> String {
+    raw_info_tokens(info)
+        .into_iter()
+        .enumerate()
+        .filter(|(i, token)| match token.split_once('=') {
+            Some((key, _)) => !INTERNAL_ATTRIBUTES.contains(&key),
+            // The first token is the language, whatever it's called
+            None => *i == 0 || !INTERNAL_FLAGS.contains(token),
+        })
+        .map(|(_, token)| token)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Splits an info string into tokens like [`tokenize_info_string`], but returns
+/// each token exactly as written, quotes and escapes included.
+fn raw_info_tokens(info: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    let mut in_quotes = false;
+    let mut escaped = false;
+
+    for (i, c) in info.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' if in_quotes => escaped = true,
+            '"' => {
+                in_quotes = !in_quotes;
+                start.get_or_insert(i);
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if let Some(start) = start.take() {
+                    tokens.push(&info[start..i]);
+                }
+            }
+            _ => {
+                start.get_or_insert(i);
+            }
+        }
+    }
+    if let Some(start) = start {
+        tokens.push(&info[start..]);
+    }
+
+    tokens
+}
+
 /// Parses an info string from a fenced code block.
 ///
-/// Returns `(language, validator, skip, hidden)` tuple.
+/// Returns `(language, validator, skip, hidden)` tuple. See
+/// [`parse_block_attributes`] for the full attribute set.
 ///
 /// # Examples
 ///
@@ -12,19 +348,8 @@
 /// - `"sql validator=sqlite hidden"` → `("sql", Some("sqlite"), false, true)`
 #[must_use]
 pub fn parse_info_string(info: &str) -> (String, Option<String>, bool, bool) {
-    let parts: Vec<&str> = info.split_whitespace().collect();
-
-    let language = parts.first().map_or(String::new(), |s| (*s).to_owned());
-
-    let validator = parts
-        .iter()
-        .find_map(|part| part.strip_prefix("validator=").map(ToOwned::to_owned))
-        .filter(|v| !v.is_empty());
-
-    let skip = parts.contains(&"skip");
-    let hidden = parts.contains(&"hidden");
-
-    (language, validator, skip, hidden)
+    let attrs = parse_block_attributes(info);
+    (attrs.language, attrs.validator, attrs.skip, attrs.hidden)
 }
 
 /// Result of extracting markers from code block content.
@@ -32,10 +357,24 @@ pub fn parse_info_string(info: &str) -> (String, Option<String>, bool, bool) {
 pub struct ExtractedMarkers {
     /// Setup content from `<!--SETUP-->` marker
     pub setup: Option<String>,
+    /// Teardown script from `<!--CLEANUP-->` marker, run after validation
+    pub cleanup: Option<String>,
     /// Assertions from `<!--ASSERT-->` marker
     pub assertions: Option<String>,
-    /// Expected output from `<!--EXPECT-->` marker
-    pub expect: Option<String>,
+    /// `(name, assertions)` from each `<!--ASSERT:name-->` marker, in order
+    pub section_assertions: Vec<(String, String)>,
+    /// Path of an assertions file from `<!--ASSERT_FILE-->` marker
+    pub assert_file: Option<String>,
+    /// TOML structured assertions from `<!--ASSERT_TOML-->` marker, parsed on the host
+    pub assert_toml: Option<String>,
+    /// Expected output from each `<!--EXPECT-->` marker, in order. Several are
+    /// matched positionally against the output's result sets.
+    pub expect: Vec<String>,
+    /// Regex the full output must match, from `<!--EXPECT_REGEX-->` marker
+    pub expect_regex: Option<String>,
+    /// Exit code the query must exit with, from `<!--EXPECT-EXIT N-->` marker
+    /// (unparsed; validation rejects a non-integer)
+    pub expect_exit: Option<String>,
     /// The visible content (with all markers removed)
     pub visible_content: String,
 }
@@ -53,8 +392,10 @@ impl ExtractedMarkers {
 
 /// Extracts markers from code block content.
 ///
-/// Parses `<!--SETUP-->`, `<!--ASSERT-->`, and `<!--EXPECT-->` blocks,
-/// returning their content and the remaining visible content.
+/// Parses `<!--SETUP-->`, `<!--CLEANUP-->`, `<!--ASSERT-->`, `<!--ASSERT:name-->`, `<!--ASSERT_FILE-->`,
+/// `<!--ASSERT_TOML-->`, `<!--EXPECT-->`, and `<!--EXPECT_REGEX-->` blocks, and the
+/// single-line `<!--EXPECT-EXIT N-->`, returning their content and the remaining
+/// visible content.
 #[must_use]
 pub fn extract_markers(content: &str) -> ExtractedMarkers {
     let mut result = ExtractedMarkers::default();
@@ -66,15 +407,58 @@ pub fn extract_markers(content: &str) -> ExtractedMarkers {
         remaining = format!("{before}{after}");
     }
 
+    // Extract CLEANUP block
+    if let Some((before, inner, after)) = extract_marker_block(&remaining, "<!--CLEANUP") {
+        result.cleanup = Some(inner);
+        remaining = format!("{before}{after}");
+    }
+
     // Extract ASSERT block
     if let Some((before, inner, after)) = extract_marker_block(&remaining, "<!--ASSERT") {
         result.assertions = Some(inner);
         remaining = format!("{before}{after}");
     }
 
-    // Extract EXPECT block
-    if let Some((before, inner, after)) = extract_marker_block(&remaining, "<!--EXPECT") {
-        result.expect = Some(inner);
+    // Extract ASSERT:name blocks (any number)
+    while let Some(start) = remaining.find(SECTION_ASSERT_PREFIX) {
+        let marker_line = remaining[start..].lines().next().unwrap_or_default();
+        let name = marker_line[SECTION_ASSERT_PREFIX.len()..].trim();
+        let Some((before, inner, after)) =
+            extract_marker_block(&remaining, &format!("{SECTION_ASSERT_PREFIX}{name}"))
+        else {
+            break;
+        };
+        result.section_assertions.push((name.to_owned(), inner));
+        remaining = format!("{before}{after}");
+    }
+
+    // Extract ASSERT_FILE block
+    if let Some((before, inner, after)) = extract_marker_block(&remaining, "<!--ASSERT_FILE") {
+        result.assert_file = Some(inner).filter(|path| !path.is_empty());
+        remaining = format!("{before}{after}");
+    }
+
+    // Extract ASSERT_TOML block
+    if let Some((before, inner, after)) = extract_marker_block(&remaining, "<!--ASSERT_TOML") {
+        result.assert_toml = Some(inner);
+        remaining = format!("{before}{after}");
+    }
+
+    // Extract EXPECT-EXIT (one line: `<!--EXPECT-EXIT N-->`)
+    if let Some((before, inner, after)) = extract_inline_marker(&remaining, "<!--EXPECT-EXIT") {
+        result.expect_exit = Some(inner);
+        remaining = format!("{before}{after}");
+    }
+
+    // Extract EXPECT blocks (any number, one per result set)
+    while let Some((before, inner, after)) = extract_marker_block(&remaining, "<!--EXPECT") {
+        result.expect.push(inner);
+        remaining = format!("{before}{after}");
+    }
+
+    // Extract EXPECT_REGEX block
+    if let Some((before, inner, after)) = extract_marker_block(&remaining, "<!--EXPECT_REGEX") {
+        result.expect_regex = Some(inner);
         remaining = format!("{before}{after}");
     }
 
@@ -84,6 +468,713 @@ pub fn extract_markers(content: &str) -> ExtractedMarkers {
     result
 }
 
+/// A named section of a block's code, started by a `-- @name` line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// Name from the `-- @name` line
+    pub name: String,
+    /// Code up to the next section line (the `-- @name` line itself excluded)
+    pub content: String,
+}
+
+/// Splits code into the lines before the first `-- @name` line and the named sections.
+///
+/// A section line must be exactly `-- @name` (surrounding whitespace allowed), with a
+/// name containing no whitespace.
+///
+/// # Examples
+///
+/// - `"CREATE TABLE t(x);\n-- @q1\nSELECT 1;"` → `("CREATE TABLE t(x);", [q1: "SELECT 1;"])`
+#[must_use]
+pub fn split_sections(content: &str) -> (String, Vec<Section>) {
+    let mut preamble = Vec::new();
+    let mut sections: Vec<(String, Vec<&str>)> = Vec::new();
+
+    for line in content.lines() {
+        let name = line
+            .trim()
+            .strip_prefix(SECTION_PREFIX)
+            .filter(|name| !name.is_empty() && !name.contains(char::is_whitespace));
+        match (name, sections.last_mut()) {
+            (Some(name), _) => sections.push((name.to_owned(), Vec::new())),
+            (None, Some((_, lines))) => lines.push(line),
+            (None, None) => preamble.push(line),
+        }
+    }
+
+    let sections = sections
+        .into_iter()
+        .map(|(name, lines)| Section {
+            name,
+            content: lines.join("\n"),
+        })
+        .collect();
+    (preamble.join("\n"), sections)
+}
+
+/// Splits YAML front matter off the start of a chapter.
+///
+/// Front matter starts with a `---` line on the chapter's first line and ends at
+/// the next `---` line. Returns `(front_matter, rest)`, or `None` if there is none.
+///
+/// # Examples
+///
+/// - `"---\ntitle: x\n---\n# Intro"` → `("title: x\n", "# Intro")`
+/// - `"# Intro\n---\n"` → `None`
+#[must_use]
+pub fn split_front_matter(content: &str) -> Option<(&str, &str)> {
+    let body = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))?;
+    let mut offset = 0;
+    for line in body.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            let (front_matter, rest) = body.split_at(offset);
+            return Some((front_matter, &rest[line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// Parses the `validator:` map out of a chapter's front matter.
+///
+/// Only that entry is read, so the rest of the front matter may use any YAML.
+/// It supports the subset validator options need: nested block mappings,
+/// `- item` and `[a, b]` lists, `{}`/`{ k: v }` maps, and plain, `'single'` or
+/// `"double"` quoted scalars, with `true`/`false` and numbers typed as in YAML.
+/// Returns `Ok(None)` if there is no top-level `validator` key.
+///
+/// # Errors
+///
+/// Returns a message naming the line if `validator:` is outside that subset.
+///
+/// # Examples
+///
+/// - `"validator:\n  sqlite:\n    timeout: 5\n"` → `{ sqlite = { timeout = 5 } }`
+/// - `"title: Intro\n"` → `None`
+pub fn front_matter_validators(front_matter: &str) -> Result<Option<toml::value::Table>, String> {
+    let mut lines = front_matter.lines().enumerate();
+    let Some((number, line)) = lines.find(|(_, line)| {
+        line.strip_prefix("validator")
+            .is_some_and(|rest| rest.trim_start().starts_with(':'))
+    }) else {
+        return Ok(None);
+    };
+    let (_, value) = split_yaml_entry(line)
+        .ok_or_else(|| format!("line {}: expected `validator:`", number + 1))?;
+    if !value.is_empty() {
+        return match parse_yaml_flow(value).map_err(|e| format!("line {}: {e}", number + 1))? {
+            toml::Value::Table(table) => Ok(Some(table)),
+            _ => Err(format!("line {}: `validator` must be a map", number + 1)),
+        };
+    }
+
+    // The map is every following line indented under `validator:`
+    let mut block = Vec::new();
+    for (number, line) in lines {
+        let content = strip_yaml_comment(line).trim_end();
+        if content.trim_start().is_empty() {
+            continue;
+        }
+        let indent = content.len() - content.trim_start_matches(' ').len();
+        if content.starts_with('\t') {
+            return Err(format!(
+                "line {}: tabs are not allowed for indentation",
+                number + 1
+            ));
+        }
+        if indent == 0 {
+            break;
+        }
+        block.push(YamlLine {
+            number: number + 1,
+            indent,
+            text: content.trim_start(),
+        });
+    }
+    let Some(first) = block.first() else {
+        return Ok(Some(toml::value::Table::new()));
+    };
+    let mut pos = 0;
+    match parse_yaml_block(&block, &mut pos, first.indent)? {
+        toml::Value::Table(table) => Ok(Some(table)),
+        _ => Err(format!("line {}: `validator` must be a map", first.number)),
+    }
+}
+
+/// A non-blank front matter line, comment removed
+struct YamlLine<'a> {
+    /// 1-based line number in the front matter
+    number: usize,
+    indent: usize,
+    text: &'a str,
+}
+
+/// Parse the mapping or `- item` list starting at `lines[*pos]`, whose lines are
+/// indented by `indent`.
+fn parse_yaml_block(
+    lines: &[YamlLine<'_>],
+    pos: &mut usize,
+    indent: usize,
+) -> Result<toml::Value, String> {
+    let is_list = lines
+        .get(*pos)
+        .is_some_and(|line| line.text == "-" || line.text.starts_with("- "));
+    let mut table = toml::value::Table::new();
+    let mut list = Vec::new();
+    while let Some(line) = lines.get(*pos) {
+        if line.indent < indent {
+            break;
+        }
+        if line.indent > indent {
+            return Err(format!("line {}: unexpected indentation", line.number));
+        }
+        *pos += 1;
+        let location = |e: String| format!("line {}: {e}", line.number);
+
+        if is_list {
+            let item = line
+                .text
+                .strip_prefix('-')
+                .filter(|item| item.is_empty() || item.starts_with(' '))
+                .ok_or_else(|| location("expected `- item`".to_owned()))?;
+            list.push(parse_yaml_flow(item.trim()).map_err(location)?);
+            continue;
+        }
+
+        let (key, value) = split_yaml_entry(line.text)
+            .ok_or_else(|| location("expected `key: value`".to_owned()))?;
+        let value = if !value.is_empty() {
+            parse_yaml_flow(value).map_err(location)?
+        } else if let Some(child) = lines.get(*pos).filter(|next| next.indent > indent) {
+            parse_yaml_block(lines, pos, child.indent)?
+        } else {
+            return Err(location(format!("`{key}` has no value")));
+        };
+        if table.insert(key.clone(), value).is_some() {
+            return Err(location(format!("duplicate key `{key}`")));
+        }
+    }
+    Ok(if is_list {
+        toml::Value::Array(list)
+    } else {
+        toml::Value::Table(table)
+    })
+}
+
+/// Split `key: value` (or `key:`) into the unquoted key and the trimmed value
+fn split_yaml_entry(text: &str) -> Option<(String, &str)> {
+    let (key, value) = match text.find(": ") {
+        Some(colon) => (&text[..colon], &text[colon + 2..]),
+        None => (text.strip_suffix(':')?, ""),
+    };
+    let Ok(toml::Value::String(key)) = parse_yaml_flow(key.trim()) else {
+        return None;
+    };
+    Some((key, value.trim()))
+}
+
+/// Parse an inline value: `[a, b]`, `{ k: v }`, a quoted string or a plain scalar
+fn parse_yaml_flow(text: &str) -> Result<toml::Value, String> {
+    let text = strip_yaml_comment(text).trim();
+    if let Some(inner) = text.strip_prefix('[') {
+        let inner = inner
+            .strip_suffix(']')
+            .ok_or_else(|| format!("unclosed list `{text}`"))?;
+        return split_yaml_flow(inner)?
+            .into_iter()
+            .map(parse_yaml_flow)
+            .collect::<Result<_, _>>()
+            .map(toml::Value::Array);
+    }
+    if let Some(inner) = text.strip_prefix('{') {
+        let inner = inner
+            .strip_suffix('}')
+            .ok_or_else(|| format!("unclosed map `{text}`"))?;
+        let mut table = toml::value::Table::new();
+        for entry in split_yaml_flow(inner)? {
+            let (key, value) = split_yaml_entry(entry)
+                .filter(|(_, value)| !value.is_empty())
+                .ok_or_else(|| format!("expected `key: value` in `{text}`"))?;
+            table.insert(key, parse_yaml_flow(value)?);
+        }
+        return Ok(toml::Value::Table(table));
+    }
+    if let Some(inner) = text.strip_prefix('"') {
+        let inner = inner
+            .strip_suffix('"')
+            .ok_or_else(|| format!("unclosed string `{text}`"))?;
+        return unescape_yaml_double(inner).map(toml::Value::String);
+    }
+    if let Some(inner) = text.strip_prefix('\'') {
+        let inner = inner
+            .strip_suffix('\'')
+            .ok_or_else(|| format!("unclosed string `{text}`"))?;
+        return Ok(toml::Value::String(inner.replace("''", "'")));
+    }
+    Ok(match text {
+        "" | "~" | "null" => return Err("null values are not supported".to_owned()),
+        "true" => toml::Value::Boolean(true),
+        "false" => toml::Value::Boolean(false),
+        _ => text
+            .parse()
+            .map(toml::Value::Integer)
+            .or_else(|_| text.parse().map(toml::Value::Float))
+            .unwrap_or_else(|_| toml::Value::String(text.to_owned())),
+    })
+}
+
+/// Split the inside of a flow list or map at top-level commas
+fn split_yaml_flow(inner: &str) -> Result<Vec<&str>, String> {
+    let mut items = Vec::new();
+    let mut depth = 0_usize;
+    let mut quote = None;
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in inner.char_indices() {
+        match (quote, c) {
+            (Some('"'), '\\') => escaped = !escaped,
+            (Some(q), c) if c == q && !escaped => quote = None,
+            (Some(_), _) => escaped = false,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[' | '{') => depth += 1,
+            (None, ']' | '}') => {
+                depth = depth
+                    .checked_sub(1)
+                    .ok_or_else(|| format!("unbalanced `{inner}`"))?;
+            }
+            (None, ',') if depth == 0 => {
+                items.push(inner[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if quote.is_some() || depth != 0 {
+        return Err(format!("unbalanced `{inner}`"));
+    }
+    items.push(inner[start..].trim());
+    // `[]`, `{}` and a trailing comma leave an empty last item
+    if items.last().is_some_and(|item| item.is_empty()) {
+        items.pop();
+    }
+    Ok(items)
+}
+
+/// Resolve the escapes of a `"double quoted"` YAML string
+fn unescape_yaml_double(inner: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        out.push(match chars.next() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('0') => '\0',
+            Some(c @ ('"' | '\\' | '/')) => c,
+            other => {
+                return Err(format!(
+                    "unsupported escape `\\{}`",
+                    other.map(String::from).unwrap_or_default()
+                ))
+            }
+        });
+    }
+    Ok(out)
+}
+
+/// Remove a ` # comment` (or a whole-line `# comment`) that isn't inside quotes
+fn strip_yaml_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut prev = ' ';
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            (None, '#') if prev.is_whitespace() => return &line[..i],
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q && prev != '\\' => quote = None,
+            _ => {}
+        }
+        prev = c;
+    }
+    line
+}
+
+/// One assertion from an `<!--ASSERT-->` block.
+///
+/// The validator scripts still receive the block's text; this is the same
+/// syntax in structured form, for checking assertions before they run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Assertion {
+    /// `rows = N`, `rows >= N`, `rows > N`, `rows <= N` or `rows < N`
+    Rows {
+        /// Comparison operator
+        op: CompareOp,
+        /// Expected row count
+        n: usize,
+    },
+    /// `rows = N ± M` or `rows = N tolerance M`
+    RowsWithin {
+        /// Expected row count
+        n: usize,
+        /// How far the actual count may be from `n`
+        tolerance: usize,
+    },
+    /// `columns = N`
+    Columns(usize),
+    /// `contains "str"`
+    Contains(String),
+    /// `contains_count "str" <op> N`
+    ContainsCount {
+        /// String to count occurrences of
+        needle: String,
+        /// Comparison operator
+        op: CompareOp,
+        /// Expected occurrence count
+        n: usize,
+    },
+    /// `distinct "col" <op> N`
+    Distinct {
+        /// Column whose distinct values are counted
+        column: String,
+        /// Comparison operator
+        op: CompareOp,
+        /// Expected distinct value count
+        n: usize,
+    },
+    /// `sorted_by "col" asc|desc`
+    SortedBy {
+        /// Column the rows are ordered by
+        column: String,
+        /// `desc` rather than `asc`
+        descending: bool,
+    },
+    /// `stderr_empty`
+    StderrEmpty,
+    /// `json_length <op> N`, evaluated on the host
+    JsonLength {
+        /// Comparison operator
+        op: CompareOp,
+        /// Expected array or object length
+        n: usize,
+    },
+    /// `contains_json '{...}'`, evaluated on the host
+    ContainsJson(String),
+    /// `exit_code = N`
+    ExitCode(i64),
+    /// `stdout_contains "str"`
+    StdoutContains(String),
+    /// `stderr_contains "str"`
+    StderrContains(String),
+    /// `max_memory "256m"`, checked against the validator's `memory_limit`
+    MaxMemory(String),
+    /// `valid_json`, evaluated on the host
+    ValidJson,
+    /// `table_columns <op> N`, evaluated on the host against a text table
+    TableColumns {
+        /// Comparison operator
+        op: CompareOp,
+        /// Expected field count of the first data row
+        n: usize,
+    },
+    /// `file_exists /path`
+    FileExists(String),
+    /// `dir_exists /path`
+    DirExists(String),
+    /// `file_contains /path "str"`
+    FileContains {
+        /// Path of the file in the container
+        path: String,
+        /// String the file must contain
+        needle: String,
+    },
+    /// `!<assertion>`: the inner assertion must not hold
+    Not(Box<Assertion>),
+}
+
+/// Renders the assertion in the syntax the validator scripts accept
+impl fmt::Display for Assertion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rows { op, n } => write!(f, "rows {} {n}", op.symbol()),
+            Self::RowsWithin { n, tolerance } => write!(f, "rows = {n} ± {tolerance}"),
+            Self::Columns(n) => write!(f, "columns = {n}"),
+            Self::Contains(needle) => write!(f, "contains \"{needle}\""),
+            Self::ContainsCount { needle, op, n } => {
+                write!(f, "contains_count \"{needle}\" {} {n}", op.symbol())
+            }
+            Self::Distinct { column, op, n } => {
+                write!(f, "distinct \"{column}\" {} {n}", op.symbol())
+            }
+            Self::SortedBy { column, descending } => {
+                let direction = if *descending { "desc" } else { "asc" };
+                write!(f, "sorted_by \"{column}\" {direction}")
+            }
+            Self::StderrEmpty => f.write_str("stderr_empty"),
+            Self::JsonLength { op, n } => write!(f, "json_length {} {n}", op.symbol()),
+            Self::ContainsJson(fragment) => write!(f, "contains_json '{fragment}'"),
+            Self::ExitCode(code) => write!(f, "exit_code = {code}"),
+            Self::StdoutContains(needle) => write!(f, "stdout_contains \"{needle}\""),
+            Self::StderrContains(needle) => write!(f, "stderr_contains \"{needle}\""),
+            Self::MaxMemory(size) => write!(f, "max_memory \"{size}\""),
+            Self::ValidJson => f.write_str("valid_json"),
+            Self::TableColumns { op, n } => write!(f, "table_columns {} {n}", op.symbol()),
+            Self::FileExists(path) => write!(f, "file_exists {path}"),
+            Self::DirExists(path) => write!(f, "dir_exists {path}"),
+            Self::FileContains { path, needle } => write!(f, "file_contains {path} \"{needle}\""),
+            Self::Not(inner) => write!(f, "!{inner}"),
+        }
+    }
+}
+
+/// Parses the body of an `<!--ASSERT-->` block, one assertion per non-blank line.
+///
+/// Accepts the syntax the validator scripts accept; whether a given validator
+/// supports an assertion is still up to its script.
+///
+/// # Errors
+///
+/// Returns a message naming the 1-based line of the first malformed or unknown
+/// assertion.
+///
+/// # Examples
+///
+/// - `"rows >= 1\n!contains \"error\""` →
+///   `[Rows { op: Ge, n: 1 }, Not(Contains("error"))]`
+pub fn parse_assertions(text: &str) -> Result<Vec<Assertion>, String> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .map(|(number, line)| {
+            parse_assertion(line).map_err(|reason| format!("line {number}: `{line}`: {reason}"))
+        })
+        .collect()
+}
+
+fn parse_assertion(line: &str) -> Result<Assertion, String> {
+    if let Some(inner) = line.strip_prefix('!') {
+        return Ok(Assertion::Not(Box::new(parse_assertion(
+            inner.trim_start(),
+        )?)));
+    }
+    let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+    let rest = rest.trim();
+    match keyword {
+        "rows" => parse_rows(rest),
+        "columns" => Ok(Assertion::Columns(parse_count(
+            rest.strip_prefix("= ").ok_or("expected `columns = N`")?,
+        )?)),
+        "contains" => Ok(Assertion::Contains(unquote(rest).to_owned())),
+        "contains_count" => {
+            let (needle, op, n) = parse_comparison(rest, "contains_count \"str\" <op> N")?;
+            Ok(Assertion::ContainsCount { needle, op, n })
+        }
+        "distinct" => {
+            let (column, op, n) = parse_comparison(rest, "distinct \"col\" <op> N")?;
+            Ok(Assertion::Distinct { column, op, n })
+        }
+        "sorted_by" => {
+            let (column, direction) = rest
+                .rsplit_once(' ')
+                .ok_or("expected `sorted_by \"col\" asc|desc`")?;
+            let descending = match direction {
+                "asc" => false,
+                "desc" => true,
+                _ => return Err(format!("unknown direction '{direction}'")),
+            };
+            Ok(Assertion::SortedBy {
+                column: unquote(column.trim()).to_owned(),
+                descending,
+            })
+        }
+        "stderr_empty" if rest.is_empty() => Ok(Assertion::StderrEmpty),
+        "json_length" => {
+            let (op, n) = rest
+                .split_once(' ')
+                .ok_or("expected `json_length <op> N`")?;
+            Ok(Assertion::JsonLength {
+                op: parse_op(op)?,
+                n: parse_count(n.trim())?,
+            })
+        }
+        "contains_json" => {
+            let fragment = rest
+                .strip_prefix('\'')
+                .and_then(|fragment| fragment.strip_suffix('\''))
+                .unwrap_or(rest);
+            Ok(Assertion::ContainsJson(fragment.to_owned()))
+        }
+        "exit_code" => {
+            let code = rest.strip_prefix("= ").ok_or("expected `exit_code = N`")?;
+            code.trim()
+                .parse()
+                .map(Assertion::ExitCode)
+                .map_err(|_| format!("invalid integer '{code}'"))
+        }
+        "stdout_contains" => Ok(Assertion::StdoutContains(unquote(rest).to_owned())),
+        "stderr_contains" => Ok(Assertion::StderrContains(unquote(rest).to_owned())),
+        "max_memory" => {
+            let size = unquote(rest);
+            parse_memory_size(size)?;
+            Ok(Assertion::MaxMemory(size.to_owned()))
+        }
+        "valid_json" if rest.is_empty() => Ok(Assertion::ValidJson),
+        "table_columns" => {
+            let (op, n) = rest
+                .split_once(' ')
+                .ok_or("expected `table_columns <op> N`")?;
+            Ok(Assertion::TableColumns {
+                op: parse_op(op)?,
+                n: parse_count(n.trim())?,
+            })
+        }
+        "file_exists" if !rest.is_empty() => Ok(Assertion::FileExists(rest.to_owned())),
+        "dir_exists" if !rest.is_empty() => Ok(Assertion::DirExists(rest.to_owned())),
+        "file_contains" => {
+            let (path, needle) = rest
+                .split_once('"')
+                .ok_or("expected `file_contains /path \"str\"`")?;
+            Ok(Assertion::FileContains {
+                path: path.trim().to_owned(),
+                needle: needle.strip_suffix('"').unwrap_or(needle).to_owned(),
+            })
+        }
+        _ => Err("unknown assertion".to_owned()),
+    }
+}
+
+/// `rows` assertion after the keyword: `= N`, `>= N`, `> N`, `= N ± M` or
+/// `= N tolerance M`
+fn parse_rows(rest: &str) -> Result<Assertion, String> {
+    let (op, spec) = rest.split_once(' ').ok_or("expected `rows <op> N`")?;
+    let op = parse_op(op)?;
+    if op == CompareOp::Ne {
+        return Err(format!("unsupported operator '{}'", op.symbol()));
+    }
+    let spec = spec.trim();
+    let tolerance = spec
+        .split_once(" ± ")
+        .or_else(|| spec.split_once(" tolerance "));
+    match tolerance {
+        Some((n, tolerance)) if op == CompareOp::Eq => Ok(Assertion::RowsWithin {
+            n: parse_count(n.trim())?,
+            tolerance: parse_count(tolerance.trim())?,
+        }),
+        Some(_) => Err("a tolerance needs `=`".to_owned()),
+        None => Ok(Assertion::Rows {
+            op,
+            n: parse_count(spec)?,
+        }),
+    }
+}
+
+/// `"subject" <op> N`, with the quotes around the subject optional
+fn parse_comparison(rest: &str, usage: &str) -> Result<(String, CompareOp, usize), String> {
+    let mut parts = rest.rsplitn(3, ' ');
+    let (Some(n), Some(op), Some(subject)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(format!("expected `{usage}`"));
+    };
+    Ok((
+        unquote(subject.trim()).to_owned(),
+        parse_op(op)?,
+        parse_count(n)?,
+    ))
+}
+
+fn parse_op(op: &str) -> Result<CompareOp, String> {
+    CompareOp::from_symbol(op).ok_or_else(|| format!("unknown operator '{op}'"))
+}
+
+fn parse_count(n: &str) -> Result<usize, String> {
+    n.parse().map_err(|_| format!("invalid count '{n}'"))
+}
+
+/// Removes a leading and a trailing `"`, as the validator scripts do
+fn unquote(s: &str) -> &str {
+    let s = s.strip_prefix('"').unwrap_or(s);
+    s.strip_suffix('"').unwrap_or(s)
+}
+
+/// Parses a memory size as Docker writes it: a number of bytes with an optional
+/// `b`, `k`, `m` or `g` suffix (binary multiples, case-insensitive).
+///
+/// # Errors
+///
+/// Returns a message when the size is malformed, zero, or overflows.
+///
+/// # Examples
+///
+/// - `"256m"` → `268435456`
+/// - `"1G"` → `1073741824`
+pub fn parse_memory_size(size: &str) -> Result<u64, String> {
+    let size = size.trim();
+    let (digits, multiplier) = match size.chars().last() {
+        Some(unit) if unit.is_ascii_alphabetic() => {
+            let multiplier: u64 = match unit.to_ascii_lowercase() {
+                'b' => 1,
+                'k' => 1 << 10,
+                'm' => 1 << 20,
+                'g' => 1 << 30,
+                _ => return Err(format!("unknown memory unit '{unit}' in '{size}'")),
+            };
+            (size.strip_suffix(unit).unwrap_or(size), multiplier)
+        }
+        _ => (size, 1),
+    };
+    digits
+        .parse::<u64>()
+        .ok()
+        .filter(|&n| n > 0)
+        .and_then(|n| n.checked_mul(multiplier))
+        .ok_or_else(|| format!("invalid memory size '{size}' (expected e.g. \"256m\")"))
+}
+
+/// Replaces each `${NAME}` in `text` with `lookup(NAME)`.
+///
+/// `NAME` must be letters, digits and underscores, not starting with a digit; any
+/// other `${` is left as is. Returns the first name `lookup` has no value for.
+///
+/// # Errors
+///
+/// Returns the name of the first undefined variable.
+///
+/// # Examples
+///
+/// - `"contains \"${VERSION}\""` with `VERSION=1.2` → `"contains \"1.2\""`
+pub fn interpolate_env(
+    text: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<String, String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        let (before, after) = rest.split_at(start);
+        out.push_str(before);
+        let after = after.strip_prefix("${").unwrap_or(after);
+        if let Some((name, tail)) = after.split_once('}').filter(|(name, _)| is_env_name(name)) {
+            out.push_str(&lookup(name).ok_or_else(|| name.to_owned())?);
+            rest = tail;
+        } else {
+            out.push_str("${");
+            rest = after;
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn is_env_name(name: &str) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
 /// Strips the `@@` prefix from lines while keeping the content.
 ///
 /// This is used for validation content - `@@` lines should be validated
@@ -102,11 +1193,22 @@ pub fn strip_double_at_prefix(content: &str) -> String {
         .join("\n")
 }
 
+/// Finds the byte offset of a marker, requiring it to end at a word boundary.
+///
+/// `<!--EXPECT` must not match the start of `<!--EXPECT_REGEX`, so a match only
+/// counts when followed by whitespace, `-->`, or the end of the content.
+pub(crate) fn find_marker(content: &str, marker: &str) -> Option<usize> {
+    content.match_indices(marker).map(|(i, _)| i).find(|&i| {
+        let rest = &content[i + marker.len()..];
+        rest.is_empty() || rest.starts_with("-->") || rest.starts_with(char::is_whitespace)
+    })
+}
+
 /// Extracts content between a marker and `-->`.
 ///
 /// Returns `(before, inner_content, after)` if found.
 fn extract_marker_block(content: &str, marker: &str) -> Option<(String, String, String)> {
-    let start = content.find(marker)?;
+    let start = find_marker(content, marker)?;
     let marker_end = content[start..].find('\n').map(|i| start + i + 1)?;
     let end_marker = content[marker_end..].find("-->")?;
     let end = marker_end + end_marker;
@@ -118,6 +1220,23 @@ fn extract_marker_block(content: &str, marker: &str) -> Option<(String, String,
     Some((before.to_owned(), inner.to_owned(), after.to_owned()))
 }
 
+/// Extracts the value between a marker and `-->` on the marker's own line,
+/// as in `<!--EXPECT-EXIT 1-->`.
+///
+/// Returns `(before, value, after)` if found.
+fn extract_inline_marker(content: &str, marker: &str) -> Option<(String, String, String)> {
+    let start = find_marker(content, marker)?;
+    let value_start = start + marker.len();
+    let line = content[value_start..].lines().next().unwrap_or_default();
+    let end = value_start + line.find("-->")?;
+
+    let before = &content[..start];
+    let value = content[value_start..end].trim();
+    let after = &content[end + 3..]; // Skip "-->"
+
+    Some((before.to_owned(), value.to_owned(), after.to_owned()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -263,7 +1382,7 @@ mod tests {
         let result = extract_markers(content);
         assert_eq!(result.setup, Some("CREATE TABLE test;".to_owned()));
         assert_eq!(result.assertions, None);
-        assert_eq!(result.expect, None);
+        assert!(result.expect.is_empty());
         assert_eq!(result.visible_content, "SELECT * FROM test;");
     }
 
@@ -273,7 +1392,7 @@ mod tests {
         let result = extract_markers(content);
         assert_eq!(result.setup, None);
         assert_eq!(result.assertions, Some("rows >= 1".to_owned()));
-        assert_eq!(result.expect, None);
+        assert!(result.expect.is_empty());
         assert_eq!(result.visible_content, "SELECT * FROM test;");
     }
 
@@ -283,7 +1402,7 @@ mod tests {
         let result = extract_markers(content);
         assert_eq!(result.setup, None);
         assert_eq!(result.assertions, None);
-        assert_eq!(result.expect, Some("[{\"1\": 1}]".to_owned()));
+        assert_eq!(result.expect, ["[{\"1\": 1}]"]);
         assert_eq!(result.visible_content, "SELECT 1;");
     }
 
@@ -293,17 +1412,200 @@ mod tests {
         let result = extract_markers(content);
         assert_eq!(result.setup, Some("CREATE TABLE t;".to_owned()));
         assert_eq!(result.assertions, Some("rows = 0".to_owned()));
-        assert_eq!(result.expect, Some("[]".to_owned()));
+        assert_eq!(result.expect, ["[]"]);
         assert_eq!(result.visible_content, "SELECT * FROM t;");
     }
 
+    #[test]
+    fn extract_markers_expect_exit() {
+        let content = "INSERT INTO t VALUES (1);\n<!--EXPECT-EXIT 19-->\n<!--EXPECT\n[]\n-->";
+        let markers = extract_markers(content);
+        assert_eq!(markers.expect_exit.as_deref(), Some("19"));
+        // EXPECT still finds its own marker, not EXPECT-EXIT's
+        assert_eq!(markers.expect, ["[]"]);
+        assert_eq!(markers.visible_content, "INSERT INTO t VALUES (1);");
+    }
+
+    #[test]
+    fn extract_markers_cleanup() {
+        let content = "<!--SETUP\ntouch /tmp/x\n-->\ncat /tmp/x\n<!--CLEANUP\nrm -f /tmp/x\n-->\n<!--ASSERT\nrows = 0\n-->";
+        let result = extract_markers(content);
+        assert_eq!(result.setup, Some("touch /tmp/x".to_owned()));
+        assert_eq!(result.cleanup, Some("rm -f /tmp/x".to_owned()));
+        assert_eq!(result.assertions, Some("rows = 0".to_owned()));
+        assert_eq!(result.visible_content, "cat /tmp/x");
+    }
+
+    #[test]
+    fn extract_markers_expect_regex_only() {
+        let content = "SELECT 1;\n<!--EXPECT_REGEX\n\\[\\{.*\\}\\]\n-->";
+        let result = extract_markers(content);
+        assert!(result.expect.is_empty());
+        assert_eq!(result.expect_regex, Some("\\[\\{.*\\}\\]".to_owned()));
+        assert_eq!(result.visible_content, "SELECT 1;");
+    }
+
+    #[test]
+    fn extract_markers_section_assertions() {
+        let content = "-- @q1\nSELECT 1;\n<!--ASSERT:q1\nrows = 1\n-->\n-- @q2\nSELECT 2;\n<!--ASSERT:q2\nrows = 2\n-->\n<!--ASSERT\nrows >= 1\n-->";
+        let result = extract_markers(content);
+        assert_eq!(
+            result.section_assertions,
+            [
+                ("q1".to_owned(), "rows = 1".to_owned()),
+                ("q2".to_owned(), "rows = 2".to_owned()),
+            ]
+        );
+        assert_eq!(result.assertions, Some("rows >= 1".to_owned()));
+        assert_eq!(
+            result.visible_content,
+            "-- @q1\nSELECT 1;\n\n-- @q2\nSELECT 2;"
+        );
+    }
+
+    #[test]
+    fn split_sections_splits_on_section_lines() {
+        let (preamble, sections) =
+            split_sections("CREATE TABLE t(x);\n-- @q1\nSELECT 1;\n-- @q2\nSELECT 2;\nSELECT 3;");
+        assert_eq!(preamble, "CREATE TABLE t(x);");
+        assert_eq!(
+            sections,
+            [
+                Section {
+                    name: "q1".to_owned(),
+                    content: "SELECT 1;".to_owned(),
+                },
+                Section {
+                    name: "q2".to_owned(),
+                    content: "SELECT 2;\nSELECT 3;".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn split_sections_ignores_ordinary_comments() {
+        let (preamble, sections) = split_sections("-- @ q1\n-- @q1 trailing\nSELECT 1;");
+        assert_eq!(preamble, "-- @ q1\n-- @q1 trailing\nSELECT 1;");
+        assert!(sections.is_empty());
+    }
+
+    #[test]
+    fn split_front_matter_splits_leading_yaml() {
+        assert_eq!(
+            split_front_matter("---\nvalidator:\n  sqlite: {}\n---\n# Intro\n"),
+            Some(("validator:\n  sqlite: {}\n", "# Intro\n"))
+        );
+        assert_eq!(
+            split_front_matter("---\r\ntitle: x\r\n---\r\nText"),
+            Some(("title: x\r\n", "Text"))
+        );
+    }
+
+    #[test]
+    fn split_front_matter_requires_leading_and_closing_lines() {
+        assert_eq!(split_front_matter("# Intro\n---\ntitle: x\n---\n"), None);
+        assert_eq!(split_front_matter("---\ntitle: x\n"), None);
+    }
+
+    #[test]
+    fn front_matter_validators_parses_nested_maps_and_scalars() {
+        let front_matter = "title: \"Intro: part 1\"\nvalidator:\n  sqlite:\n    container: keinos/sqlite3:3.45.3  # pinned\n    timeout: 5\n    allow_network: true\n    exec_command: 'sqlite3 -json {db}'\n    env:\n      TZ: UTC\n    allow_warnings:\n      - \"deprecated\"\n      - slow\n    pipeline: [trim, \"a, b\"]\n  other: {}\nauthors:\n  - someone\n";
+        let validators = front_matter_validators(front_matter).unwrap().unwrap();
+
+        let expected: toml::Value = toml::from_str(
+            r#"
+            other = {}
+            [sqlite]
+            container = "keinos/sqlite3:3.45.3"
+            timeout = 5
+            allow_network = true
+            exec_command = "sqlite3 -json {db}"
+            env = { TZ = "UTC" }
+            allow_warnings = ["deprecated", "slow"]
+            pipeline = ["trim", "a, b"]
+            "#,
+        )
+        .unwrap();
+        assert_eq!(toml::Value::Table(validators), expected);
+    }
+
+    #[test]
+    fn front_matter_validators_ignores_front_matter_without_validator_key() {
+        assert_eq!(front_matter_validators("title: Intro\n"), Ok(None));
+        assert_eq!(front_matter_validators("just some text"), Ok(None));
+        // Anything outside `validator:` is never parsed
+        assert_eq!(front_matter_validators("tags: [a, {b\n"), Ok(None));
+    }
+
+    #[test]
+    fn front_matter_validators_rejects_unsupported_yaml() {
+        for (front_matter, message) in [
+            (
+                "validator:\n  sqlite:\n      timeout: 5\n    script: x\n",
+                "line 4",
+            ),
+            ("validator:\n  sqlite:\n", "`sqlite` has no value"),
+            ("validator:\n  sqlite: ~\n", "null"),
+            ("validator:\n  sqlite:\n    env: [a\n", "unclosed list"),
+            ("validator:\n  sqlite: 1\n  sqlite: 2\n", "duplicate key"),
+            ("validator: plain\n", "must be a map"),
+        ] {
+            let err = front_matter_validators(front_matter).unwrap_err();
+            assert!(err.contains(message), "{front_matter:?}: {err}");
+        }
+    }
+
+    #[test]
+    fn interpolate_env_replaces_defined_vars() {
+        let lookup = |name: &str| (name == "VERSION").then(|| "1.2.3".to_owned());
+        assert_eq!(
+            interpolate_env("contains \"${VERSION}\"\nrows = 1", lookup),
+            Ok("contains \"1.2.3\"\nrows = 1".to_owned())
+        );
+    }
+
+    #[test]
+    fn interpolate_env_errors_on_undefined_var() {
+        assert_eq!(
+            interpolate_env("contains \"${MISSING}\"", |_| None),
+            Err("MISSING".to_owned())
+        );
+    }
+
+    #[test]
+    fn interpolate_env_leaves_non_references_alone() {
+        let text = "matches \"^a$\"\ncontains \"${not a var}\"\ncontains \"${1X}\"";
+        assert_eq!(interpolate_env(text, |_| None), Ok(text.to_owned()));
+    }
+
+    #[test]
+    fn extract_markers_assert_and_assert_file_distinct() {
+        let content =
+            "SELECT 1;\n<!--ASSERT_FILE\nassertions/users.txt\n-->\n<!--ASSERT\nrows = 1\n-->";
+        let result = extract_markers(content);
+        assert_eq!(result.assert_file, Some("assertions/users.txt".to_owned()));
+        assert_eq!(result.assertions, Some("rows = 1".to_owned()));
+        assert_eq!(result.visible_content, "SELECT 1;");
+    }
+
+    #[test]
+    fn extract_markers_expect_and_expect_regex_distinct() {
+        // EXPECT must not swallow the EXPECT_REGEX marker (prefix match)
+        let content = "SELECT 1;\n<!--EXPECT_REGEX\n.*\n-->\n<!--EXPECT\n[]\n-->";
+        let result = extract_markers(content);
+        assert_eq!(result.expect, ["[]"]);
+        assert_eq!(result.expect_regex, Some(".*".to_owned()));
+        assert_eq!(result.visible_content, "SELECT 1;");
+    }
+
     #[test]
     fn extract_markers_none() {
         let content = "SELECT * FROM users;";
         let result = extract_markers(content);
         assert_eq!(result.setup, None);
         assert_eq!(result.assertions, None);
-        assert_eq!(result.expect, None);
+        assert!(result.expect.is_empty());
         assert_eq!(result.visible_content, "SELECT * FROM users;");
     }
 
@@ -337,6 +1639,204 @@ mod tests {
         assert!(result.visible_content.contains("SELECT 1"));
     }
 
+    // ==================== parse_assertions tests ====================
+
+    #[test]
+    fn parse_assertions_row_counts() {
+        assert_eq!(
+            parse_assertions("rows = 3\nrows >= 1\nrows > 0\nrows <= 3\nrows < 4"),
+            Ok(vec![
+                Assertion::Rows {
+                    op: CompareOp::Eq,
+                    n: 3
+                },
+                Assertion::Rows {
+                    op: CompareOp::Ge,
+                    n: 1
+                },
+                Assertion::Rows {
+                    op: CompareOp::Gt,
+                    n: 0
+                },
+                Assertion::Rows {
+                    op: CompareOp::Le,
+                    n: 3
+                },
+                Assertion::Rows {
+                    op: CompareOp::Lt,
+                    n: 4
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_assertions_row_tolerance() {
+        let within = Assertion::RowsWithin {
+            n: 10,
+            tolerance: 2,
+        };
+        assert_eq!(
+            parse_assertions("rows = 10 ± 2\nrows = 10 tolerance 2"),
+            Ok(vec![within.clone(), within])
+        );
+    }
+
+    #[test]
+    fn parse_assertions_output_content() {
+        assert_eq!(
+            parse_assertions(
+                "columns = 2\ncontains \"alice\"\ncontains_count \"a b\" >= 2\n\
+                 distinct \"name\" = 3\nsorted_by id desc\nstderr_empty"
+            ),
+            Ok(vec![
+                Assertion::Columns(2),
+                Assertion::Contains("alice".to_owned()),
+                Assertion::ContainsCount {
+                    needle: "a b".to_owned(),
+                    op: CompareOp::Ge,
+                    n: 2
+                },
+                Assertion::Distinct {
+                    column: "name".to_owned(),
+                    op: CompareOp::Eq,
+                    n: 3
+                },
+                Assertion::SortedBy {
+                    column: "id".to_owned(),
+                    descending: true
+                },
+                Assertion::StderrEmpty,
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_assertions_host_json() {
+        assert_eq!(
+            parse_assertions("json_length < 5\ncontains_json '{\"id\": 1}'"),
+            Ok(vec![
+                Assertion::JsonLength {
+                    op: CompareOp::Lt,
+                    n: 5
+                },
+                Assertion::ContainsJson("{\"id\": 1}".to_owned()),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_assertions_bash_exec() {
+        assert_eq!(
+            parse_assertions(
+                "exit_code = -1\nstdout_contains \"done\"\nstderr_contains \"warn\"\n\
+                 file_exists /tmp/a\ndir_exists /tmp\nfile_contains /tmp/a \"hello world\""
+            ),
+            Ok(vec![
+                Assertion::ExitCode(-1),
+                Assertion::StdoutContains("done".to_owned()),
+                Assertion::StderrContains("warn".to_owned()),
+                Assertion::FileExists("/tmp/a".to_owned()),
+                Assertion::DirExists("/tmp".to_owned()),
+                Assertion::FileContains {
+                    path: "/tmp/a".to_owned(),
+                    needle: "hello world".to_owned()
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_assertions_max_memory() {
+        assert_eq!(
+            parse_assertions("max_memory \"256m\""),
+            Ok(vec![Assertion::MaxMemory("256m".to_owned())])
+        );
+        assert!(parse_assertions("max_memory \"lots\"").is_err());
+    }
+
+    #[test]
+    fn parse_assertions_valid_json() {
+        assert_eq!(
+            parse_assertions("valid_json\n!valid_json"),
+            Ok(vec![
+                Assertion::ValidJson,
+                Assertion::Not(Box::new(Assertion::ValidJson))
+            ])
+        );
+        assert!(parse_assertions("valid_json strict").is_err());
+    }
+
+    #[test]
+    fn parse_assertions_table_columns() {
+        assert_eq!(
+            parse_assertions("table_columns = 3\n!table_columns >= 5"),
+            Ok(vec![
+                Assertion::TableColumns {
+                    op: CompareOp::Eq,
+                    n: 3
+                },
+                Assertion::Not(Box::new(Assertion::TableColumns {
+                    op: CompareOp::Ge,
+                    n: 5
+                }))
+            ])
+        );
+        assert!(parse_assertions("table_columns 3").is_err());
+    }
+
+    #[test]
+    fn parse_memory_size_accepts_docker_units() {
+        assert_eq!(parse_memory_size("256m"), Ok(256 * 1024 * 1024));
+        assert_eq!(parse_memory_size("1G"), Ok(1024 * 1024 * 1024));
+        assert_eq!(parse_memory_size("512k"), Ok(512 * 1024));
+        assert_eq!(parse_memory_size("4096"), Ok(4096));
+        assert_eq!(parse_memory_size("10b"), Ok(10));
+        assert!(parse_memory_size("0m").is_err());
+        assert!(parse_memory_size("12x").is_err());
+        assert!(parse_memory_size("m").is_err());
+        assert!(parse_memory_size("").is_err());
+    }
+
+    #[test]
+    fn parse_assertions_negation_and_blank_lines() {
+        assert_eq!(
+            parse_assertions("\n  ! contains \"error\"  \n\n"),
+            Ok(vec![Assertion::Not(Box::new(Assertion::Contains(
+                "error".to_owned()
+            )))])
+        );
+    }
+
+    #[test]
+    fn assertion_display_round_trips() {
+        let text = "rows >= 1\nrows = 10 ± 2\n!contains \"error\"\nsorted_by \"id\" desc\n\
+                    contains_json '{\"id\": 1}'\nfile_contains /tmp/a \"hi\"";
+        let assertions = parse_assertions(text).unwrap();
+        let rendered: Vec<_> = assertions.iter().map(ToString::to_string).collect();
+        assert_eq!(rendered.join("\n"), text);
+    }
+
+    #[test]
+    fn parse_assertions_reports_malformed_line() {
+        assert_eq!(
+            parse_assertions("rows = 1\nrows = many"),
+            Err("line 2: `rows = many`: invalid count 'many'".to_owned())
+        );
+        assert_eq!(
+            parse_assertions("rows != 1"),
+            Err("line 1: `rows != 1`: unsupported operator '!='".to_owned())
+        );
+        assert_eq!(
+            parse_assertions("sorted_by id sideways"),
+            Err("line 1: `sorted_by id sideways`: unknown direction 'sideways'".to_owned())
+        );
+        assert_eq!(
+            parse_assertions("rowz = 1"),
+            Err("line 1: `rowz = 1`: unknown assertion".to_owned())
+        );
+    }
+
     // ==================== strip_double_at_prefix tests ====================
 
     #[test]
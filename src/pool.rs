@@ -0,0 +1,229 @@
+//! Process-level container pool
+//!
+//! A [`ContainerPool`] keeps validator containers alive across separate
+//! [`ValidatorPreprocessor`](crate::ValidatorPreprocessor) calls, so a program
+//! validating several books (or the same book repeatedly) starts each container
+//! once. Containers stay in the pool until the pool itself is dropped.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+
+use anyhow::Context;
+use tokio::runtime::Runtime;
+
+use crate::config::Config;
+use crate::container::{ContainerOptions, ValidatorContainer};
+use crate::preprocessor::ValidatorPreprocessor;
+
+/// Identifies a pooled container: a container is only reused by a validator
+/// with the same name and image, started with the same [`ContainerOptions`].
+///
+/// Blocks sharing a `session=` run in their validator's one container, so the
+/// validator name is what separates containers within a run.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct PoolKey {
+    validator: String,
+    image: String,
+    mount: Option<(PathBuf, String)>,
+    allow_network: bool,
+    entrypoint: Option<String>,
+    label: Option<String>,
+    memory_limit: Option<u64>,
+    // Sorted, so equal maps give equal keys
+    env: Vec<(String, String)>,
+}
+
+impl PoolKey {
+    fn new(validator: &str, image: &str, options: &ContainerOptions) -> Self {
+        let mut env: Vec<_> = options
+            .env
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        env.sort();
+        Self {
+            validator: validator.to_owned(),
+            image: image.to_owned(),
+            mount: options.mount.clone(),
+            allow_network: options.allow_network,
+            entrypoint: options.entrypoint.clone(),
+            label: options.label.clone(),
+            memory_limit: options.memory_limit,
+            env,
+        }
+    }
+}
+
+/// Containers kept alive between preprocessor calls.
+///
+/// Share it between preprocessors with an `Arc` and
+/// [`ValidatorPreprocessorBuilder::container_pool`](crate::preprocessor::ValidatorPreprocessorBuilder::container_pool).
+/// A call borrows the containers matching its config and returns them when it
+/// finishes, so state left by one call (tables, files) is visible to the next
+/// unless the validator uses `state = "fresh"`.
+pub struct ContainerPool {
+    containers: Mutex<HashMap<PoolKey, ValidatorContainer>>,
+    // Declared last so containers are dropped while the runtime is still alive
+    runtime: Runtime,
+}
+
+impl ContainerPool {
+    /// Create an empty pool.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the pool's tokio runtime can't be created.
+    pub fn new() -> anyhow::Result<Self> {
+        // Containers are bound to the runtime that started them, so every call
+        // using the pool runs on this one
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .context("Failed to create tokio runtime")?;
+
+        Ok(Self {
+            containers: Mutex::default(),
+            runtime,
+        })
+    }
+
+    /// Number of idle containers in the pool
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Whether the pool holds no idle containers
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// Run `future` to completion on the pool's runtime
+    pub(crate) fn block_on<T>(&self, future: impl Future<Output = T>) -> T {
+        self.runtime.block_on(future)
+    }
+
+    /// Take the pooled containers matching `config`'s validators, keyed by
+    /// validator name like a run's container cache.
+    pub(crate) fn checkout(
+        &self,
+        config: &Config,
+        mount: Option<&Path>,
+    ) -> HashMap<String, ValidatorContainer> {
+        let mut pooled = self.lock();
+        config
+            .validators
+            .keys()
+            .filter_map(|name| {
+                let key = Self::key(config, name, mount)?;
+                pooled
+                    .remove(&key)
+                    .map(|container| (name.clone(), container))
+            })
+            .collect()
+    }
+
+    /// Return a run's containers to the pool.
+    pub(crate) fn check_in(
+        &self,
+        config: &Config,
+        mount: Option<&Path>,
+        containers: HashMap<String, ValidatorContainer>,
+    ) {
+        let mut pooled = self.lock();
+        for (name, container) in containers {
+            // Every cached container belongs to a configured validator
+            if let Some(key) = Self::key(config, &name, mount) {
+                pooled.insert(key, container);
+            }
+        }
+    }
+
+    /// Key for `validator`'s container, or `None` if its config can't produce
+    /// container options (such a validator fails before starting a container).
+    fn key(config: &Config, validator: &str, mount: Option<&Path>) -> Option<PoolKey> {
+        let validator_config = config.validators.get(validator)?;
+        let options =
+            ValidatorPreprocessor::container_options(config, validator, validator_config, mount)
+                .ok()?;
+        Some(PoolKey::new(
+            validator,
+            &validator_config.container,
+            &options,
+        ))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<PoolKey, ValidatorContainer>> {
+        self.containers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ValidatorConfig;
+
+    fn config_with(validator: ValidatorConfig) -> Config {
+        let mut validators = HashMap::new();
+        validators.insert("sqlite".to_owned(), validator);
+        Config {
+            validators,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn key_changes_with_any_container_option() {
+        let base = ValidatorConfig {
+            container: "keinos/sqlite3:3.47.2".to_owned(),
+            ..Default::default()
+        };
+        let key = |validator: ValidatorConfig| {
+            ContainerPool::key(&config_with(validator), "sqlite", None).expect("valid config")
+        };
+        let original = key(base.clone());
+        assert_eq!(original, key(base.clone()), "same config should reuse");
+
+        let mut env = HashMap::new();
+        env.insert("TZ".to_owned(), "UTC".to_owned());
+        let changed = [
+            ValidatorConfig {
+                env,
+                ..base.clone()
+            },
+            ValidatorConfig {
+                allow_network: true,
+                ..base.clone()
+            },
+            ValidatorConfig {
+                entrypoint: Some("/bin/sh".to_owned()),
+                ..base.clone()
+            },
+            ValidatorConfig {
+                memory_limit: Some("64m".to_owned()),
+                ..base.clone()
+            },
+        ];
+        for validator in changed {
+            assert_ne!(original, key(validator.clone()), "{validator:?}");
+        }
+
+        let labeled = Config {
+            container_label: Some("docs".to_owned()),
+            ..config_with(base.clone())
+        };
+        assert_ne!(
+            Some(original.clone()),
+            ContainerPool::key(&labeled, "sqlite", None)
+        );
+        assert_ne!(
+            Some(original),
+            ContainerPool::key(&config_with(base), "sqlite", Some(Path::new("/fixtures")))
+        );
+    }
+}
@@ -2,39 +2,163 @@
 //!
 //! Bridges the synchronous mdBook Preprocessor trait to async container validation.
 
-use tracing::{debug, info, trace};
+use tracing::{debug, info, trace, warn};
 
-// Default exec commands for validators when not configured
-const DEFAULT_EXEC_SQLITE: &str = "sqlite3 -json /tmp/test.db";
-const DEFAULT_EXEC_OSQUERY: &str = "osqueryi --json";
-const DEFAULT_EXEC_FALLBACK: &str = "cat";
+/// Marker prefixes that must never survive stripping (covers `ASSERT_FILE`,
+/// `ASSERT:name` and `EXPECT_REGEX` too)
+const STRIPPED_MARKERS: [&str; 4] = ["<!--SETUP", "<!--CLEANUP", "<!--ASSERT", "<!--EXPECT"];
+
+/// Directive that makes the preprocessor leave a chapter untouched: nothing
+/// is validated or stripped (for chapters documenting validator syntax)
+const DISABLE_DIRECTIVE: &str = "<!--VALIDATOR_DISABLE-->";
+
+/// Directive naming the validator for a chapter's fences without `validator=`:
+/// `<!--VALIDATOR_DEFAULT sqlite sql-->` for its `sql` fences, or
+/// `<!--VALIDATOR_DEFAULT sqlite-->` for the validator's own languages
+const DEFAULT_DIRECTIVE: &str = "<!--VALIDATOR_DEFAULT";
+
+/// Placeholder (`<!--OUTPUT:name-->`) replaced with a `capture=name` block's output
+const OUTPUT_PREFIX: &str = "<!--OUTPUT:";
+
+/// Placeholder in `exec_command` and SETUP replaced with the block's temp database path
+const DB_PLACEHOLDER: &str = "{db}";
+
+/// Exit code of a process killed with SIGKILL, which is how the OOM killer ends it
+const OOM_EXIT_CODE: i64 = 137;
 
 use std::collections::hash_map::Entry;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fmt::Write;
-use std::path::Path;
+use std::future::Future;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use mdbook_preprocessor::book::{Book, BookItem, Chapter};
 use mdbook_preprocessor::errors::Error;
 use mdbook_preprocessor::{Preprocessor, PreprocessorContext};
 use pulldown_cmark::{CodeBlockKind, Event, Parser, Tag, TagEnd};
 
-use crate::command::RealCommandRunner;
-use crate::config::{Config, ValidatorConfig};
-use crate::container::ValidatorContainer;
-use crate::error::ValidatorError;
-use crate::host_validator;
-use crate::parser::{extract_markers, parse_info_string, ExtractedMarkers};
+use crate::command::{CommandRunner, RealCommandRunner};
+use crate::config::{
+    Config, ExpectVersion, Isolation, StateMode, Transform, ValidatorConfig, DEFAULT_EXEC_FALLBACK,
+};
+use crate::container::{
+    pull_image_via_cli, remove_labeled_containers, ContainerOptions, ImageReference,
+    ValidationResult, ValidatorContainer,
+};
+use crate::dependency::{self, DependencyChecker, RealChecker};
+use crate::error::{self, AggregatedErrors, ValidatorError};
+use crate::host_validator::{self, HostValidationResult, TomlAssertions};
+use crate::parser::{
+    extract_markers, interpolate_env, parse_assertions, parse_block_attributes, parse_memory_size,
+    split_front_matter, split_sections, strip_internal_attributes, AssertTarget, Assertion,
+    BlockAttributes, ExpectMode, ExtractedMarkers,
+};
+use crate::pool::ContainerPool;
+use crate::profile::{Phase, Profile};
+use crate::progress::Progress;
+use crate::report::{BlockResult, ExitCodes, Report, ValidationOutcome};
 use crate::transpiler::strip_markers;
 
 /// The mdbook-validator preprocessor
-pub struct ValidatorPreprocessor;
+pub struct ValidatorPreprocessor {
+    /// Containers shared with other preprocessors, kept between calls
+    pool: Option<Arc<ContainerPool>>,
+    /// Where block progress goes instead of stderr
+    progress_out: Option<Arc<Mutex<dyn io::Write + Send>>>,
+}
+
+/// Builder for a [`ValidatorPreprocessor`] with non-default options
+#[derive(Default)]
+pub struct ValidatorPreprocessorBuilder {
+    pool: Option<Arc<ContainerPool>>,
+    progress_out: Option<Arc<Mutex<dyn io::Write + Send>>>,
+}
+
+impl ValidatorPreprocessorBuilder {
+    /// Borrow containers from `pool` instead of starting new ones on every call.
+    ///
+    /// Containers are returned to the pool when a call finishes, so later calls
+    /// (from this or another preprocessor sharing the pool) reuse them.
+    #[must_use]
+    pub fn container_pool(mut self, pool: Arc<ContainerPool>) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Print block progress to `out` on every run, whatever the `progress` flag says
+    #[must_use]
+    pub fn progress_writer(mut self, out: impl io::Write + Send + 'static) -> Self {
+        self.progress_out = Some(Arc::new(Mutex::new(out)));
+        self
+    }
+
+    /// Build the preprocessor
+    #[must_use]
+    pub fn build(self) -> ValidatorPreprocessor {
+        ValidatorPreprocessor {
+            pool: self.pool,
+            progress_out: self.progress_out,
+        }
+    }
+}
+
+/// Bookkeeping for one run, threaded through chapter processing
+struct RunState {
+    profile: Profile,
+    progress: Progress,
+    report: Report,
+    /// Blocks served by each validator's current container, for `max_blocks_per_container`
+    container_blocks: HashMap<String, usize>,
+    /// Validators referenced by a block, for `warn_unused_validators`
+    used_validators: HashSet<String>,
+    /// Block and chapter failures accumulated with `fail_fast = false`
+    failures: Vec<Error>,
+}
+
+impl RunState {
+    fn new(progress: Progress) -> Self {
+        Self {
+            profile: Profile::default(),
+            progress,
+            report: Report::default(),
+            container_blocks: HashMap::new(),
+            used_validators: HashSet::new(),
+            failures: Vec::new(),
+        }
+    }
+}
 
 impl ValidatorPreprocessor {
     /// Create a new preprocessor instance
     #[must_use]
     pub fn new() -> Self {
-        Self
+        Self {
+            pool: None,
+            progress_out: None,
+        }
+    }
+
+    /// Start building a preprocessor with non-default options
+    #[must_use]
+    pub fn builder() -> ValidatorPreprocessorBuilder {
+        ValidatorPreprocessorBuilder::default()
+    }
+
+    /// Run `future` to completion on the pool's runtime, or on a new one
+    /// without a pool.
+    fn block_on<T>(&self, future: impl Future<Output = Result<T, Error>>) -> Result<T, Error> {
+        if let Some(pool) = &self.pool {
+            return pool.block_on(future);
+        }
+
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| Error::msg(format!("Failed to create tokio runtime: {e}")))?;
+        rt.block_on(future)
     }
 }
 
@@ -52,15 +176,10 @@ impl Preprocessor for ValidatorPreprocessor {
     fn run(&self, ctx: &PreprocessorContext, mut book: Book) -> Result<Book, Error> {
         // Parse config from book.toml
         let config = Config::from_context(ctx)
-            .map_err(|e| Error::msg(format!("Failed to parse config: {e}")))?;
-
-        // Create tokio runtime for async->sync bridge
-        let rt = tokio::runtime::Builder::new_current_thread()
-            .enable_all()
-            .build()
-            .map_err(|e| Error::msg(format!("Failed to create tokio runtime: {e}")))?;
+            .map_err(|e| error::with_context(e, "Failed to parse config"))?;
 
-        rt.block_on(async {
+        // Bridge async->sync on a new runtime, or the pool's
+        self.block_on(async {
             self.run_async_with_config(&mut book, &config, &ctx.root)
                 .await
         })?;
@@ -99,44 +218,213 @@ impl ValidatorPreprocessor {
         Ok(book)
     }
 
-    /// Process a book with explicit config (for testing).
+    /// Validate one block's content (markers included) with `validator_name`,
+    /// without a book.
     ///
-    /// Allows testing with a custom config without needing a full `PreprocessorContext`.
-    pub fn process_book_with_config(
-        &self,
-        mut book: Book,
+    /// Starts the validator's container, runs the content as a single block and
+    /// stops the container again, so downstream crates can unit-test their own
+    /// validator scripts. Failing validation is reported in the returned
+    /// [`ValidationOutcome`], not as an error. `before_each`/`after_each` hooks
+    /// aren't run.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the validator isn't configured or its container can't be
+    /// started.
+    pub fn validate_content(
         config: &Config,
+        validator_name: &str,
+        content: &str,
         book_root: &Path,
-    ) -> Result<Book, Error> {
+    ) -> Result<ValidationOutcome, Error> {
         let rt = tokio::runtime::Builder::new_current_thread()
             .enable_all()
             .build()
             .map_err(|e| Error::msg(format!("Failed to create tokio runtime: {e}")))?;
 
         rt.block_on(async {
+            let validator_config = config.get_validator(validator_name)?;
+            let block = ValidatorBlock::new(
+                1,
+                validator_name.to_owned(),
+                content,
+                BlockAttributes::default(),
+            );
+            let preprocessor = Self::new();
+            let mut containers = HashMap::new();
+            let mut profile = Profile::default();
+            let container = preprocessor
+                .get_or_start_container(
+                    validator_name,
+                    config,
+                    book_root,
+                    &mut containers,
+                    &mut profile,
+                )
+                .await?;
+
+            let outcome = preprocessor
+                .validate_block_host_based(
+                    container,
+                    validator_config,
+                    &block,
+                    "validate_content",
+                    book_root,
+                    &mut profile,
+                )
+                .await;
+            Ok(ValidationOutcome::from_result(
+                outcome.map(|run| run.exit_codes),
+            ))
+        })
+    }
+
+    /// Process a book with explicit config (for testing).
+    ///
+    /// Allows testing with a custom config without needing a full `PreprocessorContext`.
+    pub fn process_book_with_config(
+        &self,
+        book: Book,
+        config: &Config,
+        book_root: &Path,
+    ) -> Result<Book, Error> {
+        self.process_book_with_report(book, config, book_root)
+            .map(|(book, _)| book)
+    }
+
+    /// Like [`Self::process_book_with_config`], also returning each validated
+    /// block's result.
+    pub fn process_book_with_report(
+        &self,
+        mut book: Book,
+        config: &Config,
+        book_root: &Path,
+    ) -> Result<(Book, Report), Error> {
+        let report = self.block_on(async {
             self.run_async_with_config(&mut book, config, book_root)
                 .await
         })?;
 
-        Ok(book)
+        Ok((book, report))
+    }
+
+    /// Validate one chapter, starting containers into (or reusing them from) `containers`.
+    /// Each validated block's result, including a failing one, is added to `report`.
+    ///
+    /// Watch mode calls this repeatedly with the same cache, so containers
+    /// outlive a single run.
+    pub(crate) async fn validate_chapter(
+        &self,
+        chapter: &mut Chapter,
+        config: &Config,
+        book_root: &Path,
+        containers: &mut HashMap<String, ValidatorContainer>,
+        report: &mut Report,
+    ) -> Result<(), Error> {
+        let mut run = RunState::new(Progress::new(0, false));
+        let result = self
+            .process_chapter_with_config(chapter, config, book_root, containers, &mut run)
+            .await;
+        report.append(run.report);
+        result
     }
 
     /// Run with explicit config - starts per-validator containers.
+    ///
+    /// Returns the result of every validated block.
     async fn run_async_with_config(
         &self,
         book: &mut Book,
         config: &Config,
         book_root: &Path,
-    ) -> Result<(), Error> {
-        // Cache started containers by validator name
-        let mut containers: HashMap<String, ValidatorContainer> = HashMap::new();
+    ) -> Result<Report, Error> {
+        let total_blocks = Self::count_validator_blocks(&book.items, &config.default_validators);
+        if total_blocks > 0 {
+            Self::check_docker_available(&RealChecker, config)?;
+        }
 
+        // Pooled containers carry the label too, but aren't stale
+        let pooled = self.pool.as_ref().is_some_and(|pool| !pool.is_empty());
+        if config.clean_on_start && !pooled {
+            Self::clean_stale_containers(config).await;
+        }
+
+        // Cache started containers by validator name, starting from any pooled ones
+        let mount = if self.pool.is_some() {
+            Self::fixtures_mount(config, book_root)?
+        } else {
+            None
+        };
+        let mut containers: HashMap<String, ValidatorContainer> = self
+            .pool
+            .as_ref()
+            .map(|pool| pool.checkout(config, mount.as_deref()))
+            .unwrap_or_default();
+        let progress = match &self.progress_out {
+            Some(out) => Progress::shared(total_blocks, out),
+            None => Progress::new(total_blocks, config.progress_enabled()),
+        };
+        let mut run = RunState::new(progress);
+
+        let mut result = Ok(());
         for item in &mut book.items {
-            self.process_book_item_with_config(item, config, book_root, &mut containers)
-                .await?;
+            result = self
+                .process_book_item_with_config(item, config, book_root, &mut containers, &mut run)
+                .await;
+            if result.is_err() {
+                break;
+            }
         }
 
-        Ok(())
+        if result.is_ok() && !run.failures.is_empty() {
+            result = Err(AggregatedErrors::new(std::mem::take(&mut run.failures)).into());
+        }
+
+        // Print even when validation failed: slow failures are worth profiling too
+        if Profile::enabled_from_env() {
+            run.profile.print();
+        }
+
+        // A failed run may have stopped before reaching every block
+        if result.is_ok() && config.warn_unused_validators {
+            Self::warn_unused_validators(config, &run.used_validators);
+        }
+
+        if let Some(pool) = &self.pool {
+            pool.check_in(config, mount.as_deref(), containers);
+        }
+
+        // Written before a failure is returned, so CI sees which blocks failed
+        if let Some(report_path) = &config.report_path {
+            let path = book_root.join(report_path);
+            if let Err(e) = run.report.write_json(&path) {
+                if result.is_ok() {
+                    result = Err(e);
+                } else {
+                    warn!("{e:#}");
+                }
+            }
+        }
+
+        result.map(|()| run.report)
+    }
+
+    /// Remove containers left behind by earlier runs of this book (`clean_on_start`).
+    ///
+    /// Only containers labeled with this book's `container_label` are removed, so
+    /// other books' containers are left alone. Failures are logged, not fatal.
+    async fn clean_stale_containers(config: &Config) {
+        let Some(label) = &config.container_label else {
+            warn!("clean_on_start is set but container_label is not; skipping cleanup");
+            return;
+        };
+        match remove_labeled_containers(Some(label)).await {
+            Ok(removed) if !removed.is_empty() => {
+                info!(count = removed.len(), label = %label, "Removed stale containers");
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Failed to remove stale containers: {e:#}"),
+        }
     }
 
     /// Run with default script (for testing without config).
@@ -147,7 +435,7 @@ impl ValidatorPreprocessor {
     ) -> Result<(), Error> {
         let container = ValidatorContainer::start(validator_script)
             .await
-            .map_err(|e| Error::msg(format!("Failed to start container: {e}")))?;
+            .map_err(|e| error::with_context(e, "Failed to start container"))?;
 
         for item in &mut book.items {
             self.process_book_item(item, &container).await?;
@@ -161,6 +449,7 @@ impl ValidatorPreprocessor {
         item: &mut BookItem,
         container: &ValidatorContainer,
     ) -> Result<(), Error> {
+        // Separators and part titles have no content to validate and pass through untouched
         if let BookItem::Chapter(chapter) = item {
             self.process_chapter(chapter, container).await?;
 
@@ -178,15 +467,31 @@ impl ValidatorPreprocessor {
         config: &Config,
         book_root: &Path,
         containers: &mut HashMap<String, ValidatorContainer>,
+        run: &mut RunState,
     ) -> Result<(), Error> {
+        // Separators and part titles have no content to validate and pass through untouched
         if let BookItem::Chapter(chapter) = item {
-            self.process_chapter_with_config(chapter, config, book_root, containers)
-                .await?;
+            let result = self
+                .process_chapter_with_config(chapter, config, book_root, containers, run)
+                .await;
+            if let Err(e) = result {
+                // Without fail_fast the failure is kept and the next chapter validated
+                if config.fail_fast {
+                    return Err(e);
+                }
+                // A chapter with several failing blocks returns them aggregated
+                match e.downcast::<AggregatedErrors>() {
+                    Ok(aggregated) => run.failures.extend(aggregated.into_errors()),
+                    Err(e) => run.failures.push(e),
+                }
+            }
 
             // Process sub-items recursively
             for sub_item in &mut chapter.sub_items {
                 Box::pin(
-                    self.process_book_item_with_config(sub_item, config, book_root, containers),
+                    self.process_book_item_with_config(
+                        sub_item, config, book_root, containers, run,
+                    ),
                 )
                 .await?;
             }
@@ -199,12 +504,12 @@ impl ValidatorPreprocessor {
         chapter: &mut Chapter,
         container: &ValidatorContainer,
     ) -> Result<(), Error> {
-        if chapter.content.is_empty() {
+        if chapter.content.is_empty() || Self::is_disabled(chapter) {
             return Ok(());
         }
 
         // Collect all code blocks that need validation
-        let blocks = Self::find_validator_blocks(&chapter.content);
+        let blocks = Self::find_validator_blocks(&chapter.content, &HashMap::new());
 
         if blocks.is_empty() {
             return Ok(());
@@ -222,14 +527,11 @@ impl ValidatorPreprocessor {
                     block.markers.setup.as_deref(),
                     &validation_content,
                     block.markers.assertions.as_deref(),
-                    block.markers.expect.as_deref(),
+                    block.markers.expect.first().map(String::as_str),
                 )
                 .await
                 .map_err(|e| {
-                    Error::msg(format!(
-                        "Validation exec failed in '{}': {}",
-                        chapter.name, e
-                    ))
+                    error::with_context(e, format!("Validation exec failed in '{}'", chapter.name))
                 })?;
 
             if result.exit_code != 0 {
@@ -248,492 +550,2982 @@ impl ValidatorPreprocessor {
         }
 
         // All validations passed - strip markers from chapter content
-        chapter.content = Self::strip_markers_from_chapter(&chapter.content);
+        chapter.content = Self::strip_markers_from_chapter(&chapter.content, &HashMap::new())?;
 
         Ok(())
     }
 
+    /// Validate a chapter. With `isolation = "per-chapter"` its containers are
+    /// discarded afterwards, so the next chapter starts from new ones.
     async fn process_chapter_with_config(
         &self,
         chapter: &mut Chapter,
         config: &Config,
         book_root: &Path,
         containers: &mut HashMap<String, ValidatorContainer>,
+        run: &mut RunState,
+    ) -> Result<(), Error> {
+        let result = self
+            .process_chapter_with_overrides(chapter, config, book_root, containers, run)
+            .await;
+        if config.isolation == Isolation::PerChapter && !containers.is_empty() {
+            debug!(chapter = %chapter.name, "Discarding containers (isolation=per-chapter)");
+            containers.clear();
+        }
+        result
+    }
+
+    /// Validate a chapter, applying validator overrides from its front matter.
+    ///
+    /// Overridden validators get containers of their own for this chapter, so the
+    /// book's containers keep running with the book's config.
+    async fn process_chapter_with_overrides(
+        &self,
+        chapter: &mut Chapter,
+        config: &Config,
+        book_root: &Path,
+        containers: &mut HashMap<String, ValidatorContainer>,
+        run: &mut RunState,
+    ) -> Result<(), Error> {
+        let overrides = match split_front_matter(&chapter.content) {
+            Some((front_matter, _)) => config.with_front_matter(front_matter, &chapter.name)?,
+            None => None,
+        };
+        let Some((chapter_config, overridden)) = overrides else {
+            return self
+                .process_chapter_blocks(chapter, config, book_root, containers, run)
+                .await;
+        };
+        debug!(chapter = %chapter.name, validators = ?overridden, "Front matter overrides");
+
+        let stashed: Vec<_> = overridden
+            .iter()
+            .filter_map(|name| containers.remove_entry(name))
+            .collect();
+        let result = self
+            .process_chapter_blocks(chapter, &chapter_config, book_root, containers, run)
+            .await;
+        // Stop the chapter's own containers and restore the book's
+        for name in &overridden {
+            containers.remove(name);
+        }
+        containers.extend(stashed);
+        result?;
+
+        // The front matter only configured validation, so it goes with the markers
+        if config.strip_enabled() {
+            if let Some((_, rest)) = split_front_matter(&chapter.content) {
+                let front_matter_len = chapter.content.len() - rest.len();
+                chapter.content.drain(..front_matter_len);
+            }
+        }
+        Ok(())
+    }
+
+    /// Validate a chapter's blocks with `config`, then strip their markers.
+    async fn process_chapter_blocks(
+        &self,
+        chapter: &mut Chapter,
+        config: &Config,
+        book_root: &Path,
+        containers: &mut HashMap<String, ValidatorContainer>,
+        run: &mut RunState,
     ) -> Result<(), Error> {
-        if chapter.content.is_empty() {
+        if chapter.content.is_empty() || Self::is_disabled(chapter) {
             return Ok(());
         }
 
         // Collect all code blocks that need validation
-        let blocks = Self::find_validator_blocks(&chapter.content);
+        let mut blocks = Self::find_validator_blocks(&chapter.content, &config.default_validators);
 
         if blocks.is_empty() {
             return Ok(());
         }
+        run.used_validators
+            .extend(blocks.iter().map(|block| block.validator_name.clone()));
+
+        // quiet_success keeps passing chapters out of info-level logs
+        if config.quiet_success {
+            debug!(
+                chapter = %chapter.name,
+                source = %Self::chapter_source(chapter),
+                blocks = blocks.len(),
+                "Validating"
+            );
+        } else {
+            info!(
+                chapter = %chapter.name,
+                source = %Self::chapter_source(chapter),
+                blocks = blocks.len(),
+                "Validating"
+            );
+        }
 
-        info!(chapter = %chapter.name, blocks = blocks.len(), "Validating");
-
-        // Check for mutually exclusive attributes (fail fast)
+        // Check for mutually exclusive attributes and bad values (fail fast)
         for block in &blocks {
             if block.skip && block.hidden {
                 return Err(Error::new(ValidatorError::MutuallyExclusiveAttributes));
             }
+            if let Some(reason) = block.invalid.first() {
+                return Err(Error::new(ValidatorError::InvalidConfig {
+                    name: block.validator_name.clone(),
+                    reason: format!("{reason} (block {} in '{}')", block.index, chapter.name),
+                }));
+            }
         }
 
+        // Output of `capture=` blocks, rendered at `<!--OUTPUT:name-->` after validation
+        let mut captures: HashMap<String, String> = HashMap::new();
+        // Block failures accumulated with `fail_fast = false`
+        let mut failures: Vec<Error> = Vec::new();
+
         // Validate each block using configured validator
-        for (idx, block) in blocks.iter().enumerate() {
+        for (idx, block) in blocks.iter_mut().enumerate() {
             if block.skip {
                 debug!(block = idx + 1, validator = %block.validator_name, "Skipping (skip=true)");
+                run.report.record(BlockResult {
+                    skipped: true,
+                    ..block.report_entry(&chapter.name)
+                });
                 continue;
             }
 
-            debug!(block = idx + 1, validator = %block.validator_name, "Validating block");
-
-            // Get validator config
-            let validator_config = config.get_validator(&block.validator_name).map_err(|e| {
-                Error::msg(format!(
-                    "Unknown validator '{}': {}",
-                    block.validator_name, e
-                ))
-            })?;
-
-            // Get or start container for this validator
-            let container = self
-                .get_or_start_container(&block.validator_name, config, book_root, containers)
-                .await?;
+            match self
+                .validate_and_record_block(block, &chapter.name, config, book_root, containers, run)
+                .await
+            {
+                Ok(Some(output)) => {
+                    if let Some(name) = &block.capture {
+                        captures.insert(name.clone(), output);
+                    }
+                }
+                // Optional block whose container failed to start
+                Ok(None) => {}
+                Err(e) => {
+                    if config.fail_fast {
+                        return Err(e);
+                    }
+                    // Keep validating the chapter's remaining blocks
+                    failures.push(e);
+                }
+            }
+            run.progress.tick();
+        }
 
-            // Use host-based validation: run query in container, validate on host
-            self.validate_block_host_based(
-                container,
-                validator_config,
-                block,
-                &chapter.name,
-                book_root,
-            )
-            .await?;
+        // The chapter keeps its markers: the build fails, and no failing block is
+        // rendered as if it had passed
+        if !failures.is_empty() {
+            return Err(AggregatedErrors::new(failures).into());
         }
 
-        // All validations passed - strip markers from chapter content
-        chapter.content = Self::strip_markers_from_chapter(&chapter.content);
+        // All validations passed - strip markers from chapter content (unless
+        // `strip = false` leaves that to another pipeline)
+        if config.strip_enabled() {
+            let rendered = Self::render_captures(&chapter.content, &captures, &chapter.name)?;
+            chapter.content =
+                Self::strip_markers_from_chapter(&rendered, &config.default_validators)?;
+        }
 
-        info!(chapter = %chapter.name, "✓ Passed");
+        if config.quiet_success {
+            debug!(chapter = %chapter.name, "✓ Passed");
+        } else {
+            info!(chapter = %chapter.name, "✓ Passed");
+        }
 
         Ok(())
     }
 
-    /// Validate a code block using host-based validation.
+    /// Validate one block of a chapter and record the result in the run's report.
     ///
-    /// This runs the query in the container and validates the output on the host.
-    async fn validate_block_host_based(
+    /// Returns the query's output, or `None` for an `optional` block skipped
+    /// because its container failed to start. A failure is labelled with the
+    /// block's `desc=`.
+    async fn validate_and_record_block(
         &self,
-        container: &ValidatorContainer,
-        validator_config: &ValidatorConfig,
-        block: &ValidatorBlock,
+        block: &mut ValidatorBlock,
         chapter_name: &str,
+        config: &Config,
         book_root: &Path,
-    ) -> Result<(), Error> {
-        // 0. Verify validator script exists first (fail fast before container work)
-        let script_path = book_root.join(&validator_config.script);
-        if !script_path.exists() {
-            return Err(Error::msg(format!(
-                "Failed to read validator script '{}': file not found",
-                script_path.display()
-            )));
+        containers: &mut HashMap<String, ValidatorContainer>,
+        run: &mut RunState,
+    ) -> Result<Option<String>, Error> {
+        let started = Instant::now();
+        let outcome = self
+            .validate_chapter_block(block, chapter_name, config, book_root, containers, run)
+            .await;
+        let duration_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        let container_id = containers
+            .get(&block.validator_name)
+            .map(|container| container.id().to_owned())
+            .unwrap_or_default();
+        match outcome {
+            Ok(Some(block_run)) => {
+                run.report.record(BlockResult {
+                    container_id,
+                    exit_codes: block_run.exit_codes,
+                    passed: true,
+                    duration_ms,
+                    ..block.report_entry(chapter_name)
+                });
+                Ok(Some(block_run.output))
+            }
+            Ok(None) => Ok(None),
+            Err(e) => {
+                let e = match &block.description {
+                    Some(description) => error::with_context(e, format!("Block \"{description}\"")),
+                    None => e,
+                };
+                run.report.record(BlockResult {
+                    container_id,
+                    exit_codes: ExitCodes::from_error(&e),
+                    duration_ms,
+                    message: format!("{e:#}"),
+                    ..block.report_entry(chapter_name)
+                });
+                Err(e)
+            }
         }
+    }
 
-        debug!(script = %script_path.display(), "Using validator script");
+    /// Validate one block of a chapter: resolve its assertions, run it in its
+    /// validator's container and check the output, with the validator's hooks.
+    ///
+    /// Returns `None` for an `optional` block skipped because its container
+    /// failed to start.
+    async fn validate_chapter_block(
+        &self,
+        block: &mut ValidatorBlock,
+        chapter_name: &str,
+        config: &Config,
+        book_root: &Path,
+        containers: &mut HashMap<String, ValidatorContainer>,
+        run: &mut RunState,
+    ) -> Result<Option<BlockRun>, Error> {
+        // Splice ASSERT_FILE contents in ahead of the block's own assertions
+        if let Some(path) = &block.markers.assert_file {
+            let file_assertions = Self::read_assertion_file(path, config, book_root)?;
+            block.markers.assertions = Some(match block.markers.assertions.take() {
+                Some(own) => format!("{}\n{own}", file_assertions.trim_end()),
+                None => file_assertions,
+            });
+        }
 
-        // Get exec command (use defaults if not configured)
-        let exec_cmd = Self::get_exec_command(&block.validator_name, validator_config);
-        debug!(exec_command = %exec_cmd, "Container exec command");
+        // Expand use= references into the block's assertions
+        if !block.uses.is_empty() {
+            block.markers.assertions =
+                config.resolve_assertions(&block.uses, block.markers.assertions.as_deref())?;
+        }
 
-        // 1. Run setup script in container (if any)
-        // SETUP content IS the shell command - run directly via sh -c
-        if let Some(setup) = &block.markers.setup {
-            let setup_script = setup.trim();
-            if !setup_script.is_empty() {
-                debug!("Running SETUP script");
-                trace!(setup = %setup_script, "SETUP content");
-                let setup_result = container
-                    .exec_raw(&["sh", "-c", setup_script])
-                    .await
-                    .map_err(|e| Error::msg(format!("Setup exec failed: {e}")))?;
-
-                if setup_result.exit_code != 0 {
-                    #[allow(clippy::cast_possible_truncation)]
-                    return Err(ValidatorError::SetupFailed {
-                        exit_code: setup_result.exit_code as i32,
-                        message: format!(
-                            "in '{}' (validator: {}):\n\nScript:\n{}\n\nError:\n{}",
-                            chapter_name, block.validator_name, setup_script, setup_result.stderr
-                        ),
-                    }
-                    .into());
+        Self::interpolate_env_vars(&mut block.markers, chapter_name)?;
+
+        debug!(block = block.index, validator = %block.validator_name, "Validating block");
+
+        // Get validator config
+        let validator_config = config.get_validator(&block.validator_name)?;
+
+        let Some(container) = self
+            .acquire_container(block, chapter_name, config, book_root, containers, run)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        if let Some(hook) = &validator_config.before_each {
+            if let Some((exit_code, stderr)) = Self::run_hook(container, hook).await? {
+                return Err(ValidatorError::BeforeEachFailed {
+                    exit_code,
+                    message: Self::hook_failure_message(
+                        chapter_name,
+                        &block.validator_name,
+                        hook,
+                        &stderr,
+                    ),
                 }
+                .into());
             }
         }
 
-        // 2. Run query in container, get JSON output
-        // Content is passed via stdin to avoid shell injection
-        // Use validation_content() to strip @@ prefix (but keep line content)
-        let query_sql = block.markers.validation_content();
-        let query_sql = query_sql.trim();
-        if query_sql.is_empty() {
-            return Err(Error::msg(format!(
-                "Validation failed in '{}' (validator: {}): Query content is empty",
-                chapter_name, block.validator_name
-            )));
-        }
-
-        debug!("Executing query in container");
-        trace!(query = %query_sql, "Query content");
-
-        // Pass content via stdin (secure) instead of shell interpolation (vulnerable)
-        let query_result = container
-            .exec_with_stdin(&["sh", "-c", &exec_cmd], query_sql)
-            .await
-            .map_err(|e| Error::msg(format!("Query exec failed: {e}")))?;
-
-        trace!(exit_code = query_result.exit_code, stdout = %query_result.stdout, stderr = %query_result.stderr, "Query result");
-
-        if query_result.exit_code != 0 {
-            return Err(Error::msg(format!(
-                "Query failed in '{}' (validator: {}):\n\nSQL:\n{}\n\nError:\n{}",
-                chapter_name, block.validator_name, query_sql, query_result.stderr
-            )));
+        // Use host-based validation: run query in container, validate on host
+        let outcome = self
+            .validate_block_host_based(
+                container,
+                validator_config,
+                block,
+                chapter_name,
+                book_root,
+                &mut run.profile,
+            )
+            .await;
+
+        // A timed-out exec may still be running in the container. Stop the container
+        // rather than queue hooks behind it; the next block starts a new one.
+        if let Err(e) = &outcome {
+            if let Some(ValidatorError::Timeout { phase, .. }) = e.downcast_ref() {
+                warn!(
+                    validator = %block.validator_name,
+                    phase = %phase,
+                    "Discarding container after timeout"
+                );
+                containers.remove(&block.validator_name);
+                return outcome.map(Some);
+            }
         }
 
-        // 3. Validate JSON output on host using validator script
-        // (script_path already validated at the start of this function)
-        let script_path_str = script_path
-            .to_str()
-            .ok_or_else(|| Error::msg(format!("Invalid script path: {}", script_path.display())))?;
-
-        debug!("Running host validator");
-        let validation_result = host_validator::run_validator(
-            &RealCommandRunner,
-            script_path_str,
-            &query_result.stdout,
-            block.markers.assertions.as_deref(),
-            block.markers.expect.as_deref(),
-            Some(&query_result.stderr), // Pass container stderr for warning detection
+        Self::finish_block_hooks(
+            container,
+            validator_config,
+            &block.validator_name,
+            chapter_name,
+            outcome,
         )
-        .map_err(|e| {
-            Error::msg(format!(
-                "Host validator failed in '{}' (validator: {}): {}",
-                chapter_name, block.validator_name, e
-            ))
-        })?;
+        .await
+        .map(Some)
+    }
 
-        trace!(exit_code = validation_result.exit_code, stdout = %validation_result.stdout, stderr = %validation_result.stderr, "Validator result");
+    /// The container for `block`, started if needed.
+    ///
+    /// A `state = "fresh"` (or `per-block`) validator gets a new container for
+    /// every block, and one that has served `max_blocks_per_container` blocks is
+    /// recycled. Startup is lazy, so a validator whose image can't start only
+    /// fails the build if a non-optional block needs it: returns `None` for an
+    /// `optional` block instead.
+    async fn acquire_container<'a>(
+        &self,
+        block: &ValidatorBlock,
+        chapter_name: &str,
+        config: &Config,
+        book_root: &Path,
+        containers: &'a mut HashMap<String, ValidatorContainer>,
+        run: &mut RunState,
+    ) -> Result<Option<&'a ValidatorContainer>, Error> {
+        let validator_config = config.get_validator(&block.validator_name)?;
+
+        // Fresh mode: discard the previous block's container so nothing carries over
+        if (validator_config.state == StateMode::Fresh || config.isolation == Isolation::PerBlock)
+            && containers.remove(&block.validator_name).is_some()
+        {
+            debug!(validator = %block.validator_name, "Discarding container (state=fresh)");
+        }
 
-        if validation_result.exit_code != 0 {
-            let mut error_msg = format!(
-                "in '{}' (validator: {}):\n\nCode:\n{}\n",
-                chapter_name, block.validator_name, block.markers.visible_content
-            );
-            if !validation_result.stderr.is_empty() {
-                let _ = write!(
-                    error_msg,
-                    "\nValidator stderr:\n{}",
-                    validation_result.stderr
+        // Recycle a container that has served its share of blocks
+        let served = run
+            .container_blocks
+            .entry(block.validator_name.clone())
+            .or_default();
+        if config
+            .max_blocks_per_container
+            .is_some_and(|max| *served >= max.get())
+        {
+            if let Some(old) = containers.remove(&block.validator_name) {
+                debug!(
+                    validator = %block.validator_name,
+                    container_id = %old.id(),
+                    blocks = *served,
+                    "Recycling container (max_blocks_per_container)"
                 );
             }
-            if !validation_result.stdout.is_empty() {
-                let _ = write!(
-                    error_msg,
-                    "\nValidator stdout:\n{}",
-                    validation_result.stdout
+            *served = 0;
+        }
+        *served += 1;
+
+        match self
+            .get_or_start_container(
+                &block.validator_name,
+                config,
+                book_root,
+                containers,
+                &mut run.profile,
+            )
+            .await
+        {
+            Ok(container) => Ok(Some(container)),
+            Err(e)
+                if block.optional
+                    && matches!(
+                        e.downcast_ref::<ValidatorError>(),
+                        Some(ValidatorError::ContainerStartup { .. })
+                    ) =>
+            {
+                warn!(
+                    chapter = %chapter_name,
+                    block = block.index,
+                    validator = %block.validator_name,
+                    "Skipping optional block, container failed to start: {e}"
                 );
+                Ok(None)
             }
-            return Err(ValidatorError::ValidationFailed {
-                exit_code: validation_result.exit_code,
-                message: error_msg,
-            }
-            .into());
+            Err(e) => Err(e),
         }
-
-        Ok(())
     }
 
-    /// Get exec command for a validator.
+    /// Run the validator's `on_failure` and `after_each` hooks after a block.
     ///
-    /// Uses configured command if available, otherwise uses defaults based on validator name.
-    fn get_exec_command(validator_name: &str, config: &ValidatorConfig) -> String {
-        config
-            .exec_command
-            .clone()
-            .unwrap_or_else(|| match validator_name {
-                "sqlite" => DEFAULT_EXEC_SQLITE.to_owned(),
-                "osquery" => DEFAULT_EXEC_OSQUERY.to_owned(),
-                _ => DEFAULT_EXEC_FALLBACK.to_owned(),
-            })
-    }
-
-    /// Get an existing container or start a new one for the given validator.
-    async fn get_or_start_container<'a>(
-        &self,
+    /// `on_failure` captures diagnostics before `after_each` resets the state.
+    /// `after_each` runs even when the block failed so state is always reset;
+    /// the block's own error takes precedence over a hook failure.
+    async fn finish_block_hooks(
+        container: &ValidatorContainer,
+        validator_config: &ValidatorConfig,
         validator_name: &str,
-        config: &Config,
-        book_root: &Path,
-        containers: &'a mut HashMap<String, ValidatorContainer>,
-    ) -> Result<&'a ValidatorContainer, Error> {
-        match containers.entry(validator_name.to_owned()) {
-            Entry::Occupied(entry) => Ok(entry.into_mut()),
-            Entry::Vacant(entry) => {
-                // Look up validator config
-                let validator_config = config.get_validator(validator_name).map_err(|e| {
-                    Error::msg(format!("Unknown validator '{validator_name}': {e}"))
+        chapter_name: &str,
+        outcome: Result<BlockRun, Error>,
+    ) -> Result<BlockRun, Error> {
+        let outcome = match (outcome, &validator_config.on_failure) {
+            (Err(e), Some(command)) => {
+                Err(Self::append_failure_diagnostics(e, container, command).await)
+            }
+            (outcome, _) => outcome,
+        };
+
+        let hook_result = match &validator_config.after_each {
+            Some(hook) => Self::run_hook(container, hook).await,
+            None => Ok(None),
+        };
+        let block_run = outcome?;
+        if let (Some(hook), Some((exit_code, stderr))) =
+            (&validator_config.after_each, hook_result?)
+        {
+            return Err(ValidatorError::AfterEachFailed {
+                exit_code,
+                message: Self::hook_failure_message(chapter_name, validator_name, hook, &stderr),
+            }
+            .into());
+        }
+        Ok(block_run)
+    }
+
+    /// Replace each `<!--OUTPUT:name-->` in the chapter with the output captured by
+    /// the `capture=name` block, trailing whitespace trimmed.
+    ///
+    /// Only placeholders outside fenced blocks, or making up a fence's whole
+    /// content, are replaced. One inside a fence with other content (e.g. a
+    /// ```` ```markdown ```` fence documenting the syntax) is left as written.
+    fn render_captures(
+        content: &str,
+        captures: &HashMap<String, String>,
+        chapter_name: &str,
+    ) -> Result<String, ValidatorError> {
+        let literal: Vec<std::ops::Range<usize>> =
+            Self::scan_fenced_blocks(content, &HashMap::new())
+                .into_iter()
+                .filter(|fence| !is_output_placeholder(&fence.text))
+                .map(|fence| fence.range)
+                .collect();
+
+        let mut rendered = String::with_capacity(content.len());
+        let mut rest = content;
+        while let Some(start) = rest.find(OUTPUT_PREFIX) {
+            let offset = content.len() - rest.len() + start;
+            if literal.iter().any(|range| range.contains(&offset)) {
+                let skip = start + OUTPUT_PREFIX.len();
+                rendered.push_str(&rest[..skip]);
+                rest = &rest[skip..];
+                continue;
+            }
+            let after = &rest[start + OUTPUT_PREFIX.len()..];
+            let Some(end) = after.find("-->") else {
+                break;
+            };
+            let name = after[..end].trim();
+            let output = captures
+                .get(name)
+                .ok_or_else(|| ValidatorError::UnknownCapture {
+                    name: name.to_owned(),
+                    chapter: chapter_name.to_owned(),
                 })?;
+            rendered.push_str(&rest[..start]);
+            rendered.push_str(output.trim_end());
+            rest = &after[end + "-->".len()..];
+        }
+        rendered.push_str(rest);
+        Ok(rendered)
+    }
 
-                // Validate config values
-                validator_config.validate(validator_name)?;
+    /// Substitute `${VAR}` from the environment in a block's assertions and EXPECT.
+    fn interpolate_env_vars(
+        markers: &mut ExtractedMarkers,
+        chapter_name: &str,
+    ) -> Result<(), Error> {
+        let texts = markers
+            .assertions
+            .iter_mut()
+            .chain(
+                markers
+                    .section_assertions
+                    .iter_mut()
+                    .map(|(_, assertions)| assertions),
+            )
+            .chain(markers.assert_toml.iter_mut())
+            .chain(markers.expect.iter_mut());
+        for text in texts {
+            *text = interpolate_env(text, |name| std::env::var(name).ok()).map_err(|name| {
+                ValidatorError::UndefinedVariable {
+                    name,
+                    chapter: chapter_name.to_owned(),
+                }
+            })?;
+        }
+        Ok(())
+    }
 
-                // Resolve and validate fixtures_dir if configured
-                let mount = if let Some(ref fixtures_dir) = config.fixtures_dir {
-                    // Resolve relative path from book_root
-                    let fixtures_path = if fixtures_dir.is_absolute() {
-                        fixtures_dir.clone()
-                    } else {
-                        book_root.join(fixtures_dir)
-                    };
-
-                    // Validate fixtures_dir exists and is a directory
-                    if !fixtures_path.exists() {
-                        return Err(Error::msg(format!(
-                            "fixtures_dir '{}' does not exist",
-                            fixtures_path.display()
-                        )));
-                    }
-                    if !fixtures_path.is_dir() {
-                        return Err(Error::msg(format!(
-                            "fixtures_dir '{}' is not a directory",
-                            fixtures_path.display()
-                        )));
-                    }
+    /// Read an `<!--ASSERT_FILE-->` file.
+    ///
+    /// Relative paths are looked up in `fixtures_dir` (if configured) first, then
+    /// the book root.
+    fn read_assertion_file(path: &str, config: &Config, book_root: &Path) -> Result<String, Error> {
+        let path = Path::new(path);
+        let candidates: Vec<_> = if path.is_absolute() {
+            vec![path.to_path_buf()]
+        } else {
+            config
+                .fixtures_dir
+                .iter()
+                .map(|fixtures_dir| book_root.join(fixtures_dir).join(path))
+                .chain(std::iter::once(book_root.join(path)))
+                .collect()
+        };
+
+        let Some(found) = candidates.iter().find(|candidate| candidate.is_file()) else {
+            let searched: Vec<_> = candidates
+                .iter()
+                .map(|candidate| candidate.display().to_string())
+                .collect();
+            return Err(Error::msg(format!(
+                "ASSERT_FILE '{}' not found (looked in: {})",
+                path.display(),
+                searched.join(", ")
+            )));
+        };
+        debug!(path = %found.display(), "Reading ASSERT_FILE");
+        std::fs::read_to_string(found).map_err(|e| {
+            Error::msg(format!(
+                "Failed to read ASSERT_FILE '{}': {e}",
+                found.display()
+            ))
+        })
+    }
 
-                    // Canonicalize to resolve symlinks (Docker requires real paths)
-                    let fixtures_path = fixtures_path.canonicalize().map_err(|e| {
-                        Error::msg(format!(
-                            "fixtures_dir '{}' could not be canonicalized: {}",
-                            fixtures_path.display(),
-                            e
-                        ))
-                    })?;
+    /// Count the blocks a run will validate (validator blocks without `skip`),
+    /// including those in nested chapters. Used as the progress total.
+    fn count_validator_blocks(
+        items: &[BookItem],
+        language_defaults: &HashMap<String, String>,
+    ) -> usize {
+        items
+            .iter()
+            .map(|item| match item {
+                BookItem::Chapter(chapter) if Self::is_disabled(chapter) => {
+                    Self::count_validator_blocks(&chapter.sub_items, language_defaults)
+                }
+                BookItem::Chapter(chapter) => {
+                    Self::find_validator_blocks(&chapter.content, language_defaults)
+                        .iter()
+                        .filter(|block| !block.skip)
+                        .count()
+                        + Self::count_validator_blocks(&chapter.sub_items, language_defaults)
+                }
+                _ => 0,
+            })
+            .sum()
+    }
 
-                    Some((fixtures_path, "/fixtures"))
-                } else {
-                    None
-                };
+    /// Whether the chapter opted out of validation with `<!--VALIDATOR_DISABLE-->`.
+    ///
+    /// Only that chapter is affected; its sub-chapters are processed as usual.
+    fn is_disabled(chapter: &Chapter) -> bool {
+        chapter.content.contains(DISABLE_DIRECTIVE)
+    }
 
-                // Start the container with optional mount
-                let container = ValidatorContainer::start_raw_with_mount(
-                    &validator_config.container,
-                    mount.as_ref().map(|(p, c)| (p.as_path(), *c)),
-                )
-                .await
-                .map_err(|e| {
-                    Error::msg(format!(
-                        "Failed to start container '{}': {}",
-                        validator_config.container, e
-                    ))
-                })?;
+    /// Identify a chapter for diagnostics and path-based filtering.
+    ///
+    /// Uses the chapter's source path, falling back to its name for chapters
+    /// without one (drafts and generated chapters). Key anything path-based on
+    /// this rather than unwrapping `source_path`.
+    fn chapter_source(chapter: &Chapter) -> String {
+        chapter
+            .source_path
+            .as_ref()
+            .map_or_else(|| chapter.name.clone(), |path| path.display().to_string())
+    }
 
-                Ok(entry.insert(container))
+    /// Run a validator's `on_failure` command and append its output to `error`.
+    ///
+    /// The original error type (and its code) is kept where it has a message;
+    /// a failing command is reported in place of its output.
+    async fn append_failure_diagnostics(
+        error: Error,
+        container: &ValidatorContainer,
+        command: &str,
+    ) -> Error {
+        let diagnostics = match container.exec_raw(&["sh", "-c", command]).await {
+            Ok(result) => format!(
+                "\n\non_failure output ({command}):\n{}{}",
+                result.stdout, result.stderr
+            ),
+            Err(e) => format!("\n\non_failure command failed ({command}): {e:#}"),
+        };
+
+        match error.downcast::<ValidatorError>() {
+            Ok(mut validator_error) => {
+                if validator_error.append_message(&diagnostics) {
+                    validator_error.into()
+                } else {
+                    let message = format!("{validator_error}{diagnostics}");
+                    Error::new(validator_error).context(message)
+                }
             }
+            Err(error) => Error::msg(format!("{error}{diagnostics}")),
         }
     }
 
-    /// Find all code blocks with `validator=` attribute
-    fn find_validator_blocks(content: &str) -> Vec<ValidatorBlock> {
-        let mut blocks = Vec::new();
-        let parser = Parser::new(content);
+    /// Run a `before_each`/`after_each` hook in the container via `sh -c`.
+    ///
+    /// Returns the exit code and stderr if the hook exited non-zero.
+    async fn run_hook(
+        container: &ValidatorContainer,
+        hook: &str,
+    ) -> Result<Option<(i32, String)>, Error> {
+        let hook = hook.trim();
+        if hook.is_empty() {
+            return Ok(None);
+        }
 
-        let mut in_code_block = false;
-        let mut current_info = String::new();
-        let mut current_content = String::new();
+        trace!(hook = %hook, "Running hook");
+        let result = container
+            .exec_raw(&["sh", "-c", hook])
+            .await
+            .map_err(|e| error::with_context(e, "Hook exec failed"))?;
 
-        for event in parser {
-            match event {
-                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => {
-                    in_code_block = true;
-                    current_info = info.to_string();
-                    current_content.clear();
-                }
-                Event::Text(text) if in_code_block => {
-                    current_content.push_str(&text);
-                }
-                Event::End(TagEnd::CodeBlock) if in_code_block => {
-                    in_code_block = false;
-
-                    let (_language, validator, skip, hidden) = parse_info_string(&current_info);
-
-                    // Only process blocks with validator= attribute
-                    if let Some(validator_name) = validator {
-                        // Handle empty validator= as "no validator"
-                        if !validator_name.is_empty() {
-                            let markers = extract_markers(&current_content);
-                            blocks.push(ValidatorBlock {
-                                validator_name,
-                                markers,
-                                skip,
-                                hidden,
-                            });
-                        }
-                    }
-                }
-                _ => {}
-            }
+        if result.exit_code == 0 {
+            Ok(None)
+        } else {
+            #[allow(clippy::cast_possible_truncation)]
+            Ok(Some((result.exit_code as i32, result.stderr)))
         }
+    }
 
-        blocks
+    fn hook_failure_message(
+        chapter_name: &str,
+        validator_name: &str,
+        hook: &str,
+        stderr: &str,
+    ) -> String {
+        format!(
+            "in '{chapter_name}' (validator: {validator_name}):\n\nHook:\n{}\n\nError:\n{stderr}",
+            hook.trim()
+        )
     }
 
-    /// Strip all validation markers from chapter content, preserving code block structure.
-    ///
-    /// Uses span-based editing to surgically modify only code block contents,
-    /// preserving ALL other markdown formatting (lists, links, emphasis, etc.).
+    /// Validate a code block using host-based validation.
     ///
-    /// If a code block has the `hidden` attribute, the entire fence is removed from output.
-    fn strip_markers_from_chapter(content: &str) -> String {
-        use std::ops::Range;
+    /// This runs the query in the container and validates the output on the host,
+    /// then runs the block's `<!--CLEANUP-->` script, whether or not it passed.
+    /// Returns the exit codes of both phases and the query's output.
+    async fn validate_block_host_based(
+        &self,
+        container: &ValidatorContainer,
+        validator_config: &ValidatorConfig,
+        block: &ValidatorBlock,
+        chapter_name: &str,
+        book_root: &Path,
+        profile: &mut Profile,
+    ) -> Result<BlockRun, Error> {
+        let outcome = self
+            .run_block_host_based(
+                container,
+                validator_config,
+                block,
+                chapter_name,
+                book_root,
+                profile,
+            )
+            .await;
+
+        let Some(cleanup) = &block.markers.cleanup else {
+            return outcome;
+        };
+        // A timed-out exec may still be running; the container is discarded instead
+        if let Err(e) = &outcome {
+            if let Some(ValidatorError::Timeout { .. }) = e.downcast_ref() {
+                return outcome;
+            }
+        }
 
-        // Represents an edit to apply to the source
-        enum Edit {
-            /// Replace a range with new content (for stripping markers)
-            Replace {
-                range: Range<usize>,
-                content: String,
-            },
-            /// Delete a range entirely (for hidden blocks)
-            Delete { range: Range<usize> },
+        let db_path = Self::temp_db_path(container.id(), chapter_name, block);
+        let cleanup_script = cleanup.replace(DB_PLACEHOLDER, &db_path);
+        debug!("Running CLEANUP script");
+        let cleanup_result = Self::run_hook(container, &cleanup_script).await;
+
+        // The block's own error takes precedence over a cleanup failure
+        let block_run = match outcome {
+            Ok(block_run) => block_run,
+            Err(e) => {
+                if !matches!(cleanup_result, Ok(None)) {
+                    warn!(
+                        chapter = %chapter_name,
+                        block = block.index,
+                        validator = %block.validator_name,
+                        "CLEANUP script failed after the block failed"
+                    );
+                }
+                return Err(e);
+            }
+        };
+        if let Some((exit_code, stderr)) = cleanup_result? {
+            return Err(ValidatorError::CleanupFailed {
+                exit_code,
+                message: format!(
+                    "in '{}' (validator: {}):\n\nScript:\n{}\n\nError:\n{}",
+                    chapter_name,
+                    block.validator_name,
+                    cleanup_script.trim(),
+                    stderr
+                ),
+            }
+            .into());
         }
+        Ok(block_run)
+    }
 
-        let mut edits: Vec<Edit> = Vec::new();
-        let parser = Parser::new(content).into_offset_iter();
+    /// Run a block's SETUP, query and output checks (see
+    /// [`Self::validate_block_host_based`]).
+    async fn run_block_host_based(
+        &self,
+        container: &ValidatorContainer,
+        validator_config: &ValidatorConfig,
+        block: &ValidatorBlock,
+        chapter_name: &str,
+        book_root: &Path,
+        profile: &mut Profile,
+    ) -> Result<BlockRun, Error> {
+        profile.record_block(&block.validator_name);
 
-        let mut current_block_start: Option<usize> = None;
-        let mut current_hidden = false;
-        let mut current_has_validator = false;
-        let mut current_content_range: Option<Range<usize>> = None;
+        // 0. Verify validator script exists first (fail fast before container work)
+        let script_path = Self::validator_script(validator_config, book_root)?;
+        Self::check_memory_budget(validator_config, block, chapter_name)?;
+        let expected_exit = Self::expected_exit_code(block, chapter_name)?;
 
-        for (event, range) in parser {
-            match &event {
-                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => {
-                    let (_language, validator, _skip, hidden) = parse_info_string(info);
-                    current_hidden = hidden;
-                    current_has_validator = validator.is_some();
-                    current_block_start = Some(range.start);
-                    current_content_range = None;
-                }
-                Event::Text(_) if current_block_start.is_some() => {
-                    // Track the content range within the code block
-                    current_content_range = Some(range);
-                }
-                Event::End(TagEnd::CodeBlock) if current_block_start.is_some() => {
-                    let Some(block_start) = current_block_start.take() else {
-                        unreachable!("current_block_start must be Some here")
-                    };
-
-                    if current_hidden {
-                        // Delete the entire code block (including surrounding whitespace)
-                        // Find the start of the line containing the opening fence
-                        let line_start = content[..block_start].rfind('\n').map_or(0, |i| i + 1);
-                        // Find the end of the line containing the closing fence
-                        let line_end = content[range.end..]
-                            .find('\n')
-                            .map_or(range.end, |i| range.end + i + 1);
-
-                        edits.push(Edit::Delete {
-                            range: line_start..line_end,
-                        });
-                    } else if current_has_validator {
-                        // Strip markers from the content, but preserve the fence
-                        if let Some(content_range) = current_content_range.take() {
-                            let original_content = &content[content_range.clone()];
-                            let stripped = strip_markers(original_content);
-                            let trimmed = stripped.trim();
-                            if trimmed != original_content.trim() {
-                                // Only create an edit if content actually changed
-                                edits.push(Edit::Replace {
-                                    range: content_range,
-                                    content: format!("{trimmed}\n"),
-                                });
-                            }
-                        }
-                    }
+        let db_path = Self::temp_db_path(container.id(), chapter_name, block);
+        let exec_cmd = Self::exec_command(validator_config, block, &db_path);
+        debug!(exec_command = %exec_cmd, "Container exec command");
 
-                    current_hidden = false;
-                    current_has_validator = false;
-                }
-                _ => {}
-            }
-        }
+        // 1. Run setup script in container (if any)
+        Self::run_setup(
+            container,
+            validator_config,
+            block,
+            chapter_name,
+            &db_path,
+            profile,
+        )
+        .await?;
 
-        // Apply edits from end to start to preserve byte offsets
-        edits.sort_by(|a, b| {
-            let a_start = match a {
-                Edit::Replace { range, .. } | Edit::Delete { range } => range.start,
-            };
-            let b_start = match b {
-                Edit::Replace { range, .. } | Edit::Delete { range } => range.start,
+        // 2. Run query in container, get JSON output
+        let content = Self::query_content(validator_config, block, chapter_name)?;
+        let allow_warnings: Vec<String> = validator_config
+            .allow_warnings
+            .iter()
+            .chain(&block.allow_warnings)
+            .cloned()
+            .collect();
+        let implicit_assertions = Self::implicit_assertions(validator_config, block);
+        let block_checks = OutputChecks {
+            script_path: &script_path,
+            section: None,
+            code: &block.markers.visible_content,
+            assertions: implicit_assertions
+                .as_deref()
+                .or(block.markers.assertions.as_deref()),
+            assert_toml: block.markers.assert_toml.as_deref(),
+            expect: &block.markers.expect,
+            expect_regex: block.markers.expect_regex.as_deref(),
+            allow_warnings: &allow_warnings,
+            native_assertions: validator_config.native_assertions,
+        };
+
+        if !block.markers.section_assertions.is_empty() {
+            let query = Query {
+                exec_cmd: &exec_cmd,
+                sql: &content,
+                expected_exit,
             };
-            b_start.cmp(&a_start) // Reverse order (end to start)
-        });
+            return Self::run_sections(
+                container,
+                validator_config,
+                block,
+                chapter_name,
+                &query,
+                &block_checks,
+                profile,
+            )
+            .await;
+        }
 
-        let mut result = content.to_owned();
-        for edit in edits {
-            match edit {
-                Edit::Replace { range, content } => {
-                    result.replace_range(range, &content);
-                }
-                Edit::Delete { range } => {
-                    result.replace_range(range, "");
-                }
+        let query_sql = content.trim();
+        if query_sql.is_empty() {
+            return Err(Error::msg(format!(
+                "Validation failed in '{}' (validator: {}): Query content is empty",
+                chapter_name, block.validator_name
+            )));
+        }
+        let query_result = Self::run_query(
+            container,
+            validator_config,
+            block,
+            &Query {
+                exec_cmd: &exec_cmd,
+                sql: query_sql,
+                expected_exit,
+            },
+            chapter_name,
+            profile,
+        )
+        .await?;
+        let host = Self::check_output(
+            block,
+            chapter_name,
+            &block_checks,
+            &query_result,
+            validator_config.failure_message_template.as_deref(),
+            profile,
+        )?;
+        Ok(BlockRun {
+            exit_codes: ExitCodes {
+                query: query_result.exit_code,
+                host,
+            },
+            output: query_result.stdout,
+        })
+    }
+
+    /// Path of the validator script run on the host, checked to exist.
+    fn validator_script(
+        validator_config: &ValidatorConfig,
+        book_root: &Path,
+    ) -> Result<String, Error> {
+        let script_path = book_root.join(&validator_config.script);
+        if !script_path.exists() {
+            return Err(Error::msg(format!(
+                "Failed to read validator script '{}': file not found",
+                script_path.display()
+            )));
+        }
+        debug!(script = %script_path.display(), "Using validator script");
+        script_path
+            .to_str()
+            .map(str::to_owned)
+            .ok_or_else(|| Error::msg(format!("Invalid script path: {}", script_path.display())))
+    }
+
+    /// The block's query as sent to the container: `@@` prefixes stripped (but
+    /// line content kept), then normalized and run through the validator's pipeline.
+    fn query_content(
+        validator_config: &ValidatorConfig,
+        block: &ValidatorBlock,
+        chapter_name: &str,
+    ) -> Result<String, Error> {
+        let content = validator_config
+            .normalize
+            .apply(&block.markers.validation_content());
+        Transform::apply_all(&validator_config.pipeline, &content).map_err(|name| {
+            ValidatorError::UndefinedVariable {
+                name,
+                chapter: chapter_name.to_owned(),
             }
+            .into()
+        })
+    }
+
+    /// The validator's `implicit_assertions`, which stand in only for a block
+    /// that declares none of its own.
+    fn implicit_assertions(
+        validator_config: &ValidatorConfig,
+        block: &ValidatorBlock,
+    ) -> Option<String> {
+        let declares_assertions = block.markers.assertions.is_some()
+            || block.markers.assert_toml.is_some()
+            || !block.markers.section_assertions.is_empty();
+        if declares_assertions || validator_config.implicit_assertions.is_empty() {
+            None
+        } else {
+            Some(validator_config.implicit_assertions.join("\n"))
         }
+    }
 
-        // Clean up any excessive blank lines left by deletions
-        Self::normalize_blank_lines(&result)
+    /// The shell command a block's query is piped to: the validator's exec
+    /// command (or its default) with the block's `args=`, run in its `cwd=`.
+    fn exec_command(
+        validator_config: &ValidatorConfig,
+        block: &ValidatorBlock,
+        db_path: &str,
+    ) -> String {
+        let mut exec_cmd = Self::get_exec_command(&block.validator_name, validator_config, db_path);
+        for arg in &block.args {
+            exec_cmd.push(' ');
+            exec_cmd.push_str(&shell_quote(arg));
+        }
+        if let Some(cwd) = &block.cwd {
+            exec_cmd = format!("cd {} && {exec_cmd}", shell_quote(cwd));
+        }
+        exec_cmd
     }
 
-    /// Normalize blank lines: collapse 3+ consecutive newlines to 2, trim edges
-    fn normalize_blank_lines(content: &str) -> String {
-        let mut result = String::with_capacity(content.len());
-        let mut consecutive_newlines = 0;
+    /// Run the block's `<!--SETUP-->` script, unless `cache_setup` has already
+    /// run the same script in this container.
+    ///
+    /// SETUP content IS the shell script - run as a file via sh (no interpolation).
+    async fn run_setup(
+        container: &ValidatorContainer,
+        validator_config: &ValidatorConfig,
+        block: &ValidatorBlock,
+        chapter_name: &str,
+        db_path: &str,
+        profile: &mut Profile,
+    ) -> Result<(), Error> {
+        let Some(setup) = &block.markers.setup else {
+            return Ok(());
+        };
+        let setup_script = setup.replace(DB_PLACEHOLDER, db_path);
+        let setup_script = setup_script.trim();
+        if setup_script.is_empty() {
+            return Ok(());
+        }
+        if validator_config.cache_setup && container.has_run_setup(setup_script) {
+            debug!("Skipping SETUP script, already run in this container (cache_setup)");
+            return Ok(());
+        }
 
-        for ch in content.chars() {
-            if ch == '\n' {
-                consecutive_newlines += 1;
-                if consecutive_newlines <= 2 {
-                    result.push(ch);
-                }
-            } else {
-                consecutive_newlines = 0;
-                result.push(ch);
+        debug!("Running SETUP script");
+        trace!(setup = %setup_script, "SETUP content");
+        // The script reaches the container over stdin, so it's bounded by the
+        // block's timeout like the query
+        let timeout = validator_config.effective_timeout(block.timeout);
+        let started = Instant::now();
+        let setup_result =
+            tokio::time::timeout(timeout, container.exec_script("sh", setup_script)).await;
+        profile.record(&block.validator_name, Phase::Setup, started.elapsed());
+        let setup_result = setup_result
+            .map_err(|_| ValidatorError::Timeout {
+                phase: "Setup".to_owned(),
+                validator: block.validator_name.clone(),
+                seconds: timeout.as_secs(),
+                message: format!(
+                    "in '{}' block {}:\n\nScript:\n{}",
+                    chapter_name, block.index, setup_script
+                ),
+            })?
+            .map_err(|e| error::with_context(e, "Setup exec failed"))?;
+
+        if setup_result.exit_code != 0 {
+            #[allow(clippy::cast_possible_truncation)]
+            return Err(ValidatorError::SetupFailed {
+                exit_code: setup_result.exit_code as i32,
+                message: format!(
+                    "in '{}' (validator: {}):\n\nScript:\n{}\n\nError:\n{}",
+                    chapter_name, block.validator_name, setup_script, setup_result.stderr
+                ),
             }
+            .into());
         }
-
-        result.trim().to_owned()
+        container.record_setup(setup_script);
+        Ok(())
     }
-}
 
-/// A code block that requires validation
-struct ValidatorBlock {
-    /// Name of the validator (e.g., "osquery", "sqlite")
-    validator_name: String,
-    /// Extracted markers from the code block
-    markers: ExtractedMarkers,
-    /// Whether to skip validation
-    skip: bool,
-    /// Whether to hide the block from output (but still validate)
-    hidden: bool,
-}
+    /// Run a block split into named sections.
+    ///
+    /// Each `-- @name` section of `query.sql` runs as its own query, in order, and
+    /// its output is checked against the matching `<!--ASSERT:name-->`. Block-level
+    /// ASSERT/EXPECT/EXPECT_REGEX (`block_checks`) check the last section's output,
+    /// and EXPECT-EXIT applies to the last section's query.
+    async fn run_sections(
+        container: &ValidatorContainer,
+        validator_config: &ValidatorConfig,
+        block: &ValidatorBlock,
+        chapter_name: &str,
+        query: &Query<'_>,
+        block_checks: &OutputChecks<'_>,
+        profile: &mut Profile,
+    ) -> Result<BlockRun, Error> {
+        let (preamble, sections) = split_sections(query.sql);
+        if let Some((name, _)) = block
+            .markers
+            .section_assertions
+            .iter()
+            .find(|(name, _)| !sections.iter().any(|section| section.name == *name))
+        {
+            return Err(ValidatorError::UnknownSection { name: name.clone() }.into());
+        }
 
-#[cfg(test)]
-#[allow(clippy::needless_raw_string_hashes)]
-mod tests {
-    use super::*;
+        let preamble = preamble.trim();
+        if !preamble.is_empty() {
+            // Exit status is checked by run_query; the preamble's output is not asserted on
+            let _preamble = Self::run_query(
+                container,
+                validator_config,
+                block,
+                &Query {
+                    sql: preamble,
+                    expected_exit: 0,
+                    ..*query
+                },
+                chapter_name,
+                profile,
+            )
+            .await?;
+        }
+
+        let mut run = BlockRun {
+            exit_codes: ExitCodes::default(),
+            output: String::new(),
+        };
+        for (idx, section) in sections.iter().enumerate() {
+            debug!(section = %section.name, "Running section");
+            let is_last = idx + 1 == sections.len();
+            let query_result = Self::run_query(
+                container,
+                validator_config,
+                block,
+                &Query {
+                    sql: section.content.trim(),
+                    expected_exit: if is_last { query.expected_exit } else { 0 },
+                    ..*query
+                },
+                chapter_name,
+                profile,
+            )
+            .await?;
+
+            let assertions = block
+                .markers
+                .section_assertions
+                .iter()
+                .find(|(name, _)| *name == section.name)
+                .map(|(_, assertions)| assertions.as_str());
+            if assertions.is_some() {
+                let section_checks = OutputChecks {
+                    section: Some(&section.name),
+                    code: &section.content,
+                    assertions,
+                    assert_toml: None,
+                    expect: &[],
+                    expect_regex: None,
+                    ..*block_checks
+                };
+                Self::check_output(
+                    block,
+                    chapter_name,
+                    &section_checks,
+                    &query_result,
+                    validator_config.failure_message_template.as_deref(),
+                    profile,
+                )?;
+            }
+            if is_last {
+                let host = Self::check_output(
+                    block,
+                    chapter_name,
+                    block_checks,
+                    &query_result,
+                    validator_config.failure_message_template.as_deref(),
+                    profile,
+                )?;
+                run = BlockRun {
+                    exit_codes: ExitCodes {
+                        query: query_result.exit_code,
+                        host,
+                    },
+                    output: query_result.stdout,
+                };
+            }
+        }
+
+        Ok(run)
+    }
+
+    /// The exit code the block's query must exit with: `<!--EXPECT-EXIT N-->`, or 0.
+    fn expected_exit_code(block: &ValidatorBlock, chapter_name: &str) -> Result<i64, Error> {
+        let Some(raw) = &block.markers.expect_exit else {
+            return Ok(0);
+        };
+        raw.parse().map_err(|_| {
+            Error::msg(format!(
+                "Invalid EXPECT-EXIT in '{}' (validator: {}): '{raw}' is not an exit code",
+                chapter_name, block.validator_name
+            ))
+        })
+    }
+
+    /// Check the block's `max_memory` budgets against the validator's `memory_limit`.
+    ///
+    /// The container's limit is what enforces a budget, so a block whose budget
+    /// is below the limit (or that has no limit to rely on) is a config error.
+    fn check_memory_budget(
+        validator_config: &ValidatorConfig,
+        block: &ValidatorBlock,
+        chapter_name: &str,
+    ) -> Result<(), Error> {
+        // Line by line, so script-specific assertions the parser doesn't know are skipped
+        let assertions = block
+            .markers
+            .assertions
+            .as_deref()
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| parse_assertions(line).ok())
+            .flatten();
+        let limit = validator_config.memory_limit_bytes(&block.validator_name)?;
+        for assertion in assertions {
+            let Assertion::MaxMemory(size) = assertion else {
+                continue;
+            };
+            let budget = parse_memory_size(&size).map_err(Error::msg)?;
+            if !limit.is_some_and(|limit| limit <= budget) {
+                return Err(ValidatorError::InvalidConfig {
+                    name: block.validator_name.clone(),
+                    reason: format!(
+                        "max_memory \"{size}\" in '{chapter_name}' needs memory_limit set to at most {size}, got {}",
+                        validator_config.memory_limit.as_deref().unwrap_or("none")
+                    ),
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Run one query in the container, with the validator's timeout and retries.
+    ///
+    /// Content is passed via stdin to avoid shell injection. Any exit code other
+    /// than the query's `expected_exit` fails (and is retried).
+    async fn run_query(
+        container: &ValidatorContainer,
+        validator_config: &ValidatorConfig,
+        block: &ValidatorBlock,
+        query: &Query<'_>,
+        chapter_name: &str,
+        profile: &mut Profile,
+    ) -> Result<ValidationResult, Error> {
+        let Query {
+            exec_cmd,
+            sql: query_sql,
+            expected_exit,
+        } = *query;
+        debug!("Executing query in container");
+        trace!(query = %query_sql, "Query content");
+
+        // Pass content via stdin (secure) instead of shell interpolation (vulnerable).
+        // A failing query is re-run up to `retries` times; a timed-out one is not,
+        // since another attempt would only hang for the same timeout again.
+        let timeout = validator_config.effective_timeout(block.timeout);
+        let retries = validator_config.effective_retries(block.retry);
+        let started = Instant::now();
+        let mut attempt = 0;
+        let query_result = loop {
+            let outcome = match tokio::time::timeout(
+                timeout,
+                container.exec_with_stdin(&["sh", "-c", exec_cmd], query_sql),
+            )
+            .await
+            {
+                Ok(Ok(result)) => {
+                    trace!(exit_code = result.exit_code, stdout = %result.stdout, stderr = %result.stderr, "Query result");
+                    // Killed at the memory limit: re-running won't fit it any better. A
+                    // SIGKILL exit is only an OOM kill if Docker says so, and never when
+                    // the block expects that exit code.
+                    if let (OOM_EXIT_CODE, Some(limit)) =
+                        (result.exit_code, &validator_config.memory_limit)
+                    {
+                        if expected_exit != OOM_EXIT_CODE && container.was_oom_killed().await {
+                            profile.record(&block.validator_name, Phase::Query, started.elapsed());
+                            return Err(ValidatorError::OutOfMemory {
+                                limit: limit.clone(),
+                                message: format!(
+                                    "query killed in '{}' (validator: {}):\n\nSQL:\n{}",
+                                    chapter_name, block.validator_name, query_sql
+                                ),
+                            }
+                            .into());
+                        }
+                    }
+                    if result.exit_code == expected_exit {
+                        Ok(result)
+                    } else if expected_exit == 0 {
+                        Err(Error::msg(format!(
+                            "Query failed in '{}' (validator: {}):\n\nSQL:\n{}\n\nError:\n{}",
+                            chapter_name, block.validator_name, query_sql, result.stderr
+                        )))
+                    } else {
+                        Err(Error::msg(format!(
+                            "Query exited with {} in '{}' (validator: {}), expected {} (EXPECT-EXIT):\n\nSQL:\n{}\n\nError:\n{}",
+                            result.exit_code,
+                            chapter_name,
+                            block.validator_name,
+                            expected_exit,
+                            query_sql,
+                            result.stderr
+                        )))
+                    }
+                }
+                Ok(Err(e)) => Err(error::with_context(e, "Query exec failed")),
+                Err(_) => Err(ValidatorError::Timeout {
+                    phase: "Query".to_owned(),
+                    validator: block.validator_name.clone(),
+                    seconds: timeout.as_secs(),
+                    message: format!(
+                        "in '{}' block {}:\n\nSQL:\n{}",
+                        chapter_name, block.index, query_sql
+                    ),
+                }
+                .into()),
+            };
+            match outcome {
+                Ok(result) => break result,
+                Err(e)
+                    if attempt < retries
+                        && !matches!(e.downcast_ref(), Some(ValidatorError::Timeout { .. })) =>
+                {
+                    attempt += 1;
+                    warn!(attempt, retries, error = %e, "Query failed, retrying");
+                }
+                Err(e) => {
+                    profile.record(&block.validator_name, Phase::Query, started.elapsed());
+                    return Err(e);
+                }
+            }
+        };
+        profile.record(&block.validator_name, Phase::Query, started.elapsed());
+        Ok(query_result)
+    }
+
+    /// Check a query's output: the block's `filter=`, then the validator script and
+    /// host-side checks.
+    ///
+    /// Returns the validator script's exit code. A failure's message is rendered
+    /// from `failure_template` when one is configured.
+    fn check_output(
+        block: &ValidatorBlock,
+        chapter_name: &str,
+        checks: &OutputChecks<'_>,
+        query_result: &ValidationResult,
+        failure_template: Option<&str>,
+        profile: &mut Profile,
+    ) -> Result<i64, Error> {
+        let location = match checks.section {
+            Some(section) => format!(
+                "in '{}' (validator: {}, section: {})",
+                chapter_name, block.validator_name, section
+            ),
+            None => format!(
+                "in '{}' (validator: {})",
+                chapter_name, block.validator_name
+            ),
+        };
+
+        let output = Self::filtered_output(block, checks, query_result, &location)?;
+
+        // 3. Validate JSON output on host using validator script
+        let script_expect = Self::script_expect(block, checks.expect);
+        let host_expect = if script_expect.is_none() {
+            checks.expect
+        } else {
+            &[]
+        };
+
+        // ASSERT_TOML is evaluated on the host (step 4); reject a malformed one before running anything
+        let toml_assertions = checks
+            .assert_toml
+            .map(TomlAssertions::parse)
+            .transpose()
+            .map_err(|e| Error::msg(format!("Invalid ASSERT_TOML {location}: {e}")))?;
+
+        // `json_length` and `contains_json` are evaluated on the host (step 4), the rest by the script
+        let (script_assertions, host_assertions) = checks
+            .assertions
+            .map(host_validator::split_host_assertions)
+            .unwrap_or_default();
+
+        let started = Instant::now();
+        let validation_result = Self::run_script_checks(
+            checks,
+            &output,
+            &query_result.stderr,
+            script_assertions.as_deref(),
+            script_expect,
+        )
+        .map_err(|e| Error::msg(format!("Host validator failed {location}: {e}")))?;
+        profile.record(
+            &block.validator_name,
+            Phase::HostValidation,
+            started.elapsed(),
+        );
+        trace!(exit_code = validation_result.exit_code, stdout = %validation_result.stdout, stderr = %validation_result.stderr, "Validator result");
+
+        // 4. Check `json_length`/`contains_json`, EXPECT templates, EXPECT_REGEX and ASSERT_TOML on the host
+        // (native, no validator script). These run even if the script failed, so all failures are reported together.
+        let host_failures: Vec<_> = host_assertions
+            .iter()
+            .map(|assertion| host_validator::check_host_assertion(assertion, &output))
+            .chain(
+                [
+                    Self::check_host_expect(block, host_expect, &output),
+                    checks
+                        .expect_regex
+                        .map(|pattern| host_validator::check_expect_regex(pattern, &output)),
+                    toml_assertions
+                        .as_ref()
+                        .map(|assertions| assertions.check(&output)),
+                ]
+                .into_iter()
+                .flatten(),
+            )
+            .filter(|result| result.exit_code != 0)
+            .collect();
+
+        if validation_result.exit_code == 0 && host_failures.is_empty() {
+            return Ok(i64::from(validation_result.exit_code));
+        }
+        Err(Self::validation_failure(
+            block,
+            chapter_name,
+            checks,
+            &location,
+            &validation_result,
+            &host_failures,
+            failure_template,
+        ))
+    }
+
+    /// The output assertions and EXPECT see: stdout (plus stderr with
+    /// `assert_target=combined`), projected through the block's `filter=`.
+    fn filtered_output(
+        block: &ValidatorBlock,
+        checks: &OutputChecks<'_>,
+        query_result: &ValidationResult,
+        location: &str,
+    ) -> Result<String, Error> {
+        let input = host_validator::assertion_input(
+            block.assert_target,
+            &query_result.stdout,
+            &query_result.stderr,
+        );
+        let Some(filter) = &block.filter else {
+            return Ok(input.into_owned());
+        };
+
+        let filtered = host_validator::apply_filter(filter, &input)
+            .map_err(|e| Error::msg(format!("Filter failed {location}: {e}")))?;
+        if filtered.exit_code != 0 {
+            return Err(ValidatorError::ValidationFailed {
+                exit_code: filtered.exit_code,
+                message: format!(
+                    "{location}:\n\nCode:\n{}\n\n{}",
+                    checks.code, filtered.stderr
+                ),
+            }
+            .into());
+        }
+        trace!(output = %filtered.stdout, "Filtered output");
+        Ok(filtered.stdout)
+    }
+
+    /// The EXPECT compared by the validator script, if any.
+    ///
+    /// A single plain EXPECT is compared by the script. EXPECT with `<*>` wildcards,
+    /// `expect_ignore=` fields, `expect_unordered` or `expect_json`, and several
+    /// EXPECTs (one per result set), are matched on the host.
+    fn script_expect<'a>(block: &ValidatorBlock, expect: &'a [String]) -> Option<&'a str> {
+        match expect {
+            [expect]
+                if !expect.contains(host_validator::EXPECT_WILDCARD)
+                    && block.expect_ignore.is_empty()
+                    && block.expect_mode == ExpectMode::Exact =>
+            {
+                Some(expect.as_str())
+            }
+            _ => None,
+        }
+    }
+
+    /// Check assertions (and a plain EXPECT) with the validator script, or in
+    /// Rust with `native_assertions` when the script would only check assertions
+    /// that can be evaluated natively, skipping the script (and jq).
+    fn run_script_checks(
+        checks: &OutputChecks<'_>,
+        output: &str,
+        query_stderr: &str,
+        script_assertions: Option<&str>,
+        script_expect: Option<&str>,
+    ) -> Result<HostValidationResult, Error> {
+        let container_stderr =
+            host_validator::filter_allowed_warnings(query_stderr, checks.allow_warnings);
+        if checks.native_assertions && script_expect.is_none() {
+            if let Some(result) = host_validator::check_native_assertions(
+                script_assertions,
+                output,
+                &container_stderr,
+            ) {
+                debug!("Evaluated assertions natively");
+                return Ok(result);
+            }
+        }
+
+        debug!("Running host validator");
+        host_validator::run_validator(
+            &RealCommandRunner,
+            checks.script_path,
+            output,
+            script_assertions,
+            script_expect,
+            Some(&container_stderr), // Pass container stderr for warning detection
+        )
+    }
+
+    /// Match EXPECT templates the script doesn't handle: one template against the
+    /// whole output, or several against its result sets, honoring `expect_ignore=`,
+    /// `expect_unordered` and `expect_json`.
+    fn check_host_expect(
+        block: &ValidatorBlock,
+        expect: &[String],
+        output: &str,
+    ) -> Option<HostValidationResult> {
+        let ignore = &block.expect_ignore;
+        Some(match (expect, block.expect_mode) {
+            ([], _) => return None,
+            ([template], ExpectMode::Exact) => {
+                host_validator::check_expect_ignoring(template, output, ignore)
+            }
+            ([template], ExpectMode::Unordered) => {
+                host_validator::check_expect_unordered(template, output, ignore)
+            }
+            ([template], ExpectMode::Json) => {
+                host_validator::check_expect_json(template, output, ignore)
+            }
+            (templates, ExpectMode::Exact) => {
+                host_validator::check_expect_documents(templates, output, ignore)
+            }
+            (templates, ExpectMode::Unordered) => {
+                host_validator::check_expect_documents_unordered(templates, output, ignore)
+            }
+            (templates, ExpectMode::Json) => {
+                host_validator::check_expect_documents_json(templates, output, ignore)
+            }
+        })
+    }
+
+    /// The error for a block whose script or host checks failed, rendered with
+    /// the validator's `failure_message_template` if it has one.
+    fn validation_failure(
+        block: &ValidatorBlock,
+        chapter_name: &str,
+        checks: &OutputChecks<'_>,
+        location: &str,
+        validation_result: &HostValidationResult,
+        host_failures: &[HostValidationResult],
+        failure_template: Option<&str>,
+    ) -> Error {
+        let mut details = String::new();
+        if validation_result.exit_code != 0 {
+            if !validation_result.stderr.is_empty() {
+                let _ = write!(details, "\nValidator stderr:\n{}", validation_result.stderr);
+            }
+            if !validation_result.stdout.is_empty() {
+                let _ = write!(details, "\nValidator stdout:\n{}", validation_result.stdout);
+            }
+        }
+        for failure in host_failures {
+            let _ = write!(details, "\n{}\n", failure.stderr);
+        }
+
+        let exit_code = std::iter::once(validation_result.exit_code)
+            .chain(host_failures.iter().map(|result| result.exit_code))
+            .find(|code| *code != 0)
+            .unwrap_or(1);
+        let error_msg = match failure_template {
+            Some(template) => render_template(
+                template,
+                &[
+                    ("chapter", chapter_name),
+                    ("validator", &block.validator_name),
+                    ("section", checks.section.unwrap_or_default()),
+                    ("code", checks.code),
+                    ("exit_code", &exit_code.to_string()),
+                    ("stderr", details.trim()),
+                ],
+            ),
+            None => format!("{location}:\n\nCode:\n{}\n{details}", checks.code),
+        };
+        ValidatorError::ValidationFailed {
+            exit_code,
+            message: error_msg,
+        }
+        .into()
+    }
+
+    /// Get exec command for a validator.
+    ///
+    /// Uses configured command if available, otherwise uses defaults based on validator name.
+    /// `{db}` in the command is replaced with `db_path`.
+    fn get_exec_command(validator_name: &str, config: &ValidatorConfig, db_path: &str) -> String {
+        config
+            .resolved_exec_command(validator_name)
+            .replace(DB_PLACEHOLDER, db_path)
+    }
+
+    /// Warn when a validator runs blocks through [`DEFAULT_EXEC_FALLBACK`].
+    ///
+    /// `cat` only echoes the block back, so such a validator checks nothing about
+    /// the content unless its script does. Returns whether the fallback is used.
+    fn warn_if_fallback_exec(validator_name: &str, config: &ValidatorConfig) -> bool {
+        let fallback =
+            config.exec_command.is_none() && !matches!(validator_name, "sqlite" | "osquery");
+        if fallback {
+            warn!(
+                validator = %validator_name,
+                "No exec_command configured for '{validator_name}'; blocks are passed through `{DEFAULT_EXEC_FALLBACK}`, so the container does not run them. Set exec_command to the tool that should execute the content"
+            );
+        }
+        fallback
+    }
+
+    /// With `require_docker`, fail before any block runs if Docker isn't available.
+    fn check_docker_available<C: DependencyChecker>(
+        checker: &C,
+        config: &Config,
+    ) -> Result<(), ValidatorError> {
+        if config.require_docker_enabled() && !dependency::check_docker(checker) {
+            return Err(ValidatorError::DockerUnavailable);
+        }
+        Ok(())
+    }
+
+    /// With `pull_via_cli`, pull `image` through the Docker CLI. Returns whether it was pulled.
+    fn pull_image_if_configured<R: CommandRunner>(
+        runner: &R,
+        config: &Config,
+        image: &str,
+    ) -> Result<bool, ValidatorError> {
+        if !config.pull_via_cli {
+            return Ok(false);
+        }
+        pull_image_via_cli(runner, image).map_err(|e| ValidatorError::ContainerStartup {
+            message: format!("image '{image}': {e:#}"),
+        })?;
+        Ok(true)
+    }
+
+    /// Warn about each configured validator that no block referenced.
+    ///
+    /// Returns the unused names, sorted.
+    fn warn_unused_validators(config: &Config, used: &HashSet<String>) -> Vec<String> {
+        let unused: BTreeSet<&String> = config
+            .validators
+            .keys()
+            .filter(|name| !used.contains(*name))
+            .collect();
+        for name in &unused {
+            warn!(
+                validator = %name,
+                "Validator '{name}' is configured but no block uses it; remove it from book.toml or add validator={name} to a block"
+            );
+        }
+        unused.into_iter().cloned().collect()
+    }
+
+    /// Temp database path substituted for `{db}`.
+    ///
+    /// Blocks with the same `session=` share a path; otherwise each block gets its
+    /// own, derived from the chapter and the block's position in it. Containers are
+    /// shared across chapters, so the chapter is part of the name. The path is also
+    /// namespaced by the (short) container id, so state never carries over if a
+    /// container's `/tmp` is reused or shared, e.g. by a restarted or parallel container.
+    fn temp_db_path(container_id: &str, chapter_name: &str, block: &ValidatorBlock) -> String {
+        let key = block.session.as_ref().map_or_else(
+            || format!("{chapter_name}-{}", block.index),
+            |session| format!("session-{session}"),
+        );
+        let key: String = key
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let short_id: String = container_id.chars().take(12).collect();
+        format!("/tmp/mdbook-validator-{short_id}-{key}.db")
+    }
+
+    /// Get an existing container or start a new one for the given validator.
+    ///
+    /// A cached container that is no longer running (crash, OOM kill) is
+    /// replaced with a fresh one instead of failing every remaining block.
+    async fn get_or_start_container<'a>(
+        &self,
+        validator_name: &str,
+        config: &Config,
+        book_root: &Path,
+        containers: &'a mut HashMap<String, ValidatorContainer>,
+        profile: &mut Profile,
+    ) -> Result<&'a ValidatorContainer, Error> {
+        if let Some(cached) = containers.get(validator_name) {
+            if !cached.is_running().await {
+                warn!(
+                    validator = %validator_name,
+                    container_id = %cached.id(),
+                    "Cached container is no longer running, starting a new one"
+                );
+                containers.remove(validator_name);
+            }
+        }
+
+        match containers.entry(validator_name.to_owned()) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => {
+                // Look up validator config
+                let validator_config = config.get_validator(validator_name)?;
+
+                // Validate config values
+                validator_config.validate(validator_name)?;
+                Self::warn_if_fallback_exec(validator_name, validator_config);
+
+                let mount = Self::fixtures_mount(config, book_root)?;
+                let options = Self::container_options(
+                    config,
+                    validator_name,
+                    validator_config,
+                    mount.as_deref(),
+                )?;
+                let started = Instant::now();
+                Self::pull_image_if_configured(
+                    &RealCommandRunner,
+                    config,
+                    &validator_config.container,
+                )?;
+                let container = ValidatorContainer::start_raw_with_options(
+                    &validator_config.container,
+                    &options,
+                )
+                .await
+                .map_err(|e| ValidatorError::ContainerStartup {
+                    message: format!("image '{}': {e:#}", validator_config.container),
+                })?;
+
+                if let Some(digest) = ImageReference::parse(&validator_config.container).digest {
+                    container.verify_digest(&digest).await?;
+                }
+                if let Some(expect_version) = &validator_config.expect_version {
+                    Self::check_tool_version(&container, validator_name, expect_version).await?;
+                }
+                profile.record(validator_name, Phase::ContainerStartup, started.elapsed());
+
+                Ok(entry.insert(container))
+            }
+        }
+    }
+
+    /// Options a validator's container is started with: the fixtures mount (if
+    /// any) and label, networking off unless allowed.
+    pub(crate) fn container_options(
+        config: &Config,
+        validator_name: &str,
+        validator_config: &ValidatorConfig,
+        mount: Option<&Path>,
+    ) -> Result<ContainerOptions, Error> {
+        Ok(ContainerOptions {
+            mount: mount.map(|fixtures_path| (fixtures_path.to_path_buf(), "/fixtures".to_owned())),
+            allow_network: validator_config.allow_network,
+            entrypoint: validator_config.entrypoint.clone(),
+            label: config.container_label.clone(),
+            memory_limit: validator_config.memory_limit_bytes(validator_name)?,
+            env: validator_config.env.clone(),
+        })
+    }
+
+    /// Resolve and validate `fixtures_dir`, relative to `book_root`, into the
+    /// real path mounted at `/fixtures`.
+    fn fixtures_mount(config: &Config, book_root: &Path) -> Result<Option<PathBuf>, Error> {
+        let Some(ref fixtures_dir) = config.fixtures_dir else {
+            return Ok(None);
+        };
+
+        // Resolve relative path from book_root
+        let fixtures_path = if fixtures_dir.is_absolute() {
+            fixtures_dir.clone()
+        } else {
+            book_root.join(fixtures_dir)
+        };
+
+        // Validate fixtures_dir exists and is a directory
+        if !fixtures_path.exists() {
+            return Err(ValidatorError::FixturesError {
+                message: format!("fixtures_dir '{}' does not exist", fixtures_path.display()),
+            }
+            .into());
+        }
+        if !fixtures_path.is_dir() {
+            return Err(ValidatorError::FixturesError {
+                message: format!(
+                    "fixtures_dir '{}' is not a directory",
+                    fixtures_path.display()
+                ),
+            }
+            .into());
+        }
+
+        // Canonicalize to resolve symlinks (Docker requires real paths)
+        let fixtures_path =
+            fixtures_path
+                .canonicalize()
+                .map_err(|e| ValidatorError::FixturesError {
+                    message: format!(
+                        "fixtures_dir '{}' could not be canonicalized: {e}",
+                        fixtures_path.display()
+                    ),
+                })?;
+
+        Ok(Some(fixtures_path))
+    }
+
+    /// Run the validator's version probe in a freshly started container.
+    ///
+    /// The probe's stdout and stderr are searched together, since some tools
+    /// print their version on stderr.
+    async fn check_tool_version(
+        container: &ValidatorContainer,
+        validator_name: &str,
+        expect_version: &ExpectVersion,
+    ) -> Result<(), Error> {
+        let result = container
+            .exec_raw(&["sh", "-c", &expect_version.command])
+            .await
+            .map_err(|e| ValidatorError::ContainerExec {
+                message: format!("version probe for '{validator_name}': {e:#}"),
+            })?;
+        let output = format!("{}{}", result.stdout, result.stderr);
+        debug!(validator = %validator_name, version = %output.trim(), "Tool version");
+
+        if result.exit_code != 0 || !output.contains(&expect_version.contains) {
+            return Err(ValidatorError::VersionMismatch {
+                name: validator_name.to_owned(),
+                command: expect_version.command.clone(),
+                expected: expect_version.contains.clone(),
+                actual: output.trim().to_owned(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Scan chapter content for fenced code blocks.
+    ///
+    /// Each fence's info string is parsed exactly once here; both validation
+    /// ([`Self::find_validator_blocks`]) and output stripping
+    /// ([`Self::strip_markers_from_chapter`]) work from the returned list. The
+    /// chapter's default validator is filled in here too, so both treat a bare
+    /// fence it covers like one with `validator=`.
+    fn scan_fenced_blocks(
+        content: &str,
+        language_defaults: &HashMap<String, String>,
+    ) -> Vec<FencedBlock> {
+        let mut fences = Vec::new();
+        let parser = Parser::new(content).into_offset_iter();
+        let default_validator = DefaultValidator::find(content, language_defaults);
+
+        let mut current: Option<FencedBlock> = None;
+
+        for (event, range) in parser {
+            match event {
+                Event::Start(Tag::CodeBlock(CodeBlockKind::Fenced(info))) => {
+                    let mut attrs = parse_block_attributes(&info);
+                    // `validator=` wins, then the chapter's default, then the book's
+                    // default for the language
+                    if attrs.validator.is_none() {
+                        attrs.validator = default_validator
+                            .as_ref()
+                            .filter(|default| default.applies_to(&attrs.language))
+                            .map(|default| default.name.clone())
+                            .or_else(|| language_defaults.get(&attrs.language).cloned());
+                    }
+                    current = Some(FencedBlock {
+                        range,
+                        content_range: None,
+                        text: String::new(),
+                        attrs,
+                    });
+                }
+                Event::Text(text) => {
+                    if let Some(fence) = current.as_mut() {
+                        fence.text.push_str(&text);
+                        // Cover every text event in the block, not just the last one
+                        fence.content_range = Some(match fence.content_range.take() {
+                            Some(existing) => existing.start..range.end,
+                            None => range,
+                        });
+                    }
+                }
+                Event::End(TagEnd::CodeBlock) => {
+                    if let Some(mut fence) = current.take() {
+                        fence.range.end = range.end;
+                        fences.push(fence);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        fences
+    }
+
+    /// Find all code blocks with `validator=` attribute, or covered by the
+    /// chapter's `<!--VALIDATOR_DEFAULT-->`
+    fn find_validator_blocks(
+        content: &str,
+        language_defaults: &HashMap<String, String>,
+    ) -> Vec<ValidatorBlock> {
+        Self::scan_fenced_blocks(content, language_defaults)
+            .into_iter()
+            .filter_map(|mut fence| {
+                // Only process blocks with validator= attribute
+                let validator_name = fence.attrs.validator.take()?;
+                Some((validator_name, fence))
+            })
+            .enumerate()
+            .map(|(idx, (validator_name, fence))| ValidatorBlock {
+                line: content[..fence.range.start].matches('\n').count() + 1,
+                ..ValidatorBlock::new(idx + 1, validator_name, &fence.text, fence.attrs)
+            })
+            .collect()
+    }
+
+    /// Strip all validation markers from chapter content, preserving code block structure.
+    ///
+    /// Uses span-based editing to surgically modify only code block contents,
+    /// preserving ALL other markdown formatting (lists, links, emphasis, etc.).
+    ///
+    /// If a code block has the `hidden` attribute, the entire fence is removed from output.
+    /// Other validator blocks lose their markers and, on the opening line, this
+    /// preprocessor's attributes ([`Self::strip_internal_fence_attributes`]); all
+    /// edits come from one scan of the chapter.
+    ///
+    /// Each stripped block is self-checked (see [`Self::check_strip_invariants`]) so a
+    /// stripping bug fails the build instead of silently publishing broken markdown.
+    fn strip_markers_from_chapter(
+        content: &str,
+        language_defaults: &HashMap<String, String>,
+    ) -> Result<String, ValidatorError> {
+        use std::ops::Range;
+
+        // Represents an edit to apply to the source
+        enum Edit {
+            /// Replace a range with new content (for stripping markers)
+            Replace {
+                range: Range<usize>,
+                content: String,
+            },
+            /// Delete a range entirely (for hidden blocks)
+            Delete { range: Range<usize> },
+        }
+
+        let mut edits: Vec<Edit> = Vec::new();
+
+        for fence in Self::scan_fenced_blocks(content, language_defaults) {
+            if fence.attrs.hidden {
+                // Delete the entire code block (including surrounding whitespace)
+                // Find the start of the line containing the opening fence
+                let line_start = content[..fence.range.start]
+                    .rfind('\n')
+                    .map_or(0, |i| i + 1);
+                // Find the end of the line containing the closing fence
+                let line_end = content[fence.range.end..]
+                    .find('\n')
+                    .map_or(fence.range.end, |i| fence.range.end + i + 1);
+
+                edits.push(Edit::Delete {
+                    range: line_start..line_end,
+                });
+            } else if fence.attrs.validator.is_some() {
+                if let Some((range, content)) =
+                    Self::strip_internal_fence_attributes(content, &fence)
+                {
+                    edits.push(Edit::Replace { range, content });
+                }
+                // Strip markers from the content, but preserve the fence
+                if let Some(content_range) = fence.content_range {
+                    let original_content = &content[content_range.clone()];
+                    let stripped = strip_markers(original_content);
+                    let trimmed = stripped.trim();
+                    Self::check_strip_invariants(&content[fence.range.clone()], trimmed)?;
+                    if trimmed != original_content.trim() {
+                        // Only create an edit if content actually changed
+                        edits.push(Edit::Replace {
+                            range: content_range,
+                            content: format!("{trimmed}\n"),
+                        });
+                    }
+                }
+            }
+        }
+
+        // Apply edits from end to start to preserve byte offsets
+        edits.sort_by(|a, b| {
+            let a_start = match a {
+                Edit::Replace { range, .. } | Edit::Delete { range } => range.start,
+            };
+            let b_start = match b {
+                Edit::Replace { range, .. } | Edit::Delete { range } => range.start,
+            };
+            b_start.cmp(&a_start) // Reverse order (end to start)
+        });
+
+        let mut result = content.to_owned();
+        for edit in edits {
+            match edit {
+                Edit::Replace { range, content } => {
+                    result.replace_range(range, &content);
+                }
+                Edit::Delete { range } => {
+                    result.replace_range(range, "");
+                }
+            }
+        }
+
+        // Clean up any excessive blank lines left by deletions
+        Ok(Self::normalize_blank_lines(&result))
+    }
+
+    /// The edit removing mdbook-validator's attributes from a validator block's
+    /// opening fence line, keeping the language and mdBook's own (`editable`,
+    /// `noplayground`, ...). `None` if the line is unchanged.
+    ///
+    /// A block's `caption="..."` is rendered as a blockquote line above its fence,
+    /// unless the block is `skip` and so was never validated.
+    fn strip_internal_fence_attributes(
+        content: &str,
+        fence: &FencedBlock,
+    ) -> Option<(std::ops::Range<usize>, String)> {
+        let opening = &content[fence.range.clone()];
+        let line_len = opening.find('\n').unwrap_or(opening.len());
+        let line = &opening[..line_len];
+        // Everything up to the info string: indentation and the backticks or tildes
+        let info = line.trim_start().trim_start_matches(['`', '~']);
+        let fence_start = &line[..line.len() - info.len()];
+        let mut stripped = format!("{fence_start}{}", strip_internal_attributes(info));
+        if let (Some(caption), false) = (&fence.attrs.caption, fence.attrs.skip) {
+            let indent = &line[..line.len() - line.trim_start().len()];
+            stripped = format!("{indent}> {caption}\n\n{stripped}");
+        }
+        (stripped != line).then(|| (fence.range.start..fence.range.start + line_len, stripped))
+    }
+
+    /// Verify a validator block's stripped content before it replaces the original:
+    /// no `<!--SETUP`, `<!--ASSERT` or `<!--EXPECT` marker is left, and no line of it
+    /// would close the fence early.
+    ///
+    /// `source` is the block's fence as written. A fence the author left unclosed
+    /// stays unclosed; that's their markdown, not a stripping bug.
+    fn check_strip_invariants(source: &str, stripped: &str) -> Result<(), ValidatorError> {
+        let opening = source.lines().next().unwrap_or_default().trim_start();
+        let fence_char = opening.chars().next().unwrap_or('`');
+        let fence_len = opening.chars().take_while(|c| *c == fence_char).count();
+
+        if let Some(marker) = STRIPPED_MARKERS
+            .iter()
+            .find(|marker| stripped.contains(**marker))
+        {
+            return Err(ValidatorError::StripInvariantViolation {
+                reason: format!("'{marker}' left in block '{opening}'"),
+            });
+        }
+        let closes_fence = |line: &str| {
+            let line = line.trim();
+            line.len() >= fence_len && line.chars().all(|c| c == fence_char)
+        };
+        if stripped.lines().any(closes_fence) {
+            return Err(ValidatorError::StripInvariantViolation {
+                reason: format!("stripped content closes code fence '{opening}' early"),
+            });
+        }
+        Ok(())
+    }
+
+    /// Normalize blank lines: collapse 3+ consecutive newlines to 2, trim edges
+    fn normalize_blank_lines(content: &str) -> String {
+        let mut result = String::with_capacity(content.len());
+        let mut consecutive_newlines = 0;
+
+        for ch in content.chars() {
+            if ch == '\n' {
+                consecutive_newlines += 1;
+                if consecutive_newlines <= 2 {
+                    result.push(ch);
+                }
+            } else {
+                consecutive_newlines = 0;
+                result.push(ch);
+            }
+        }
+
+        result.trim().to_owned()
+    }
+}
+
+/// Quote a word for `sh -c` so it is passed as a single literal argument.
+fn shell_quote(word: &str) -> String {
+    format!("'{}'", word.replace('\'', r"'\''"))
+}
+
+/// Replace each `{name}` in `template` with its value from `values`, in one pass
+/// (so braces inside values are never expanded). Unknown placeholders are kept.
+fn render_template(template: &str, values: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let (before, after) = rest.split_at(start);
+        out.push_str(before);
+        let value = after
+            .strip_prefix('{')
+            .and_then(|after| after.split_once('}'))
+            .and_then(|(name, tail)| {
+                values
+                    .iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| (*value, tail))
+            });
+        if let Some((value, tail)) = value {
+            out.push_str(value);
+            rest = tail;
+        } else {
+            out.push('{');
+            rest = after.strip_prefix('{').unwrap_or(after);
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// A fenced code block found in chapter source, with its info string already parsed
+struct FencedBlock {
+    /// Byte range of the whole fence, opening line through closing fence
+    range: std::ops::Range<usize>,
+    /// Byte range of the block's content (`None` for an empty block)
+    content_range: Option<std::ops::Range<usize>>,
+    /// Block content as reported by the markdown parser
+    text: String,
+    /// Attributes parsed from the info string
+    attrs: BlockAttributes,
+}
+
+/// Whether a fence's `text` is a lone `<!--OUTPUT:name-->` placeholder
+fn is_output_placeholder(text: &str) -> bool {
+    text.trim()
+        .strip_prefix(OUTPUT_PREFIX)
+        .and_then(|rest| rest.strip_suffix("-->"))
+        .is_some_and(|name| !name.contains("-->"))
+}
+
+/// A chapter's `<!--VALIDATOR_DEFAULT name [language ...]-->` directive
+struct DefaultValidator {
+    /// Validator for fences without `validator=`
+    name: String,
+    /// Fence languages the default applies to
+    languages: Vec<String>,
+}
+
+impl DefaultValidator {
+    /// The first `<!--VALIDATOR_DEFAULT-->` line outside the chapter's fences, if any.
+    ///
+    /// Without a language list, the default covers the validator's own languages:
+    /// those `language_defaults` maps to it, otherwise `sql` for the built-in SQL
+    /// validators, otherwise the language named like the validator.
+    fn find(content: &str, language_defaults: &HashMap<String, String>) -> Option<Self> {
+        // Fence content arrives as text events, so HTML events are outside fences
+        let (name, mut languages) = Parser::new(content).find_map(|event| match event {
+            Event::Html(html) | Event::InlineHtml(html) => html.lines().find_map(Self::parse_line),
+            _ => None,
+        })?;
+        if languages.is_empty() {
+            languages = language_defaults
+                .iter()
+                .filter(|(_, validator)| **validator == name)
+                .map(|(language, _)| language.clone())
+                .collect();
+            languages.sort();
+        }
+        if languages.is_empty() {
+            let own = match name.as_str() {
+                "sqlite" | "osquery" => "sql",
+                other => other,
+            };
+            languages.push(own.to_owned());
+        }
+        Some(Self { name, languages })
+    }
+
+    /// The validator name and languages of a directive line
+    fn parse_line(line: &str) -> Option<(String, Vec<String>)> {
+        let rest = line
+            .trim()
+            .strip_prefix(DEFAULT_DIRECTIVE)?
+            .strip_suffix("-->")?;
+        if !rest.starts_with(char::is_whitespace) {
+            return None;
+        }
+        let mut words = rest.split_whitespace();
+        let name = words.next()?.to_owned();
+        Some((name, words.map(ToOwned::to_owned).collect()))
+    }
+
+    /// Whether a fence tagged `language` (and without `validator=`) gets the default
+    fn applies_to(&self, language: &str) -> bool {
+        self.languages.iter().any(|l| l == language)
+    }
+}
+
+/// One query to run in a block's container
+struct Query<'a> {
+    /// Shell command the query is piped into
+    exec_cmd: &'a str,
+    sql: &'a str,
+    /// Exit code the query must exit with (`<!--EXPECT-EXIT-->`, otherwise 0)
+    expected_exit: i64,
+}
+
+/// What one query's output is checked against
+struct OutputChecks<'a> {
+    /// Validator script run on the host
+    script_path: &'a str,
+    /// Section name for `<!--ASSERT:name-->` checks, `None` for the whole block
+    section: Option<&'a str>,
+    /// Code shown in failure messages
+    code: &'a str,
+    assertions: Option<&'a str>,
+    /// Body of `<!--ASSERT_TOML-->`, parsed and checked on the host
+    assert_toml: Option<&'a str>,
+    expect: &'a [String],
+    expect_regex: Option<&'a str>,
+    /// Container warnings (validator's and block's) that don't fail validation
+    allow_warnings: &'a [String],
+    /// Evaluate supported assertions in Rust instead of the validator script
+    native_assertions: bool,
+}
+
+/// A code block that requires validation
+struct ValidatorBlock {
+    /// 1-based position among the chapter's validator blocks
+    index: usize,
+    /// 1-based line of the block's opening fence in the chapter
+    line: usize,
+    /// Name of the validator (e.g., "osquery", "sqlite")
+    validator_name: String,
+    /// Extracted markers from the code block
+    markers: ExtractedMarkers,
+    /// Whether to skip validation
+    skip: bool,
+    /// Whether to hide the block from output (but still validate)
+    hidden: bool,
+    /// Whether a container startup failure skips the block instead of failing
+    optional: bool,
+    /// Named assertion sets referenced with `use=`
+    uses: Vec<String>,
+    /// jq expression from `filter="..."`, applied to the output before validation
+    filter: Option<String>,
+    /// Extra arguments from `args="..."`, appended (shell-quoted) to the exec command
+    args: Vec<String>,
+    /// Session name from `session=`, selecting a shared `{db}` path
+    session: Option<String>,
+    /// Query timeout in seconds from `timeout=`, overriding the validator's
+    timeout: Option<u64>,
+    /// Query retry count from `retry=`, overriding the validator's
+    retry: Option<u32>,
+    /// Why a `timeout=` or `retry=` value was rejected (E008 before any block runs)
+    invalid: Vec<String>,
+    /// JSON keys from `expect_ignore=`, removed before comparing EXPECT
+    expect_ignore: Vec<String>,
+    /// How EXPECT is compared, from `expect_unordered` or `expect_json`
+    expect_mode: ExpectMode,
+    /// Allowed container warnings from `allow_warning="..."`
+    allow_warnings: Vec<String>,
+    /// Name from `capture=`, under which the block's output is kept for `<!--OUTPUT:name-->`
+    capture: Option<String>,
+    /// Output checked by assertions and EXPECT, from `assert_target=`
+    assert_target: AssertTarget,
+    /// Container directory from `cwd=`, entered before the exec command runs
+    cwd: Option<String>,
+    /// Description from `desc="..."`, shown in reports and failure messages
+    description: Option<String>,
+}
+
+/// What validating one block produced
+struct BlockRun {
+    exit_codes: ExitCodes,
+    /// The query's output (the last section's, for sectioned blocks)
+    output: String,
+}
+
+impl ValidatorBlock {
+    /// A block with code (markers included) `text` and its fence's attributes
+    fn new(index: usize, validator_name: String, text: &str, attrs: BlockAttributes) -> Self {
+        Self {
+            index,
+            line: 1,
+            validator_name,
+            markers: extract_markers(text),
+            skip: attrs.skip,
+            hidden: attrs.hidden,
+            optional: attrs.optional,
+            uses: attrs.uses,
+            filter: attrs.filter,
+            args: attrs.args,
+            session: attrs.session,
+            timeout: attrs.timeout,
+            retry: attrs.retry,
+            invalid: attrs.invalid,
+            expect_ignore: attrs.expect_ignore,
+            expect_mode: attrs.expect_mode,
+            allow_warnings: attrs.allow_warnings,
+            capture: attrs.capture,
+            assert_target: attrs.assert_target,
+            cwd: attrs.cwd,
+            description: attrs.description,
+        }
+    }
+
+    /// Report entry for this block in `chapter`: not run, not passed
+    fn report_entry(&self, chapter: &str) -> BlockResult {
+        BlockResult {
+            chapter: chapter.to_owned(),
+            block: self.index,
+            line: self.line,
+            validator: self.validator_name.clone(),
+            description: self.description.clone(),
+            container_id: String::new(),
+            exit_codes: ExitCodes::default(),
+            passed: false,
+            skipped: false,
+            hidden: self.hidden,
+            duration_ms: 0,
+            message: String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::needless_raw_string_hashes)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_EXEC_SQLITE;
+
+    // ==================== chapter_source tests ====================
+
+    #[test]
+    fn chapter_source_uses_source_path() {
+        let chapter = Chapter::new("Intro", String::new(), "intro.md", vec![]);
+        assert_eq!(ValidatorPreprocessor::chapter_source(&chapter), "intro.md");
+    }
+
+    #[test]
+    fn chapter_source_falls_back_to_name_without_source_path() {
+        let mut chapter = Chapter::new("Generated", String::new(), "generated.md", vec![]);
+        chapter.source_path = None;
+        assert_eq!(ValidatorPreprocessor::chapter_source(&chapter), "Generated");
+    }
+
+    // ==================== render_captures tests ====================
+
+    #[test]
+    fn render_captures_replaces_placeholders_with_trimmed_output() {
+        let captures = HashMap::from([("users".to_owned(), "[{\"n\":1}]\n".to_owned())]);
+        let rendered = ValidatorPreprocessor::render_captures(
+            "```json\n<!--OUTPUT:users-->\n```\n",
+            &captures,
+            "Intro",
+        )
+        .unwrap();
+        assert_eq!(rendered, "```json\n[{\"n\":1}]\n```\n");
+    }
+
+    #[test]
+    fn render_captures_leaves_placeholders_in_documentation_fences() {
+        let captures = HashMap::from([("users".to_owned(), "[]".to_owned())]);
+        let content = "````markdown\n```json\n<!--OUTPUT:users-->\n```\n````\n\n\
+                       ```markdown\nWrite <!--OUTPUT:users--> to render it.\n```\n\n\
+                       Inline: <!--OUTPUT:users-->\n";
+        let rendered = ValidatorPreprocessor::render_captures(content, &captures, "Intro").unwrap();
+        assert_eq!(
+            rendered,
+            "````markdown\n```json\n<!--OUTPUT:users-->\n```\n````\n\n\
+             ```markdown\nWrite <!--OUTPUT:users--> to render it.\n```\n\n\
+             Inline: []\n"
+        );
+    }
+
+    #[test]
+    fn render_captures_rejects_unknown_name() {
+        let err = ValidatorPreprocessor::render_captures(
+            "<!--OUTPUT:missing-->",
+            &HashMap::new(),
+            "Intro",
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), "E021");
+    }
+
+    // ==================== count_validator_blocks tests ====================
+
+    #[test]
+    fn count_validator_blocks_counts_nested_chapters_and_ignores_skip() {
+        let two_blocks =
+            "```sql validator=sqlite\nSELECT 1;\n```\n\n```sql validator=sqlite\nSELECT 2;\n```\n";
+        let mixed = "```sql validator=sqlite skip\nSELECT 1;\n```\n\n```sql\nSELECT 2;\n```\n\n```bash validator=bash-exec\necho hi\n```\n";
+
+        let mut parent = Chapter::new("Parent", two_blocks.to_owned(), "parent.md", vec![]);
+        parent.sub_items.push(BookItem::Chapter(Chapter::new(
+            "Child",
+            mixed.to_owned(),
+            "child.md",
+            vec![],
+        )));
+        let items = vec![
+            BookItem::PartTitle("Part".to_owned()),
+            BookItem::Chapter(parent),
+            BookItem::Separator,
+            BookItem::Chapter(Chapter::new(
+                "Plain",
+                "# No code".to_owned(),
+                "plain.md",
+                vec![],
+            )),
+        ];
+
+        assert_eq!(
+            ValidatorPreprocessor::count_validator_blocks(&items, &HashMap::new()),
+            3
+        );
+    }
+
+    // ==================== shell_quote tests ====================
+
+    #[test]
+    fn shell_quote_wraps_in_single_quotes() {
+        assert_eq!(shell_quote("/tmp/other.db"), "'/tmp/other.db'");
+        assert_eq!(shell_quote("a;b $(rm -rf /)"), "'a;b $(rm -rf /)'");
+    }
+
+    #[test]
+    fn shell_quote_escapes_single_quotes() {
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    }
+
+    // ==================== render_template tests ====================
+
+    #[test]
+    fn render_template_substitutes_known_placeholders() {
+        let rendered = render_template(
+            "{validator} failed in {chapter}: {stderr} (see {docs})",
+            &[
+                ("chapter", "Intro"),
+                ("validator", "sqlite"),
+                ("stderr", "{chapter}"),
+            ],
+        );
+        // Values are inserted verbatim and unknown placeholders kept
+        assert_eq!(rendered, "sqlite failed in Intro: {chapter} (see {docs})");
+    }
+
+    #[test]
+    fn render_template_keeps_unbalanced_braces() {
+        assert_eq!(render_template("a { b", &[("b", "x")]), "a { b");
+        assert_eq!(render_template("{", &[]), "{");
+    }
+
+    // ==================== temp_db_path tests ====================
+
+    #[test]
+    fn temp_db_path_is_unique_per_block_and_shared_per_session() {
+        let content = "```sql validator=sqlite\nA\n```\n\n```sql validator=sqlite\nB\n```\n\n\
+                       ```sql validator=sqlite session=orders\nC\n```\n\n\
+                       ```sql validator=sqlite session=orders\nD\n```";
+        let blocks = ValidatorPreprocessor::find_validator_blocks(content, &HashMap::new());
+        let paths: Vec<String> = blocks
+            .iter()
+            .map(|block| {
+                ValidatorPreprocessor::temp_db_path("0123456789abcdef", "My Chapter", block)
+            })
+            .collect();
+
+        assert_eq!(
+            paths[0],
+            "/tmp/mdbook-validator-0123456789ab-My_Chapter-1.db"
+        );
+        assert_eq!(
+            paths[1],
+            "/tmp/mdbook-validator-0123456789ab-My_Chapter-2.db"
+        );
+        assert_eq!(
+            paths[2],
+            "/tmp/mdbook-validator-0123456789ab-session-orders.db"
+        );
+        assert_eq!(paths[2], paths[3], "same session shares a path");
+    }
+
+    #[test]
+    fn temp_db_path_is_namespaced_by_container() {
+        let blocks = ValidatorPreprocessor::find_validator_blocks(
+            "```sql validator=sqlite session=orders\nA\n```",
+            &HashMap::new(),
+        );
+        let first = ValidatorPreprocessor::temp_db_path("aaaaaaaaaaaa", "ch", &blocks[0]);
+        let second = ValidatorPreprocessor::temp_db_path("bbbbbbbbbbbb", "ch", &blocks[0]);
+        assert_ne!(
+            first, second,
+            "same session in two containers gets two paths"
+        );
+    }
+
+    #[test]
+    fn get_exec_command_substitutes_db_placeholder() {
+        let config = ValidatorConfig {
+            exec_command: Some("sqlite3 -json {db}".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(
+            ValidatorPreprocessor::get_exec_command("sqlite", &config, "/tmp/x.db"),
+            "sqlite3 -json /tmp/x.db"
+        );
+        // The default command keeps its fixed path
+        assert_eq!(
+            ValidatorPreprocessor::get_exec_command(
+                "sqlite",
+                &ValidatorConfig::default(),
+                "/tmp/x.db"
+            ),
+            DEFAULT_EXEC_SQLITE
+        );
+    }
+
+    /// Run `f` with log output captured, returning its result and the logs
+    fn capture_logs<T>(f: impl FnOnce() -> T) -> (T, String) {
+        use std::io;
+        use std::sync::{Arc, Mutex};
+
+        #[derive(Clone, Default)]
+        struct Captured(Arc<Mutex<Vec<u8>>>);
+
+        impl io::Write for Captured {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                if let Ok(mut out) = self.0.lock() {
+                    out.extend_from_slice(buf);
+                }
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+
+        let result = tracing::subscriber::with_default(subscriber, f);
+        let logs = captured
+            .0
+            .lock()
+            .map(|out| String::from_utf8_lossy(&out).into_owned())
+            .unwrap_or_default();
+        (result, logs)
+    }
+
+    #[test]
+    fn warn_if_fallback_exec_warns_for_unknown_validator_without_exec_command() {
+        let configured = ValidatorConfig {
+            exec_command: Some("mytool --check".to_owned()),
+            ..Default::default()
+        };
+        let (fallbacks, logs) = capture_logs(|| {
+            [
+                ValidatorPreprocessor::warn_if_fallback_exec("mytool", &ValidatorConfig::default()),
+                ValidatorPreprocessor::warn_if_fallback_exec("mytool", &configured),
+                ValidatorPreprocessor::warn_if_fallback_exec("sqlite", &ValidatorConfig::default()),
+            ]
+        });
+
+        assert_eq!(fallbacks, [true, false, false]);
+        assert_eq!(logs.matches("WARN").count(), 1, "{logs}");
+        assert!(
+            logs.contains("No exec_command configured for 'mytool'"),
+            "{logs}"
+        );
+    }
+
+    // ==================== check_docker_available tests ====================
+
+    /// Reports every command as available, or none
+    struct StubChecker(bool);
+
+    impl DependencyChecker for StubChecker {
+        fn check_command(&self, _cmd: &str, _args: &[&str]) -> bool {
+            self.0
+        }
+    }
+
+    fn config_with_validator() -> Config {
+        toml::from_str(
+            r#"
+            [validators.sqlite]
+            container = "keinos/sqlite3:3.47.2"
+            script = "validators/validate-sqlite.sh"
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn check_docker_available_fails_early_when_docker_is_down() {
+        let err = ValidatorPreprocessor::check_docker_available(
+            &StubChecker(false),
+            &config_with_validator(),
+        )
+        .unwrap_err();
+
+        assert_eq!(err.code(), "E025");
+    }
+
+    #[test]
+    fn check_docker_available_passes_when_docker_is_up() {
+        ValidatorPreprocessor::check_docker_available(&StubChecker(true), &config_with_validator())
+            .unwrap();
+    }
+
+    #[test]
+    fn check_docker_available_skipped_when_not_required() {
+        let config = Config {
+            require_docker: Some(false),
+            ..config_with_validator()
+        };
+
+        ValidatorPreprocessor::check_docker_available(&StubChecker(false), &config).unwrap();
+    }
+
+    // ==================== pull_image_if_configured tests ====================
+
+    /// Records every command it's asked to run and reports `exit_code`
+    struct RecordingRunner {
+        exit_code: i32,
+        commands: std::sync::Mutex<Vec<Vec<String>>>,
+    }
+
+    impl RecordingRunner {
+        fn new(exit_code: i32) -> Self {
+            Self {
+                exit_code,
+                commands: std::sync::Mutex::default(),
+            }
+        }
+
+        fn commands(&self) -> Vec<Vec<String>> {
+            self.commands.lock().unwrap().clone()
+        }
+    }
+
+    impl CommandRunner for RecordingRunner {
+        fn run_script(
+            &self,
+            _script_path: &str,
+            _stdin_content: &str,
+            _env_vars: &[(&str, &str)],
+        ) -> anyhow::Result<std::process::Output> {
+            Err(anyhow::anyhow!("only run_command is expected"))
+        }
+
+        fn run_command(
+            &self,
+            program: &str,
+            args: &[&str],
+        ) -> anyhow::Result<std::process::Output> {
+            let mut command = vec![program.to_owned()];
+            command.extend(args.iter().map(|arg| (*arg).to_owned()));
+            self.commands.lock().unwrap().push(command);
+            #[cfg(unix)]
+            let status = {
+                use std::os::unix::process::ExitStatusExt;
+                std::process::ExitStatus::from_raw(self.exit_code << 8)
+            };
+            #[cfg(not(unix))]
+            let status = panic!("Mock exit status not supported on this platform");
+            Ok(std::process::Output {
+                status,
+                stdout: Vec::new(),
+                stderr: b"denied: requested access to the resource is denied".to_vec(),
+            })
+        }
+    }
+
+    #[test]
+    fn pull_image_if_configured_runs_docker_pull_when_enabled() {
+        let runner = RecordingRunner::new(0);
+        let config = Config {
+            pull_via_cli: true,
+            ..Default::default()
+        };
+
+        let pulled =
+            ValidatorPreprocessor::pull_image_if_configured(&runner, &config, "alpine:3").unwrap();
+
+        assert!(pulled);
+        assert_eq!(runner.commands(), [["docker", "pull", "alpine:3"]]);
+    }
+
+    #[test]
+    fn pull_image_if_configured_skips_pull_when_disabled() {
+        let runner = RecordingRunner::new(0);
+
+        let pulled = ValidatorPreprocessor::pull_image_if_configured(
+            &runner,
+            &Config::default(),
+            "alpine:3",
+        )
+        .unwrap();
+
+        assert!(!pulled);
+        assert!(runner.commands().is_empty());
+    }
+
+    #[test]
+    fn pull_image_if_configured_reports_failed_pull_as_startup_error() {
+        let runner = RecordingRunner::new(1);
+        let config = Config {
+            pull_via_cli: true,
+            ..Default::default()
+        };
+
+        let err =
+            ValidatorPreprocessor::pull_image_if_configured(&runner, &config, "private/img:1")
+                .unwrap_err();
+
+        assert_eq!(err.code(), "E002");
+        assert!(err.to_string().contains("requested access"), "{err}");
+    }
+
+    // ==================== warn_unused_validators tests ====================
+
+    fn config_with_validators(names: &[&str]) -> Config {
+        Config {
+            validators: names
+                .iter()
+                .map(|name| ((*name).to_owned(), ValidatorConfig::default()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn warn_unused_validators_names_unreferenced_validator() {
+        let config = config_with_validators(&["sqlite", "osquery"]);
+        let used = HashSet::from(["sqlite".to_owned()]);
+
+        let (unused, logs) =
+            capture_logs(|| ValidatorPreprocessor::warn_unused_validators(&config, &used));
+
+        assert_eq!(unused, ["osquery"]);
+        assert_eq!(logs.matches("WARN").count(), 1, "{logs}");
+        assert!(
+            logs.contains("Validator 'osquery' is configured but no block uses it"),
+            "{logs}"
+        );
+    }
+
+    #[test]
+    fn warn_unused_validators_is_silent_when_all_used() {
+        let config = config_with_validators(&["sqlite", "osquery"]);
+        let used = HashSet::from(["sqlite".to_owned(), "osquery".to_owned()]);
+
+        let (unused, logs) =
+            capture_logs(|| ValidatorPreprocessor::warn_unused_validators(&config, &used));
+
+        assert!(unused.is_empty());
+        assert!(logs.is_empty(), "{logs}");
+    }
+
+    // ==================== scan_fenced_blocks tests ====================
+
+    #[test]
+    fn scan_fenced_blocks_parses_attributes_once_per_fence() {
+        let content = r#"Intro
+
+```python
+print("plain")
+```
+
+```sql validator=sqlite skip
+SELECT 1;
+```
+
+```sql validator=sqlite hidden
+SELECT 2;
+```"#;
+        let fences = ValidatorPreprocessor::scan_fenced_blocks(content, &HashMap::new());
+        assert_eq!(fences.len(), 3);
+
+        assert_eq!(fences[0].attrs.validator, None);
+        assert_eq!(fences[0].text, "print(\"plain\")\n");
+
+        assert_eq!(fences[1].attrs.validator.as_deref(), Some("sqlite"));
+        assert!(fences[1].attrs.skip);
+        assert!(!fences[1].attrs.hidden);
+
+        assert_eq!(fences[2].attrs.validator.as_deref(), Some("sqlite"));
+        assert!(!fences[2].attrs.skip);
+        assert!(fences[2].attrs.hidden);
+        assert!(content[fences[2].range.clone()].starts_with("```sql validator=sqlite hidden"));
+    }
+
+    #[test]
+    fn scan_fenced_blocks_empty_block_has_no_content_range() {
+        let content = "```sql validator=sqlite\n```";
+        let fences = ValidatorPreprocessor::scan_fenced_blocks(content, &HashMap::new());
+        assert_eq!(fences.len(), 1);
+        assert!(fences[0].content_range.is_none());
+        assert!(fences[0].text.is_empty());
+    }
+
+    #[test]
+    fn find_validator_blocks_ignores_fences_without_validator() {
+        let content = "```python\nx = 1\n```\n\n```sql validator=sqlite\nSELECT 1;\n```";
+        let blocks = ValidatorPreprocessor::find_validator_blocks(content, &HashMap::new());
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].validator_name, "sqlite");
+        assert_eq!(blocks[0].markers.visible_content, "SELECT 1;");
+    }
+
+    #[test]
+    fn find_validator_blocks_uses_book_default_validators() {
+        let config: Config = toml::from_str(r#"default_validators = { sql = "sqlite" }"#).unwrap();
+        let content = "```sql\nSELECT 1;\n```\n\n```sql validator=osquery\nSELECT 2;\n```\n\n```python\nx = 1\n```";
+        let blocks =
+            ValidatorPreprocessor::find_validator_blocks(content, &config.default_validators);
+        let names: Vec<_> = blocks
+            .iter()
+            .map(|block| block.validator_name.as_str())
+            .collect();
+        // An explicit validator= still wins
+        assert_eq!(names, ["sqlite", "osquery"]);
+
+        // So does the chapter's own default
+        let content = "<!--VALIDATOR_DEFAULT bash-exec sql-->\n\n```sql\nSELECT 1;\n```";
+        let blocks =
+            ValidatorPreprocessor::find_validator_blocks(content, &config.default_validators);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].validator_name, "bash-exec");
+    }
+
+    #[test]
+    fn strip_markers_from_chapter_strips_book_default_validator_blocks() {
+        let defaults = HashMap::from([("sql".to_owned(), "sqlite".to_owned())]);
+        let content = "```sql\nSELECT 1;\n<!--ASSERT\nrows = 1\n-->\n```";
+        let result = ValidatorPreprocessor::strip_markers_from_chapter(content, &defaults).unwrap();
+        assert_eq!(result, "```sql\nSELECT 1;\n```");
+    }
+
+    #[test]
+    fn find_validator_blocks_uses_chapter_default_validator() {
+        let content = "<!--VALIDATOR_DEFAULT sqlite sql-->\n\n```sql\nSELECT 1;\n```\n\n```sql validator=osquery\nSELECT 2;\n```\n\n```python\nx = 1\n```\n\n```\nplain\n```";
+        let blocks = ValidatorPreprocessor::find_validator_blocks(content, &HashMap::new());
+        let names: Vec<_> = blocks
+            .iter()
+            .map(|block| block.validator_name.as_str())
+            .collect();
+        assert_eq!(names, ["sqlite", "osquery"]);
+
+        // Without languages, only the validator's own language gets the default
+        let content =
+            "<!--VALIDATOR_DEFAULT sqlite-->\n\n```sql\nSELECT 1;\n```\n\n```json\n{}\n```";
+        let blocks = ValidatorPreprocessor::find_validator_blocks(content, &HashMap::new());
+        assert_eq!(blocks.len(), 1, "the json fence stays unvalidated");
+        assert_eq!(blocks[0].validator_name, "sqlite");
+
+        // ... as mapped by `default_validators`, else the language named like it
+        let defaults = HashMap::from([("sh".to_owned(), "bash-exec".to_owned())]);
+        let content =
+            "<!--VALIDATOR_DEFAULT bash-exec-->\n\n```sh\necho hi\n```\n\n```bash\necho hi\n```";
+        let blocks = ValidatorPreprocessor::find_validator_blocks(content, &defaults);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].line, 3, "only the sh fence");
+        let content =
+            "<!--VALIDATOR_DEFAULT python-->\n\n```python\nx = 1\n```\n\n```json\n{}\n```";
+        let blocks = ValidatorPreprocessor::find_validator_blocks(content, &HashMap::new());
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].line, 3, "only the python fence");
+
+        assert!(ValidatorPreprocessor::find_validator_blocks(
+            "<!--VALIDATOR_DEFAULTS sqlite-->\n\n```sql\nSELECT 1;\n```",
+            &HashMap::new()
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn find_validator_blocks_ignores_default_directive_inside_fences() {
+        let content =
+            "````markdown\n<!--VALIDATOR_DEFAULT sqlite sql-->\n````\n\n```sql\nSELECT 1;\n```";
+        assert!(
+            ValidatorPreprocessor::find_validator_blocks(content, &HashMap::new()).is_empty(),
+            "a documented directive doesn't set the chapter's default"
+        );
+    }
+
+    #[test]
+    fn strip_markers_from_chapter_strips_default_validator_blocks() {
+        let content =
+            "<!--VALIDATOR_DEFAULT sqlite-->\n\n```sql\nSELECT 1;\n<!--ASSERT\nrows = 1\n-->\n```";
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
+        assert_eq!(
+            result,
+            "<!--VALIDATOR_DEFAULT sqlite-->\n\n```sql\nSELECT 1;\n```"
+        );
+    }
+
+    #[test]
+    fn find_validator_blocks_records_fence_line() {
+        let content = "# Title\n\n```sql validator=sqlite\nSELECT 1;\n```\n\n- item\n\n  ```sql validator=sqlite\n  SELECT 2;\n  ```";
+        let lines: Vec<_> = ValidatorPreprocessor::find_validator_blocks(content, &HashMap::new())
+            .iter()
+            .map(|block| block.line)
+            .collect();
+        assert_eq!(lines, [3, 9]);
+    }
+
+    #[test]
+    fn strip_markers_from_chapter_exact_output_mixed_fences() {
+        // Pins the exact output for a mix of plain, validated, and hidden fences
+        let content = r#"# Title
+
+```sql validator=sqlite
+<!--SETUP
+CREATE TABLE t;
+-->
+SELECT 1;
+```
+
+```sql validator=sqlite hidden
+SELECT 2;
+```
+
+```python
+print("x")
+```"#;
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
+        assert_eq!(
+            result,
+            "# Title\n\n```sql\nSELECT 1;\n```\n\n```python\nprint(\"x\")\n```"
+        );
+    }
+
+    #[test]
+    fn strip_markers_from_chapter_keeps_mdbook_fence_attributes() {
+        let content = "```rust editable validator=sqlite\nfn main() {}\n```\n\n  ~~~sql noplayground ignore validator=sqlite desc=\"Create users\" timeout=5\n  SELECT 1;\n  ~~~\n\n```rust validator=\n```\n";
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
+        assert_eq!(
+            result,
+            "```rust editable\nfn main() {}\n```\n\n  ~~~sql noplayground ignore\n  SELECT 1;\n  ~~~\n\n```rust validator=\n```"
+        );
+    }
+
+    #[test]
+    fn strip_markers_from_chapter_keeps_tilde_fences() {
+        // Tildes let the block contain backticks; the fence must not become ```
+        let content = "~~~sql validator=sqlite\nSELECT '```';\n<!--ASSERT\nrows = 1\n-->\n~~~\n";
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
+        assert_eq!(result, "~~~sql\nSELECT '```';\n~~~");
+    }
+
+    #[test]
+    fn strip_markers_from_chapter_keeps_fence_length() {
+        let content = "````sql validator=sqlite\nSELECT '```';\n<!--ASSERT\nrows = 1\n-->\n````\n";
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
+        assert_eq!(result, "````sql\nSELECT '```';\n````");
+    }
+
+    // ==================== strip invariant tests ====================
+
+    #[test]
+    fn strip_markers_from_chapter_renders_caption_above_block() {
+        let content = "Intro\n\n```sql validator=sqlite caption=\"✓ Tested against SQLite 3.47\"\nSELECT 1;\n```\n\n```sql validator=sqlite skip caption=\"Untested\"\nSELECT 2;\n```\n";
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
+        assert_eq!(
+            result,
+            "Intro\n\n> ✓ Tested against SQLite 3.47\n\n```sql\nSELECT 1;\n```\n\n```sql\nSELECT 2;\n```"
+        );
+    }
+
+    #[test]
+    fn strip_markers_from_chapter_rejects_unterminated_marker() {
+        // Without its `-->` the ASSERT marker can't be stripped; previously it was
+        // silently published
+        let content = "```sql validator=sqlite\nSELECT 1;\n<!--ASSERT\nrows = 1\n```\n";
+        let err = ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new())
+            .unwrap_err();
+        assert_eq!(err.code(), "E020");
+        assert!(
+            err.to_string().contains("'<!--ASSERT' left in block"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn strip_markers_from_chapter_keeps_unclosed_fence_at_end() {
+        // The author's unclosed fence runs to the end of the chapter; it's
+        // stripped like any other block rather than reported as a stripping bug
+        let content = "Intro\n\n```sql validator=sqlite\nSELECT 1;\n<!--ASSERT\nrows = 1\n-->\n";
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
+        assert_eq!(result, "Intro\n\n```sql\nSELECT 1;");
+    }
+
+    #[test]
+    fn check_strip_invariants_rejects_content_closing_the_fence() {
+        let err = ValidatorPreprocessor::check_strip_invariants(
+            "````sql validator=sqlite\nSELECT 1;\n````",
+            "SELECT 1;\n````",
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), "E020");
+        assert!(
+            err.to_string()
+                .contains("closes code fence '````sql validator=sqlite' early"),
+            "{err}"
+        );
+        // Shorter runs, other fence characters and info strings don't close it
+        assert!(ValidatorPreprocessor::check_strip_invariants(
+            "````sql validator=sqlite\nx\n````",
+            "```\n~~~~\n````sql",
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn strip_markers_from_chapter_ignores_non_validator_blocks() {
+        let content =
+            "```html\n<!--SETUP is fine here\n```\n\n~~~sql validator=sqlite\nSELECT 1;\n~~~";
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
+        assert_eq!(
+            result,
+            "```html\n<!--SETUP is fine here\n```\n\n~~~sql\nSELECT 1;\n~~~"
+        );
+    }
 
     // ==================== strip_markers_from_chapter hidden block tests ====================
 
@@ -746,7 +3538,8 @@ SELECT 1;
 ```
 
 More text"#;
-        let result = ValidatorPreprocessor::strip_markers_from_chapter(content);
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
         // Hidden block should be completely removed
         assert!(!result.contains("SELECT 1"));
         assert!(!result.contains("```sql"));
@@ -763,7 +3556,8 @@ SELECT 1;
 ```
 
 More text"#;
-        let result = ValidatorPreprocessor::strip_markers_from_chapter(content);
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
         // Non-hidden block should be kept (with markers stripped)
         assert!(result.contains("SELECT 1"));
         assert!(result.contains("```sql"));
@@ -786,7 +3580,8 @@ VISIBLE QUERY;
 ```
 
 End"#;
-        let result = ValidatorPreprocessor::strip_markers_from_chapter(content);
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
         // Hidden block removed, non-hidden kept
         assert!(!result.contains("HIDDEN QUERY"));
         assert!(result.contains("VISIBLE QUERY"));
@@ -808,7 +3603,8 @@ HIDDEN 2;
 ```
 
 End"#;
-        let result = ValidatorPreprocessor::strip_markers_from_chapter(content);
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
         // Both hidden blocks should be removed
         assert!(!result.contains("HIDDEN 1"));
         assert!(!result.contains("HIDDEN 2"));
@@ -823,7 +3619,8 @@ HIDDEN;
 ```
 
 Visible content"#;
-        let result = ValidatorPreprocessor::strip_markers_from_chapter(content);
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
         // Hidden block at start should not leave leading whitespace
         assert!(!result.contains("HIDDEN"));
         assert!(result.contains("Visible content"));
@@ -838,7 +3635,8 @@ Visible content"#;
 ```sql validator=sqlite hidden
 HIDDEN;
 ```"#;
-        let result = ValidatorPreprocessor::strip_markers_from_chapter(content);
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
         // Hidden block at end should not leave trailing whitespace
         assert!(!result.contains("HIDDEN"));
         assert!(result.contains("Visible content"));
@@ -851,7 +3649,8 @@ HIDDEN;
         let content = r#"```sql validator=sqlite hidden
 HIDDEN;
 ```"#;
-        let result = ValidatorPreprocessor::strip_markers_from_chapter(content);
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
         // Single hidden block should result in empty output
         assert!(!result.contains("HIDDEN"));
         assert!(result.is_empty() || result.trim().is_empty());
@@ -872,7 +3671,8 @@ rows >= 1
 ```
 
 More text"#;
-        let result = ValidatorPreprocessor::strip_markers_from_chapter(content);
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
         // Hidden block with markers should be completely removed
         assert!(!result.contains("SETUP"));
         assert!(!result.contains("ASSERT"));
@@ -899,7 +3699,8 @@ Some text:
 ### Next Section
 
 More text."#;
-        let result = ValidatorPreprocessor::strip_markers_from_chapter(content);
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
         // Lists must be preserved exactly
         assert!(
             result.contains("- Item one"),
@@ -939,7 +3740,8 @@ SELECT 1;
 ### Next Section
 
 More text."#;
-        let result = ValidatorPreprocessor::strip_markers_from_chapter(content);
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
         // Lists must be preserved
         assert!(
             result.contains("- Item one"),
@@ -973,7 +3775,8 @@ More text."#;
 3. Third step
 
 Done."#;
-        let result = ValidatorPreprocessor::strip_markers_from_chapter(content);
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
         assert!(
             result.contains("1. First step"),
             "Numbered lists must be preserved"
@@ -996,7 +3799,8 @@ Done."#;
 > with multiple lines
 
 End."#;
-        let result = ValidatorPreprocessor::strip_markers_from_chapter(content);
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
         assert!(
             result.contains("> This is a blockquote"),
             "Blockquotes must be preserved"
@@ -1008,7 +3812,8 @@ End."#;
         let content = r#"See [the documentation](https://example.com) for details.
 
 And [another link](https://other.com)."#;
-        let result = ValidatorPreprocessor::strip_markers_from_chapter(content);
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
         assert!(
             result.contains("[the documentation](https://example.com)"),
             "Links must be preserved"
@@ -1024,7 +3829,8 @@ And [another link](https://other.com)."#;
         let content = r#"Use the `SELECT` statement to query data.
 
 Also `INSERT` works."#;
-        let result = ValidatorPreprocessor::strip_markers_from_chapter(content);
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
         assert!(result.contains("`SELECT`"), "Inline code must be preserved");
         assert!(result.contains("`INSERT`"), "Inline code must be preserved");
     }
@@ -1034,7 +3840,8 @@ Also `INSERT` works."#;
         let content = r#"This is *italic* and **bold** text.
 
 Also _underscores_ and __double__."#;
-        let result = ValidatorPreprocessor::strip_markers_from_chapter(content);
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
         assert!(result.contains("*italic*"), "Italic must be preserved");
         assert!(result.contains("**bold**"), "Bold must be preserved");
     }
@@ -1045,7 +3852,8 @@ Also _underscores_ and __double__."#;
 |----------|----------|
 | Value 1  | Value 2  |
 | Value 3  | Value 4  |"#;
-        let result = ValidatorPreprocessor::strip_markers_from_chapter(content);
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
         assert!(
             result.contains("| Column A | Column B |"),
             "Tables must be preserved"
@@ -1066,7 +3874,8 @@ def hello():
 ```
 
 End."#;
-        let result = ValidatorPreprocessor::strip_markers_from_chapter(content);
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
         assert!(result.contains("```python"), "Code fence must be preserved");
         assert!(
             result.contains("def hello():"),
@@ -1121,7 +3930,8 @@ See [SQL documentation](https://sqlite.org) for more.
 3. We verified the results
 
 Done!"#;
-        let result = ValidatorPreprocessor::strip_markers_from_chapter(content);
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
 
         // Lists preserved
         assert!(
@@ -1186,7 +3996,8 @@ SELECT 1;
 ### [Advanced Topics](https://example.com/advanced)
 
 More content."#;
-        let result = ValidatorPreprocessor::strip_markers_from_chapter(content);
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
 
         // Headings with links must be preserved exactly
         assert!(
@@ -1223,7 +4034,8 @@ SELECT 1;
 ```
 
 The path `/tmp/*` is commonly used."#;
-        let result = ValidatorPreprocessor::strip_markers_from_chapter(content);
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
 
         // Paths with wildcards must be preserved exactly
         assert!(
@@ -1268,7 +4080,8 @@ SELECT 1;
 ```
 
 Also try `jq '.[] | .name'` for JSON parsing."#;
-        let result = ValidatorPreprocessor::strip_markers_from_chapter(content);
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
 
         // Inline code must be preserved exactly
         assert!(
@@ -1315,7 +4128,8 @@ SELECT 1;
 ```
 
 Done."#;
-        let result = ValidatorPreprocessor::strip_markers_from_chapter(content);
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
 
         // Asterisks in various contexts
         assert!(
@@ -1344,7 +4158,8 @@ SELECT 1;
 ```
 
 End."#;
-        let result = ValidatorPreprocessor::strip_markers_from_chapter(content);
+        let result =
+            ValidatorPreprocessor::strip_markers_from_chapter(content, &HashMap::new()).unwrap();
 
         assert!(result.contains("**bold**"), "Bold preserved");
         assert!(result.contains("*italic*"), "Italic preserved");
@@ -0,0 +1,179 @@
+//! Timing breakdown of validation phases
+//!
+//! Enabled with `MDBOOK_VALIDATOR_PROFILE=1`. Durations are aggregated per
+//! validator and printed to stderr when the run finishes.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::io::Write as _;
+use std::time::Duration;
+
+/// Environment variable that enables profile output
+pub const PROFILE_ENV: &str = "MDBOOK_VALIDATOR_PROFILE";
+
+/// A timed phase of block validation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Starting the validator's container
+    ContainerStartup,
+    /// Running `<!--SETUP-->` in the container
+    Setup,
+    /// Running the block content in the container
+    Query,
+    /// Running the validator script (and `EXPECT_REGEX`) on the host
+    HostValidation,
+}
+
+impl Phase {
+    /// All phases, in the order they run
+    pub const ALL: [Self; 4] = [
+        Self::ContainerStartup,
+        Self::Setup,
+        Self::Query,
+        Self::HostValidation,
+    ];
+
+    /// Column label used in the profile table
+    #[must_use]
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::ContainerStartup => "container startup",
+            Self::Setup => "setup",
+            Self::Query => "query",
+            Self::HostValidation => "host validation",
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Self::ContainerStartup => 0,
+            Self::Setup => 1,
+            Self::Query => 2,
+            Self::HostValidation => 3,
+        }
+    }
+}
+
+/// Accumulated timings for one validator
+#[derive(Debug, Default, Clone)]
+struct ValidatorTimings {
+    blocks: usize,
+    phases: [Duration; 4],
+}
+
+impl ValidatorTimings {
+    fn phase_mut(&mut self, phase: Phase) -> Option<&mut Duration> {
+        self.phases.get_mut(phase.index())
+    }
+
+    fn phase(&self, phase: Phase) -> Duration {
+        self.phases.get(phase.index()).copied().unwrap_or_default()
+    }
+
+    fn total(&self) -> Duration {
+        self.phases.iter().sum()
+    }
+}
+
+/// Per-validator timing profile for a run
+#[derive(Debug, Default)]
+pub struct Profile {
+    validators: BTreeMap<String, ValidatorTimings>,
+}
+
+impl Profile {
+    /// Whether profiling was requested via [`PROFILE_ENV`]
+    #[must_use]
+    pub fn enabled_from_env() -> bool {
+        matches!(std::env::var(PROFILE_ENV).as_deref(), Ok("1" | "true"))
+    }
+
+    /// Add `duration` to `phase` for `validator`
+    pub fn record(&mut self, validator: &str, phase: Phase, duration: Duration) {
+        if let Some(total) = self
+            .validators
+            .entry(validator.to_owned())
+            .or_default()
+            .phase_mut(phase)
+        {
+            *total += duration;
+        }
+    }
+
+    /// Count one validated block for `validator`
+    pub fn record_block(&mut self, validator: &str) {
+        self.validators
+            .entry(validator.to_owned())
+            .or_default()
+            .blocks += 1;
+    }
+
+    /// Render the profile as a table, one row per validator
+    #[must_use]
+    pub fn render(&self) -> String {
+        let mut out = String::from("mdbook-validator profile\n");
+
+        let _ = write!(out, "{:<20} {:>6}", "validator", "blocks");
+        for phase in Phase::ALL {
+            let _ = write!(out, " {:>18}", phase.label());
+        }
+        let _ = writeln!(out, " {:>10}", "total");
+
+        for (name, timings) in &self.validators {
+            let _ = write!(out, "{name:<20} {:>6}", timings.blocks);
+            for phase in Phase::ALL {
+                let _ = write!(out, " {:>17.3}s", timings.phase(phase).as_secs_f64());
+            }
+            let _ = writeln!(out, " {:>9.3}s", timings.total().as_secs_f64());
+        }
+
+        out
+    }
+
+    /// Print the rendered profile to stderr (stdout carries the book JSON)
+    pub fn print(&self) {
+        let _ = std::io::stderr().write_all(self.render().as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_contains_phase_labels_and_validators() {
+        let mut profile = Profile::default();
+        profile.record_block("sqlite");
+        profile.record(
+            "sqlite",
+            Phase::ContainerStartup,
+            Duration::from_millis(1500),
+        );
+        profile.record("sqlite", Phase::Query, Duration::from_millis(250));
+        profile.record("sqlite", Phase::Query, Duration::from_millis(250));
+
+        let out = profile.render();
+        for label in [
+            "container startup",
+            "setup",
+            "query",
+            "host validation",
+            "total",
+        ] {
+            assert!(out.contains(label), "missing {label}: {out}");
+        }
+        assert!(out.contains("sqlite"), "{out}");
+        assert!(out.contains("1.500s"), "startup time: {out}");
+        assert!(
+            out.contains("0.500s"),
+            "query time should accumulate: {out}"
+        );
+        assert!(out.contains("2.000s"), "total: {out}");
+    }
+
+    #[test]
+    fn render_empty_profile_has_header_only() {
+        let out = Profile::default().render();
+        assert_eq!(out.lines().count(), 2, "{out}");
+    }
+}
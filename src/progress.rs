@@ -0,0 +1,117 @@
+//! Block progress reporting
+//!
+//! Prints `validated N/M blocks` to stderr as blocks finish, so long builds
+//! don't look hung. The total comes from a pre-flight count of the book's
+//! validator blocks. Controlled by the `progress` config flag, or sent
+//! elsewhere with `ValidatorPreprocessorBuilder::progress_writer`.
+
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+/// Where a run's progress lines go
+pub type ProgressOut = Box<dyn Write + Send>;
+
+/// Progress through a run's validator blocks
+#[derive(Debug)]
+pub struct Progress<W: Write = ProgressOut> {
+    total: usize,
+    done: usize,
+    out: Option<W>,
+}
+
+impl Progress {
+    /// Progress over `total` blocks, printed to stderr when `enabled`
+    #[must_use]
+    pub fn new(total: usize, enabled: bool) -> Self {
+        Self {
+            total,
+            done: 0,
+            out: enabled.then(|| Box::new(io::stderr()) as ProgressOut),
+        }
+    }
+
+    /// Progress over `total` blocks, always printed to `out`, which outlives the run
+    #[must_use]
+    pub fn shared(total: usize, out: &Arc<Mutex<dyn Write + Send>>) -> Self {
+        Self::with_writer(total, Box::new(SharedWriter(Arc::clone(out))))
+    }
+}
+
+/// Writes through to a writer shared between runs
+struct SharedWriter(Arc<Mutex<dyn Write + Send>>);
+
+impl Write for SharedWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .lock()
+            .map_err(|_| io::Error::other("progress writer lock poisoned"))?
+            .write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0
+            .lock()
+            .map_err(|_| io::Error::other("progress writer lock poisoned"))?
+            .flush()
+    }
+}
+
+impl<W: Write> Progress<W> {
+    /// Progress over `total` blocks, always printed to `out`
+    #[must_use]
+    pub fn with_writer(total: usize, out: W) -> Self {
+        Self {
+            total,
+            done: 0,
+            out: Some(out),
+        }
+    }
+
+    /// Record one finished block and print the updated count
+    pub fn tick(&mut self) {
+        self.done += 1;
+        if let Some(out) = &mut self.out {
+            let _ = writeln!(
+                out,
+                "mdbook-validator: validated {}/{} blocks",
+                self.done, self.total
+            );
+        }
+    }
+
+    /// Blocks finished so far
+    #[must_use]
+    pub fn done(&self) -> usize {
+        self.done
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tick_prints_running_count() {
+        let mut progress = Progress::with_writer(3, Vec::new());
+        for _ in 0..3 {
+            progress.tick();
+        }
+
+        let out = String::from_utf8(progress.out.take().unwrap_or_default()).unwrap_or_default();
+        assert_eq!(
+            out.lines().collect::<Vec<_>>(),
+            [
+                "mdbook-validator: validated 1/3 blocks",
+                "mdbook-validator: validated 2/3 blocks",
+                "mdbook-validator: validated 3/3 blocks",
+            ]
+        );
+    }
+
+    #[test]
+    fn disabled_progress_still_counts() {
+        let mut progress = Progress::new(2, false);
+        progress.tick();
+        assert_eq!(progress.done(), 1);
+    }
+}
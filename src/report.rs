@@ -0,0 +1,267 @@
+//! Per-block validation results
+//!
+//! Every validated block is recorded, passing or not, so a run can be audited
+//! after the fact.
+
+use std::path::Path;
+
+use anyhow::Context;
+use serde::Serialize;
+
+use crate::error::ValidatorError;
+
+/// Exit codes of a block's two validation phases
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ExitCodes {
+    /// The block's query in the container (the last section's, for sectioned blocks)
+    pub query: i64,
+    /// The validator script on the host
+    pub host: i64,
+}
+
+impl ExitCodes {
+    /// Exit codes of a failed block: a validation failure means the query
+    /// succeeded and the host validator didn't; anything else leaves both zero
+    #[must_use]
+    pub fn from_error(err: &anyhow::Error) -> Self {
+        match err.downcast_ref::<ValidatorError>() {
+            Some(ValidatorError::ValidationFailed { exit_code, .. }) => Self {
+                query: 0,
+                host: i64::from(*exit_code),
+            },
+            _ => Self::default(),
+        }
+    }
+}
+
+/// The outcome of validating one block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BlockResult {
+    /// Name of the chapter containing the block
+    pub chapter: String,
+    /// 1-based position of the block among the chapter's validator blocks
+    pub block: usize,
+    /// 1-based line of the block's opening fence in the chapter
+    pub line: usize,
+    /// Validator that ran the block
+    pub validator: String,
+    /// Description from the block's `desc="..."` attribute
+    pub description: Option<String>,
+    /// Container the block ran in (empty if it failed before one started)
+    pub container_id: String,
+    /// Exit codes of the container query and host validator
+    pub exit_codes: ExitCodes,
+    /// Whether the block passed validation
+    pub passed: bool,
+    /// Whether the block was skipped (`skip`) rather than validated
+    pub skipped: bool,
+    /// Whether the block is removed from the rendered book (`hidden`)
+    pub hidden: bool,
+    /// Time spent validating the block, in milliseconds
+    pub duration_ms: u64,
+    /// Failure message; empty when passing
+    pub message: String,
+}
+
+/// Block counts of a run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Summary {
+    /// Every recorded block, skipped ones included
+    pub total: usize,
+    /// Validated blocks that passed
+    pub passed: usize,
+    /// Validated blocks that failed
+    pub failed: usize,
+    /// Blocks marked `skip`
+    pub skipped: usize,
+}
+
+/// The file written for `report_path`
+#[derive(Serialize)]
+struct ReportFile<'a> {
+    summary: Summary,
+    blocks: &'a [BlockResult],
+}
+
+/// Results of every block validated in a run, in validation order
+#[derive(Debug, Default)]
+pub struct Report {
+    blocks: Vec<BlockResult>,
+}
+
+impl Report {
+    /// Add a block's result
+    pub fn record(&mut self, result: BlockResult) {
+        self.blocks.push(result);
+    }
+
+    /// All recorded results
+    #[must_use]
+    pub fn blocks(&self) -> &[BlockResult] {
+        &self.blocks
+    }
+
+    /// Move every result of `other` to the end of this report
+    pub fn append(&mut self, mut other: Self) {
+        self.blocks.append(&mut other.blocks);
+    }
+
+    /// Count the recorded blocks by outcome
+    #[must_use]
+    pub fn summary(&self) -> Summary {
+        let mut summary = Summary {
+            total: self.blocks.len(),
+            ..Summary::default()
+        };
+        for block in &self.blocks {
+            if block.skipped {
+                summary.skipped += 1;
+            } else if block.passed {
+                summary.passed += 1;
+            } else {
+                summary.failed += 1;
+            }
+        }
+        summary
+    }
+
+    /// Write the summary and every result to `path` as JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the file can't be written.
+    pub fn write_json(&self, path: &Path) -> anyhow::Result<()> {
+        let file = ReportFile {
+            summary: self.summary(),
+            blocks: &self.blocks,
+        };
+        let json = serde_json::to_string_pretty(&file).context("Failed to serialize report")?;
+        std::fs::write(path, json)
+            .with_context(|| format!("Failed to write report to '{}'", path.display()))
+    }
+}
+
+/// The outcome of validating a single piece of content, passing or failing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationOutcome {
+    /// Whether the content passed validation
+    pub passed: bool,
+    /// Exit code of the container query, when it completed successfully
+    pub query_exit_code: Option<i64>,
+    /// Exit code of the host validator, when it ran
+    pub host_exit_code: Option<i64>,
+    /// Error code of the failure (e.g. `E006`), when it has one
+    pub error_code: Option<&'static str>,
+    /// Failure message, including captured validator stderr/stdout; empty when passing
+    pub message: String,
+}
+
+impl ValidationOutcome {
+    /// Build an outcome from a block's validation result
+    #[must_use]
+    pub fn from_result(result: Result<ExitCodes, anyhow::Error>) -> Self {
+        let err = match result {
+            Ok(exit_codes) => {
+                return Self {
+                    passed: true,
+                    query_exit_code: Some(exit_codes.query),
+                    host_exit_code: Some(exit_codes.host),
+                    error_code: None,
+                    message: String::new(),
+                }
+            }
+            Err(err) => err,
+        };
+
+        let validator_error = err.downcast_ref::<ValidatorError>();
+        // A validation failure means the query itself succeeded
+        let (query_exit_code, host_exit_code) = match validator_error {
+            Some(ValidatorError::ValidationFailed { exit_code, .. }) => {
+                (Some(0), Some(i64::from(*exit_code)))
+            }
+            _ => (None, None),
+        };
+        Self {
+            passed: false,
+            query_exit_code,
+            host_exit_code,
+            error_code: validator_error.map(ValidatorError::code),
+            message: format!("{err:#}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_keeps_validation_order() {
+        let mut report = Report::default();
+        for block in 1..=2 {
+            report.record(BlockResult {
+                chapter: "Intro".to_owned(),
+                block,
+                line: 1,
+                validator: "sqlite".to_owned(),
+                description: None,
+                container_id: "abc123".to_owned(),
+                exit_codes: ExitCodes::default(),
+                passed: true,
+                skipped: false,
+                hidden: false,
+                duration_ms: 0,
+                message: String::new(),
+            });
+        }
+
+        let blocks: Vec<_> = report.blocks().iter().map(|result| result.block).collect();
+        assert_eq!(blocks, [1, 2]);
+    }
+
+    #[test]
+    fn summary_counts_blocks_by_outcome() {
+        let mut report = Report::default();
+        for (passed, skipped) in [(true, false), (false, false), (false, true), (true, false)] {
+            report.record(BlockResult {
+                chapter: "Intro".to_owned(),
+                block: 1,
+                line: 1,
+                validator: "sqlite".to_owned(),
+                description: None,
+                container_id: String::new(),
+                exit_codes: ExitCodes::default(),
+                passed,
+                skipped,
+                hidden: false,
+                duration_ms: 0,
+                message: String::new(),
+            });
+        }
+
+        assert_eq!(
+            report.summary(),
+            Summary {
+                total: 4,
+                passed: 2,
+                failed: 1,
+                skipped: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn outcome_from_validation_failure_keeps_exit_codes() {
+        let outcome = ValidationOutcome::from_result(Err(ValidatorError::ValidationFailed {
+            exit_code: 3,
+            message: "rows = 2".to_owned(),
+        }
+        .into()));
+
+        assert!(!outcome.passed);
+        assert_eq!(outcome.query_exit_code, Some(0));
+        assert_eq!(outcome.host_exit_code, Some(3));
+        assert_eq!(outcome.error_code, Some("E006"));
+        assert!(outcome.message.contains("rows = 2"), "{}", outcome.message);
+    }
+}
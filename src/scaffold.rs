@@ -0,0 +1,176 @@
+//! `mdbook-validator init` scaffolding
+//!
+//! Writes a starter setup into an mdBook directory: a `[preprocessor.validator]`
+//! block in `book.toml`, the sqlite validator script, and a sample chapter showing
+//! the marker syntax. Existing files are never overwritten.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+/// Config block appended to `book.toml`
+pub const SAMPLE_CONFIG: &str = r#"[preprocessor.validator]
+command = "mdbook-validator"
+
+[preprocessor.validator.validators.sqlite]
+container = "keinos/sqlite3:3.47.2"
+script = "validators/validate-sqlite.sh"
+"#;
+
+/// Starter validator script, written to `validators/validate-sqlite.sh`
+pub const SAMPLE_SCRIPT: &str = include_str!("../validators/validate-sqlite.sh");
+
+/// Sample chapter, written to `src/validator-example.md`
+pub const SAMPLE_CHAPTER: &str = r#"# Validated Examples
+
+Every `sql` block with `validator=sqlite` runs against SQLite in a container
+when the book builds. Blocks share one database, in document order.
+
+## Setup and assertions
+
+```sql validator=sqlite
+<!--SETUP
+sqlite3 /tmp/test.db "CREATE TABLE users (id INTEGER, name TEXT); INSERT INTO users VALUES (1, 'alice'), (2, 'bob');"
+-->
+SELECT name FROM users WHERE id = 1;
+<!--ASSERT
+rows = 1
+contains "alice"
+-->
+```
+
+## Exact output
+
+```sql validator=sqlite
+SELECT id, name FROM users ORDER BY id;
+<!--EXPECT
+[{"id": 1, "name": "alice"}, {"id": 2, "name": "bob"}]
+-->
+```
+
+## Hiding lines with `@@`
+
+Lines starting with `@@` are validated but not shown to readers.
+
+```sql validator=sqlite
+SELECT name
+FROM users
+@@WHERE id > 0
+ORDER BY name;
+<!--ASSERT
+rows = 2
+-->
+```
+
+## Hidden blocks
+
+A `hidden` block is validated but removed from the rendered page, which is
+useful for preparing state that later blocks rely on.
+
+```sql validator=sqlite hidden
+<!--SETUP
+sqlite3 /tmp/test.db "INSERT INTO users VALUES (3, 'carol');"
+-->
+SELECT COUNT(*) AS total FROM users;
+<!--EXPECT
+[{"total": 3}]
+-->
+```
+
+```sql validator=sqlite
+SELECT name FROM users WHERE id = 3;
+<!--ASSERT
+contains "carol"
+-->
+```
+"#;
+
+/// What `init` did with `book.toml`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigAction {
+    /// [`SAMPLE_CONFIG`] was appended
+    Appended,
+    /// `book.toml` already has a `[preprocessor.validator]` section; left untouched
+    AlreadyConfigured,
+    /// There is no `book.toml`; the caller should print [`SAMPLE_CONFIG`] instead
+    NoBookToml,
+}
+
+/// Summary of what `init` wrote
+#[derive(Debug)]
+#[must_use]
+pub struct InitReport {
+    /// What happened to `book.toml`
+    pub config: ConfigAction,
+    /// Files that were created
+    pub created: Vec<PathBuf>,
+    /// Files that already existed and were left untouched
+    pub skipped: Vec<PathBuf>,
+}
+
+/// Scaffold a validator setup in the book at `book_root`.
+///
+/// # Errors
+///
+/// Returns error if a file or directory cannot be read or written.
+pub fn init(book_root: &Path) -> Result<InitReport> {
+    let mut report = InitReport {
+        config: init_config(&book_root.join("book.toml"))?,
+        created: Vec::new(),
+        skipped: Vec::new(),
+    };
+
+    let files = [
+        (
+            book_root.join("validators").join("validate-sqlite.sh"),
+            SAMPLE_SCRIPT,
+        ),
+        (
+            book_root.join("src").join("validator-example.md"),
+            SAMPLE_CHAPTER,
+        ),
+    ];
+    for (path, content) in files {
+        if path.exists() {
+            report.skipped.push(path);
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+        report.created.push(path);
+    }
+
+    Ok(report)
+}
+
+/// Append [`SAMPLE_CONFIG`] to `book.toml` unless the validator is already configured.
+fn init_config(book_toml: &Path) -> Result<ConfigAction> {
+    if !book_toml.exists() {
+        return Ok(ConfigAction::NoBookToml);
+    }
+
+    let existing = fs::read_to_string(book_toml)
+        .with_context(|| format!("Failed to read {}", book_toml.display()))?;
+    if existing
+        .lines()
+        .any(|line| line.trim_start().starts_with("[preprocessor.validator"))
+    {
+        return Ok(ConfigAction::AlreadyConfigured);
+    }
+
+    let separator = if existing.is_empty() || existing.ends_with("\n\n") {
+        ""
+    } else if existing.ends_with('\n') {
+        "\n"
+    } else {
+        "\n\n"
+    };
+    fs::write(book_toml, format!("{existing}{separator}{SAMPLE_CONFIG}"))
+        .with_context(|| format!("Failed to write {}", book_toml.display()))?;
+
+    Ok(ConfigAction::Appended)
+}
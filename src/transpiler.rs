@@ -1,11 +1,18 @@
 //! Strip validation markers from output
 
+use crate::parser::{find_marker, SECTION_ASSERT_PREFIX};
+
 /// Strips all validation markers from a code block, returning clean content.
 ///
 /// This removes:
 /// - `<!--SETUP-->` ... `-->` blocks
+/// - `<!--CLEANUP-->` ... `-->` blocks
 /// - `<!--ASSERT-->` ... `-->` blocks
+/// - `<!--ASSERT:name-->` ... `-->` blocks
+/// - `<!--ASSERT_FILE-->` ... `-->` blocks
+/// - `<!--ASSERT_TOML-->` ... `-->` blocks
 /// - `<!--EXPECT-->` ... `-->` blocks
+/// - `<!--EXPECT_REGEX-->` ... `-->` blocks
 /// - Lines starting with `@@` prefix
 #[must_use]
 pub fn strip_markers(content: &str) -> String {
@@ -14,12 +21,30 @@ pub fn strip_markers(content: &str) -> String {
     // Strip <!--SETUP ... --> blocks
     result = strip_marker_block(&result, "<!--SETUP");
 
+    // Strip <!--CLEANUP ... --> blocks
+    result = strip_marker_block(&result, "<!--CLEANUP");
+
     // Strip <!--ASSERT ... --> blocks
     result = strip_marker_block(&result, "<!--ASSERT");
 
+    // Strip <!--ASSERT:name ... --> blocks
+    result = strip_blocks(&result, |content| content.find(SECTION_ASSERT_PREFIX));
+
+    // Strip <!--ASSERT_FILE ... --> blocks
+    result = strip_marker_block(&result, "<!--ASSERT_FILE");
+
+    // Strip <!--ASSERT_TOML ... --> blocks
+    result = strip_marker_block(&result, "<!--ASSERT_TOML");
+
     // Strip <!--EXPECT ... --> blocks
     result = strip_marker_block(&result, "<!--EXPECT");
 
+    // Strip <!--EXPECT_REGEX ... --> blocks
+    result = strip_marker_block(&result, "<!--EXPECT_REGEX");
+
+    // Strip <!--EXPECT-EXIT N--> markers
+    result = strip_marker_block(&result, "<!--EXPECT-EXIT");
+
     // Strip lines starting with @@
     result = strip_double_at_lines(&result);
 
@@ -35,9 +60,14 @@ fn strip_double_at_lines(content: &str) -> String {
 }
 
 fn strip_marker_block(content: &str, marker: &str) -> String {
+    strip_blocks(content, |content| find_marker(content, marker))
+}
+
+/// Removes every block from the offset `find` returns through its `-->`.
+fn strip_blocks(content: &str, find: impl Fn(&str) -> Option<usize>) -> String {
     let mut result = content.to_owned();
 
-    while let Some(start) = result.find(marker) {
+    while let Some(start) = find(&result) {
         if let Some(end_offset) = result[start..].find("-->") {
             let end = start + end_offset + 3; // Include "-->"
 
@@ -88,6 +118,22 @@ mod tests {
         assert!(result.contains("SELECT * FROM t;"));
     }
 
+    #[test]
+    fn strip_markers_removes_cleanup() {
+        let content = "SELECT * FROM t;\n<!--CLEANUP\nrm -f /tmp/t.db\n-->";
+        let result = strip_markers(content);
+        assert!(!result.contains("CLEANUP"));
+        assert!(!result.contains("rm -f"));
+        assert_eq!(result, "SELECT * FROM t;");
+    }
+
+    #[test]
+    fn strip_markers_removes_expect_exit() {
+        let content = "INSERT INTO t VALUES (1);\n<!--EXPECT-EXIT 19-->";
+        let result = strip_markers(content);
+        assert_eq!(result, "INSERT INTO t VALUES (1);");
+    }
+
     #[test]
     fn strip_markers_removes_expect() {
         let content = "SELECT 1;\n<!--EXPECT\n[{\"id\": 1}]\n-->";
@@ -111,6 +157,31 @@ mod tests {
         assert!(result.contains("query;"));
     }
 
+    #[test]
+    fn strip_markers_removes_assert_toml() {
+        let content = "SELECT 1;\n<!--ASSERT_TOML\ncontains = [\"alice\"]\n-->";
+        let result = strip_markers(content);
+        assert_eq!(result.trim(), "SELECT 1;");
+    }
+
+    #[test]
+    fn strip_markers_removes_assert_file() {
+        let content = "SELECT 1;\n<!--ASSERT_FILE\nassertions/users.txt\n-->";
+        let result = strip_markers(content);
+        assert!(!result.contains("ASSERT_FILE"));
+        assert!(!result.contains("users.txt"));
+        assert!(result.contains("SELECT 1;"));
+    }
+
+    #[test]
+    fn strip_markers_removes_expect_regex() {
+        let content = "SELECT 1;\n<!--EXPECT_REGEX\n\\[.*\\]\n-->";
+        let result = strip_markers(content);
+        assert!(!result.contains("EXPECT_REGEX"));
+        assert!(!result.contains("\\[.*\\]"));
+        assert_eq!(result, "SELECT 1;");
+    }
+
     #[test]
     fn strip_markers_no_markers() {
         let content = "SELECT * FROM users;";
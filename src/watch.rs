@@ -0,0 +1,264 @@
+//! Watch mode
+//!
+//! `mdbook-validator watch <book_dir>` validates every chapter under `src/`,
+//! then re-validates chapters as they change. Containers are kept alive for
+//! the whole session so re-runs skip container startup.
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use mdbook_preprocessor::book::Chapter;
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use tokio::runtime::Runtime;
+
+use crate::config::Config;
+use crate::container::ValidatorContainer;
+use crate::error::ValidatorError;
+use crate::report::Report;
+use crate::ValidatorPreprocessor;
+
+/// How long to wait for more events after a change before re-validating.
+/// Editors often save a file as several writes/renames.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// A book being watched, with the containers started so far
+pub struct WatchSession {
+    preprocessor: ValidatorPreprocessor,
+    config: Config,
+    book_root: PathBuf,
+    containers: HashMap<String, ValidatorContainer>,
+    // Declared last so containers are dropped while the runtime is still alive
+    runtime: Runtime,
+}
+
+impl WatchSession {
+    /// Load `book.toml` from `book_root` and prepare a session.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if `book.toml` can't be read, has no
+    /// `[preprocessor.validator]` section, or the runtime can't be created.
+    pub fn load(book_root: &Path) -> Result<Self> {
+        let config = load_config(book_root)?;
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .context("Failed to create tokio runtime")?;
+
+        Ok(Self {
+            preprocessor: ValidatorPreprocessor::new(),
+            config,
+            book_root: book_root.to_owned(),
+            containers: HashMap::new(),
+            runtime,
+        })
+    }
+
+    /// The chapter source directory being watched
+    #[must_use]
+    pub fn src_dir(&self) -> PathBuf {
+        self.book_root.join("src")
+    }
+
+    /// Validate one markdown file as a chapter.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first validation failure in the file, or an error if it
+    /// can't be read.
+    pub fn validate_file(&mut self, path: &Path) -> Result<()> {
+        self.validate_file_with_report(path, &mut Report::default())
+    }
+
+    /// Like [`Self::validate_file`], also adding each validated block's result,
+    /// including a failing one, to `report`.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first validation failure in the file, or an error if it
+    /// can't be read.
+    pub fn validate_file_with_report(&mut self, path: &Path, report: &mut Report) -> Result<()> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let src_dir = self.src_dir();
+        let source_path = path.strip_prefix(&src_dir).unwrap_or(path).to_owned();
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut chapter = Chapter::new(&name, content, source_path, Vec::new());
+
+        self.runtime.block_on(self.preprocessor.validate_chapter(
+            &mut chapter,
+            &self.config,
+            &self.book_root,
+            &mut self.containers,
+            report,
+        ))
+    }
+}
+
+/// Read the `[preprocessor.validator]` section of `book_root/book.toml`.
+///
+/// # Errors
+///
+/// Returns error if the file can't be read or parsed, or has no
+/// `[preprocessor.validator]` section.
+pub fn load_config(book_root: &Path) -> Result<Config> {
+    let path = book_root.join("book.toml");
+    let text = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let book: toml::Value =
+        toml::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))?;
+    let raw = book
+        .get("preprocessor")
+        .and_then(|preprocessor| preprocessor.get("validator"))
+        .cloned()
+        .ok_or_else(|| ValidatorError::Config {
+            message: "No [preprocessor.validator] section in book.toml".into(),
+        })?;
+    Config::from_value(raw)
+}
+
+/// Whether `path` is a chapter file worth re-validating
+#[must_use]
+pub fn is_chapter_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "md")
+        && path.file_name().is_some_and(|name| name != "SUMMARY.md")
+}
+
+/// Chapter files referenced by a change event, if it created or modified anything
+#[must_use]
+pub fn changed_chapters(event: &Event) -> Vec<PathBuf> {
+    if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+        return Vec::new();
+    }
+    event
+        .paths
+        .iter()
+        .filter(|path| is_chapter_file(path))
+        .cloned()
+        .collect()
+}
+
+/// All chapter files under `dir`, sorted
+#[must_use]
+pub fn chapter_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_owned()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.filter_map(|entry| entry.ok().map(|e| e.path())) {
+            if path.is_dir() {
+                pending.push(path);
+            } else if is_chapter_file(&path) {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// Validate every chapter, then re-validate chapters as they change.
+///
+/// `report` is called with each file and its result. Runs until the watcher
+/// shuts down.
+///
+/// # Errors
+///
+/// Returns error if the source directory can't be watched.
+pub fn watch<F>(session: &mut WatchSession, mut report: F) -> Result<()>
+where
+    F: FnMut(&Path, &Result<()>),
+{
+    let src_dir = session.src_dir();
+    for path in chapter_files(&src_dir) {
+        let result = session.validate_file(&path);
+        report(&path, &result);
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher
+        .watch(&src_dir, RecursiveMode::Recursive)
+        .with_context(|| format!("Failed to watch {}", src_dir.display()))?;
+
+    while let Ok(first) = rx.recv() {
+        let mut changed = BTreeSet::new();
+        let mut collect = |event: notify::Result<Event>| match event {
+            Ok(event) => changed.extend(changed_chapters(&event)),
+            Err(e) => tracing::warn!(error = %e, "File watch error"),
+        };
+        collect(first);
+        while let Ok(event) = rx.recv_timeout(DEBOUNCE) {
+            collect(event);
+        }
+
+        // Deleted or renamed-away files show up as modify events too
+        for path in changed.into_iter().filter(|path| path.is_file()) {
+            let result = session.validate_file(&path);
+            report(&path, &result);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, ModifyKind, RemoveKind};
+
+    fn event(kind: EventKind, paths: &[&str]) -> Event {
+        paths
+            .iter()
+            .fold(Event::new(kind), |event, path| event.add_path(path.into()))
+    }
+
+    #[test]
+    fn changed_chapters_keeps_markdown_creates_and_modifies() {
+        let modify = event(
+            EventKind::Modify(ModifyKind::Any),
+            &["src/a.md", "src/img.png", "src/SUMMARY.md"],
+        );
+        assert_eq!(changed_chapters(&modify), [PathBuf::from("src/a.md")]);
+
+        let create = event(EventKind::Create(CreateKind::File), &["src/b.md"]);
+        assert_eq!(changed_chapters(&create), [PathBuf::from("src/b.md")]);
+    }
+
+    #[test]
+    fn changed_chapters_ignores_removals() {
+        let remove = event(EventKind::Remove(RemoveKind::File), &["src/a.md"]);
+        assert!(changed_chapters(&remove).is_empty());
+    }
+
+    #[test]
+    fn load_config_requires_validator_section() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("book.toml"), "[book]\ntitle = \"x\"\n").unwrap();
+
+        let err = load_config(dir.path()).unwrap_err();
+        assert!(format!("{err:#}").contains("[preprocessor.validator]"));
+    }
+
+    #[test]
+    fn chapter_files_finds_nested_markdown() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("part")).unwrap();
+        for file in ["a.md", "SUMMARY.md", "part/b.md", "part/c.txt"] {
+            std::fs::write(dir.path().join(file), "").unwrap();
+        }
+
+        assert_eq!(
+            chapter_files(dir.path()),
+            [dir.path().join("a.md"), dir.path().join("part/b.md")]
+        );
+    }
+}
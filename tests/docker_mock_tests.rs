@@ -1,7 +1,8 @@
 //! Tests for `DockerOperations` trait error paths using mocks.
 //!
 //! These tests verify that container.rs properly handles and propagates
-//! errors from Docker operations (`create_exec`, `start_exec`, `inspect_exec`).
+//! errors from Docker operations (`create_exec`, `start_exec`, `inspect_exec`)
+//! and reads container state (`inspect_container`).
 #![allow(
     clippy::panic,
     clippy::expect_used,
@@ -14,7 +15,9 @@ use std::sync::Arc;
 use anyhow::Result;
 use async_trait::async_trait;
 use bollard::exec::{CreateExecOptions, CreateExecResults, StartExecOptions, StartExecResults};
-use bollard::service::ExecInspectResponse;
+use bollard::service::{
+    ContainerInspectResponse, ContainerState, ExecInspectResponse, ImageInspect,
+};
 use mdbook_validator::container::ValidatorContainer;
 use mdbook_validator::docker::DockerOperations;
 use mdbook_validator::error::ValidatorError;
@@ -49,6 +52,14 @@ impl DockerOperations for FailOnCreateExec {
     async fn inspect_exec(&self, _exec_id: &str) -> Result<ExecInspectResponse> {
         panic!("inspect_exec should not be called when create_exec fails");
     }
+
+    async fn inspect_container(&self, _container_id: &str) -> Result<ContainerInspectResponse> {
+        panic!("inspect_container should not be called when create_exec fails");
+    }
+
+    async fn inspect_image(&self, _image: &str) -> Result<ImageInspect> {
+        panic!("inspect_image should not be called when create_exec fails");
+    }
 }
 
 /// Mock that succeeds on `create_exec` but fails on `start_exec`
@@ -82,6 +93,58 @@ impl DockerOperations for FailOnStartExec {
     async fn inspect_exec(&self, _exec_id: &str) -> Result<ExecInspectResponse> {
         panic!("inspect_exec should not be called when start_exec fails");
     }
+
+    async fn inspect_container(&self, _container_id: &str) -> Result<ContainerInspectResponse> {
+        panic!("inspect_container should not be called when start_exec fails");
+    }
+
+    async fn inspect_image(&self, _image: &str) -> Result<ImageInspect> {
+        panic!("inspect_image should not be called when start_exec fails");
+    }
+}
+
+/// Mock reporting a fixed container state, e.g. a container that died mid-book
+struct ContainerStateMock {
+    running: Option<bool>,
+    oom_killed: Option<bool>,
+}
+
+#[async_trait]
+impl DockerOperations for ContainerStateMock {
+    async fn create_exec(
+        &self,
+        _container_id: &str,
+        _options: CreateExecOptions<String>,
+    ) -> Result<CreateExecResults> {
+        panic!("create_exec should not be called when inspecting state");
+    }
+
+    async fn start_exec(
+        &self,
+        _exec_id: &str,
+        _options: Option<StartExecOptions>,
+    ) -> Result<StartExecResults> {
+        panic!("start_exec should not be called when inspecting state");
+    }
+
+    async fn inspect_exec(&self, _exec_id: &str) -> Result<ExecInspectResponse> {
+        panic!("inspect_exec should not be called when inspecting state");
+    }
+
+    async fn inspect_container(&self, _container_id: &str) -> Result<ContainerInspectResponse> {
+        Ok(ContainerInspectResponse {
+            state: Some(ContainerState {
+                running: self.running,
+                oom_killed: self.oom_killed,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    }
+
+    async fn inspect_image(&self, _image: &str) -> Result<ImageInspect> {
+        panic!("inspect_image should not be called when inspecting state");
+    }
 }
 
 // === Error path tests ===
@@ -255,6 +318,77 @@ async fn test_inspect_exec_failure_returns_error() {
     );
 }
 
+// === is_running tests ===
+
+#[tokio::test]
+async fn test_is_running_reflects_inspected_state() {
+    for (running, expected) in [(Some(true), true), (Some(false), false), (None, false)] {
+        let container = GenericImage::new("alpine", "3")
+            .with_cmd(["sleep", "infinity"])
+            .start()
+            .await
+            .expect("Failed to start test container");
+
+        let validator = ValidatorContainer::with_docker(
+            container,
+            Arc::new(ContainerStateMock {
+                running,
+                oom_killed: None,
+            }),
+        );
+
+        assert_eq!(
+            validator.is_running().await,
+            expected,
+            "running: {:?}",
+            running
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_was_oom_killed_reflects_inspected_state() {
+    for (oom_killed, expected) in [(Some(true), true), (Some(false), false), (None, false)] {
+        let container = GenericImage::new("alpine", "3")
+            .with_cmd(["sleep", "infinity"])
+            .start()
+            .await
+            .expect("Failed to start test container");
+
+        let validator = ValidatorContainer::with_docker(
+            container,
+            Arc::new(ContainerStateMock {
+                running: Some(true),
+                oom_killed,
+            }),
+        );
+
+        assert_eq!(
+            validator.was_oom_killed().await,
+            expected,
+            "oom_killed: {:?}",
+            oom_killed
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_inspect_container_failure_returns_error() {
+    use mdbook_validator::docker::BollardDocker;
+    use testcontainers::core::client::docker_client_instance;
+
+    let docker = docker_client_instance()
+        .await
+        .expect("Docker should be available");
+    let bollard_docker = BollardDocker::new(docker);
+
+    let result = bollard_docker
+        .inspect_container("nonexistent-container-id")
+        .await;
+
+    assert!(result.is_err(), "Expected error for missing container");
+}
+
 // === Trait implementation tests ===
 
 #[test]
@@ -270,4 +404,5 @@ fn test_fail_mocks_implement_trait() {
     fn assert_docker_ops<T: DockerOperations>() {}
     assert_docker_ops::<FailOnCreateExec>();
     assert_docker_ops::<FailOnStartExec>();
+    assert_docker_ops::<ContainerStateMock>();
 }
@@ -1,14 +1,15 @@
 //! Tests for `ValidatorError` enum
 //!
 //! Verifies:
-//! - Display impl shows error codes (E001-E010)
+//! - Display impl shows error codes (E001-E025)
 //! - `code()` method returns correct codes
 //! - `matches!()` macro works for pattern matching
 //! - Edge cases (empty messages, negative exit codes, special chars)
 
 #![allow(clippy::panic, clippy::expect_used)]
 
-use mdbook_validator::ValidatorError;
+use mdbook_validator::error::{explain, with_context};
+use mdbook_validator::{AggregatedErrors, ValidatorError};
 
 // === Display tests (verify error codes in message) ===
 
@@ -112,10 +113,96 @@ fn test_script_not_found_displays_path() {
     );
 }
 
+#[test]
+fn test_before_each_failed_displays_with_code() {
+    let err = ValidatorError::BeforeEachFailed {
+        exit_code: 2,
+        message: "snapshot failed".into(),
+    };
+    let display = err.to_string();
+    assert!(display.contains("[E012]"), "Should contain E012: {display}");
+    assert!(
+        display.contains("exit 2"),
+        "Should contain exit code: {display}"
+    );
+}
+
+#[test]
+fn test_after_each_failed_displays_with_code() {
+    let err = ValidatorError::AfterEachFailed {
+        exit_code: 1,
+        message: "rollback failed".into(),
+    };
+    let display = err.to_string();
+    assert!(display.contains("[E013]"), "Should contain E013: {display}");
+}
+
+#[test]
+fn test_unknown_assertion_set_displays_name() {
+    let err = ValidatorError::UnknownAssertionSet {
+        name: "valid_resp".into(),
+    };
+    let display = err.to_string();
+    assert!(display.contains("[E014]"), "Should contain E014: {display}");
+    assert!(
+        display.contains("valid_resp"),
+        "Should contain name: {display}"
+    );
+}
+
+#[test]
+fn test_config_parse_displays_path() {
+    let err = ValidatorError::ConfigParse {
+        path: "preprocessor.validator.validators.sqlite".into(),
+        message: "missing field `container`".into(),
+    };
+    let display = err.to_string();
+    assert!(display.contains("[E015]"), "Should contain E015: {display}");
+    assert!(
+        display.contains("validators.sqlite"),
+        "Should contain key path: {display}"
+    );
+}
+
+#[test]
+fn test_version_mismatch_displays_expected_and_actual() {
+    let err = ValidatorError::VersionMismatch {
+        name: "sqlite".into(),
+        command: "sqlite3 --version".into(),
+        expected: "3.47".into(),
+        actual: "3.45.1 2024-01-30".into(),
+    };
+    let display = err.to_string();
+    assert!(display.contains("[E016]"), "Should contain E016: {display}");
+    assert!(
+        display.contains("'3.47'"),
+        "Should contain expected: {display}"
+    );
+    assert!(
+        display.contains("3.45.1"),
+        "Should contain actual output: {display}"
+    );
+}
+
+#[test]
+fn test_digest_mismatch_displays_expected_digest() {
+    let err = ValidatorError::DigestMismatch {
+        image: "sha256:img".into(),
+        expected: "sha256:abc".into(),
+        actual: "keinos/sqlite3@sha256:def".into(),
+    };
+    let display = err.to_string();
+    assert!(display.contains("[E017]"), "Should contain E017: {display}");
+    assert!(
+        display.contains("sha256:abc") && display.contains("sha256:def"),
+        "Should contain both digests: {display}"
+    );
+}
+
 // === code() method tests ===
 
 #[test]
-fn test_code_returns_correct_codes() {
+fn test_code_returns_correct_codes_for_config_errors() {
     assert_eq!(
         ValidatorError::Config {
             message: String::new()
@@ -123,6 +210,55 @@ fn test_code_returns_correct_codes() {
         .code(),
         "E001"
     );
+    assert_eq!(
+        ValidatorError::UnknownValidator {
+            name: String::new()
+        }
+        .code(),
+        "E007"
+    );
+    assert_eq!(
+        ValidatorError::InvalidConfig {
+            name: String::new(),
+            reason: String::new()
+        }
+        .code(),
+        "E008"
+    );
+    assert_eq!(
+        ValidatorError::FixturesError {
+            message: String::new()
+        }
+        .code(),
+        "E009"
+    );
+    assert_eq!(
+        ValidatorError::ScriptNotFound {
+            path: String::new()
+        }
+        .code(),
+        "E010"
+    );
+    assert_eq!(
+        ValidatorError::UnknownAssertionSet {
+            name: String::new()
+        }
+        .code(),
+        "E014"
+    );
+    assert_eq!(
+        ValidatorError::ConfigParse {
+            path: String::new(),
+            message: String::new()
+        }
+        .code(),
+        "E015"
+    );
+    assert_eq!(ValidatorError::DockerUnavailable.code(), "E025");
+}
+
+#[test]
+fn test_code_returns_correct_codes_for_container_errors() {
     assert_eq!(
         ValidatorError::ContainerStartup {
             message: String::new()
@@ -162,33 +298,187 @@ fn test_code_returns_correct_codes() {
         "E006"
     );
     assert_eq!(
-        ValidatorError::UnknownValidator {
+        ValidatorError::VersionMismatch {
+            name: String::new(),
+            command: String::new(),
+            expected: String::new(),
+            actual: String::new()
+        }
+        .code(),
+        "E016"
+    );
+    assert_eq!(
+        ValidatorError::DigestMismatch {
+            image: String::new(),
+            expected: String::new(),
+            actual: String::new()
+        }
+        .code(),
+        "E017"
+    );
+    assert_eq!(
+        ValidatorError::OutOfMemory {
+            limit: String::new(),
+            message: String::new()
+        }
+        .code(),
+        "E022"
+    );
+    assert_eq!(
+        ValidatorError::Timeout {
+            phase: String::new(),
+            validator: String::new(),
+            seconds: 0,
+            message: String::new()
+        }
+        .code(),
+        "E023"
+    );
+}
+
+#[test]
+fn test_code_returns_correct_codes_for_hook_errors() {
+    assert_eq!(
+        ValidatorError::BeforeEachFailed {
+            exit_code: 0,
+            message: String::new()
+        }
+        .code(),
+        "E012"
+    );
+    assert_eq!(
+        ValidatorError::AfterEachFailed {
+            exit_code: 0,
+            message: String::new()
+        }
+        .code(),
+        "E013"
+    );
+    assert_eq!(
+        ValidatorError::CleanupFailed {
+            exit_code: 0,
+            message: String::new()
+        }
+        .code(),
+        "E024"
+    );
+}
+
+#[test]
+fn test_code_returns_correct_codes_for_marker_errors() {
+    assert_eq!(
+        ValidatorError::UnknownSection {
             name: String::new()
         }
         .code(),
-        "E007"
+        "E018"
     );
     assert_eq!(
-        ValidatorError::InvalidConfig {
+        ValidatorError::UndefinedVariable {
             name: String::new(),
-            reason: String::new()
+            chapter: String::new()
         }
         .code(),
-        "E008"
+        "E019"
     );
     assert_eq!(
-        ValidatorError::FixturesError {
-            message: String::new()
+        ValidatorError::StripInvariantViolation {
+            reason: String::new()
         }
         .code(),
-        "E009"
+        "E020"
     );
     assert_eq!(
-        ValidatorError::ScriptNotFound {
-            path: String::new()
+        ValidatorError::UnknownCapture {
+            name: String::new(),
+            chapter: String::new()
         }
         .code(),
-        "E010"
+        "E021"
+    );
+}
+
+#[test]
+fn test_unknown_section_names_section() {
+    let err = ValidatorError::UnknownSection { name: "q2".into() };
+    let display = err.to_string();
+    assert!(display.contains("[E018]"), "Should contain E018: {display}");
+    assert!(
+        display.contains("-- @q2"),
+        "Should name the missing section: {display}"
+    );
+}
+
+#[test]
+fn test_undefined_variable_shows_reference() {
+    let err = ValidatorError::UndefinedVariable {
+        name: "EXPECTED_VERSION".into(),
+        chapter: "Intro".into(),
+    };
+    let display = err.to_string();
+    assert!(display.contains("[E019]"), "Should contain E019: {display}");
+    assert!(
+        display.contains("${EXPECTED_VERSION}") && display.contains("'Intro'"),
+        "Should show the reference and chapter: {display}"
+    );
+}
+
+#[test]
+fn test_unknown_capture_names_capture_and_chapter() {
+    let err = ValidatorError::UnknownCapture {
+        name: "users".into(),
+        chapter: "Intro".into(),
+    };
+    let display = err.to_string();
+    assert!(display.contains("[E021]"), "Should contain E021: {display}");
+    assert!(
+        display.contains("OUTPUT:users") && display.contains("'Intro'"),
+        "Should name the capture and chapter: {display}"
+    );
+}
+
+#[test]
+fn test_out_of_memory_shows_limit() {
+    let err = ValidatorError::OutOfMemory {
+        limit: "64m".into(),
+        message: "query in 'Intro' (validator: sqlite) was killed".into(),
+    };
+    let display = err.to_string();
+    assert!(display.contains("[E022]"), "Should contain E022: {display}");
+    assert!(
+        display.contains("limit 64m") && display.contains("'Intro'"),
+        "Should show the limit and message: {display}"
+    );
+}
+
+#[test]
+fn test_timeout_names_phase_and_duration() {
+    let err = ValidatorError::Timeout {
+        phase: "Setup".into(),
+        validator: "osquery".into(),
+        seconds: 60,
+        message: "in 'Intro' block 2".into(),
+    };
+    let display = err.to_string();
+    assert!(display.contains("[E023]"), "Should contain E023: {display}");
+    assert!(
+        display.contains("Setup timed out after 60s (validator: osquery)")
+            && display.contains("block 2"),
+        "Should show the phase, timeout and block: {display}"
+    );
+}
+
+#[test]
+fn test_cleanup_failed_shows_exit_code() {
+    let err = ValidatorError::CleanupFailed {
+        exit_code: 1,
+        message: "in 'Intro' (validator: sqlite)".into(),
+    };
+    let display = err.to_string();
+    assert!(display.contains("[E024]"), "Should contain E024: {display}");
+    assert!(
+        display.contains("Cleanup script failed (exit 1)") && display.contains("'Intro'"),
+        "Should show the exit code and message: {display}"
     );
 }
 
@@ -219,6 +509,30 @@ fn test_matches_on_validation_failed_with_exit_code() {
     ));
 }
 
+// === append_message() tests ===
+
+#[test]
+fn test_append_message_keeps_code() {
+    let mut err = ValidatorError::ValidationFailed {
+        exit_code: 1,
+        message: "rows = 5: got 2".into(),
+    };
+    assert!(err.append_message("\n\non_failure output:\n2 rows"));
+    let display = err.to_string();
+    assert!(display.contains("[E006]"), "code kept: {display}");
+    assert!(
+        display.contains("got 2\n\non_failure output:\n2 rows"),
+        "appended after the message: {display}"
+    );
+}
+
+#[test]
+fn test_append_message_without_message_field() {
+    let mut err = ValidatorError::MutuallyExclusiveAttributes;
+    assert!(!err.append_message("extra"));
+    assert!(!err.to_string().contains("extra"));
+}
+
 // === Edge case tests ===
 
 #[test]
@@ -261,3 +575,101 @@ fn test_special_chars_in_message() {
         "Should preserve special chars: {display}"
     );
 }
+
+// === explain() tests ===
+
+#[test]
+fn test_explain_known_code() {
+    let text = explain("E011").expect("E011 should have an explanation");
+    assert!(text.contains("mutually exclusive"), "got: {text}");
+    assert!(text.contains("Fix:"), "should include remediation: {text}");
+}
+
+#[test]
+fn test_explain_is_case_insensitive() {
+    assert_eq!(explain("e007"), explain("E007"));
+    assert!(explain(" e007 ").is_some());
+}
+
+#[test]
+fn test_explain_unknown_code() {
+    assert!(explain("E999").is_none());
+    assert!(explain("not-a-code").is_none());
+}
+
+#[test]
+fn test_explain_covers_every_code() {
+    for n in 1..=25 {
+        let code = format!("E{n:03}");
+        let text = explain(&code).unwrap_or_else(|| panic!("{code} has no explanation"));
+        assert!(
+            text.starts_with(&code),
+            "explanation should start with its code: {text}"
+        );
+    }
+}
+
+// === anyhow conversion tests ===
+
+#[test]
+fn test_into_anyhow_with_context_prefixes_message_and_keeps_type() {
+    let err = ValidatorError::ContainerExec {
+        message: "exec broke".into(),
+    }
+    .into_anyhow_with_context("Setup exec failed");
+
+    assert_eq!(
+        err.to_string(),
+        "[E003] Container exec failed: Setup exec failed: exec broke"
+    );
+    assert!(matches!(
+        err.downcast_ref::<ValidatorError>(),
+        Some(ValidatorError::ContainerExec { .. })
+    ));
+}
+
+#[test]
+fn test_into_anyhow_with_context_wraps_variants_without_message() {
+    let err = ValidatorError::ScriptNotFound {
+        path: "missing.sh".into(),
+    }
+    .into_anyhow_with_context("Loading validator");
+
+    assert!(
+        err.to_string().starts_with("Loading validator: [E010]"),
+        "got: {err}"
+    );
+    assert!(matches!(
+        err.downcast_ref::<ValidatorError>(),
+        Some(ValidatorError::ScriptNotFound { .. })
+    ));
+}
+
+#[test]
+fn test_with_context_stringifies_other_errors() {
+    let err = with_context(anyhow::anyhow!("connection reset"), "Hook exec failed");
+    assert_eq!(err.to_string(), "Hook exec failed: connection reset");
+    assert!(err.downcast_ref::<ValidatorError>().is_none());
+}
+
+#[test]
+fn test_aggregated_errors_display_numbers_each_failure() {
+    let errors = AggregatedErrors::new(vec![
+        ValidatorError::UnknownValidator {
+            name: "missing".into(),
+        }
+        .into(),
+        anyhow::anyhow!("Query timed out"),
+    ]);
+
+    assert_eq!(
+        errors.to_string(),
+        "2 blocks failed validation:\n\n\
+         --- Failure 1 of 2 ---\n\
+         [E007] Unknown validator 'missing'\n\n\
+         --- Failure 2 of 2 ---\n\
+         Query timed out"
+    );
+    assert_eq!(errors.errors().len(), 2);
+    assert_eq!(errors.validator_errors().count(), 1);
+}
@@ -4,10 +4,19 @@
 //! Tests for `host_validator` module
 
 use mdbook_validator::command::RealCommandRunner;
-use mdbook_validator::host_validator::run_validator;
+use mdbook_validator::host_validator::{
+    apply_filter, assertion_input, check_contains_json, check_expect_documents,
+    check_expect_ignoring, check_expect_json, check_expect_regex, check_expect_template,
+    check_expect_unordered, check_host_assertion, check_json_length, check_native_assertions,
+    check_table_columns, check_valid_json, filter_allowed_warnings, run_validator,
+    split_host_assertions, CompareOp, CountCheck, TomlAssertions,
+};
+use mdbook_validator::parser::AssertTarget;
 
 const ECHO_VALIDATOR: &str = "tests/fixtures/echo_validator.sh";
 const EXIT_CODE_VALIDATOR: &str = "tests/fixtures/exit_code_validator.sh";
+const PYTHON_VALIDATOR: &str = "validators/validate-python.sh";
+const SQLITE_VALIDATOR: &str = "validators/validate-sqlite.sh";
 
 #[test]
 fn test_host_validator_runs_script() {
@@ -124,3 +133,796 @@ fn test_host_validator_nonexistent_script_returns_error_exit() {
         result.stderr
     );
 }
+
+#[test]
+fn test_expect_regex_matches_timestamp_output() {
+    // Stable structure, variable values: timestamp and id differ run to run
+    let output = r#"[{"id":1742,"created_at":"2024-06-01T12:34:56Z"}]"#;
+    let pattern = r#"\[\{"id":\d+,"created_at":"\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z"\}\]"#;
+
+    let result = check_expect_regex(pattern, output);
+
+    assert_eq!(
+        result.exit_code, 0,
+        "pattern should match: {}",
+        result.stderr
+    );
+}
+
+#[test]
+fn test_expect_regex_ignores_surrounding_whitespace() {
+    let result = check_expect_regex("\n  rows: \\d+  \n", "rows: 3\n");
+
+    assert_eq!(result.exit_code, 0, "should match: {}", result.stderr);
+}
+
+#[test]
+fn test_expect_regex_mismatch_reports_pattern_and_output() {
+    let output = r#"[{"id":"abc","created_at":"yesterday"}]"#;
+    let pattern = r#"\[\{"id":\d+,"created_at":"\d{4}-\d{2}-\d{2}T[\d:]+Z"\}\]"#;
+
+    let result = check_expect_regex(pattern, output);
+
+    assert_eq!(result.exit_code, 1, "pattern should not match");
+    assert!(
+        result.stderr.contains(pattern),
+        "stderr should contain the pattern: {}",
+        result.stderr
+    );
+    assert!(
+        result.stderr.contains(output),
+        "stderr should contain the actual output: {}",
+        result.stderr
+    );
+}
+
+#[test]
+fn test_expect_regex_must_match_full_output() {
+    // A partial match is not enough - the pattern is anchored at both ends
+    let result = check_expect_regex(r"\d+", "id 42");
+
+    assert_eq!(result.exit_code, 1, "partial match should fail");
+}
+
+#[test]
+fn test_expect_regex_invalid_pattern_fails() {
+    let result = check_expect_regex("[unclosed", "anything");
+
+    assert_eq!(result.exit_code, 1);
+    assert!(
+        result.stderr.contains("Invalid EXPECT_REGEX pattern"),
+        "stderr should explain the invalid pattern: {}",
+        result.stderr
+    );
+}
+
+#[test]
+fn test_expect_template_wildcard_tolerates_differing_values() {
+    let template = r#"[{"id":<*>,"name":"alice"}]"#;
+
+    for output in [
+        r#"[{"id":1,"name":"alice"}]"#,
+        r#"[{"id":"a1b2","name":"alice"}]"#,
+        r#"[{"name": "alice", "id": null}]"#,
+    ] {
+        let result = check_expect_template(template, output);
+        assert_eq!(
+            result.exit_code, 0,
+            "{output} should match: {}",
+            result.stderr
+        );
+    }
+}
+
+#[test]
+fn test_expect_template_non_wildcard_positions_must_match() {
+    let template = r#"[{"id":<*>,"name":"alice"}]"#;
+
+    let result = check_expect_template(template, r#"[{"id":1,"name":"bob"}]"#);
+
+    assert_eq!(result.exit_code, 1, "name differs, should fail");
+    assert!(
+        result.stderr.contains(template),
+        "stderr should contain the template: {}",
+        result.stderr
+    );
+    assert!(
+        result.stderr.contains(r#""name":"bob""#),
+        "stderr should contain the actual output: {}",
+        result.stderr
+    );
+}
+
+#[test]
+fn test_expect_template_structure_must_match() {
+    let template = r#"[{"id":<*>}]"#;
+
+    // Extra key, extra row, and missing row all fail
+    for output in [
+        r#"[{"id":1,"name":"alice"}]"#,
+        r#"[{"id":1},{"id":2}]"#,
+        "[]",
+    ] {
+        let result = check_expect_template(template, output);
+        assert_eq!(result.exit_code, 1, "{output} should not match");
+    }
+}
+
+#[test]
+fn test_expect_template_wildcard_matches_nested_values() {
+    // A wildcard stands for a whole value, including objects and arrays
+    let template = r#"{"meta":<*>,"rows":[<*>, 2]}"#;
+
+    let result = check_expect_template(template, r#"{"meta":{"ts":"now"},"rows":[[1],2]}"#);
+
+    assert_eq!(result.exit_code, 0, "should match: {}", result.stderr);
+}
+
+#[test]
+fn test_expect_template_quoted_wildcard_and_literal_in_string() {
+    // "<*>" as a whole string is a wildcard; inside a longer string it is literal text
+    let result = check_expect_template(r#"[{"id":"<*>"}]"#, r#"[{"id":7}]"#);
+    assert_eq!(result.exit_code, 0, "quoted wildcard: {}", result.stderr);
+
+    let result = check_expect_template(r#"[{"s":"a <*> b"}]"#, r#"[{"s":"a x b"}]"#);
+    assert_eq!(result.exit_code, 1, "wildcard inside a string is literal");
+}
+
+#[test]
+fn test_expect_ignoring_strips_fields_from_both_sides() {
+    let template = r#"[{"id":1,"name":"alice","created_at":"2024-01-01"}]"#;
+    let output = r#"[{"id":57,"name":"alice","created_at":"2026-10-16 09:12:44"}]"#;
+    let ignore = ["created_at".to_owned(), "id".to_owned()];
+
+    assert_eq!(check_expect_template(template, output).exit_code, 1);
+    let result = check_expect_ignoring(template, output, &ignore);
+    assert_eq!(result.exit_code, 0, "should match: {}", result.stderr);
+
+    // Template may omit ignored fields entirely; nested objects are stripped too
+    let result = check_expect_ignoring(
+        r#"{"rows":[{"name":"alice"}]}"#,
+        r#"{"rows":[{"id":3,"name":"alice"}]}"#,
+        &ignore,
+    );
+    assert_eq!(result.exit_code, 0, "nested: {}", result.stderr);
+}
+
+#[test]
+fn test_expect_ignoring_still_compares_other_fields() {
+    let result = check_expect_ignoring(
+        r#"[{"id":1,"name":"alice"}]"#,
+        r#"[{"id":2,"name":"bob"}]"#,
+        &["id".to_owned()],
+    );
+
+    assert_eq!(result.exit_code, 1);
+    assert!(
+        result.stderr.contains("(ignoring id)"),
+        "stderr should name the ignored fields: {}",
+        result.stderr
+    );
+}
+
+#[test]
+fn test_expect_documents_match_result_sets_in_order() {
+    let output = "[{\"a\":1}]\n[{\"b\":2,\"at\":\"now\"}]\n";
+    let templates = [r#"[{"a": 1}]"#.to_owned(), r#"[{"b": <*>}]"#.to_owned()];
+
+    let result = check_expect_documents(&templates, output, &["at".to_owned()]);
+
+    assert_eq!(result.exit_code, 0, "{}", result.stderr);
+}
+
+#[test]
+fn test_expect_documents_name_the_mismatched_expect() {
+    let output = "[{\"a\":1}]\n[{\"b\":2}]";
+    let templates = [r#"[{"a": 1}]"#.to_owned(), r#"[{"b": 3}]"#.to_owned()];
+
+    let result = check_expect_documents(&templates, output, &[]);
+
+    assert_eq!(result.exit_code, 1);
+    assert!(result.stderr.contains("EXPECT #2"), "{}", result.stderr);
+    assert!(!result.stderr.contains("EXPECT #1"), "{}", result.stderr);
+}
+
+#[test]
+fn test_expect_documents_count_must_match() {
+    let templates = ["[]".to_owned(), "[]".to_owned()];
+
+    let result = check_expect_documents(&templates, "[]", &[]);
+
+    assert_eq!(result.exit_code, 1);
+    assert!(
+        result.stderr.contains("Expected 2 result sets"),
+        "{}",
+        result.stderr
+    );
+}
+
+#[test]
+fn test_expect_json_ignores_key_order_and_whitespace() {
+    let template = r#"[{ "name": "alice", "id": 1 }]"#;
+    let output = "[{\"id\":1,\"name\":\"alice\"}]\n";
+
+    let result = check_expect_json(template, output, &[]);
+    assert_eq!(result.exit_code, 0, "stderr: {}", result.stderr);
+
+    let result = check_expect_json("[\n  {\n    \"id\" : 1\n  }\n]", r#"[{"id":1}]"#, &[]);
+    assert_eq!(result.exit_code, 0, "stderr: {}", result.stderr);
+}
+
+#[test]
+fn test_expect_json_value_difference_fails_with_diff() {
+    let template = r#"[{"name":"alice","id":1}]"#;
+    let result = check_expect_json(template, r#"[{"id":2,"name":"alice"}]"#, &[]);
+
+    assert_eq!(result.exit_code, 1);
+    // Normalized forms are diffed, keys sorted, so only the changed line differs
+    assert!(
+        result
+            .stderr
+            .contains("  -     \"id\": 1,\n  +     \"id\": 2,"),
+        "stderr: {}",
+        result.stderr
+    );
+    assert!(
+        result.stderr.contains("        \"name\": \"alice\""),
+        "stderr: {}",
+        result.stderr
+    );
+}
+
+#[test]
+fn test_expect_unordered_passes_reordered_rows() {
+    let template = r#"[{"id":1,"name":"alice"},{"id":2,"name":"bob"}]"#;
+    let output = r#"[{"id":2,"name":"bob"},{"id":1,"name":"alice"}]"#;
+
+    assert_eq!(check_expect_template(template, output).exit_code, 1);
+    let result = check_expect_unordered(template, output, &[]);
+    assert_eq!(result.exit_code, 0, "stderr: {}", result.stderr);
+}
+
+#[test]
+fn test_expect_unordered_compares_as_multiset() {
+    // Duplicate rows must appear as often as expected
+    let template = r#"[{"id":1},{"id":1},{"id":<*>}]"#;
+    let result = check_expect_unordered(template, r#"[{"id":3},{"id":1},{"id":1}]"#, &[]);
+    assert_eq!(result.exit_code, 0, "stderr: {}", result.stderr);
+
+    let result = check_expect_unordered(template, r#"[{"id":1},{"id":2},{"id":3}]"#, &[]);
+    assert_eq!(result.exit_code, 1);
+}
+
+#[test]
+fn test_expect_unordered_reports_missing_and_extra_rows() {
+    let template = r#"[{"id":1},{"id":2}]"#;
+    let result = check_expect_unordered(template, r#"[{"id":2},{"id":3}]"#, &[]);
+
+    assert_eq!(result.exit_code, 1);
+    assert!(
+        result.stderr.contains("Missing rows:\n    {\"id\":1}"),
+        "stderr: {}",
+        result.stderr
+    );
+    assert!(
+        result.stderr.contains("Extra rows:\n    {\"id\":3}"),
+        "stderr: {}",
+        result.stderr
+    );
+}
+
+#[test]
+fn test_expect_unordered_requires_arrays() {
+    let result = check_expect_unordered(r#"{"id":1}"#, r#"{"id":1}"#, &[]);
+    assert_eq!(result.exit_code, 1);
+    assert!(
+        result.stderr.contains("JSON array"),
+        "stderr: {}",
+        result.stderr
+    );
+}
+
+#[test]
+fn test_expect_template_invalid_output_fails() {
+    let result = check_expect_template(r#"[{"id":<*>}]"#, "not json");
+
+    assert_eq!(result.exit_code, 1);
+    assert!(
+        result.stderr.contains("not valid JSON"),
+        "stderr should explain the invalid output: {}",
+        result.stderr
+    );
+}
+
+#[test]
+fn test_filter_allowed_warnings_drops_matching_lines() {
+    let stderr =
+        "W1 Cannot set unknown or invalid flag: foo\nW2 Cannot set unknown or invalid flag: bar";
+
+    let filtered = filter_allowed_warnings(stderr, &["flag: foo".to_owned()]);
+
+    assert_eq!(filtered, "W2 Cannot set unknown or invalid flag: bar");
+    assert_eq!(filter_allowed_warnings(stderr, &[]), stderr);
+}
+
+#[test]
+fn test_apply_filter_projects_rows() {
+    let output = r#"[{"id":1,"name":"alice"},{"id":2,"name":"bob"}]"#;
+
+    let result = apply_filter(".[] | {id}", output).expect("jq should run");
+
+    assert_eq!(
+        result.exit_code, 0,
+        "filter should apply: {}",
+        result.stderr
+    );
+    assert_eq!(result.stdout, r#"[{"id":1},{"id":2}]"#);
+}
+
+#[test]
+fn test_apply_filter_handles_output_larger_than_pipe_buffers() {
+    // A stream of JSON values, far more than a pipe holds: jq prints the result for
+    // each value while later ones are still being written to it
+    let output = (0..100_000)
+        .map(|i| format!("{{\"id\":{i},\"name\":\"user-{i}\"}}\n"))
+        .collect::<Vec<_>>()
+        .concat();
+
+    let result = apply_filter(".id", &output).expect("jq should run");
+
+    assert_eq!(result.exit_code, 0, "{}", result.stderr);
+    assert_eq!(result.stdout.lines().count(), 100_000);
+}
+
+#[test]
+fn test_apply_filter_invalid_expression_fails() {
+    let result = apply_filter(".[] | {id", "[]").expect("jq should run");
+
+    assert_eq!(result.exit_code, 1, "invalid jq should fail");
+    assert!(
+        result.stderr.contains("Invalid filter") && result.stderr.contains(".[] | {id"),
+        "stderr should name the filter: {}",
+        result.stderr
+    );
+}
+
+#[test]
+fn test_json_length_counts_array_elements() {
+    let output = r#"[{"id":1},{"id":2},{"id":3}]"#;
+
+    for assertion in [
+        "json_length = 3",
+        "json_length >= 2",
+        "json_length > 2",
+        "json_length <= 3",
+        "json_length < 4",
+        "json_length != 0",
+        "!json_length = 0",
+    ] {
+        let result = check_json_length(assertion, output);
+        assert_eq!(result.exit_code, 0, "{assertion}: {}", result.stderr);
+    }
+
+    let result = check_json_length("json_length = 2", output);
+    assert_eq!(result.exit_code, 1);
+    assert!(result.stderr.contains("got 3"), "{}", result.stderr);
+}
+
+#[test]
+fn test_json_length_counts_object_keys() {
+    let output = r#"{"version":"5.17.0","build":"abc","platform":"linux"}"#;
+
+    let result = check_json_length("json_length = 3", output);
+    assert_eq!(result.exit_code, 0, "{}", result.stderr);
+
+    let result = check_json_length("json_length > 3", output);
+    assert_eq!(result.exit_code, 1);
+    assert!(result.stderr.contains("got 3"), "{}", result.stderr);
+}
+
+#[test]
+fn test_json_length_negated_holding_assertion_fails() {
+    let result = check_json_length("!json_length = 1", "[1]");
+
+    assert_eq!(result.exit_code, 1);
+    assert!(
+        result.stderr.contains("holds but is negated"),
+        "{}",
+        result.stderr
+    );
+}
+
+#[test]
+fn test_json_length_rejects_unsuitable_output_and_malformed_assertions() {
+    let cases = [
+        ("json_length = 1", "42", "not a JSON array or object"),
+        ("json_length = 1", "not json", "not valid JSON"),
+        ("json_length = x", "[]", "invalid integer"),
+        ("json_length ~ 1", "[]", "unknown operator"),
+        ("json_length 1", "[]", "expected `json_length <op> N`"),
+        // Malformed still fails when negated
+        ("!json_length = 1", "42", "not a JSON array or object"),
+    ];
+    for (assertion, output, reason) in cases {
+        let result = check_json_length(assertion, output);
+        assert_eq!(result.exit_code, 1, "{assertion} on {output} should fail");
+        assert!(
+            result.stderr.contains(reason),
+            "{assertion}: expected {reason:?} in {}",
+            result.stderr
+        );
+    }
+}
+
+#[test]
+fn test_contains_json_matches_subset_of_any_row() {
+    let output =
+        r#"[{"id":1,"status":"pending"},{"id":2,"status":"ok","meta":{"region":"eu","zone":3}}]"#;
+
+    for assertion in [
+        r#"contains_json '{"status":"ok"}'"#,
+        r#"contains_json {"id":2,"status":"ok"}"#,
+        r#"contains_json '{"meta":{"region":"eu"}}'"#,
+        r#"!contains_json '{"status":"failed"}'"#,
+    ] {
+        let result = check_contains_json(assertion, output);
+        assert_eq!(result.exit_code, 0, "{assertion}: {}", result.stderr);
+    }
+
+    // A single object is one row
+    let result = check_contains_json(r#"contains_json '{"a":1}'"#, r#"{"a":1,"b":2}"#);
+    assert_eq!(result.exit_code, 0, "{}", result.stderr);
+}
+
+#[test]
+fn test_contains_json_reports_unmatched_fragment() {
+    let output = r#"[{"id":1,"status":"pending"},{"id":2,"status":"ok"}]"#;
+
+    // Both pairs exist, but not in the same row
+    let result = check_contains_json(r#"contains_json '{"id":1,"status":"ok"}'"#, output);
+
+    assert_eq!(result.exit_code, 1);
+    assert!(
+        result
+            .stderr
+            .contains(r#"no row contains {"id":1,"status":"ok"}"#),
+        "{}",
+        result.stderr
+    );
+}
+
+#[test]
+fn test_contains_json_rejects_malformed_fragment() {
+    for (assertion, reason) in [
+        ("contains_json '{status}'", "not valid JSON"),
+        ("contains_json '[1]'", "must be a JSON object"),
+    ] {
+        let result = check_contains_json(assertion, "[]");
+        assert_eq!(result.exit_code, 1, "{assertion} should fail");
+        assert!(result.stderr.contains(reason), "{}", result.stderr);
+    }
+}
+
+#[test]
+fn test_check_host_assertion_dispatches_by_keyword() {
+    let output = r#"[{"status":"ok"}]"#;
+
+    assert_eq!(check_host_assertion("json_length = 1", output).exit_code, 0);
+    assert_eq!(
+        check_host_assertion(r#"contains_json '{"status":"ok"}'"#, output).exit_code,
+        0
+    );
+}
+
+#[test]
+fn test_split_host_assertions_routes_json_length_to_host() {
+    let (script, host) = split_host_assertions(
+        "rows >= 1\njson_length = 2\n  !json_length = 0\ncontains \"json_length\"",
+    );
+
+    assert_eq!(
+        script.as_deref(),
+        Some("rows >= 1\ncontains \"json_length\"")
+    );
+    assert_eq!(host, vec!["json_length = 2", "  !json_length = 0"]);
+
+    let (script, host) = split_host_assertions("json_length = 1");
+    assert_eq!(script, None, "no script assertions remain");
+    assert_eq!(host.len(), 1);
+}
+
+#[test]
+fn test_split_host_assertions_routes_contains_json_to_host() {
+    let (script, host) =
+        split_host_assertions("contains \"ok\"\ncontains_json '{\"status\":\"ok\"}'");
+
+    assert_eq!(script.as_deref(), Some("contains \"ok\""));
+    assert_eq!(host, vec!["contains_json '{\"status\":\"ok\"}'"]);
+}
+
+#[test]
+fn test_split_host_assertions_routes_max_memory_to_host() {
+    let (script, host) = split_host_assertions("rows >= 1\nmax_memory \"64m\"");
+
+    assert_eq!(script.as_deref(), Some("rows >= 1"));
+    assert_eq!(host, vec!["max_memory \"64m\""]);
+    assert_eq!(
+        check_host_assertion("max_memory \"64m\"", "[]").exit_code,
+        0,
+        "the budget is enforced by the container, not the output"
+    );
+}
+
+#[test]
+fn test_valid_json_passes_well_formed_output() {
+    for output in [r#"[{"id":1}]"#, r#"{"status":"ok"}"#, "42", "\n\"text\"\n"] {
+        let result = check_valid_json("valid_json", output);
+        assert_eq!(result.exit_code, 0, "{output}: {}", result.stderr);
+    }
+}
+
+#[test]
+fn test_valid_json_reports_parse_error_location() {
+    let result = check_valid_json("valid_json", "[{\"id\": 1},\n {\"id\": 2,}]");
+
+    assert_eq!(result.exit_code, 1);
+    assert!(
+        result.stderr.contains("output is not valid JSON") && result.stderr.contains("line 2"),
+        "stderr: {}",
+        result.stderr
+    );
+    assert_eq!(check_valid_json("valid_json", "").exit_code, 1);
+}
+
+#[test]
+fn test_valid_json_negated_and_routed_to_host() {
+    assert_eq!(check_valid_json("!valid_json", "not json").exit_code, 0);
+    assert_eq!(check_valid_json("!valid_json", "[]").exit_code, 1);
+
+    let (script, host) = split_host_assertions("rows >= 1\nvalid_json");
+    assert_eq!(script.as_deref(), Some("rows >= 1"));
+    assert_eq!(host, vec!["valid_json"]);
+    assert_eq!(check_host_assertion("valid_json", "{oops").exit_code, 1);
+}
+
+const COLUMN_TABLE: &str = "name   age  city
+-----  ---  ------
+alice  30   Berlin
+bob    25   Paris
+";
+
+#[test]
+fn test_table_columns_counts_first_data_row() {
+    assert_eq!(
+        check_table_columns("table_columns = 3", COLUMN_TABLE).exit_code,
+        0
+    );
+    assert_eq!(
+        check_table_columns("table_columns >= 2", COLUMN_TABLE).exit_code,
+        0
+    );
+
+    let result = check_table_columns("table_columns = 5", COLUMN_TABLE);
+    assert_eq!(result.exit_code, 1);
+    assert!(
+        result.stderr.contains("got 3 in row 'alice  30   Berlin'"),
+        "stderr: {}",
+        result.stderr
+    );
+}
+
+#[test]
+fn test_table_columns_skips_box_borders() {
+    let table = "+------+-----+\n| name | age |\n+------+-----+\n| bob  | 25  |\n+------+-----+\n";
+    // Field count includes the `|` cell borders
+    assert_eq!(check_table_columns("table_columns = 5", table).exit_code, 0);
+}
+
+#[test]
+fn test_table_columns_fails_without_data_row() {
+    let result = check_table_columns("table_columns = 3", "name  age  city\n----  ---  ----\n");
+    assert_eq!(result.exit_code, 1);
+    assert!(
+        result.stderr.contains("no data row"),
+        "stderr: {}",
+        result.stderr
+    );
+    // Unsuitable output fails even when negated
+    assert_eq!(check_table_columns("!table_columns = 3", "").exit_code, 1);
+    assert_eq!(
+        check_table_columns("table_columns = x", COLUMN_TABLE).exit_code,
+        1
+    );
+}
+
+#[test]
+fn test_table_columns_negated_and_routed_to_host() {
+    assert_eq!(
+        check_table_columns("!table_columns = 5", COLUMN_TABLE).exit_code,
+        0
+    );
+
+    let (script, host) = split_host_assertions("rows >= 1\ntable_columns = 3");
+    assert_eq!(script.as_deref(), Some("rows >= 1"));
+    assert_eq!(host, vec!["table_columns = 3"]);
+    assert_eq!(
+        check_host_assertion("table_columns = 5", COLUMN_TABLE).exit_code,
+        1
+    );
+}
+
+#[test]
+fn test_combined_assert_target_matches_pattern_split_across_streams() {
+    // The tool printed "Hello, " to stdout and "world" to stderr
+    let (stdout, stderr) = ("Hello, ", "world");
+    let assertions = Some(r#"contains "Hello, world""#);
+
+    for (target, expected_exit) in [(AssertTarget::Stdout, 1), (AssertTarget::Combined, 0)] {
+        let input = assertion_input(target, stdout, stderr);
+        let result = run_validator(
+            &RealCommandRunner,
+            PYTHON_VALIDATOR,
+            &input,
+            assertions,
+            None,
+            Some(stderr),
+        )
+        .expect("validator should run");
+        assert_eq!(
+            result.exit_code, expected_exit,
+            "{target:?}: {}",
+            result.stderr
+        );
+    }
+}
+
+#[test]
+fn test_toml_assertions_parse_structured_block() {
+    let assertions = TomlAssertions::parse(
+        r#"
+        rows = { op = ">=", value = 1 }
+        contains = ["alice", "bob"]
+        not_contains = ["password"]
+        "#,
+    )
+    .expect("should parse");
+
+    assert_eq!(
+        assertions,
+        TomlAssertions {
+            rows: Some(CountCheck {
+                op: CompareOp::Ge,
+                value: 1,
+            }),
+            contains: vec!["alice".to_owned(), "bob".to_owned()],
+            not_contains: vec!["password".to_owned()],
+        }
+    );
+}
+
+#[test]
+fn test_toml_assertions_reject_unknown_keys_and_operators() {
+    assert!(TomlAssertions::parse("rowz = { op = \"=\", value = 1 }").is_err());
+    assert!(TomlAssertions::parse("rows = { op = \"~\", value = 1 }").is_err());
+}
+
+#[test]
+fn test_toml_assertions_pass_when_all_hold() {
+    let assertions = TomlAssertions::parse(
+        r#"
+        rows = { op = "=", value = 2 }
+        contains = ["alice", "bob"]
+        not_contains = ["carol"]
+        "#,
+    )
+    .expect("should parse");
+
+    let result = assertions.check(r#"[{"name":"alice"},{"name":"bob"}]"#);
+    assert_eq!(result.exit_code, 0, "stderr: {}", result.stderr);
+}
+
+#[test]
+fn test_toml_assertions_report_every_failure() {
+    let assertions = TomlAssertions::parse(
+        r#"
+        rows = { op = ">", value = 5 }
+        contains = ["alice", "dave"]
+        not_contains = ["bob"]
+        "#,
+    )
+    .expect("should parse");
+
+    let result = assertions.check(r#"[{"name":"alice"},{"name":"bob"}]"#);
+    assert_eq!(result.exit_code, 1);
+    assert!(
+        result.stderr.contains("rows > 5: got 2"),
+        "{}",
+        result.stderr
+    );
+    assert!(
+        result.stderr.contains(r#"contains "dave": not found"#),
+        "{}",
+        result.stderr
+    );
+    assert!(
+        result.stderr.contains(r#"not_contains "bob": found"#),
+        "{}",
+        result.stderr
+    );
+    assert!(!result.stderr.contains("alice"), "{}", result.stderr);
+}
+
+#[test]
+fn test_toml_assertions_count_empty_output_as_zero_rows() {
+    let assertions =
+        TomlAssertions::parse(r#"rows = { op = "=", value = 0 }"#).expect("should parse");
+    assert_eq!(assertions.check("").exit_code, 0);
+}
+
+#[test]
+fn test_native_assertions_match_sqlite_script() {
+    let runner = RealCommandRunner;
+    let output = r#"[{"name":"alice","tags":["admin"]},{"name":"bob","tags":[]}]"#;
+    let cases = [
+        ("rows = 2", ""),
+        ("rows = 3", ""),
+        ("rows >= 1\nrows > 1", ""),
+        ("rows > 2", ""),
+        ("rows = 3 ± 1", ""),
+        ("rows = 5 ± 1", ""),
+        ("rows = 1 tolerance 1", ""),
+        ("contains \"admin\"", ""),
+        ("contains \"carol\"", ""),
+        ("!contains \"carol\"", ""),
+        ("!rows = 2", ""),
+        ("stderr_empty", ""),
+        ("stderr_empty", "Error: near line 1"),
+    ];
+
+    for (assertions, stderr) in cases {
+        let script = run_validator(
+            &runner,
+            SQLITE_VALIDATOR,
+            output,
+            Some(assertions),
+            None,
+            Some(stderr),
+        )
+        .expect("validator should run");
+        let native = check_native_assertions(Some(assertions), output, stderr)
+            .unwrap_or_else(|| panic!("{assertions} should be evaluated natively"));
+
+        assert_eq!(
+            native.exit_code, script.exit_code,
+            "{assertions}: native {:?}, script {:?}",
+            native.stderr, script.stderr
+        );
+        assert_eq!(native.stderr.trim(), script.stderr.trim(), "{assertions}");
+    }
+}
+
+#[test]
+fn test_native_assertions_check_json_length_and_invalid_json() {
+    let result = check_native_assertions(Some("json_length = 1"), r#"{"a": 1}"#, "")
+        .expect("json_length is native");
+    assert_eq!(result.exit_code, 0, "{}", result.stderr);
+
+    let result = check_native_assertions(Some("rows = 1"), "not json", "").expect("rows is native");
+    assert_eq!(result.exit_code, 1);
+    assert_eq!(result.stderr, "Invalid JSON output");
+}
+
+#[test]
+fn test_native_assertions_fall_back_for_other_assertions() {
+    for assertions in [
+        "columns = 2",
+        "rows = 1\nsorted_by id asc",
+        "!!rows = 1",
+        "rows = x",
+    ] {
+        assert!(
+            check_native_assertions(Some(assertions), "[]", "").is_none(),
+            "{assertions} should fall back to the script"
+        );
+    }
+}
@@ -12,10 +12,16 @@
 
 use mdbook_preprocessor::book::{Book, BookItem, Chapter};
 use mdbook_preprocessor::Preprocessor;
-use mdbook_validator::config::{Config, ValidatorConfig};
-use mdbook_validator::ValidatorPreprocessor;
+use mdbook_validator::config::{
+    Config, ExpectVersion, Isolation, NormalizeMode, StateMode, Transform, ValidatorConfig,
+};
+use mdbook_validator::pool::ContainerPool;
+use mdbook_validator::report::ExitCodes;
+use mdbook_validator::{AggregatedErrors, ValidatorError, ValidatorPreprocessor};
 use std::collections::HashMap;
+use std::io::{self, Write};
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 
 /// Creates a test config with sqlite validator
 fn create_sqlite_config() -> Config {
@@ -26,6 +32,7 @@ fn create_sqlite_config() -> Config {
             container: "keinos/sqlite3:3.47.2".to_string(),
             script: PathBuf::from("validators/validate-sqlite.sh"),
             exec_command: Some("sqlite3 -json /tmp/test.db".to_string()),
+            ..Default::default()
         },
     );
 
@@ -33,6 +40,7 @@ fn create_sqlite_config() -> Config {
         validators,
         fail_fast: true,
         fixtures_dir: None,
+        ..Default::default()
     }
 }
 
@@ -135,8 +143,12 @@ More text after.
                 "Visible content should remain. Output:\n{output}"
             );
             assert!(
-                output.contains("validator=sqlite"),
-                "Code block info should remain. Output:\n{output}"
+                output.contains("```sql\nSELECT 1"),
+                "Code block language should remain. Output:\n{output}"
+            );
+            assert!(
+                !output.contains("validator=sqlite"),
+                "validator= should be stripped from the fence. Output:\n{output}"
             );
 
             println!("Integration test passed! Output:\n{output}");
@@ -591,6 +603,55 @@ fn preprocessor_handles_nested_chapters() {
     }
 }
 
+/// Writer that keeps everything written to it, for reading back after a run
+#[derive(Clone, Default)]
+struct CapturedOutput(Arc<Mutex<Vec<u8>>>);
+
+impl Write for CapturedOutput {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Test: progress counts blocks across chapters, nested ones included
+#[test]
+fn progress_reports_blocks_across_chapters() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_sqlite_config();
+
+    // Two blocks in the nested chapters, plus one validated and one skipped here
+    let mut book = create_book_with_nested_chapters();
+    book.items.push(BookItem::Chapter(Chapter::new(
+        "Second Chapter",
+        "# Second Chapter\n\n```sql validator=sqlite\nSELECT 'second';\n```\n\n```sql validator=sqlite skip\nSELECT 'skipped';\n```\n".to_string(),
+        PathBuf::from("second.md"),
+        vec![],
+    )));
+
+    let captured = CapturedOutput::default();
+    let preprocessor = ValidatorPreprocessor::builder()
+        .progress_writer(captured.clone())
+        .build();
+    preprocessor
+        .process_book_with_config(book, &config, &book_root)
+        .expect("book should validate");
+
+    let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+    assert_eq!(
+        output.lines().collect::<Vec<_>>(),
+        [
+            "mdbook-validator: validated 1/3 blocks",
+            "mdbook-validator: validated 2/3 blocks",
+            "mdbook-validator: validated 3/3 blocks",
+        ]
+    );
+}
+
 // ============================================================================
 // Config-based validator tests
 // ============================================================================
@@ -615,6 +676,7 @@ fn preprocessor_uses_configured_osquery_validator() {
             container: "osquery/osquery:5.17.0-ubuntu22.04".to_string(),
             script: PathBuf::from("validators/validate-osquery.sh"),
             exec_command: None,
+            ..Default::default()
         },
     );
 
@@ -622,6 +684,7 @@ fn preprocessor_uses_configured_osquery_validator() {
         validators,
         fail_fast: true,
         fixtures_dir: None,
+        ..Default::default()
     };
 
     // Verify the validator script exists
@@ -682,6 +745,143 @@ rows >= 1
     }
 }
 
+/// Test: A chapter's front matter overrides the book's sqlite image for that chapter
+#[test]
+fn front_matter_overrides_validator_image() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    // The book's image doesn't exist, so the block only passes with the override
+    let mut config = create_sqlite_config();
+    if let Some(sqlite) = config.validators.get_mut("sqlite") {
+        sqlite.container = "mdbook-validator-test/no-such-image:1".to_string();
+    }
+
+    let chapter_content = r"---
+validator:
+  sqlite:
+    container: keinos/sqlite3:3.47.2
+---
+# Overridden
+
+```sql validator=sqlite
+SELECT 1 AS n;
+<!--ASSERT
+rows = 1
+-->
+```
+";
+    let book = create_book_with_content(chapter_content);
+
+    let processed = ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .unwrap_or_else(|e| panic!("front matter image should be used: {e:#}"));
+
+    let Some(BookItem::Chapter(chapter)) = processed.items.first() else {
+        panic!("book should have a chapter");
+    };
+    assert!(
+        chapter.content.starts_with("# Overridden"),
+        "front matter should be stripped: {}",
+        chapter.content
+    );
+}
+
+/// Creates a sqlite config whose container is capped at `memory_limit`
+fn create_memory_limited_sqlite_config(memory_limit: &str) -> Config {
+    let mut config = create_sqlite_config();
+    if let Some(sqlite) = config.validators.get_mut("sqlite") {
+        sqlite.memory_limit = Some(memory_limit.to_string());
+    }
+    config
+}
+
+/// Test: A query killed at the container's memory limit fails with E022.
+///
+/// This test requires Docker to be running.
+#[test]
+fn query_exceeding_memory_limit_is_out_of_memory() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_memory_limited_sqlite_config("16m");
+
+    // 200 MB of random bytes can't fit in 16m, so the kernel OOM-kills sqlite3
+    let chapter_content = r#"# Memory
+
+```sql validator=sqlite
+SELECT length(randomblob(200000000)) AS n;
+<!--ASSERT
+max_memory "16m"
+-->
+```
+"#;
+    let book = create_book_with_content(chapter_content);
+
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .expect_err("query should be OOM-killed");
+
+    let validator_error = err
+        .downcast_ref::<ValidatorError>()
+        .unwrap_or_else(|| panic!("should be a ValidatorError: {err:#}"));
+    assert!(
+        matches!(validator_error, ValidatorError::OutOfMemory { limit, .. } if limit == "16m"),
+        "should be OutOfMemory: {err:#}"
+    );
+}
+
+/// Test: A query within the memory limit passes its `max_memory` assertion.
+///
+/// This test requires Docker to be running.
+#[test]
+fn query_within_memory_limit_passes_max_memory() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_memory_limited_sqlite_config("64m");
+
+    let chapter_content = r#"# Memory
+
+```sql validator=sqlite
+SELECT 1 AS n;
+<!--ASSERT
+rows = 1
+max_memory "64m"
+-->
+```
+"#;
+    let book = create_book_with_content(chapter_content);
+
+    ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .unwrap_or_else(|e| panic!("query should fit in 64m: {e:#}"));
+}
+
+/// Test: `max_memory` below the validator's `memory_limit` can't be enforced.
+///
+/// This test requires Docker to be running.
+#[test]
+fn memory_limit_above_max_memory_is_config_error() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_memory_limited_sqlite_config("64m");
+
+    let chapter_content = r#"```sql validator=sqlite
+SELECT 1 AS n;
+<!--ASSERT
+max_memory "16m"
+-->
+```
+"#;
+    let book = create_book_with_content(chapter_content);
+
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .expect_err("budget below the limit should be rejected");
+
+    assert!(
+        matches!(
+            err.downcast_ref::<ValidatorError>(),
+            Some(ValidatorError::InvalidConfig { reason, .. }) if reason.contains("max_memory \"16m\"")
+        ),
+        "should be InvalidConfig: {err:#}"
+    );
+}
+
 /// Test: Preprocessor errors for unknown validator name
 #[test]
 fn preprocessor_errors_for_unknown_validator() {
@@ -692,6 +892,7 @@ fn preprocessor_errors_for_unknown_validator() {
         validators: HashMap::new(),
         fail_fast: true,
         fixtures_dir: None,
+        ..Default::default()
     };
 
     // Create a book with unknown validator
@@ -722,6 +923,124 @@ SELECT 1;
     }
 }
 
+/// Test: An unknown-validator failure stays a typed `ValidatorError` through the run
+#[test]
+fn unknown_validator_error_downcasts_to_validator_error() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let book = create_book_with_content("# Test\n\n```sql validator=nonexistent\nSELECT 1;\n```\n");
+
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(book, &Config::default(), &book_root)
+        .expect_err("should fail for unknown validator");
+
+    match err.downcast_ref::<ValidatorError>() {
+        Some(ValidatorError::UnknownValidator { name }) => assert_eq!(name, "nonexistent"),
+        other => panic!("expected UnknownValidator, got {other:?} ({err:#})"),
+    }
+}
+
+/// Test: Without fail_fast, every failing chapter is reported as one aggregated error
+#[test]
+fn non_fail_fast_run_aggregates_typed_failures() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let mut book = Book::new();
+    for name in ["first", "second"] {
+        book.items.push(BookItem::Chapter(Chapter::new(
+            name,
+            format!("# {name}\n\n```sql validator={name}\nSELECT 1;\n```\n"),
+            PathBuf::from(format!("{name}.md")),
+            vec![],
+        )));
+    }
+    let config = Config {
+        fail_fast: false,
+        ..Default::default()
+    };
+
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .expect_err("both chapters should fail");
+
+    let aggregated = err
+        .downcast_ref::<AggregatedErrors>()
+        .unwrap_or_else(|| panic!("expected AggregatedErrors, got {err:#}"));
+    let names: Vec<_> = aggregated
+        .validator_errors()
+        .map(|error| match error {
+            ValidatorError::UnknownValidator { name } => name.as_str(),
+            other => panic!("expected UnknownValidator, got {other}"),
+        })
+        .collect();
+    assert_eq!(names, ["first", "second"]);
+    assert!(err.to_string().starts_with("2 blocks failed validation:"));
+}
+
+/// Test: Without fail_fast, a failing block doesn't stop the rest of its chapter
+/// or the next chapter; every failing block is reported
+#[test]
+fn non_fail_fast_run_reports_every_failing_block() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let mut book = Book::new();
+    book.items.push(BookItem::Chapter(Chapter::new(
+        "one",
+        "# One\n\n```sql validator=first\nSELECT 1;\n```\n\n```sql validator=second\nSELECT 2;\n```\n".to_owned(),
+        PathBuf::from("one.md"),
+        vec![],
+    )));
+    book.items.push(BookItem::Chapter(Chapter::new(
+        "two",
+        "# Two\n\n```sql validator=third\nSELECT 3;\n```\n".to_owned(),
+        PathBuf::from("two.md"),
+        vec![],
+    )));
+    let config = Config {
+        fail_fast: false,
+        ..Default::default()
+    };
+
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .expect_err("every block should fail");
+
+    let aggregated = err
+        .downcast_ref::<AggregatedErrors>()
+        .unwrap_or_else(|| panic!("expected AggregatedErrors, got {err:#}"));
+    let names: Vec<_> = aggregated
+        .validator_errors()
+        .map(|error| match error {
+            ValidatorError::UnknownValidator { name } => name.as_str(),
+            other => panic!("expected UnknownValidator, got {other}"),
+        })
+        .collect();
+    assert_eq!(names, ["first", "second", "third"]);
+    let message = err.to_string();
+    assert!(
+        message.starts_with("3 blocks failed validation:"),
+        "{message}"
+    );
+    assert!(message.contains("--- Failure 3 of 3 ---"), "{message}");
+}
+
+/// Test: A block's `desc=` appears in its failure message
+#[test]
+fn block_description_appears_in_failure_message() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let book = create_book_with_content(
+        "# Test\n\n```sql validator=nonexistent desc=\"Create users table\"\nSELECT 1;\n```\n",
+    );
+
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(book, &Config::default(), &book_root)
+        .expect_err("should fail for unknown validator");
+
+    let message = format!("{err}");
+    assert!(
+        message.contains("Block \"Create users table\""),
+        "failure should name the block's description: {message}"
+    );
+    assert!(err.downcast_ref::<ValidatorError>().is_some());
+}
+
 /// Test: EXPECT marker passes when output matches expected
 ///
 /// Full end-to-end test with `SQLite` container, EXPECT marker parsed from markdown.
@@ -737,6 +1056,7 @@ fn preprocessor_expect_marker_passes_when_output_matches() {
             container: "keinos/sqlite3:3.47.2".to_string(),
             script: PathBuf::from("validators/validate-sqlite.sh"),
             exec_command: None,
+            ..Default::default()
         },
     );
 
@@ -744,6 +1064,7 @@ fn preprocessor_expect_marker_passes_when_output_matches() {
         validators,
         fail_fast: true,
         fixtures_dir: None,
+        ..Default::default()
     };
 
     // Create book with EXPECT marker that should match
@@ -819,6 +1140,7 @@ fn preprocessor_expect_marker_fails_when_output_differs() {
             container: "keinos/sqlite3:3.47.2".to_string(),
             script: PathBuf::from("validators/validate-sqlite.sh"),
             exec_command: None,
+            ..Default::default()
         },
     );
 
@@ -826,6 +1148,7 @@ fn preprocessor_expect_marker_fails_when_output_differs() {
         validators,
         fail_fast: true,
         fixtures_dir: None,
+        ..Default::default()
     };
 
     // Create book with EXPECT marker that WON'T match (expecting id=999, actual is id=1)
@@ -865,136 +1188,458 @@ SELECT id FROM items;
     }
 }
 
-/// Test: Preprocessor errors when validator script not found
+/// Test: EXPECT with `<*>` wildcards tolerates variable values but not other differences
 #[test]
-fn preprocessor_errors_for_missing_script() {
+fn preprocessor_expect_wildcard_template() {
     let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_sqlite_config();
+    let preprocessor = ValidatorPreprocessor::new();
 
-    // Config with non-existent script
-    let mut validators = HashMap::new();
-    validators.insert(
-        "test".to_string(),
-        ValidatorConfig {
-            container: "alpine:3".to_string(),
-            script: PathBuf::from("validators/does-not-exist.sh"),
-            exec_command: None,
-        },
-    );
+    let chapter = |name: &str| {
+        format!(
+            r#"# EXPECT Wildcard Test
 
-    let config = Config {
-        validators,
-        fail_fast: true,
-        fixtures_dir: None,
+```sql validator=sqlite
+SELECT abs(random()) AS id, '{name}' AS name;
+<!--EXPECT
+[{{"id":<*>,"name":"alice"}}]
+-->
+```
+"#
+        )
     };
 
-    let chapter_content = r#"# Test
+    let result = preprocessor.process_book_with_config(
+        create_book_with_content(&chapter("alice")),
+        &config,
+        &book_root,
+    );
+    assert!(result.is_ok(), "wildcard id should match: {result:?}");
 
-```sql validator=test
-SELECT 1;
+    let result = preprocessor.process_book_with_config(
+        create_book_with_content(&chapter("bob")),
+        &config,
+        &book_root,
+    );
+    let err = result.expect_err("name differs from template, should fail");
+    assert!(
+        format!("{err}").contains("does not match EXPECT template"),
+        "error should explain the template mismatch: {err}"
+    );
+}
+
+/// Test: `filter="..."` projects the output before EXPECT is compared
+#[test]
+fn preprocessor_filter_projects_output_before_expect() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_sqlite_config();
+    let chapter_content = r#"# Filter Test
+
+```sql validator=sqlite filter=".[] | {id}"
+<!--SETUP
+sqlite3 /tmp/test.db "CREATE TABLE people(id INTEGER, name TEXT, created_at TEXT); INSERT INTO people VALUES(1, 'alice', datetime('now')), (2, 'bob', datetime('now'));"
+-->
+SELECT id, name, created_at FROM people ORDER BY id;
+<!--ASSERT
+rows = 2
+-->
+<!--EXPECT
+[{"id":1},{"id":2}]
+-->
 ```
 "#;
 
-    let book = create_book_with_content(chapter_content);
-    let preprocessor = ValidatorPreprocessor::new();
-
-    let result = preprocessor.process_book_with_config(book, &config, &book_root);
+    let result = ValidatorPreprocessor::new().process_book_with_config(
+        create_book_with_content(chapter_content),
+        &config,
+        &book_root,
+    );
 
-    match result {
-        Ok(_) => {
-            panic!("Should have failed for missing script");
-        }
-        Err(e) => {
-            let error_msg = format!("{e}");
-            assert!(
-                error_msg.contains("Failed to read validator script")
-                    || error_msg.contains("does-not-exist"),
-                "Error should mention missing script: {error_msg}"
-            );
-            println!("Missing script test passed! Error: {error_msg}");
-        }
-    }
+    assert!(
+        result.is_ok(),
+        "filtered output should match EXPECT: {result:?}"
+    );
 }
 
-/// Test: hidden and skip together returns E011 error
-///
-/// Verifies that `hidden` and `skip` are mutually exclusive.
-/// Using both should produce a clear E011 error.
+/// Test: an invalid `filter=` expression fails validation
 #[test]
-fn preprocessor_errors_on_hidden_and_skip_together() {
+fn preprocessor_filter_invalid_jq_fails() {
     let book_root = std::env::current_dir().expect("should get current dir");
     let config = create_sqlite_config();
+    let chapter_content = r#"# Bad Filter
 
-    // Code block with both hidden AND skip - should fail with E011
-    let chapter_content = r#"# Mutual Exclusivity Test
-
-```sql validator=sqlite hidden skip
-SELECT 1;
+```sql validator=sqlite filter=".[] | {id"
+SELECT 1 AS id;
 ```
 "#;
 
-    let book = create_book_with_content(chapter_content);
-    let preprocessor = ValidatorPreprocessor::new();
-
-    let result = preprocessor.process_book_with_config(book, &config, &book_root);
+    let result = ValidatorPreprocessor::new().process_book_with_config(
+        create_book_with_content(chapter_content),
+        &config,
+        &book_root,
+    );
 
-    match result {
-        Ok(_) => {
-            panic!("Should have failed with E011 for hidden+skip combination");
-        }
-        Err(e) => {
-            let error_msg = format!("{e}");
+    let err = result.expect_err("invalid jq should fail");
+    assert!(
+        format!("{err}").contains("Invalid filter"),
+        "error should report the filter: {err}"
+    );
+}
 
-            // Verify E011 error message
-            assert!(
-                error_msg.contains("E011") || error_msg.contains("mutually exclusive"),
-                "Error should mention E011 or mutual exclusivity. Got: {error_msg}"
-            );
-            assert!(
-                error_msg.contains("hidden") && error_msg.contains("skip"),
-                "Error should mention both 'hidden' and 'skip'. Got: {error_msg}"
-            );
+/// Config whose exec command only accepts the exact query `SELECT 1`
+/// (no trailing `;`), standing in for a tool that is picky about terminators.
+fn create_picky_config(normalize: NormalizeMode) -> Config {
+    let mut validators = HashMap::new();
+    validators.insert(
+        "picky".to_string(),
+        ValidatorConfig {
+            container: "keinos/sqlite3:3.47.2".to_string(),
+            script: PathBuf::from("validators/validate-sqlite.sh"),
+            exec_command: Some(
+                r#"q=$(cat); [ "$q" = "SELECT 1" ] || { echo "rejected: $q" >&2; exit 1; }; echo '[]'"#
+                    .to_string(),
+            ),
+            normalize,
+            ..Default::default()
+        },
+    );
 
-            println!("E011 mutual exclusivity test passed! Error: {error_msg}");
-        }
+    Config {
+        validators,
+        fail_fast: true,
+        ..Default::default()
     }
 }
 
-/// Test: hidden attribute removes entire code block from output
-///
-/// Full end-to-end test verifying that:
-/// 1. Code block with `hidden` attribute is validated (query runs)
-/// 2. Entire code fence is removed from output (no fence delimiters, no content)
-/// 3. Non-hidden blocks in same document remain visible
+/// Test: `normalize = "semicolon"` strips the trailing blank line and `;` before execution
 #[test]
-fn preprocessor_hidden_attribute_removes_entire_block() {
+fn preprocessor_normalize_strips_trailing_semicolon() {
     let book_root = std::env::current_dir().expect("should get current dir");
-    let config = create_sqlite_config();
+    let preprocessor = ValidatorPreprocessor::new();
+    let content = "# Normalize\n\n```sql validator=picky\nSELECT 1;   \n\n```\n";
 
-    // Document has: hidden block (should be removed) + visible block (should remain)
-    let chapter_content = r#"# Hidden Block Test
+    let result = preprocessor.process_book_with_config(
+        create_book_with_content(content),
+        &create_picky_config(NormalizeMode::Semicolon),
+        &book_root,
+    );
+    assert!(result.is_ok(), "normalized block should pass: {result:?}");
 
-Setup text before.
+    let result = preprocessor.process_book_with_config(
+        create_book_with_content(content),
+        &create_picky_config(NormalizeMode::Off),
+        &book_root,
+    );
+    let err = result.expect_err("without normalization the `;` reaches the tool");
+    assert!(
+        format!("{err}").contains("rejected: SELECT 1;"),
+        "error should show the raw query: {err}"
+    );
+}
 
-```sql validator=sqlite hidden
+/// Test: a block with a passing ASSERT and a failing EXPECT fails on the EXPECT
+#[test]
+fn preprocessor_assert_pass_expect_fail_reports_expect() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_sqlite_config();
+    let chapter_content = r#"# ASSERT and EXPECT
+
+```sql validator=sqlite
 <!--SETUP
-sqlite3 /tmp/test.db 'CREATE TABLE IF NOT EXISTS hidden_test(id INTEGER); INSERT INTO hidden_test VALUES(42);'
+sqlite3 /tmp/test.db 'CREATE TABLE items(id INTEGER); INSERT INTO items VALUES(1);'
 -->
-SELECT id FROM hidden_test;
+SELECT id FROM items;
 <!--ASSERT
-rows >= 1
+rows = 1
+-->
+<!--EXPECT
+[{"id":2}]
 -->
 ```
+"#;
 
-Middle text.
+    let result = ValidatorPreprocessor::new().process_book_with_config(
+        create_book_with_content(chapter_content),
+        &config,
+        &book_root,
+    );
+
+    let err = result.expect_err("EXPECT mismatch should fail the block");
+    let msg = format!("{err}");
+    assert!(
+        msg.contains("Output mismatch"),
+        "error should mention EXPECT: {msg}"
+    );
+    assert!(
+        !msg.contains("Assertion failed"),
+        "passing ASSERT should not be reported: {msg}"
+    );
+}
+
+/// Test: `args="..."` is appended to the exec command while the query still comes via stdin
+#[test]
+fn preprocessor_args_attribute_selects_database() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let mut validators = HashMap::new();
+    validators.insert(
+        "sqlite".to_string(),
+        ValidatorConfig {
+            container: "keinos/sqlite3:3.47.2".to_string(),
+            script: PathBuf::from("validators/validate-sqlite.sh"),
+            // No database: each block picks one with args=
+            exec_command: Some("sqlite3 -json".to_string()),
+            ..Default::default()
+        },
+    );
+    let config = Config {
+        validators,
+        fail_fast: true,
+        ..Default::default()
+    };
+
+    let chapter = |attrs: &str| {
+        format!(
+            r#"# Args
+
+```sql validator=sqlite {attrs}
+<!--SETUP
+sqlite3 /tmp/other.db 'CREATE TABLE IF NOT EXISTS orders(id INTEGER); INSERT INTO orders VALUES(1);'
+-->
+SELECT COUNT(*) AS n FROM orders;
+<!--ASSERT
+rows = 1
+-->
+```
+"#
+        )
+    };
+
+    let result = ValidatorPreprocessor::new().process_book_with_config(
+        create_book_with_content(&chapter(r#"args="/tmp/other.db""#)),
+        &config,
+        &book_root,
+    );
+    assert!(
+        result.is_ok(),
+        "query should run against /tmp/other.db: {result:?}"
+    );
+
+    // Without args, sqlite3 uses an in-memory database that has no orders table
+    let result = ValidatorPreprocessor::new().process_book_with_config(
+        create_book_with_content(&chapter("")),
+        &config,
+        &book_root,
+    );
+    let err = result.expect_err("in-memory database has no orders table");
+    assert!(format!("{err}").contains("no such table"), "got: {err}");
+}
+
+/// sqlite config whose exec command opens the block's `{db}` file
+fn create_db_placeholder_config() -> Config {
+    let mut validators = HashMap::new();
+    validators.insert(
+        "sqlite".to_string(),
+        ValidatorConfig {
+            container: "keinos/sqlite3:3.47.2".to_string(),
+            script: PathBuf::from("validators/validate-sqlite.sh"),
+            exec_command: Some("sqlite3 -json {db}".to_string()),
+            ..Default::default()
+        },
+    );
+    Config {
+        validators,
+        fail_fast: true,
+        ..Default::default()
+    }
+}
+
+/// Test: `{db}` gives each session its own database file in the shared container
+#[test]
+fn preprocessor_db_placeholder_isolates_sessions() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_db_placeholder_config();
+
+    // Both sessions create the same table; with a shared file the second CREATE would fail
+    let chapter_content = r#"# Sessions
+
+```sql validator=sqlite session=a
+<!--SETUP
+sqlite3 {db} 'CREATE TABLE t(id INTEGER); INSERT INTO t VALUES(1);'
+-->
+SELECT COUNT(*) AS n FROM t;
+<!--EXPECT
+[{"n":1}]
+-->
+```
+
+```sql validator=sqlite session=b
+<!--SETUP
+sqlite3 {db} 'CREATE TABLE t(id INTEGER); INSERT INTO t VALUES(1), (2);'
+-->
+SELECT COUNT(*) AS n FROM t;
+<!--EXPECT
+[{"n":2}]
+-->
+```
+
+```sql validator=sqlite session=a
+SELECT COUNT(*) AS n FROM t;
+<!--EXPECT
+[{"n":1}]
+-->
+```
+"#;
+
+    let result = ValidatorPreprocessor::new().process_book_with_config(
+        create_book_with_content(chapter_content),
+        &config,
+        &book_root,
+    );
+
+    assert!(
+        result.is_ok(),
+        "sessions should not share state: {result:?}"
+    );
+}
+
+/// Test: a table created in one `{db}` session doesn't exist in another
+#[test]
+fn preprocessor_db_placeholder_hides_tables_from_other_sessions() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_db_placeholder_config();
+
+    let chapter_content = r"# Sessions
+
+```sql validator=sqlite session=a
+<!--SETUP
+sqlite3 {db} 'CREATE TABLE only_a(id INTEGER);'
+-->
+SELECT COUNT(*) AS n FROM only_a;
+```
+
+```sql validator=sqlite session=b
+SELECT COUNT(*) AS n FROM only_a;
+```
+";
+
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(
+            create_book_with_content(chapter_content),
+            &config,
+            &book_root,
+        )
+        .expect_err("session b should not see session a's table");
+    let error_msg = format!("{err:#}");
+    assert!(error_msg.contains("no such table: only_a"), "{error_msg}");
+}
+
+/// sqlite config plus a "niche" validator whose image does not exist
+fn create_config_with_unpullable_validator() -> Config {
+    let mut config = create_sqlite_config();
+    config.validators.insert(
+        "niche".to_string(),
+        ValidatorConfig {
+            container: "mdbook-validator-test/does-not-exist:0.0.0".to_string(),
+            script: PathBuf::from("validators/validate-sqlite.sh"),
+            ..Default::default()
+        },
+    );
+    config
+}
+
+/// Test: a validator whose image can't start is skipped when only `optional` blocks use it
+#[test]
+fn preprocessor_optional_blocks_tolerate_container_startup_failure() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let chapter_content = r#"# Optional
+
+```sql validator=niche optional
+SELECT 1;
+```
 
 ```sql validator=sqlite
-SELECT 'visible_query' as result;
+SELECT 1 AS n;
 <!--ASSERT
-rows >= 1
+rows = 1
 -->
 ```
 
-End text.
+```sql validator=niche optional
+SELECT 2;
+```
+"#;
+
+    let result = ValidatorPreprocessor::new().process_book_with_config(
+        create_book_with_content(chapter_content),
+        &create_config_with_unpullable_validator(),
+        &book_root,
+    );
+
+    let book = result.expect("optional-only validator should not fail the build");
+    let Some(BookItem::Chapter(chapter)) = book.items.first() else {
+        panic!("Expected chapter");
+    };
+    assert!(
+        chapter.content.contains("SELECT 2;"),
+        "skipped optional blocks stay in the output: {}",
+        chapter.content
+    );
+}
+
+/// Test: the same startup failure still fails the build when a non-optional block needs it
+#[test]
+fn preprocessor_required_block_fails_on_container_startup_failure() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let chapter_content = r#"# Required
+
+```sql validator=niche optional
+SELECT 1;
+```
+
+```sql validator=niche
+SELECT 2;
+```
+"#;
+
+    let result = ValidatorPreprocessor::new().process_book_with_config(
+        create_book_with_content(chapter_content),
+        &create_config_with_unpullable_validator(),
+        &book_root,
+    );
+
+    let err = result.expect_err("non-optional block needs the container");
+    assert!(format!("{err}").contains("[E002]"), "got: {err}");
+}
+
+/// Test: Preprocessor errors when validator script not found
+#[test]
+fn preprocessor_errors_for_missing_script() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+
+    // Config with non-existent script
+    let mut validators = HashMap::new();
+    validators.insert(
+        "test".to_string(),
+        ValidatorConfig {
+            container: "alpine:3".to_string(),
+            script: PathBuf::from("validators/does-not-exist.sh"),
+            exec_command: None,
+            ..Default::default()
+        },
+    );
+
+    let config = Config {
+        validators,
+        fail_fast: true,
+        fixtures_dir: None,
+        ..Default::default()
+    };
+
+    let chapter_content = r#"# Test
+
+```sql validator=test
+SELECT 1;
+```
 "#;
 
     let book = create_book_with_content(chapter_content);
@@ -1003,61 +1648,2075 @@ End text.
     let result = preprocessor.process_book_with_config(book, &config, &book_root);
 
     match result {
-        Ok(processed_book) => {
-            let Some(BookItem::Chapter(chapter)) = processed_book.items.first() else {
-                panic!("Expected chapter in processed book");
-            };
-
-            let output = &chapter.content;
-
-            // Hidden block should be COMPLETELY removed (no fence, no content)
-            assert!(
-                !output.contains("hidden_test"),
-                "Hidden block table name should not appear. Output:\n{output}"
-            );
+        Ok(_) => {
+            panic!("Should have failed for missing script");
+        }
+        Err(e) => {
+            let error_msg = format!("{e}");
             assert!(
-                !output.contains("SELECT id FROM"),
-                "Hidden block query should not appear. Output:\n{output}"
+                error_msg.contains("Failed to read validator script")
+                    || error_msg.contains("does-not-exist"),
+                "Error should mention missing script: {error_msg}"
             );
+            println!("Missing script test passed! Error: {error_msg}");
+        }
+    }
+}
 
-            // Verify no fence delimiters for hidden block remain
-            // Count sql blocks - should only be 1 (the visible one)
-            let sql_block_count = output.matches("```sql").count();
-            assert_eq!(
-                sql_block_count, 1,
-                "Should have exactly 1 sql block (visible only). Output:\n{output}"
-            );
+/// Test: hidden and skip together returns E011 error
+///
+/// Verifies that `hidden` and `skip` are mutually exclusive.
+/// Using both should produce a clear E011 error.
+#[test]
+fn preprocessor_errors_on_hidden_and_skip_together() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_sqlite_config();
 
-            // Visible block should remain
-            assert!(
-                output.contains("visible_query"),
-                "Visible block should remain. Output:\n{output}"
-            );
+    // Code block with both hidden AND skip - should fail with E011
+    let chapter_content = r#"# Mutual Exclusivity Test
 
-            // Text content should remain
-            assert!(
-                output.contains("Setup text before"),
-                "Text before should remain. Output:\n{output}"
-            );
-            assert!(
-                output.contains("Middle text"),
-                "Middle text should remain. Output:\n{output}"
-            );
+```sql validator=sqlite hidden skip
+SELECT 1;
+```
+"#;
+
+    let book = create_book_with_content(chapter_content);
+    let preprocessor = ValidatorPreprocessor::new();
+
+    let result = preprocessor.process_book_with_config(book, &config, &book_root);
+
+    match result {
+        Ok(_) => {
+            panic!("Should have failed with E011 for hidden+skip combination");
+        }
+        Err(e) => {
+            let error_msg = format!("{e}");
+
+            // Verify E011 error message
             assert!(
-                output.contains("End text"),
-                "End text should remain. Output:\n{output}"
+                error_msg.contains("E011") || error_msg.contains("mutually exclusive"),
+                "Error should mention E011 or mutual exclusivity. Got: {error_msg}"
             );
-
-            // Markers should be stripped from visible block
             assert!(
-                !output.contains("<!--ASSERT"),
-                "ASSERT marker should be stripped. Output:\n{output}"
+                error_msg.contains("hidden") && error_msg.contains("skip"),
+                "Error should mention both 'hidden' and 'skip'. Got: {error_msg}"
             );
 
-            println!("Hidden attribute E2E test passed! Output:\n{output}");
-        }
-        Err(e) => {
-            panic!("Preprocessor failed - hidden block should still validate: {e}");
+            println!("E011 mutual exclusivity test passed! Error: {error_msg}");
         }
     }
 }
+
+/// Test: bad `timeout=` and `retry=` values fail with E008 before any block runs
+#[test]
+fn preprocessor_errors_on_invalid_timeout_and_retry() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    // No block runs, so this doesn't need Docker
+    let config = Config {
+        require_docker: Some(false),
+        ..create_sqlite_config()
+    };
+
+    for (attribute, reason) in [
+        ("timeout=0", "at least 1 second"),
+        ("timeout=abc", "got 'abc'"),
+        ("retry=x", "got 'x'"),
+    ] {
+        let chapter_content =
+            format!("# Bad Attribute\n\n```sql validator=sqlite {attribute}\nSELECT 1;\n```\n");
+        let book = create_book_with_content(&chapter_content);
+
+        let err = ValidatorPreprocessor::new()
+            .process_book_with_config(book, &config, &book_root)
+            .expect_err("invalid attribute should fail the book");
+        let error_msg = format!("{err:#}");
+        assert!(error_msg.contains("E008"), "{attribute}: {error_msg}");
+        assert!(error_msg.contains(reason), "{attribute}: {error_msg}");
+    }
+}
+
+/// Test: hidden attribute removes entire code block from output
+///
+/// Full end-to-end test verifying that:
+/// 1. Code block with `hidden` attribute is validated (query runs)
+/// 2. Entire code fence is removed from output (no fence delimiters, no content)
+/// 3. Non-hidden blocks in same document remain visible
+#[test]
+fn preprocessor_hidden_attribute_removes_entire_block() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_sqlite_config();
+
+    // Document has: hidden block (should be removed) + visible block (should remain)
+    let chapter_content = r#"# Hidden Block Test
+
+Setup text before.
+
+```sql validator=sqlite hidden
+<!--SETUP
+sqlite3 /tmp/test.db 'CREATE TABLE IF NOT EXISTS hidden_test(id INTEGER); INSERT INTO hidden_test VALUES(42);'
+-->
+SELECT id FROM hidden_test;
+<!--ASSERT
+rows >= 1
+-->
+```
+
+Middle text.
+
+```sql validator=sqlite
+SELECT 'visible_query' as result;
+<!--ASSERT
+rows >= 1
+-->
+```
+
+End text.
+"#;
+
+    let book = create_book_with_content(chapter_content);
+    let preprocessor = ValidatorPreprocessor::new();
+
+    let result = preprocessor.process_book_with_config(book, &config, &book_root);
+
+    match result {
+        Ok(processed_book) => {
+            let Some(BookItem::Chapter(chapter)) = processed_book.items.first() else {
+                panic!("Expected chapter in processed book");
+            };
+
+            let output = &chapter.content;
+
+            // Hidden block should be COMPLETELY removed (no fence, no content)
+            assert!(
+                !output.contains("hidden_test"),
+                "Hidden block table name should not appear. Output:\n{output}"
+            );
+            assert!(
+                !output.contains("SELECT id FROM"),
+                "Hidden block query should not appear. Output:\n{output}"
+            );
+
+            // Verify no fence delimiters for hidden block remain
+            // Count sql blocks - should only be 1 (the visible one)
+            let sql_block_count = output.matches("```sql").count();
+            assert_eq!(
+                sql_block_count, 1,
+                "Should have exactly 1 sql block (visible only). Output:\n{output}"
+            );
+
+            // Visible block should remain
+            assert!(
+                output.contains("visible_query"),
+                "Visible block should remain. Output:\n{output}"
+            );
+
+            // Text content should remain
+            assert!(
+                output.contains("Setup text before"),
+                "Text before should remain. Output:\n{output}"
+            );
+            assert!(
+                output.contains("Middle text"),
+                "Middle text should remain. Output:\n{output}"
+            );
+            assert!(
+                output.contains("End text"),
+                "End text should remain. Output:\n{output}"
+            );
+
+            // Markers should be stripped from visible block
+            assert!(
+                !output.contains("<!--ASSERT"),
+                "ASSERT marker should be stripped. Output:\n{output}"
+            );
+
+            println!("Hidden attribute E2E test passed! Output:\n{output}");
+        }
+        Err(e) => {
+            panic!("Preprocessor failed - hidden block should still validate: {e}");
+        }
+    }
+}
+
+/// Creates a sqlite config whose `after_each` rolls the database back to a
+/// snapshot taken by `before_each`
+fn create_sqlite_config_with_rollback() -> Config {
+    let mut config = create_sqlite_config();
+    if let Some(sqlite) = config.validators.get_mut("sqlite") {
+        sqlite.before_each =
+            Some("touch /tmp/test.db && cp /tmp/test.db /tmp/test.db.bak".to_string());
+        sqlite.after_each = Some("mv /tmp/test.db.bak /tmp/test.db".to_string());
+    }
+    config
+}
+
+/// Test: `after_each` rollback keeps blocks isolated from each other.
+///
+/// Both blocks insert one row into the same table. Without the rollback the
+/// container is reused and the second block would see 2 rows.
+#[test]
+fn preprocessor_after_each_rollback_keeps_blocks_isolated() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_sqlite_config_with_rollback();
+
+    let chapter_content = r#"# Rollback Test
+
+```sql validator=sqlite
+<!--SETUP
+sqlite3 /tmp/test.db 'CREATE TABLE IF NOT EXISTS items(id INTEGER); INSERT INTO items VALUES(1);'
+-->
+SELECT * FROM items;
+<!--ASSERT
+rows = 1
+-->
+```
+
+```sql validator=sqlite
+<!--SETUP
+sqlite3 /tmp/test.db 'CREATE TABLE IF NOT EXISTS items(id INTEGER); INSERT INTO items VALUES(1);'
+-->
+SELECT * FROM items;
+<!--ASSERT
+rows = 1
+-->
+```
+"#;
+
+    let book = create_book_with_content(chapter_content);
+    let preprocessor = ValidatorPreprocessor::new();
+
+    let result = preprocessor.process_book_with_config(book, &config, &book_root);
+
+    if let Err(e) = result {
+        panic!("Second block should not see the first block's row: {e}");
+    }
+}
+
+/// Test: a failing `before_each` hook stops validation with E012
+#[test]
+fn preprocessor_before_each_failure_returns_e012() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let mut config = create_sqlite_config();
+    if let Some(sqlite) = config.validators.get_mut("sqlite") {
+        sqlite.before_each = Some("echo 'snapshot failed' >&2; exit 3".to_string());
+    }
+
+    let chapter_content = r#"# Hook Failure
+
+```sql validator=sqlite
+SELECT 1 AS one;
+```
+"#;
+
+    let book = create_book_with_content(chapter_content);
+    let preprocessor = ValidatorPreprocessor::new();
+
+    let result = preprocessor.process_book_with_config(book, &config, &book_root);
+
+    let err = result.expect_err("failing before_each should fail the chapter");
+    let error_msg = format!("{err}");
+    assert!(
+        error_msg.contains("E012") && error_msg.contains("exit 3"),
+        "Error should be E012 with the hook's exit code. Got: {error_msg}"
+    );
+    assert!(
+        error_msg.contains("snapshot failed"),
+        "Error should include hook stderr. Got: {error_msg}"
+    );
+}
+
+/// Test: a block pulls its assertions from a named set with `use=`
+#[test]
+fn preprocessor_expands_named_assertion_set() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let mut config = create_sqlite_config();
+    config.assertions.insert(
+        "valid_resp".to_string(),
+        "rows >= 1\ncontains \"alice\"".to_string(),
+    );
+
+    let chapter_content = r#"# Named Assertions
+
+```sql validator=sqlite use=valid_resp
+<!--SETUP
+sqlite3 /tmp/test.db 'CREATE TABLE IF NOT EXISTS people(name TEXT); INSERT INTO people VALUES("alice");'
+-->
+SELECT name FROM people;
+<!--ASSERT
+columns = 1
+-->
+```
+"#;
+
+    let book = create_book_with_content(chapter_content);
+    let preprocessor = ValidatorPreprocessor::new();
+
+    let result = preprocessor.process_book_with_config(book, &config, &book_root);
+
+    let book = result.expect("block should pass the named assertion set");
+    let Some(BookItem::Chapter(chapter)) = book.items.first() else {
+        panic!("Expected chapter in processed book");
+    };
+    assert!(
+        chapter.content.contains("```sql\n") && !chapter.content.contains("use=valid_resp"),
+        "internal attributes should be stripped from the info string. Output:\n{}",
+        chapter.content
+    );
+    assert!(
+        !chapter.content.contains("<!--ASSERT"),
+        "ASSERT marker should be stripped. Output:\n{}",
+        chapter.content
+    );
+}
+
+/// Test: referencing an undefined assertion set fails with E014
+#[test]
+fn preprocessor_errors_for_unknown_assertion_set() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_sqlite_config();
+
+    let chapter_content = r#"# Unknown Set
+
+```sql validator=sqlite use=nope
+SELECT 1;
+```
+"#;
+
+    let book = create_book_with_content(chapter_content);
+    let preprocessor = ValidatorPreprocessor::new();
+
+    let err = preprocessor
+        .process_book_with_config(book, &config, &book_root)
+        .expect_err("unknown use= should fail");
+    let error_msg = format!("{err}");
+    assert!(
+        error_msg.contains("E014") && error_msg.contains("nope"),
+        "Error should be E014 naming the set. Got: {error_msg}"
+    );
+}
+
+/// Three blocks: the first two each create a table, the third counts tables
+fn three_block_state_chapter(expected_tables: u32) -> String {
+    format!(
+        r#"# State Modes
+
+```sql validator=sqlite
+<!--SETUP
+sqlite3 /tmp/test.db 'CREATE TABLE first(id INTEGER);'
+-->
+SELECT 1 AS one;
+```
+
+```sql validator=sqlite
+<!--SETUP
+sqlite3 /tmp/test.db 'CREATE TABLE second(id INTEGER);'
+-->
+SELECT 1 AS one;
+```
+
+```sql validator=sqlite
+SELECT count(*) AS n FROM sqlite_master WHERE type = 'table';
+<!--EXPECT
+[{{"n":{expected_tables}}}]
+-->
+```
+"#
+    )
+}
+
+/// Test: in cumulative mode (the default) block 3 sees tables from blocks 1 and 2
+#[test]
+fn preprocessor_cumulative_state_persists_across_blocks() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_sqlite_config();
+    assert_eq!(
+        config.validators.get("sqlite").map(|v| v.state),
+        Some(StateMode::Cumulative)
+    );
+
+    let book = create_book_with_content(&three_block_state_chapter(2));
+    let preprocessor = ValidatorPreprocessor::new();
+
+    let result = preprocessor.process_book_with_config(book, &config, &book_root);
+
+    if let Err(e) = result {
+        panic!("Block 3 should see both tables in cumulative mode: {e}");
+    }
+}
+
+/// Test: in fresh mode block 3 runs in a new container and sees no tables
+#[test]
+fn preprocessor_fresh_state_resets_between_blocks() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let mut config = create_sqlite_config();
+    if let Some(sqlite) = config.validators.get_mut("sqlite") {
+        sqlite.state = StateMode::Fresh;
+    }
+
+    let book = create_book_with_content(&three_block_state_chapter(0));
+    let preprocessor = ValidatorPreprocessor::new();
+
+    let result = preprocessor.process_book_with_config(book, &config, &book_root);
+
+    if let Err(e) = result {
+        panic!("Block 3 should see no tables in fresh mode: {e}");
+    }
+}
+
+/// Test: Separators and part titles round-trip unchanged alongside validated chapters.
+///
+/// This test requires Docker to be running.
+#[test]
+fn separators_and_part_titles_pass_through_unchanged() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_sqlite_config();
+
+    let block = r"```sql validator=sqlite
+SELECT 1 AS n;
+<!--ASSERT
+rows = 1
+-->
+```
+";
+    let mut nested = Chapter::new(
+        "Nested",
+        format!("# Nested\n\n{block}"),
+        PathBuf::from("nested.md"),
+        vec![],
+    );
+    nested.sub_items.push(BookItem::Separator);
+    let mut parent = Chapter::new(
+        "Parent",
+        format!("# Parent\n\n{block}"),
+        PathBuf::from("parent.md"),
+        vec![],
+    );
+    parent.sub_items.push(BookItem::Chapter(nested));
+
+    let mut book = Book::new();
+    book.items.push(BookItem::PartTitle("Part One".to_string()));
+    book.items.push(BookItem::Chapter(parent));
+    book.items.push(BookItem::Separator);
+    book.items.push(BookItem::PartTitle("Part Two".to_string()));
+
+    let preprocessor = ValidatorPreprocessor::new();
+    let processed = preprocessor
+        .process_book_with_config(book, &config, &book_root)
+        .expect("book with separators and part titles should validate");
+
+    assert_eq!(
+        processed.items.len(),
+        4,
+        "no items should be added or dropped"
+    );
+    assert!(matches!(
+        processed.items.first(),
+        Some(BookItem::PartTitle(title)) if title == "Part One"
+    ));
+    assert!(matches!(processed.items.get(2), Some(BookItem::Separator)));
+    assert!(matches!(
+        processed.items.get(3),
+        Some(BookItem::PartTitle(title)) if title == "Part Two"
+    ));
+
+    let Some(BookItem::Chapter(parent)) = processed.items.get(1) else {
+        panic!("Expected chapter at index 1");
+    };
+    assert!(
+        !parent.content.contains("<!--ASSERT"),
+        "parent chapter should be processed: {}",
+        parent.content
+    );
+    let Some(BookItem::Chapter(nested)) = parent.sub_items.first() else {
+        panic!("Expected nested chapter");
+    };
+    assert!(
+        !nested.content.contains("<!--ASSERT"),
+        "nested chapter should be processed: {}",
+        nested.content
+    );
+    assert!(matches!(
+        nested.sub_items.first(),
+        Some(BookItem::Separator)
+    ));
+}
+
+/// Creates a sqlite config whose exec command sleeps before running the query
+fn create_slow_sqlite_config(timeout: Option<u64>) -> Config {
+    let mut validators = HashMap::new();
+    validators.insert(
+        "sqlite".to_string(),
+        ValidatorConfig {
+            container: "keinos/sqlite3:3.47.2".to_string(),
+            script: PathBuf::from("validators/validate-sqlite.sh"),
+            exec_command: Some("sleep 3; sqlite3 -json /tmp/test.db".to_string()),
+            timeout,
+            ..Default::default()
+        },
+    );
+
+    Config {
+        validators,
+        fail_fast: true,
+        fixtures_dir: None,
+        ..Default::default()
+    }
+}
+
+/// Test: A validator-level timeout applies to blocks without their own `timeout=`.
+///
+/// This test requires Docker to be running.
+#[test]
+fn validator_timeout_applies_to_block_without_timeout() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_slow_sqlite_config(Some(1));
+
+    let book = create_book_with_content(
+        r"```sql validator=sqlite
+SELECT 1 AS n;
+```
+",
+    );
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .expect_err("query slower than the validator timeout should fail");
+
+    let msg = format!("{err:#}");
+    assert!(msg.contains("timed out after 1s"), "got: {msg}");
+}
+
+/// Test: A block's `timeout=` overrides the validator-level timeout.
+///
+/// This test requires Docker to be running.
+#[test]
+fn block_timeout_overrides_validator_timeout() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_slow_sqlite_config(Some(1));
+
+    let book = create_book_with_content(
+        r"```sql validator=sqlite timeout=30
+SELECT 1 AS n;
+<!--ASSERT
+rows = 1
+-->
+```
+",
+    );
+    ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .expect("block timeout should win over the validator timeout");
+}
+
+/// Creates a sqlite config that expects the container's sqlite3 version to contain `version`
+fn create_versioned_sqlite_config(version: &str) -> Config {
+    let mut validators = HashMap::new();
+    validators.insert(
+        "sqlite".to_string(),
+        ValidatorConfig {
+            container: "keinos/sqlite3:3.47.2".to_string(),
+            script: PathBuf::from("validators/validate-sqlite.sh"),
+            exec_command: Some("sqlite3 -json /tmp/test.db".to_string()),
+            expect_version: Some(ExpectVersion {
+                command: "sqlite3 --version".to_string(),
+                contains: version.to_string(),
+            }),
+            ..Default::default()
+        },
+    );
+
+    Config {
+        validators,
+        fail_fast: true,
+        fixtures_dir: None,
+        ..Default::default()
+    }
+}
+
+/// Test: A container whose tool version doesn't match `expect_version` fails startup.
+///
+/// This test requires Docker to be running.
+#[test]
+fn expect_version_mismatch_fails_startup() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_versioned_sqlite_config("9.99");
+
+    let book = create_book_with_content(
+        r"```sql validator=sqlite
+SELECT 1 AS n;
+```
+",
+    );
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .expect_err("mismatched tool version should fail");
+
+    let msg = format!("{err:#}");
+    assert!(msg.contains("[E016]"), "got: {msg}");
+    assert!(
+        msg.contains("'9.99'"),
+        "should name expected version: {msg}"
+    );
+    assert!(msg.contains("3.47"), "should show actual version: {msg}");
+}
+
+/// Test: A matching `expect_version` lets validation proceed.
+///
+/// This test requires Docker to be running.
+#[test]
+fn expect_version_match_validates() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_versioned_sqlite_config("3.47");
+
+    let book = create_book_with_content(
+        r"```sql validator=sqlite
+SELECT 1 AS n;
+<!--ASSERT
+rows = 1
+-->
+```
+",
+    );
+    ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .expect("matching tool version should validate");
+}
+
+/// Test: `<!--ASSERT_FILE-->` assertions from an external file are applied to the block.
+///
+/// This test requires Docker to be running.
+#[test]
+fn assert_file_assertions_are_applied() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_sqlite_config();
+
+    let passing = create_book_with_content(
+        r"```sql validator=sqlite
+SELECT 'alice' AS name;
+<!--ASSERT_FILE
+tests/fixtures/assertions/single-alice.txt
+-->
+```
+",
+    );
+    let processed = ValidatorPreprocessor::new()
+        .process_book_with_config(passing, &config, &book_root)
+        .expect("output satisfies the file's assertions");
+    let Some(BookItem::Chapter(chapter)) = processed.items.first() else {
+        panic!("Expected chapter in processed book");
+    };
+    assert!(
+        !chapter.content.contains("ASSERT_FILE"),
+        "marker should be stripped: {}",
+        chapter.content
+    );
+
+    // Two rows violate the file's `rows = 1`
+    let failing = create_book_with_content(
+        r"```sql validator=sqlite
+SELECT 'alice' AS name UNION ALL SELECT 'bob';
+<!--ASSERT_FILE
+tests/fixtures/assertions/single-alice.txt
+-->
+```
+",
+    );
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(failing, &config, &book_root)
+        .expect_err("file assertions should apply");
+    let msg = format!("{err:#}");
+    assert!(msg.contains("rows = 1"), "got: {msg}");
+}
+
+/// Test: A missing `<!--ASSERT_FILE-->` file fails with the paths searched.
+#[test]
+fn assert_file_missing_fails() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_sqlite_config();
+
+    let book = create_book_with_content(
+        r"```sql validator=sqlite
+SELECT 1;
+<!--ASSERT_FILE
+assertions/does-not-exist.txt
+-->
+```
+",
+    );
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .expect_err("missing assertions file should fail");
+    let msg = format!("{err:#}");
+    assert!(
+        msg.contains("ASSERT_FILE 'assertions/does-not-exist.txt' not found"),
+        "got: {msg}"
+    );
+}
+
+/// Test: A validator's `on_failure` output is appended to a failing block's error.
+///
+/// This test requires Docker to be running.
+#[test]
+fn on_failure_output_appears_in_error() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let mut config = create_sqlite_config();
+    if let Some(sqlite) = config.validators.get_mut("sqlite") {
+        sqlite.on_failure = Some(
+            "echo table-dump-marker; sqlite3 /tmp/test.db 'SELECT COUNT(*) FROM diag;'".to_string(),
+        );
+    }
+
+    let book = create_book_with_content(
+        r"```sql validator=sqlite
+<!--SETUP
+sqlite3 /tmp/test.db 'CREATE TABLE IF NOT EXISTS diag(id INTEGER); INSERT INTO diag VALUES (41);'
+-->
+SELECT id FROM diag;
+<!--ASSERT
+rows = 5
+-->
+```
+",
+    );
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .expect_err("rows = 5 should fail");
+
+    let msg = err.to_string();
+    assert!(msg.contains("[E006]"), "error code kept: {msg}");
+    assert!(msg.contains("on_failure output"), "got: {msg}");
+    assert!(msg.contains("table-dump-marker"), "got: {msg}");
+}
+
+/// Test: `<!--ASSERT:name-->` assertions check only their `-- @name` section's output.
+///
+/// This test requires Docker to be running.
+#[test]
+fn section_assertions_check_their_own_section() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_sqlite_config();
+
+    let passing = create_book_with_content(
+        r#"```sql validator=sqlite
+CREATE TABLE users(name TEXT, active INTEGER);
+INSERT INTO users VALUES ('alice', 1), ('bob', 1), ('carol', 0);
+-- @active
+SELECT name FROM users WHERE active = 1;
+<!--ASSERT:active
+rows = 2
+-->
+-- @inactive
+SELECT name FROM users WHERE active = 0;
+<!--ASSERT:inactive
+rows = 1
+contains "carol"
+-->
+```
+"#,
+    );
+    let processed = ValidatorPreprocessor::new()
+        .process_book_with_config(passing, &config, &book_root)
+        .expect("each section satisfies its own assertions");
+    let Some(BookItem::Chapter(chapter)) = processed.items.first() else {
+        panic!("Expected chapter in processed book");
+    };
+    assert!(
+        !chapter.content.contains("<!--ASSERT:"),
+        "section markers should be stripped: {}",
+        chapter.content
+    );
+    assert!(chapter.content.contains("-- @inactive"));
+
+    // `active` passes, `inactive` has one row, not three
+    let failing = create_book_with_content(
+        r"```sql validator=sqlite
+CREATE TABLE users(name TEXT, active INTEGER);
+INSERT INTO users VALUES ('alice', 1), ('bob', 1), ('carol', 0);
+-- @active
+SELECT name FROM users WHERE active = 1;
+<!--ASSERT:active
+rows = 2
+-->
+-- @inactive
+SELECT name FROM users WHERE active = 0;
+<!--ASSERT:inactive
+rows = 3
+-->
+```
+",
+    );
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(failing, &config, &book_root)
+        .expect_err("inactive section has one row");
+    let msg = format!("{err:#}");
+    assert!(msg.contains("section: inactive"), "got: {msg}");
+    assert!(msg.contains("rows = 3"), "got: {msg}");
+}
+
+/// Test: `<!--ASSERT:name-->` without a matching `-- @name` line fails with E018.
+///
+/// This test requires Docker to be running.
+#[test]
+fn section_assertions_without_section_fail() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_sqlite_config();
+
+    let book = create_book_with_content(
+        r"```sql validator=sqlite
+-- @q1
+SELECT 1;
+<!--ASSERT:q2
+rows = 1
+-->
+```
+",
+    );
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .expect_err("q2 has no section");
+    let msg = format!("{err:#}");
+    assert!(msg.contains("[E018]"), "got: {msg}");
+}
+
+/// Test: With `quiet_success`, a fully passing run logs nothing at info level.
+///
+/// This test requires Docker to be running.
+#[test]
+fn quiet_success_passing_run_logs_nothing() {
+    use std::io;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = Config {
+        quiet_success: true,
+        ..create_sqlite_config()
+    };
+    let book = create_book_with_content(
+        r"```sql validator=sqlite
+SELECT 1 AS n;
+<!--ASSERT
+rows = 1
+-->
+```
+",
+    );
+
+    let captured = Captured::default();
+    let writer = captured.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_max_level(tracing::Level::INFO)
+        .with_ansi(false)
+        .finish();
+    tracing::subscriber::with_default(subscriber, || {
+        ValidatorPreprocessor::new()
+            .process_book_with_config(book, &config, &book_root)
+            .expect("block should pass");
+    });
+
+    let logs = String::from_utf8_lossy(&captured.0.lock().unwrap()).into_owned();
+    assert!(logs.is_empty(), "expected no output, got: {logs}");
+}
+
+/// Test: `${VAR}` in assertions is replaced with the environment variable's value.
+///
+/// This test requires Docker to be running.
+#[test]
+fn env_vars_are_interpolated_in_assertions() {
+    std::env::set_var("MDBOOK_VALIDATOR_TEST_BUILD", "build-4711");
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_sqlite_config();
+
+    let book = create_book_with_content(
+        r#"```sql validator=sqlite
+SELECT 'build-4711' AS build;
+<!--ASSERT
+contains "${MDBOOK_VALIDATOR_TEST_BUILD}"
+-->
+```
+"#,
+    );
+    ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .expect("interpolated value should be found in the output");
+}
+
+/// Test: An undefined `${VAR}` fails with E019 before anything runs.
+#[test]
+fn undefined_env_var_in_assertions_fails() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_sqlite_config();
+
+    let book = create_book_with_content(
+        r#"```sql validator=sqlite
+SELECT 1;
+<!--EXPECT
+[{"v":"${MDBOOK_VALIDATOR_TEST_UNSET}"}]
+-->
+```
+"#,
+    );
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .expect_err("undefined variable should fail");
+    let msg = format!("{err:#}");
+    assert!(msg.contains("[E019]"), "got: {msg}");
+    assert!(msg.contains("MDBOOK_VALIDATOR_TEST_UNSET"), "got: {msg}");
+}
+
+/// Test: `expect_ignore=` drops volatile fields before comparing EXPECT.
+///
+/// This test requires Docker to be running.
+#[test]
+fn expect_ignore_skips_volatile_fields() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_sqlite_config();
+
+    let book = create_book_with_content(
+        r#"```sql validator=sqlite expect_ignore=created_at
+SELECT 'alice' AS name, datetime('now') AS created_at;
+<!--EXPECT
+[{"name": "alice", "created_at": "2024-01-01 00:00:00"}]
+-->
+```
+"#,
+    );
+    ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .expect("created_at differs but is ignored");
+
+    let book = create_book_with_content(
+        r#"```sql validator=sqlite expect_ignore=created_at
+SELECT 'bob' AS name, datetime('now') AS created_at;
+<!--EXPECT
+[{"name": "alice", "created_at": "2024-01-01 00:00:00"}]
+-->
+```
+"#,
+    );
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .expect_err("name still has to match");
+    let msg = format!("{err:#}");
+    assert!(msg.contains("ignoring created_at"), "got: {msg}");
+}
+
+/// Test: Two runs of the same validator in parallel don't see each other's state.
+///
+/// Each run starts its own container, so the fixed `/tmp/test.db` path is never
+/// shared. If it were, one run would count the other's row.
+///
+/// This test requires Docker to be running.
+#[test]
+fn parallel_runs_of_same_validator_are_isolated() {
+    let content = r#"```sql validator=sqlite
+<!--SETUP
+sqlite3 /tmp/test.db "CREATE TABLE IF NOT EXISTS runs(id INTEGER); INSERT INTO runs VALUES (1);"
+-->
+SELECT id FROM runs;
+<!--ASSERT
+rows = 1
+-->
+```
+"#;
+
+    let runs: Vec<_> = (0..2)
+        .map(|_| {
+            std::thread::spawn(move || {
+                let book_root = std::env::current_dir().expect("should get current dir");
+                ValidatorPreprocessor::new().process_book_with_config(
+                    create_book_with_content(content),
+                    &create_sqlite_config(),
+                    &book_root,
+                )
+            })
+        })
+        .collect();
+
+    for run in runs {
+        run.join()
+            .expect("run should not panic")
+            .expect("each run should only see its own row");
+    }
+}
+
+/// Test: `failure_message_template` replaces the default failure layout.
+///
+/// This test requires Docker to be running.
+#[test]
+fn failure_message_template_formats_validation_errors() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let mut config = create_sqlite_config();
+    if let Some(sqlite) = config.validators.get_mut("sqlite") {
+        sqlite.failure_message_template = Some(
+            "{validator} block in {chapter} failed (exit {exit_code}).\n\
+             See https://docs.example.com/validator\n{stderr}"
+                .to_string(),
+        );
+    }
+
+    let book = create_book_with_content(
+        r"```sql validator=sqlite
+SELECT 1;
+<!--ASSERT
+rows = 2
+-->
+```
+",
+    );
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .expect_err("one row, not two");
+    let msg = format!("{err:#}");
+    assert!(
+        msg.contains("sqlite block in Test Chapter failed (exit 1)."),
+        "got: {msg}"
+    );
+    assert!(
+        msg.contains("See https://docs.example.com/validator"),
+        "got: {msg}"
+    );
+    assert!(msg.contains("rows = 2"), "stderr should be included: {msg}");
+    assert!(!msg.contains("Code:"), "default layout replaced: {msg}");
+}
+
+/// Test: `<!--VALIDATOR_DISABLE-->` leaves a chapter's validator blocks
+/// unvalidated and unstripped (no container is needed).
+#[test]
+fn validator_disable_leaves_chapter_untouched() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let content = r"# Writing Validated Blocks
+
+<!--VALIDATOR_DISABLE-->
+
+```sql validator=sqlite
+SELECT 1;
+<!--ASSERT
+rows = 99
+-->
+```
+";
+
+    let book = ValidatorPreprocessor::new()
+        .process_book_with_config(
+            create_book_with_content(content),
+            &create_sqlite_config(),
+            &book_root,
+        )
+        .expect("disabled chapter should not be validated");
+
+    let Some(BookItem::Chapter(chapter)) = book.items.first() else {
+        panic!("Expected chapter in processed book");
+    };
+    assert_eq!(chapter.content, content, "chapter should be unchanged");
+}
+
+/// Test: passing blocks are recorded in the report with both phases' exit codes.
+///
+/// This test requires Docker to be running.
+#[test]
+fn report_records_exit_codes_for_passing_blocks() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let book = create_book_with_content(
+        r"```sql validator=sqlite
+SELECT 1;
+<!--ASSERT
+rows = 1
+-->
+```
+",
+    );
+
+    let (_, report) = ValidatorPreprocessor::new()
+        .process_book_with_report(book, &create_sqlite_config(), &book_root)
+        .expect("block should pass");
+
+    let [result] = report.blocks() else {
+        panic!("expected one report entry: {:?}", report.blocks());
+    };
+    assert_eq!(result.chapter, "Test Chapter");
+    assert_eq!(result.block, 1);
+    assert_eq!(result.validator, "sqlite");
+    assert_eq!(result.exit_codes, ExitCodes { query: 0, host: 0 });
+}
+
+/// Test: `report_path` writes each block's status and a summary as JSON.
+///
+/// This test requires Docker to be running.
+#[test]
+fn report_path_writes_passing_and_skipped_blocks() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let dir = tempfile::TempDir::new().expect("should create temp dir");
+    let report_path = dir.path().join("report.json");
+    let mut config = create_sqlite_config();
+    config.report_path = Some(report_path.clone());
+    let book = create_book_with_content(
+        r"```sql validator=sqlite
+SELECT 1;
+<!--ASSERT
+rows = 1
+-->
+```
+
+```sql validator=sqlite skip
+SELECT * FROM missing_table;
+```
+",
+    );
+
+    ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .expect("book should pass");
+
+    let text = std::fs::read_to_string(&report_path).expect("report should be written");
+    let report: serde_json::Value = serde_json::from_str(&text).expect("report should be JSON");
+    assert_eq!(
+        report["summary"],
+        serde_json::json!({"total": 2, "passed": 1, "failed": 0, "skipped": 1})
+    );
+    let Some([passing, skipped]) = report["blocks"].as_array().map(Vec::as_slice) else {
+        panic!("expected two report entries: {text}");
+    };
+    assert_eq!(passing["chapter"], "Test Chapter");
+    assert_eq!(passing["validator"], "sqlite");
+    assert_eq!(passing["passed"], true);
+    assert_eq!(passing["skipped"], false);
+    assert_eq!(skipped["passed"], false);
+    assert_eq!(skipped["skipped"], true);
+}
+
+/// Test: `strip = false` validates the chapter but leaves its markers in place.
+///
+/// This test requires Docker to be running.
+#[test]
+fn strip_false_keeps_markers_after_validation() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let mut config = create_sqlite_config();
+    config.strip = Some(false);
+    let content = r"```sql validator=sqlite
+<!--SETUP
+sqlite3 /tmp/test.db 'CREATE TABLE IF NOT EXISTS kept(id INTEGER);'
+-->
+SELECT 1;
+<!--ASSERT
+rows = 1
+-->
+```
+";
+
+    let book = ValidatorPreprocessor::new()
+        .process_book_with_config(create_book_with_content(content), &config, &book_root)
+        .expect("block should pass");
+    let Some(BookItem::Chapter(chapter)) = book.items.first() else {
+        panic!("Expected chapter in processed book");
+    };
+    assert_eq!(chapter.content, content, "markers should be kept");
+
+    // Validation still runs
+    let failing = content.replace("rows = 1", "rows = 2");
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(create_book_with_content(&failing), &config, &book_root)
+        .expect_err("one row, not two");
+    assert!(format!("{err:#}").contains("rows = 2"), "got: {err:#}");
+}
+
+/// Test: several EXPECT blocks are matched in order against each statement's result set.
+///
+/// This test requires Docker to be running.
+#[test]
+fn multiple_expect_blocks_match_result_sets_in_order() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let content = r#"```sql validator=sqlite
+SELECT 1 AS a;
+SELECT 2 AS b;
+<!--EXPECT
+[{"a": 1}]
+-->
+<!--EXPECT
+[{"b": 2}]
+-->
+```
+"#;
+
+    ValidatorPreprocessor::new()
+        .process_book_with_config(
+            create_book_with_content(content),
+            &create_sqlite_config(),
+            &book_root,
+        )
+        .expect("both result sets match");
+
+    // Second EXPECT no longer matches
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(
+            create_book_with_content(&content.replace(r#"{"b": 2}"#, r#"{"b": 3}"#)),
+            &create_sqlite_config(),
+            &book_root,
+        )
+        .expect_err("second result set differs");
+    let msg = format!("{err:#}");
+    assert!(msg.contains("EXPECT #2"), "got: {msg}");
+    assert!(!msg.contains("EXPECT #1"), "first set matched: {msg}");
+}
+
+/// Test: `validate_content` reports passing and failing snippets as outcomes.
+///
+/// This test requires Docker to be running.
+#[test]
+fn validate_content_returns_structured_outcomes() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_sqlite_config();
+
+    let passing = ValidatorPreprocessor::validate_content(
+        &config,
+        "sqlite",
+        "SELECT 1 AS n;\n<!--ASSERT\nrows = 1\n-->",
+        &book_root,
+    )
+    .expect("validation should run");
+    assert!(passing.passed, "{}", passing.message);
+    assert_eq!(passing.query_exit_code, Some(0));
+    assert_eq!(passing.host_exit_code, Some(0));
+    assert!(passing.message.is_empty());
+
+    let failing = ValidatorPreprocessor::validate_content(
+        &config,
+        "sqlite",
+        "SELECT 1 AS n;\n<!--ASSERT\nrows = 2\n-->",
+        &book_root,
+    )
+    .expect("a failing snippet is an outcome, not an error");
+    assert!(!failing.passed);
+    assert_eq!(failing.query_exit_code, Some(0));
+    assert_ne!(failing.host_exit_code, Some(0));
+    assert_eq!(failing.error_code, Some("E006"));
+    assert!(failing.message.contains("rows = 2"), "{}", failing.message);
+}
+
+/// Test: a hidden `capture=` block's output is rendered in a later visible block.
+///
+/// This test requires Docker to be running.
+#[test]
+fn hidden_block_capture_renders_in_later_block() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let content = r#"```sql validator=sqlite hidden capture=users
+SELECT 'alice' AS name;
+<!--ASSERT
+rows = 1
+-->
+```
+
+The query returns:
+
+```json
+<!--OUTPUT:users-->
+```
+"#;
+
+    let book = ValidatorPreprocessor::new()
+        .process_book_with_config(
+            create_book_with_content(content),
+            &create_sqlite_config(),
+            &book_root,
+        )
+        .expect("hidden block should pass");
+    let Some(BookItem::Chapter(chapter)) = book.items.first() else {
+        panic!("Expected chapter in processed book");
+    };
+    assert!(
+        chapter.content.contains(r#""name":"alice""#),
+        "got: {}",
+        chapter.content
+    );
+    assert!(
+        !chapter.content.contains("SELECT"),
+        "hidden block should be removed"
+    );
+    assert!(!chapter.content.contains("<!--OUTPUT"));
+
+    // A name no block captured is an error
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(
+            create_book_with_content(&content.replace("OUTPUT:users", "OUTPUT:orders")),
+            &create_sqlite_config(),
+            &book_root,
+        )
+        .expect_err("nothing captured as orders");
+    assert!(format!("{err:#}").contains("E021"), "got: {err:#}");
+}
+
+/// Test: `max_blocks_per_container` replaces a container after it served N blocks.
+///
+/// This test requires Docker to be running.
+#[test]
+fn max_blocks_per_container_recycles_container() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let mut config = create_sqlite_config();
+    config.max_blocks_per_container = std::num::NonZeroUsize::new(2);
+    let block = "```sql validator=sqlite\nSELECT 1;\n```\n\n";
+
+    let (_, report) = ValidatorPreprocessor::new()
+        .process_book_with_report(
+            create_book_with_content(&block.repeat(3)),
+            &config,
+            &book_root,
+        )
+        .expect("blocks should pass");
+    let ids: Vec<&str> = report
+        .blocks()
+        .iter()
+        .map(|result| result.container_id.as_str())
+        .collect();
+    let [first, second, third] = ids.as_slice() else {
+        panic!("Expected 3 block results, got {ids:?}");
+    };
+    assert_eq!(first, second, "first two blocks share a container");
+    assert_ne!(second, third, "third block should get a fresh container");
+}
+
+/// Test: `cwd=` decides which fixture a relative path in the block resolves to.
+///
+/// This test requires Docker to be running.
+#[test]
+fn cwd_attribute_resolves_relative_paths_per_block() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let fixtures = tempfile::TempDir::new().expect("should create fixtures dir");
+    for (dir, name) in [("a", "alpha"), ("b", "beta")] {
+        std::fs::create_dir(fixtures.path().join(dir)).expect("should create subdir");
+        std::fs::write(fixtures.path().join(dir).join("name.txt"), name)
+            .expect("should write fixture");
+    }
+    let mut config = create_sqlite_config();
+    config.fixtures_dir = Some(fixtures.path().to_owned());
+
+    let content = r#"```sql validator=sqlite cwd=/fixtures/a
+SELECT CAST(readfile('name.txt') AS TEXT) AS name;
+<!--EXPECT
+[{"name": "alpha"}]
+-->
+```
+
+```sql validator=sqlite cwd=/fixtures/b
+SELECT CAST(readfile('name.txt') AS TEXT) AS name;
+<!--EXPECT
+[{"name": "beta"}]
+-->
+```
+"#;
+
+    ValidatorPreprocessor::new()
+        .process_book_with_config(create_book_with_content(content), &config, &book_root)
+        .expect("each block should read its own fixture");
+}
+
+/// Test: `<!--ASSERT_TOML-->` assertions are checked on the host and stripped.
+///
+/// This test requires Docker to be running.
+#[test]
+fn assert_toml_block_is_evaluated_and_stripped() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let content = r#"```sql validator=sqlite
+SELECT 'alice' AS name UNION ALL SELECT 'bob';
+<!--ASSERT_TOML
+rows = { op = "=", value = 2 }
+contains = ["alice", "bob"]
+-->
+```
+"#;
+
+    let book = ValidatorPreprocessor::new()
+        .process_book_with_config(
+            create_book_with_content(content),
+            &create_sqlite_config(),
+            &book_root,
+        )
+        .expect("assertions should pass");
+    let Some(BookItem::Chapter(chapter)) = book.items.first() else {
+        panic!("Expected chapter in processed book");
+    };
+    assert!(
+        !chapter.content.contains("ASSERT_TOML"),
+        "{}",
+        chapter.content
+    );
+
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(
+            create_book_with_content(&content.replace("value = 2", "value = 3")),
+            &create_sqlite_config(),
+            &book_root,
+        )
+        .expect_err("two rows, not three");
+    assert!(
+        format!("{err:#}").contains("rows = 3: got 2"),
+        "got: {err:#}"
+    );
+}
+
+#[test]
+fn native_assertions_skip_the_validator_script() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    // The script always fails, so passing blocks prove it (and jq) never ran
+    let mut config = create_sqlite_config();
+    for validator in config.validators.values_mut() {
+        validator.script = PathBuf::from("tests/fixtures/exit_code_validator.sh");
+        validator.native_assertions = true;
+    }
+    let content = r#"```sql validator=sqlite
+SELECT 'alice' AS name UNION ALL SELECT 'bob';
+<!--ASSERT
+rows = 2
+contains "alice"
+!contains "carol"
+-->
+```
+"#;
+
+    ValidatorPreprocessor::new()
+        .process_book_with_config(create_book_with_content(content), &config, &book_root)
+        .expect("assertions should be evaluated natively");
+
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(
+            create_book_with_content(&content.replace("rows = 2", "rows = 3")),
+            &config,
+            &book_root,
+        )
+        .expect_err("two rows, not three");
+    assert!(
+        format!("{err:#}").contains("rows = 3: got 2"),
+        "got: {err:#}"
+    );
+}
+
+/// Test: `caption="..."` renders above a validated block in the output
+#[test]
+fn caption_is_rendered_before_validated_block() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_sqlite_config();
+
+    let chapter_content = r#"# Captions
+
+```sql validator=sqlite caption="✓ Tested against SQLite 3.47"
+SELECT 1 AS one;
+```
+"#;
+
+    let book = create_book_with_content(chapter_content);
+    let preprocessor = ValidatorPreprocessor::new();
+
+    let book = preprocessor
+        .process_book_with_config(book, &config, &book_root)
+        .expect("captioned block should validate");
+    let Some(BookItem::Chapter(chapter)) = book.items.first() else {
+        panic!("Expected chapter in processed book");
+    };
+    let caption = chapter
+        .content
+        .find("> ✓ Tested against SQLite 3.47")
+        .expect("caption should be rendered");
+    let fence = chapter
+        .content
+        .find("```sql\n")
+        .expect("fence should remain");
+    assert!(
+        caption < fence,
+        "caption should come before the block. Output:\n{}",
+        chapter.content
+    );
+    assert!(
+        !chapter.content.contains("caption="),
+        "Output:\n{}",
+        chapter.content
+    );
+}
+
+/// Test: a validator's `pipeline` transforms block content, in order, before it runs
+#[test]
+fn pipeline_transforms_content_before_exec() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let mut config = create_sqlite_config();
+    if let Some(sqlite) = config.validators.get_mut("sqlite") {
+        sqlite.pipeline = vec![Transform::StripPrompt, Transform::InterpolateEnv];
+    }
+    std::env::set_var("MDBOOK_VALIDATOR_PIPELINE_VALUE", "piped");
+
+    // Only valid SQL once the prompt is stripped and the variable substituted
+    let chapter_content = r#"# Pipeline
+
+```sql validator=sqlite
+sqlite> SELECT '${MDBOOK_VALIDATOR_PIPELINE_VALUE}' AS v;
+<!--EXPECT
+[{"v": "piped"}]
+-->
+```
+"#;
+
+    let book = create_book_with_content(chapter_content);
+    let preprocessor = ValidatorPreprocessor::new();
+
+    let book = preprocessor
+        .process_book_with_config(book, &config, &book_root)
+        .expect("transformed content should run and match EXPECT");
+    let Some(BookItem::Chapter(chapter)) = book.items.first() else {
+        panic!("Expected chapter in processed book");
+    };
+    // The rendered block keeps the content as written
+    assert!(
+        chapter
+            .content
+            .contains("sqlite> SELECT '${MDBOOK_VALIDATOR_PIPELINE_VALUE}'"),
+        "Output:\n{}",
+        chapter.content
+    );
+}
+
+/// Test: `implicit_assertions` apply only to blocks without assertions of their own
+///
+/// This test requires Docker to be running.
+#[test]
+fn implicit_assertions_apply_only_to_blocks_without_assertions() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let mut config = create_sqlite_config();
+    if let Some(sqlite) = config.validators.get_mut("sqlite") {
+        sqlite.implicit_assertions = vec!["rows >= 0".to_owned(), "rows = 2".to_owned()];
+    }
+
+    // No assertions: the implicit `rows = 2` applies and fails on one row
+    let book = create_book_with_content("```sql validator=sqlite\nSELECT 1 AS n;\n```\n");
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .expect_err("implicit assertions should apply to an assertion-less block");
+    let msg = format!("{err:#}");
+    assert!(msg.contains("rows = 2"), "got: {msg}");
+
+    // Own assertions: the implicit ones are not added
+    let book = create_book_with_content(
+        "```sql validator=sqlite\nSELECT 1 AS n;\n<!--ASSERT\nrows = 1\n-->\n```\n",
+    );
+    ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .expect("a block with its own assertions should skip the implicit ones");
+}
+
+/// Test: `<!--VALIDATOR_DEFAULT-->` validates bare fences; `validator=` overrides it
+///
+/// This test requires Docker to be running.
+#[test]
+fn chapter_default_validator_applies_to_bare_fences() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_sqlite_config();
+
+    let book = create_book_with_content(
+        r"<!--VALIDATOR_DEFAULT sqlite sql-->
+
+```sql
+SELECT 1 AS n;
+<!--ASSERT
+rows = 1
+-->
+```
+",
+    );
+    let book = ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .expect("bare sql block should validate with the chapter default");
+    let Some(BookItem::Chapter(chapter)) = book.items.first() else {
+        panic!("Expected chapter in processed book");
+    };
+    assert!(
+        !chapter.content.contains("<!--ASSERT"),
+        "markers should be stripped from the default-validated block. Output:\n{}",
+        chapter.content
+    );
+
+    // A failing assertion proves the bare block really ran
+    let book = create_book_with_content(
+        "<!--VALIDATOR_DEFAULT sqlite-->\n\n```sql\nSELECT 1 AS n;\n<!--ASSERT\nrows = 2\n-->\n```\n",
+    );
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .expect_err("bare block should be validated and fail");
+    assert!(format!("{err:#}").contains("rows = 2"), "got: {err:#}");
+
+    // An explicit validator= wins over the default
+    let book = create_book_with_content(
+        "<!--VALIDATOR_DEFAULT sqlite-->\n\n```sql validator=other\nSELECT 1;\n```\n",
+    );
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(book, &config, &book_root)
+        .expect_err("unknown explicit validator should fail");
+    match err.downcast_ref::<ValidatorError>() {
+        Some(ValidatorError::UnknownValidator { name }) => assert_eq!(name, "other"),
+        other => panic!("expected UnknownValidator, got {other:?} ({err:#})"),
+    }
+}
+
+/// Test: two separate calls sharing a `ContainerPool` run in the same container.
+///
+/// This test requires Docker to be running.
+#[test]
+fn container_pool_reuses_container_across_calls() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_sqlite_config();
+    let pool = Arc::new(ContainerPool::new().expect("should create pool"));
+    let content = "```sql validator=sqlite\nSELECT 1;\n<!--ASSERT\nrows = 1\n-->\n```\n";
+
+    let container_id = |pool: &Arc<ContainerPool>| {
+        let (_, report) = ValidatorPreprocessor::builder()
+            .container_pool(Arc::clone(pool))
+            .build()
+            .process_book_with_report(create_book_with_content(content), &config, &book_root)
+            .expect("block should pass");
+        let [result] = report.blocks() else {
+            panic!("expected one report entry: {:?}", report.blocks());
+        };
+        result.container_id.clone()
+    };
+
+    let first = container_id(&pool);
+    assert_eq!(pool.len(), 1, "container should be returned to the pool");
+    let second = container_id(&pool);
+    assert_eq!(
+        first, second,
+        "second call should reuse the pooled container"
+    );
+    assert_eq!(pool.len(), 1);
+}
+
+/// Test: a pooled container is not reused once its validator's container options
+/// change, since it was started with the old ones.
+///
+/// This test requires Docker to be running.
+#[test]
+fn container_pool_does_not_reuse_container_after_option_change() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let pool = Arc::new(ContainerPool::new().expect("should create pool"));
+    let content = "```sql validator=sqlite\nSELECT 1;\n<!--ASSERT\nrows = 1\n-->\n```\n";
+
+    let container_id = |config: &Config| {
+        let (_, report) = ValidatorPreprocessor::builder()
+            .container_pool(Arc::clone(&pool))
+            .build()
+            .process_book_with_report(create_book_with_content(content), config, &book_root)
+            .expect("block should pass");
+        let [result] = report.blocks() else {
+            panic!("expected one report entry: {:?}", report.blocks());
+        };
+        result.container_id.clone()
+    };
+
+    let mut config = create_sqlite_config();
+    let first = container_id(&config);
+    config
+        .validators
+        .get_mut("sqlite")
+        .expect("sqlite validator")
+        .env
+        .insert("POOL_TEST".to_owned(), "changed".to_owned());
+    let second = container_id(&config);
+    assert_ne!(
+        first, second,
+        "changed env should start a new container, not reuse the pooled one"
+    );
+    assert_eq!(pool.len(), 2, "both containers should be pooled");
+}
+
+/// Test: `isolation = "per-block"` gives each block a new container, so SETUPs
+/// that would collide in a shared container both succeed.
+///
+/// This test requires Docker to be running.
+#[test]
+fn per_block_isolation_runs_conflicting_setups() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let block = "```sql validator=sqlite\n<!--SETUP\nsqlite3 /tmp/test.db 'CREATE TABLE t(x INTEGER);'\n-->\nSELECT * FROM t;\n<!--ASSERT\nrows = 0\n-->\n```\n";
+    let content = format!("{block}\n{block}");
+
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(
+            create_book_with_content(&content),
+            &create_sqlite_config(),
+            &book_root,
+        )
+        .expect_err("second CREATE TABLE should collide in a shared container");
+    assert!(
+        matches!(
+            err.downcast_ref::<ValidatorError>(),
+            Some(ValidatorError::SetupFailed { .. })
+        ),
+        "expected SetupFailed, got: {err:#}"
+    );
+
+    let mut config = create_sqlite_config();
+    config.isolation = Isolation::PerBlock;
+    let (_, report) = ValidatorPreprocessor::new()
+        .process_book_with_report(create_book_with_content(&content), &config, &book_root)
+        .expect("each block should get its own container");
+    let [first, second] = report.blocks() else {
+        panic!("expected two report entries: {:?}", report.blocks());
+    };
+    assert_ne!(first.container_id, second.container_id);
+}
+
+/// Test: a query that fails on purpose passes when `<!--EXPECT-EXIT-->` matches its exit code.
+///
+/// This test requires Docker to be running.
+#[test]
+fn expect_exit_passes_deliberately_failing_query() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let block = |exit_code: i64| {
+        format!(
+            "```sql validator=sqlite\n<!--SETUP\nsqlite3 /tmp/test.db 'CREATE TABLE t(x INTEGER NOT NULL);'\n-->\nINSERT INTO t VALUES (NULL);\n<!--EXPECT-EXIT {exit_code}-->\n```\n"
+        )
+    };
+
+    let book = ValidatorPreprocessor::new()
+        .process_book_with_config(
+            create_book_with_content(&block(1)),
+            &create_sqlite_config(),
+            &book_root,
+        )
+        .expect("constraint violation should match EXPECT-EXIT 1");
+    let Some(BookItem::Chapter(chapter)) = book.items.first() else {
+        panic!("Expected chapter in processed book");
+    };
+    assert_eq!(chapter.content, "```sql\nINSERT INTO t VALUES (NULL);\n```");
+
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(
+            create_book_with_content(&block(2)),
+            &create_sqlite_config(),
+            &book_root,
+        )
+        .expect_err("exit code 1 should not match EXPECT-EXIT 2");
+    assert!(
+        format!("{err:#}").contains("expected 2 (EXPECT-EXIT)"),
+        "unexpected error: {err:#}"
+    );
+}
+
+/// Test: a validator's `env` is visible to the query in its container.
+///
+/// This test requires Docker to be running.
+#[test]
+fn validator_env_is_set_in_container() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config: Config = toml::from_str(
+        r#"
+        [validators.alpine]
+        container = "alpine:3"
+        script = "tests/fixtures/echo_validator.sh"
+        exec_command = "sh"
+        env = { GREETING = "hi" }
+        "#,
+    )
+    .expect("config should parse");
+    let content = "```sh validator=alpine hidden capture=greeting\necho \"$GREETING\"\n```\n\nGreeting: <!--OUTPUT:greeting-->\n";
+
+    let book = ValidatorPreprocessor::new()
+        .process_book_with_config(create_book_with_content(content), &config, &book_root)
+        .expect("block should pass");
+    let Some(BookItem::Chapter(chapter)) = book.items.first() else {
+        panic!("Expected chapter in processed book");
+    };
+    assert!(
+        chapter.content.contains("Greeting: hi"),
+        "got: {}",
+        chapter.content
+    );
+}
+
+/// Test: the chapter and config written by `init` validate as scaffolded.
+///
+/// This test requires Docker to be running.
+#[test]
+fn scaffolded_sample_chapter_validates() {
+    let dir = tempfile::TempDir::new().expect("should create temp dir");
+    std::fs::write(dir.path().join("book.toml"), "[book]\ntitle = \"Test\"\n")
+        .expect("should write book.toml");
+    let _ = mdbook_validator::scaffold::init(dir.path()).expect("init should succeed");
+
+    let book_toml: toml::Value = toml::from_str(
+        &std::fs::read_to_string(dir.path().join("book.toml")).expect("should read book.toml"),
+    )
+    .expect("book.toml should parse");
+    let config = Config::from_value(book_toml["preprocessor"]["validator"].clone())
+        .expect("scaffolded config should parse");
+    let chapter = std::fs::read_to_string(dir.path().join("src/validator-example.md"))
+        .expect("should read sample chapter");
+
+    ValidatorPreprocessor::new()
+        .process_book_with_config(create_book_with_content(&chapter), &config, dir.path())
+        .expect("scaffolded sample chapter should validate");
+}
+
+/// Creates a config with an `alpine` validator that runs each block with `sh`
+fn create_alpine_config(timeout: u64) -> Config {
+    toml::from_str(&format!(
+        r#"
+        [validators.alpine]
+        container = "alpine:3"
+        script = "tests/fixtures/echo_validator.sh"
+        exec_command = "sh"
+        timeout = {timeout}
+        "#
+    ))
+    .expect("config should parse")
+}
+
+/// Test: with `cache_setup`, an identical SETUP runs once per container;
+/// without it, once per block.
+///
+/// This test requires Docker to be running.
+#[test]
+fn cache_setup_runs_identical_setup_once_per_container() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let block = |expect: &str| {
+        format!(
+            "```sh validator=alpine\n<!--SETUP\necho run >> /tmp/setup-runs\n-->\ngrep -c run /tmp/setup-runs\n<!--EXPECT\n{expect}\n-->\n```\n\n"
+        )
+    };
+
+    for (cache_setup, second_runs) in [(true, "1"), (false, "2")] {
+        let mut config = create_alpine_config(60);
+        config
+            .validators
+            .get_mut("alpine")
+            .expect("alpine validator")
+            .cache_setup = cache_setup;
+        let content = format!("{}{}", block("1"), block(second_runs));
+
+        ValidatorPreprocessor::new()
+            .process_book_with_config(create_book_with_content(&content), &config, &book_root)
+            .unwrap_or_else(|e| panic!("cache_setup = {cache_setup}: {e:#}"));
+    }
+}
+
+/// Test: a SETUP script that is large and also writes a lot of output completes;
+/// its stdin and stdout are pumped concurrently rather than one after the other.
+///
+/// A deadlock would show up as a SETUP timeout. This test requires Docker to be running.
+#[test]
+fn large_setup_with_output_does_not_deadlock() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    // 4 MiB of script on stdin, while the script itself writes 4 MiB to stdout
+    let size = 4 * 1024 * 1024;
+    let content = format!(
+        "```sh validator=alpine\n<!--SETUP\n# {}\nhead -c {size} /dev/zero\necho ready > /tmp/ready\n-->\ncat /tmp/ready\n<!--EXPECT\nready\n-->\n```\n",
+        "x".repeat(size)
+    );
+
+    ValidatorPreprocessor::new()
+        .process_book_with_config(
+            create_book_with_content(&content),
+            &create_alpine_config(60),
+            &book_root,
+        )
+        .expect("large SETUP should complete");
+}
+
+/// Test: SETUP is bounded by the block's timeout.
+///
+/// This test requires Docker to be running.
+#[test]
+fn setup_is_bounded_by_block_timeout() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let content = "```sh validator=alpine\n<!--SETUP\nsleep 30\n-->\necho done\n```\n";
+
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(
+            create_book_with_content(content),
+            &create_alpine_config(1),
+            &book_root,
+        )
+        .expect_err("SETUP should time out");
+    assert!(
+        format!("{err:#}").contains("Setup timed out after 1s"),
+        "{err:#}"
+    );
+    assert!(
+        matches!(
+            err.downcast_ref::<ValidatorError>(),
+            Some(ValidatorError::Timeout { phase, seconds: 1, .. }) if phase == "Setup"
+        ),
+        "{err:#}"
+    );
+}
+
+/// Test: a hung query times out as its own phase, after a SETUP that finished.
+///
+/// This test requires Docker to be running.
+#[test]
+fn hung_query_times_out_separately_from_setup() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let content = "```sh validator=alpine\n<!--SETUP\ntrue\n-->\nsleep 30\n```\n";
+
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(
+            create_book_with_content(content),
+            &create_alpine_config(1),
+            &book_root,
+        )
+        .expect_err("query should time out");
+    assert!(
+        matches!(
+            err.downcast_ref::<ValidatorError>(),
+            Some(ValidatorError::Timeout { phase, validator, seconds: 1, message })
+                if phase == "Query"
+                    && validator == "alpine"
+                    && message.contains("'Test Chapter' block 1")
+        ),
+        "expected Query timeout, got {err:#}"
+    );
+}
+
+/// Test: `retry=` doesn't re-run a query that timed out.
+///
+/// This test requires Docker to be running.
+#[test]
+fn timed_out_query_is_not_retried() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_alpine_config(2);
+    let pool = Arc::new(ContainerPool::new().expect("should create pool"));
+    let preprocessor = || {
+        ValidatorPreprocessor::builder()
+            .container_pool(Arc::clone(&pool))
+            .build()
+    };
+    // Start the container first, so only the query is timed below
+    preprocessor()
+        .process_book_with_config(
+            create_book_with_content("```sh validator=alpine\ntrue\n```\n"),
+            &config,
+            &book_root,
+        )
+        .expect("warm-up block should pass");
+
+    let started = std::time::Instant::now();
+    let err = preprocessor()
+        .process_book_with_config(
+            create_book_with_content("```sh validator=alpine retry=3\nsleep 30\n```\n"),
+            &config,
+            &book_root,
+        )
+        .expect_err("query should time out");
+    assert!(
+        matches!(
+            err.downcast_ref::<ValidatorError>(),
+            Some(ValidatorError::Timeout { phase, .. }) if phase == "Query"
+        ),
+        "{err:#}"
+    );
+    // Four 2-second attempts would take at least 8 seconds
+    assert!(started.elapsed().as_secs() < 6, "{:?}", started.elapsed());
+}
+
+/// Test: CLEANUP runs after a failing block, and the block's own error is reported.
+///
+/// This test requires Docker to be running.
+#[test]
+fn cleanup_runs_after_failing_block() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let mut config = create_alpine_config(60);
+    config.fail_fast = false;
+    // The second block shares the container and sees whether CLEANUP ran
+    let content = "```sh validator=alpine\n<!--SETUP\ntouch /tmp/state\n-->\nexit 1\n<!--CLEANUP\nrm /tmp/state\n-->\n```\n\n\
+                   ```sh validator=alpine\ntest -e /tmp/state && echo left || echo removed\n<!--EXPECT\nremoved\n-->\n```\n";
+
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(create_book_with_content(content), &config, &book_root)
+        .expect_err("the first block's query should fail");
+    let aggregated = err
+        .downcast_ref::<AggregatedErrors>()
+        .unwrap_or_else(|| panic!("expected AggregatedErrors, got {err:#}"));
+    assert_eq!(
+        aggregated.errors().len(),
+        1,
+        "only the failing block should fail; CLEANUP should have removed the file: {err:#}"
+    );
+    assert!(
+        !aggregated
+            .validator_errors()
+            .any(|error| matches!(error, ValidatorError::CleanupFailed { .. })),
+        "the block's own error should be reported: {err:#}"
+    );
+}
+
+/// Test: a failing CLEANUP fails a block that otherwise passed.
+///
+/// This test requires Docker to be running.
+#[test]
+fn failing_cleanup_fails_passing_block() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let content = "```sh validator=alpine\necho ok\n<!--CLEANUP\necho nope >&2; exit 3\n-->\n```\n";
+
+    let err = ValidatorPreprocessor::new()
+        .process_book_with_config(
+            create_book_with_content(content),
+            &create_alpine_config(60),
+            &book_root,
+        )
+        .expect_err("CLEANUP failure should fail the block");
+    assert!(
+        matches!(
+            err.downcast_ref::<ValidatorError>(),
+            Some(ValidatorError::CleanupFailed { exit_code: 3, message })
+                if message.contains("nope")
+        ),
+        "expected CleanupFailed, got {err:#}"
+    );
+}
+
+/// Test: a pooled container that died between calls is replaced with a fresh one
+/// instead of failing every block.
+///
+/// This test requires Docker to be running.
+#[test]
+fn dead_pooled_container_is_restarted() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_alpine_config(60);
+    let pool = Arc::new(ContainerPool::new().expect("should create pool"));
+    let container_id = || {
+        let (_, report) = ValidatorPreprocessor::builder()
+            .container_pool(Arc::clone(&pool))
+            .build()
+            .process_book_with_report(
+                create_book_with_content("```sh validator=alpine\necho ok\n```\n"),
+                &config,
+                &book_root,
+            )
+            .expect("block should pass");
+        let [result] = report.blocks() else {
+            panic!("expected one report entry: {:?}", report.blocks());
+        };
+        result.container_id.clone()
+    };
+
+    let stale = container_id();
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .expect("should build runtime")
+        .block_on(async {
+            bollard::Docker::connect_with_local_defaults()
+                .expect("Docker available")
+                .kill_container::<String>(&stale, None)
+                .await
+        })
+        .expect("should kill the pooled container");
+
+    assert_ne!(container_id(), stale, "dead container should be replaced");
+}
@@ -33,6 +33,17 @@ async fn run_osquery_config_validator(
     config_json: &str,
     assertions: Option<&str>,
     expect: Option<&str>,
+) -> (i32, String, String) {
+    run_osquery_config_validator_allowing(config_json, assertions, expect, &[]).await
+}
+
+/// Like [`run_osquery_config_validator`], but container warnings matching
+/// `allow_warnings` are dropped before the host validator sees them.
+async fn run_osquery_config_validator_allowing(
+    config_json: &str,
+    assertions: Option<&str>,
+    expect: Option<&str>,
+    allow_warnings: &[String],
 ) -> (i32, String, String) {
     let container = ValidatorContainer::start_raw(OSQUERY_IMAGE)
         .await
@@ -80,13 +91,14 @@ async fn run_osquery_config_validator(
     // Validate config with host validator (checks assertions and container warnings)
     // Pass container stderr so validator can detect osquery warnings like "Cannot set unknown"
     let runner = RealCommandRunner;
+    let container_stderr = host_validator::filter_allowed_warnings(&result.stderr, allow_warnings);
     let validation_result = host_validator::run_validator(
         &runner,
         VALIDATOR_SCRIPT,
         &result.stdout,
         assertions,
         expect,
-        Some(&result.stderr),
+        Some(&container_stderr),
     )
     .expect("host validator should run");
 
@@ -157,6 +169,37 @@ async fn test_osquery_config_unknown_option_fails() {
     );
 }
 
+/// Test: a warning on the allow list doesn't fail validation
+#[tokio::test]
+async fn test_osquery_config_allowed_warning_passes() {
+    let config = r#"{"options": {"completely_fake_nonexistent_option_xyz_12345": "value"}}"#;
+    let allow = vec!["completely_fake_nonexistent_option_xyz_12345".to_owned()];
+    let (exit_code, _, stderr) =
+        run_osquery_config_validator_allowing(config, None, None, &allow).await;
+
+    assert_eq!(
+        exit_code, 0,
+        "allowed warning should not fail validation: {}",
+        stderr
+    );
+}
+
+/// Test: warnings not on the allow list still fail validation
+#[tokio::test]
+async fn test_osquery_config_unlisted_warning_still_fails() {
+    let config = r#"{"options": {"completely_fake_nonexistent_option_xyz_12345": "value", "another_fake_option_abc_67890": "value"}}"#;
+    let allow = vec!["completely_fake_nonexistent_option_xyz_12345".to_owned()];
+    let (exit_code, _, stderr) =
+        run_osquery_config_validator_allowing(config, None, None, &allow).await;
+
+    assert_ne!(exit_code, 0, "unlisted warning should fail validation");
+    assert!(
+        stderr.contains("another_fake_option_abc_67890"),
+        "stderr should mention the unlisted option: {}",
+        stderr
+    );
+}
+
 /// Test: contains assertion passes when config contains the string
 #[tokio::test]
 async fn test_osquery_config_contains_assertion_passes() {
@@ -72,7 +72,7 @@ async fn run_osquery_validator(
         &query_result.stdout,
         assertions,
         expect,
-        None,
+        Some(&query_result.stderr),
     )
     .expect("host validator should run");
 
@@ -244,3 +244,98 @@ async fn test_osquery_rows_greater_than_assertion_fails() {
         stderr
     );
 }
+
+/// Test: stderr_empty passes for a clean osquery run
+#[tokio::test]
+async fn test_osquery_stderr_empty_passes_on_clean_run() {
+    let (exit_code, _, stderr) =
+        run_osquery_validator("SELECT 1 AS one;", Some("stderr_empty"), None).await;
+    assert_eq!(
+        exit_code, 0,
+        "clean run should pass stderr_empty: {}",
+        stderr
+    );
+}
+
+/// Test: stderr_empty fails when osquery wrote a warning to stderr
+#[test]
+fn test_osquery_stderr_empty_fails_on_warning() {
+    let warning = "W0101 12:00:00.000000 1 options.cpp:123] Unknown option: foo\n";
+    let result = host_validator::run_validator(
+        &RealCommandRunner,
+        VALIDATOR_SCRIPT,
+        r#"[{"one":"1"}]"#,
+        Some("stderr_empty"),
+        None,
+        Some(warning),
+    )
+    .expect("host validator should run");
+
+    assert_eq!(result.exit_code, 1, "warning should fail stderr_empty");
+    assert!(
+        result.stderr.contains("stderr_empty") && result.stderr.contains("Unknown option"),
+        "failure should name the assertion and show the warning: {}",
+        result.stderr
+    );
+}
+
+/// Test: whitespace-only stderr counts as empty
+#[test]
+fn test_osquery_stderr_empty_ignores_whitespace() {
+    let result = host_validator::run_validator(
+        &RealCommandRunner,
+        VALIDATOR_SCRIPT,
+        "[]",
+        Some("stderr_empty"),
+        None,
+        Some("  \n\t\n"),
+    )
+    .expect("host validator should run");
+
+    assert_eq!(result.exit_code, 0, "stderr: {}", result.stderr);
+}
+
+/// Test: rows = N ± M passes when the count is within M of N and fails outside
+#[tokio::test]
+async fn test_osquery_rows_tolerance_assertion() {
+    // Query returns 3 rows
+    let sql = "SELECT 1 AS n UNION ALL SELECT 2 UNION ALL SELECT 3;";
+
+    let (exit_code, _, stderr) = run_osquery_validator(sql, Some("rows = 4 ± 1"), None).await;
+    assert_eq!(exit_code, 0, "3 is within 4 ± 1: {}", stderr);
+
+    let (exit_code, _, stderr) = run_osquery_validator(sql, Some("rows = 10 ± 2"), None).await;
+    assert_ne!(exit_code, 0, "3 is outside 10 ± 2");
+    assert!(
+        stderr.contains("rows = 10 ± 2: got 3"),
+        "stderr should show the range and actual count: {}",
+        stderr
+    );
+}
+
+/// Test: rows = N tolerance M is the ASCII spelling of rows = N ± M
+#[test]
+fn test_osquery_rows_tolerance_keyword() {
+    let run = |assertion| {
+        host_validator::run_validator(
+            &RealCommandRunner,
+            VALIDATOR_SCRIPT,
+            r#"[{"pid":"1"},{"pid":"2"}]"#,
+            Some(assertion),
+            None,
+            None,
+        )
+        .expect("host validator should run")
+    };
+
+    assert_eq!(run("rows = 3 tolerance 1").exit_code, 0);
+    assert_eq!(run("rows = 5 tolerance 1").exit_code, 1);
+
+    let malformed = run("rows = 3 tolerance x");
+    assert_eq!(malformed.exit_code, 1);
+    assert!(
+        malformed.stderr.contains("invalid integer"),
+        "stderr: {}",
+        malformed.stderr
+    );
+}
@@ -1,7 +1,10 @@
 //! Tests for markdown parsing and code block extraction
 #![allow(clippy::str_to_string)]
 
-use mdbook_validator::parser::{extract_markers, parse_info_string};
+use mdbook_validator::parser::{
+    extract_markers, parse_block_attributes, parse_info_string, strip_internal_attributes,
+    AssertTarget, ExpectMode,
+};
 
 #[test]
 fn parse_info_string_extracts_language_and_validator() {
@@ -33,6 +36,263 @@ fn parse_info_string_handles_skip_attribute() {
     assert!(!hidden);
 }
 
+#[test]
+fn parse_block_attributes_extracts_use() {
+    let attrs = parse_block_attributes("sql validator=sqlite use=valid_resp");
+
+    assert_eq!(attrs.language, "sql");
+    assert_eq!(attrs.validator, Some("sqlite".to_string()));
+    assert_eq!(attrs.uses, vec!["valid_resp".to_string()]);
+}
+
+#[test]
+fn parse_block_attributes_use_accepts_comma_list_and_repeats() {
+    let attrs = parse_block_attributes("sql validator=sqlite use=a,b use=c skip");
+
+    assert_eq!(attrs.uses, vec!["a", "b", "c"]);
+    assert!(attrs.skip);
+}
+
+#[test]
+fn parse_block_attributes_without_use_is_empty() {
+    let attrs = parse_block_attributes("sql validator=sqlite use=");
+
+    assert!(attrs.uses.is_empty());
+}
+
+#[test]
+fn parse_block_attributes_extracts_quoted_filter() {
+    let attrs = parse_block_attributes(r#"sql validator=sqlite filter=".[] | {id}" hidden"#);
+
+    assert_eq!(attrs.validator, Some("sqlite".to_string()));
+    assert_eq!(attrs.filter, Some(".[] | {id}".to_string()));
+    assert!(
+        attrs.hidden,
+        "attributes after the quoted value still parse"
+    );
+}
+
+#[test]
+fn parse_block_attributes_filter_keeps_escaped_quotes() {
+    let attrs =
+        parse_block_attributes(r#"sql validator=sqlite filter="map(select(.name == \"alice\"))""#);
+
+    assert_eq!(
+        attrs.filter,
+        Some(r#"map(select(.name == "alice"))"#.to_string())
+    );
+}
+
+#[test]
+fn parse_block_attributes_without_filter_is_none() {
+    assert_eq!(parse_block_attributes("sql validator=sqlite").filter, None);
+    assert_eq!(
+        parse_block_attributes(r#"sql validator=sqlite filter="""#).filter,
+        None
+    );
+}
+
+#[test]
+fn parse_block_attributes_extracts_optional() {
+    assert!(parse_block_attributes("sql validator=sqlite optional").optional);
+    assert!(!parse_block_attributes("sql validator=sqlite").optional);
+}
+
+#[test]
+fn parse_block_attributes_extracts_args() {
+    let attrs = parse_block_attributes(r#"sql validator=sqlite args="/tmp/other.db -bail" skip"#);
+
+    assert_eq!(attrs.args, vec!["/tmp/other.db", "-bail"]);
+    assert!(attrs.skip);
+    assert!(parse_block_attributes("sql validator=sqlite")
+        .args
+        .is_empty());
+}
+
+#[test]
+fn parse_block_attributes_extracts_session() {
+    let attrs = parse_block_attributes("sql validator=sqlite session=orders");
+
+    assert_eq!(attrs.session, Some("orders".to_string()));
+    assert_eq!(parse_block_attributes("sql validator=sqlite").session, None);
+}
+
+#[test]
+fn parse_block_attributes_extracts_timeout_and_retry() {
+    let attrs = parse_block_attributes("sql validator=sqlite timeout=10 retry=2");
+
+    assert_eq!(attrs.timeout, Some(10));
+    assert_eq!(attrs.retry, Some(2));
+
+    let unset = parse_block_attributes("sql validator=sqlite");
+    assert_eq!(unset.timeout, None);
+    assert_eq!(unset.retry, None);
+    assert!(unset.invalid.is_empty());
+}
+
+#[test]
+fn parse_block_attributes_rejects_bad_timeout_and_retry() {
+    // Bad values are reported rather than guessed at
+    for (info, reason) in [
+        ("sql validator=sqlite timeout=soon", "got 'soon'"),
+        ("sql validator=sqlite timeout=0", "at least 1 second"),
+        ("sql validator=sqlite timeout=-5", "got '-5'"),
+        (
+            "sql validator=sqlite retry=x",
+            "retry must be a whole number",
+        ),
+    ] {
+        let attrs = parse_block_attributes(info);
+        assert_eq!(attrs.timeout, None, "{info}");
+        assert_eq!(attrs.retry, None, "{info}");
+        assert_eq!(attrs.invalid.len(), 1, "{info}");
+        assert!(
+            attrs.invalid[0].contains(reason),
+            "{info}: {:?}",
+            attrs.invalid
+        );
+    }
+
+    // retry=0 turns retries off; it isn't an error
+    let attrs = parse_block_attributes("sql validator=sqlite retry=0");
+    assert_eq!(attrs.retry, Some(0));
+    assert!(attrs.invalid.is_empty());
+}
+
+#[test]
+fn parse_block_attributes_extracts_expect_ignore() {
+    let attrs = parse_block_attributes("sql validator=sqlite expect_ignore=created_at,id");
+    assert_eq!(attrs.expect_ignore, ["created_at", "id"]);
+
+    let quoted = parse_block_attributes(r#"sql validator=sqlite expect_ignore="created_at, id""#);
+    assert_eq!(quoted.expect_ignore, ["created_at", "id"]);
+
+    assert!(parse_block_attributes("sql validator=sqlite")
+        .expect_ignore
+        .is_empty());
+}
+
+#[test]
+fn parse_block_attributes_extracts_caption() {
+    let attrs = parse_block_attributes(r#"sql validator=sqlite caption="Tested against SQLite""#);
+    assert_eq!(attrs.caption.as_deref(), Some("Tested against SQLite"));
+    assert_eq!(
+        parse_block_attributes("sql validator=sqlite caption=").caption,
+        None
+    );
+}
+
+#[test]
+fn parse_block_attributes_extracts_expect_unordered() {
+    assert_eq!(
+        parse_block_attributes("sql validator=sqlite expect_unordered").expect_mode,
+        ExpectMode::Unordered
+    );
+    assert_eq!(
+        parse_block_attributes("sql validator=sqlite").expect_mode,
+        ExpectMode::Exact
+    );
+    assert_eq!(
+        strip_internal_attributes("sql validator=sqlite expect_unordered"),
+        "sql"
+    );
+}
+
+#[test]
+fn parse_block_attributes_extracts_expect_json() {
+    assert_eq!(
+        parse_block_attributes("sql validator=sqlite expect_json").expect_mode,
+        ExpectMode::Json
+    );
+    assert_eq!(
+        strip_internal_attributes("sql validator=sqlite expect_json"),
+        "sql"
+    );
+}
+
+#[test]
+fn parse_block_attributes_collects_allow_warnings() {
+    let attrs = parse_block_attributes(
+        r#"json validator=osquery-config allow_warning="unknown flag: foo" allow_warning=bar"#,
+    );
+    assert_eq!(attrs.allow_warnings, ["unknown flag: foo", "bar"]);
+
+    assert!(parse_block_attributes("json validator=osquery-config")
+        .allow_warnings
+        .is_empty());
+}
+
+#[test]
+fn parse_block_attributes_reads_assert_target() {
+    let attrs = parse_block_attributes("python validator=python assert_target=combined");
+    assert_eq!(attrs.assert_target, AssertTarget::Combined);
+
+    assert_eq!(
+        parse_block_attributes("python validator=python").assert_target,
+        AssertTarget::Stdout
+    );
+}
+
+#[test]
+fn parse_block_attributes_reads_cwd() {
+    let attrs = parse_block_attributes(r#"sql validator=sqlite cwd="/fixtures/my data""#);
+    assert_eq!(attrs.cwd.as_deref(), Some("/fixtures/my data"));
+
+    assert_eq!(
+        parse_block_attributes("sql validator=sqlite cwd=").cwd,
+        None
+    );
+}
+
+#[test]
+fn parse_block_attributes_reads_description() {
+    let attrs = parse_block_attributes(r#"sql validator=sqlite desc="Create users table" hidden"#);
+    assert_eq!(attrs.description.as_deref(), Some("Create users table"));
+    assert!(attrs.hidden);
+
+    assert_eq!(
+        parse_block_attributes(r#"sql validator=sqlite desc="""#).description,
+        None
+    );
+}
+
+#[test]
+fn strip_internal_attributes_keeps_mdbook_attributes() {
+    assert_eq!(
+        strip_internal_attributes("rust editable validator=rust-exec"),
+        "rust editable"
+    );
+    assert_eq!(
+        strip_internal_attributes(
+            r#"sql noplayground validator=sqlite desc="Create \"users\" table" skip ignore"#
+        ),
+        "sql noplayground ignore"
+    );
+    assert_eq!(
+        strip_internal_attributes("rust,editable validator=rust-exec"),
+        "rust,editable"
+    );
+    // Unknown attributes may belong to another preprocessor, so they're kept as written
+    assert_eq!(
+        strip_internal_attributes(r#"sql title="a b" validator=sqlite"#),
+        r#"sql title="a b""#
+    );
+}
+
+#[test]
+fn extract_markers_gets_assert_toml_content() {
+    let input = "SELECT 1;\n<!--ASSERT_TOML\nrows = { op = \">=\", value = 1 }\n-->\n<!--ASSERT\nrows = 1\n-->";
+
+    let markers = extract_markers(input);
+
+    assert_eq!(
+        markers.assert_toml.as_deref(),
+        Some(r#"rows = { op = ">=", value = 1 }"#)
+    );
+    assert_eq!(markers.assertions.as_deref(), Some("rows = 1"));
+    assert_eq!(markers.visible_content, "SELECT 1;");
+}
+
 #[test]
 fn extract_markers_gets_setup_content() {
     let input = r"<!--SETUP
@@ -82,10 +342,30 @@ rows >= 1
         Some("CREATE TABLE t (x INTEGER);".to_string())
     );
     assert_eq!(markers.assertions, Some("rows >= 1".to_string()));
-    assert_eq!(markers.expect, Some(r#"[{"x": 1}]"#.to_string()));
+    assert_eq!(markers.expect, [r#"[{"x": 1}]"#]);
     assert_eq!(markers.visible_content, "SELECT * FROM t");
 }
 
+#[test]
+fn extract_markers_collects_multiple_expect_blocks_in_order() {
+    let input = r#"SELECT 1 AS a; SELECT 2 AS b;
+<!--EXPECT
+[{"a": 1}]
+-->
+<!--EXPECT_REGEX
+.*
+-->
+<!--EXPECT
+[{"b": 2}]
+-->"#;
+
+    let markers = extract_markers(input);
+
+    assert_eq!(markers.expect, [r#"[{"a": 1}]"#, r#"[{"b": 2}]"#]);
+    assert_eq!(markers.expect_regex, Some(".*".to_string()));
+    assert_eq!(markers.visible_content, "SELECT 1 AS a; SELECT 2 AS b;");
+}
+
 // === parse_info_string edge cases ===
 
 #[test]
@@ -148,6 +428,6 @@ fn extract_markers_no_markers() {
 
     assert_eq!(markers.setup, None);
     assert_eq!(markers.assertions, None);
-    assert_eq!(markers.expect, None);
+    assert!(markers.expect.is_empty());
     assert_eq!(markers.visible_content, "SELECT 1;");
 }
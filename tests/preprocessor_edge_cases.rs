@@ -1,7 +1,7 @@
 //! Tests for preprocessor and container edge cases
 //!
 //! Tests Default trait, container ID, skip attribute, exec command fallback,
-//! and container caching behavior.
+//! container caching behavior, and chapters without a source path.
 #![allow(
     clippy::panic,
     clippy::expect_used,
@@ -64,6 +64,7 @@ fn create_sqlite_config() -> Config {
             container: "keinos/sqlite3:3.47.2".to_string(),
             script: PathBuf::from("validators/validate-sqlite.sh"),
             exec_command: Some("sqlite3 -json /tmp/test.db".to_string()),
+            ..Default::default()
         },
     );
 
@@ -71,6 +72,7 @@ fn create_sqlite_config() -> Config {
         validators,
         fail_fast: true,
         fixtures_dir: None,
+        ..Default::default()
     }
 }
 
@@ -162,6 +164,7 @@ print("hello")
             container: "python:3.12-slim".to_string(),
             script: PathBuf::from("validators/validate-python.sh"),
             exec_command: None, // No exec_command = use fallback "sh -c"
+            ..Default::default()
         },
     );
 
@@ -169,6 +172,7 @@ print("hello")
         fail_fast: true,
         fixtures_dir: None,
         validators,
+        ..Default::default()
     };
 
     let preprocessor = ValidatorPreprocessor::new();
@@ -233,6 +237,7 @@ rows = 999
             container: "keinos/sqlite3:3.47.2".to_string(),
             script: PathBuf::from("validators/validate-sqlite.sh"),
             exec_command: Some("sqlite3 -json /tmp/test.db".to_string()),
+            ..Default::default()
         },
     );
 
@@ -240,6 +245,7 @@ rows = 999
         fail_fast: true,
         fixtures_dir: None,
         validators,
+        ..Default::default()
     };
 
     let preprocessor = ValidatorPreprocessor::new();
@@ -304,6 +310,7 @@ SELECT 2;
             container: "keinos/sqlite3:3.47.2".to_string(),
             script: PathBuf::from("validators/validate-sqlite.sh"),
             exec_command: Some("sqlite3 -json /tmp/test.db".to_string()),
+            ..Default::default()
         },
     );
 
@@ -311,6 +318,7 @@ SELECT 2;
         fail_fast: true,
         fixtures_dir: None,
         validators,
+        ..Default::default()
     };
 
     let preprocessor = ValidatorPreprocessor::new();
@@ -341,3 +349,83 @@ SELECT 2;
         }
     }
 }
+
+// =============================================================================
+// Test 6: Chapter without a source_path (draft/generated chapters)
+// Target: preprocessor.rs chapter_source fallback
+// =============================================================================
+
+/// Creates a book whose only chapter has `source_path == None`
+fn create_book_without_source_path(name: &str, content: &str) -> Book {
+    let mut chapter = Chapter::new(
+        name,
+        content.to_string(),
+        PathBuf::from("generated.md"),
+        vec![],
+    );
+    chapter.source_path = None;
+
+    let mut book = Book::new();
+    book.items.push(BookItem::Chapter(chapter));
+    book
+}
+
+#[test]
+fn preprocessor_validates_chapter_without_source_path() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_sqlite_config();
+
+    let chapter_content = r#"# Generated
+
+```sql validator=sqlite
+SELECT 1 AS one;
+<!--ASSERT
+rows = 1
+-->
+```
+"#;
+
+    let book = create_book_without_source_path("Generated Chapter", chapter_content);
+    let preprocessor = ValidatorPreprocessor::new();
+
+    let processed = preprocessor
+        .process_book_with_config(book, &config, &book_root)
+        .expect("chapter without source_path should still validate");
+
+    let Some(BookItem::Chapter(chapter)) = processed.items.first() else {
+        panic!("Expected chapter");
+    };
+    assert!(
+        !chapter.content.contains("<!--ASSERT"),
+        "Markers should be stripped, proving the block was processed. Output:\n{}",
+        chapter.content
+    );
+}
+
+#[test]
+fn preprocessor_error_without_source_path_names_chapter() {
+    let book_root = std::env::current_dir().expect("should get current dir");
+    let config = create_sqlite_config();
+
+    let chapter_content = r#"# Generated
+
+```sql validator=sqlite
+SELECT 1 AS one;
+<!--ASSERT
+rows = 5
+-->
+```
+"#;
+
+    let book = create_book_without_source_path("Generated Chapter", chapter_content);
+    let preprocessor = ValidatorPreprocessor::new();
+
+    let err = preprocessor
+        .process_book_with_config(book, &config, &book_root)
+        .expect_err("failing block should fail the build");
+    let error_msg = format!("{err}");
+    assert!(
+        error_msg.contains("Generated Chapter"),
+        "Error should reference the chapter name. Got: {error_msg}"
+    );
+}
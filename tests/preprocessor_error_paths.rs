@@ -34,6 +34,7 @@ fn create_sqlite_config() -> Config {
             container: "keinos/sqlite3:3.47.2".to_string(),
             script: PathBuf::from("validators/validate-sqlite.sh"),
             exec_command: Some("sqlite3 -json /tmp/test.db".to_string()),
+            ..Default::default()
         },
     );
 
@@ -41,6 +42,7 @@ fn create_sqlite_config() -> Config {
         validators,
         fail_fast: true,
         fixtures_dir: None,
+        ..Default::default()
     }
 }
 
@@ -229,6 +231,7 @@ SELECT 1;
             container: "keinos/sqlite3:3.47.2".to_string(),
             script: PathBuf::from("validators/validate-sqlite.sh"),
             exec_command: Some("sqlite3 -json /tmp/test.db".to_string()),
+            ..Default::default()
         },
     );
 
@@ -236,6 +239,7 @@ SELECT 1;
         fail_fast: true,
         fixtures_dir: None,
         validators,
+        ..Default::default()
     };
 
     let preprocessor = ValidatorPreprocessor::new();
@@ -293,6 +297,7 @@ sqlite3 /tmp/test.db 'CREATE TABLE IF NOT EXISTS t(id INT)'
             container: "keinos/sqlite3:3.47.2".to_string(),
             script: PathBuf::from("validators/validate-sqlite.sh"),
             exec_command: Some("sqlite3 -json /tmp/test.db".to_string()),
+            ..Default::default()
         },
     );
 
@@ -300,6 +305,7 @@ sqlite3 /tmp/test.db 'CREATE TABLE IF NOT EXISTS t(id INT)'
         fail_fast: true,
         fixtures_dir: None,
         validators,
+        ..Default::default()
     };
 
     let preprocessor = ValidatorPreprocessor::new();
@@ -359,6 +365,7 @@ rows = 999
             container: "keinos/sqlite3:3.47.2".to_string(),
             script: PathBuf::from("validators/validate-sqlite.sh"),
             exec_command: Some("sqlite3 -json /tmp/test.db".to_string()),
+            ..Default::default()
         },
     );
 
@@ -366,6 +373,7 @@ rows = 999
         fail_fast: true,
         fixtures_dir: None,
         validators,
+        ..Default::default()
     };
 
     let preprocessor = ValidatorPreprocessor::new();
@@ -427,6 +435,7 @@ SELECT 'parent' as name;
             container: "keinos/sqlite3:3.47.2".to_string(),
             script: PathBuf::from("validators/validate-sqlite.sh"),
             exec_command: Some("sqlite3 -json /tmp/test.db".to_string()),
+            ..Default::default()
         },
     );
 
@@ -434,6 +443,7 @@ SELECT 'parent' as name;
         fail_fast: true,
         fixtures_dir: None,
         validators,
+        ..Default::default()
     };
 
     let preprocessor = ValidatorPreprocessor::new();
@@ -483,6 +493,7 @@ fn test_empty_chapter_with_config_returns_early() {
             container: "keinos/sqlite3:3.47.2".to_string(),
             script: PathBuf::from("validators/validate-sqlite.sh"),
             exec_command: Some("sqlite3 -json /tmp/test.db".to_string()),
+            ..Default::default()
         },
     );
 
@@ -490,6 +501,7 @@ fn test_empty_chapter_with_config_returns_early() {
         fail_fast: true,
         fixtures_dir: None,
         validators,
+        ..Default::default()
     };
 
     let preprocessor = ValidatorPreprocessor::new();
@@ -527,6 +539,7 @@ fn test_no_validator_blocks_with_config_returns_early() {
             container: "keinos/sqlite3:3.47.2".to_string(),
             script: PathBuf::from("validators/validate-sqlite.sh"),
             exec_command: Some("sqlite3 -json /tmp/test.db".to_string()),
+            ..Default::default()
         },
     );
 
@@ -534,6 +547,7 @@ fn test_no_validator_blocks_with_config_returns_early() {
         fail_fast: true,
         fixtures_dir: None,
         validators,
+        ..Default::default()
     };
 
     let preprocessor = ValidatorPreprocessor::new();
@@ -577,6 +591,7 @@ SELECT 1;
             container: "keinos/sqlite3:3.47.2".to_string(),
             script: PathBuf::from("validators/validate-sqlite.sh"),
             exec_command: Some("sqlite3 -json /tmp/test.db".to_string()),
+            ..Default::default()
         },
     );
 
@@ -584,6 +599,7 @@ SELECT 1;
         fail_fast: true,
         fixtures_dir: Some(PathBuf::from("nonexistent_fixtures_dir_12345")),
         validators,
+        ..Default::default()
     };
 
     let preprocessor = ValidatorPreprocessor::new();
@@ -633,6 +649,7 @@ SELECT 1;
             container: "keinos/sqlite3:3.47.2".to_string(),
             script: PathBuf::from("validators/validate-sqlite.sh"),
             exec_command: Some("sqlite3 -json /tmp/test.db".to_string()),
+            ..Default::default()
         },
     );
 
@@ -641,6 +658,7 @@ SELECT 1;
         fail_fast: true,
         fixtures_dir: Some(PathBuf::from("Cargo.toml")),
         validators,
+        ..Default::default()
     };
 
     let preprocessor = ValidatorPreprocessor::new();
@@ -687,6 +705,7 @@ SELECT 1;
             container: "keinos/sqlite3:3.47.2".to_string(),
             script: PathBuf::from("validators/validate-sqlite.sh"),
             exec_command: Some("sqlite3 -json /tmp/test.db".to_string()),
+            ..Default::default()
         },
     );
 
@@ -696,6 +715,7 @@ SELECT 1;
         fail_fast: true,
         fixtures_dir: Some(fixtures_path),
         validators,
+        ..Default::default()
     };
 
     let preprocessor = ValidatorPreprocessor::new();
@@ -745,6 +765,7 @@ rows = 0
             container: "keinos/sqlite3:3.47.2".to_string(),
             script: PathBuf::from("validators/validate-sqlite.sh"),
             exec_command: Some("sqlite3 -json /tmp/test.db".to_string()),
+            ..Default::default()
         },
     );
 
@@ -752,6 +773,7 @@ rows = 0
         fail_fast: true,
         fixtures_dir: None,
         validators,
+        ..Default::default()
     };
 
     let preprocessor = ValidatorPreprocessor::new();
@@ -802,6 +824,7 @@ SELECT 1;
             container: String::new(), // Empty container is invalid
             script: PathBuf::from("validators/validate-sqlite.sh"),
             exec_command: None,
+            ..Default::default()
         },
     );
 
@@ -809,6 +832,7 @@ SELECT 1;
         fail_fast: true,
         fixtures_dir: None,
         validators,
+        ..Default::default()
     };
 
     let preprocessor = ValidatorPreprocessor::new();
@@ -1002,6 +1026,7 @@ SELECT 'this is skipped';
             container: "keinos/sqlite3:3.47.2".to_string(),
             script: PathBuf::from("validators/validate-sqlite.sh"),
             exec_command: Some("sqlite3 -json /tmp/test.db".to_string()),
+            ..Default::default()
         },
     );
 
@@ -1009,6 +1034,7 @@ SELECT 'this is skipped';
         fail_fast: true,
         fixtures_dir: None,
         validators,
+        ..Default::default()
     };
 
     let preprocessor = ValidatorPreprocessor::new();
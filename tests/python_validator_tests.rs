@@ -20,6 +20,7 @@
 )]
 
 use mdbook_validator::command::RealCommandRunner;
+use mdbook_validator::config::Transform;
 use mdbook_validator::container::ValidatorContainer;
 use mdbook_validator::host_validator;
 
@@ -278,3 +279,93 @@ print(text)
     let (exit_code, _, _) = run_python_validator(script, None).await;
     assert_eq!(exit_code, 0, "multiline string script should pass");
 }
+
+/// Test: A block indented inside a list item passes only after `trim_common_indent`
+#[tokio::test]
+async fn test_python_over_indented_block_passes_after_trim_common_indent() {
+    let script = "    def greet(name):\n        return name\n\n    greet(\"World\")\n";
+
+    let (exit_code, _, stderr) = run_python_validator(script, None).await;
+    assert_ne!(exit_code, 0, "over-indented script should fail as-is");
+    assert!(
+        stderr.contains("IndentationError"),
+        "stderr should mention IndentationError: {}",
+        stderr
+    );
+
+    let dedented = Transform::TrimCommonIndent
+        .apply(script)
+        .expect("trim_common_indent should not fail");
+    let (exit_code, _, stderr) = run_python_validator(&dedented, None).await;
+    assert_eq!(exit_code, 0, "dedented script should pass: {}", stderr);
+}
+
+// ============================================================================
+// Formatting check recipe (python-format-exec.sh + validate-bash-exec.sh)
+// ============================================================================
+
+const FORMAT_EXEC_SCRIPT: &str = "validators/python-format-exec.sh";
+const BASH_EXEC_VALIDATOR: &str = "validators/validate-bash-exec.sh";
+
+/// Helper to run the formatting check recipe.
+///
+/// The container runs `python-format-exec.sh` on the snippet, which reports
+/// whether formatting would change it as bash-exec JSON; `validate-bash-exec.sh`
+/// then checks that JSON on the host. Returns (exit code, validator stderr).
+async fn run_python_format_check(snippet: &str, assertions: Option<&str>) -> (i32, String) {
+    let container = ValidatorContainer::start_raw(PYTHON_IMAGE)
+        .await
+        .expect("python container should start");
+    let exec_script =
+        std::fs::read_to_string(FORMAT_EXEC_SCRIPT).expect("format exec script should exist");
+
+    let result = container
+        .exec_with_stdin(&["sh", "-c", &exec_script], snippet)
+        .await
+        .expect("format check should run");
+    println!("Container stdout: {}", result.stdout);
+    assert_eq!(result.exit_code, 0, "stderr: {}", result.stderr);
+
+    let validation_result = host_validator::run_validator(
+        &RealCommandRunner,
+        BASH_EXEC_VALIDATOR,
+        &result.stdout,
+        assertions,
+        None,
+        Some(&result.stderr),
+    )
+    .expect("host validator should run");
+    println!("Validation stderr: {}", validation_result.stderr);
+
+    (validation_result.exit_code, validation_result.stderr)
+}
+
+/// Test: An already-formatted snippet passes (formatting changes nothing)
+#[tokio::test]
+async fn test_python_format_check_formatted_passes() {
+    let snippet = "def greet(name):\n    return f'Hello, {name}'\nprint(greet('world'))";
+    let (exit_code, stderr) = run_python_format_check(snippet, None).await;
+    assert_eq!(exit_code, 0, "formatted snippet should pass: {stderr}");
+}
+
+/// Test: A badly formatted snippet fails with the formatter's diff
+#[tokio::test]
+async fn test_python_format_check_unformatted_fails() {
+    let snippet = "def greet( name ):\n    return f\"Hello, {name}\"";
+    let (exit_code, stderr) = run_python_format_check(snippet, None).await;
+    assert_ne!(exit_code, 0, "unformatted snippet should fail");
+    assert!(stderr.contains("would reformat"), "stderr: {stderr}");
+    assert!(
+        stderr.contains("+def greet(name):"),
+        "should show the diff: {stderr}"
+    );
+}
+
+/// Test: exit_code and stderr_contains assert that a snippet is *not* formatted
+#[tokio::test]
+async fn test_python_format_check_expected_failure_passes() {
+    let snippet = "x=1";
+    let assertions = "exit_code = 1\nstderr_contains \"would reformat\"";
+    let (exit_code, stderr) = run_python_format_check(snippet, Some(assertions)).await;
+    assert_eq!(exit_code, 0, "asserted failure should pass: {stderr}");
+}
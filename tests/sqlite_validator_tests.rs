@@ -264,6 +264,63 @@ async fn test_sqlite_rows_gt_assertion_fails() {
     );
 }
 
+/// Test: rows <= N assertion passes when row count is at most N
+#[tokio::test]
+async fn test_sqlite_rows_le_assertion_passes() {
+    let setup = "sqlite3 /tmp/test.db 'CREATE TABLE t(x INTEGER); INSERT INTO t VALUES(1), (2);'";
+    let (exit_code, _, _) =
+        run_sqlite_validator("SELECT * FROM t;", Some(setup), Some("rows <= 2"), None).await;
+    assert_eq!(exit_code, 0, "rows <= 2 should pass when 2 rows returned");
+}
+
+/// Test: rows <= N assertion fails when row count exceeds N
+#[tokio::test]
+async fn test_sqlite_rows_le_assertion_fails() {
+    let setup =
+        "sqlite3 /tmp/test.db 'CREATE TABLE t(x INTEGER); INSERT INTO t VALUES(1), (2), (3);'";
+    let (exit_code, _, stderr) =
+        run_sqlite_validator("SELECT * FROM t;", Some(setup), Some("rows <= 2"), None).await;
+    assert_ne!(exit_code, 0, "rows <= 2 should fail when 3 rows returned");
+    assert!(
+        stderr.contains("Assertion failed"),
+        "stderr should mention assertion failure: {}",
+        stderr
+    );
+    assert!(
+        stderr.contains("rows <= 2"),
+        "stderr should show expected bound: {}",
+        stderr
+    );
+}
+
+/// Test: rows < N assertion passes when row count is below N
+#[tokio::test]
+async fn test_sqlite_rows_lt_assertion_passes() {
+    let setup = "sqlite3 /tmp/test.db 'CREATE TABLE t(x INTEGER); INSERT INTO t VALUES(1), (2);'";
+    let (exit_code, _, _) =
+        run_sqlite_validator("SELECT * FROM t;", Some(setup), Some("rows < 3"), None).await;
+    assert_eq!(exit_code, 0, "rows < 3 should pass when 2 rows returned");
+}
+
+/// Test: rows < N assertion fails when row count is N or more
+#[tokio::test]
+async fn test_sqlite_rows_lt_assertion_fails() {
+    let setup = "sqlite3 /tmp/test.db 'CREATE TABLE t(x INTEGER); INSERT INTO t VALUES(1), (2);'";
+    let (exit_code, _, stderr) =
+        run_sqlite_validator("SELECT * FROM t;", Some(setup), Some("rows < 2"), None).await;
+    assert_ne!(exit_code, 0, "rows < 2 should fail when 2 rows returned");
+    assert!(
+        stderr.contains("Assertion failed"),
+        "stderr should mention assertion failure: {}",
+        stderr
+    );
+    assert!(
+        stderr.contains("rows < 2"),
+        "stderr should show expected bound: {}",
+        stderr
+    );
+}
+
 /// Test: contains "string" assertion passes when string is in output
 #[tokio::test]
 async fn test_sqlite_contains_assertion_passes() {
@@ -302,6 +359,123 @@ async fn test_sqlite_contains_assertion_fails() {
     );
 }
 
+const ORDERS_SETUP: &str = r#"sqlite3 /tmp/test.db "CREATE TABLE orders(id INTEGER, status TEXT); INSERT INTO orders VALUES(1, 'new'), (2, 'shipped'), (3, 'new'), (4, 'returned');""#;
+
+/// Test: distinct "col" = N passes when the column has N distinct values
+#[tokio::test]
+async fn test_sqlite_distinct_assertion_passes() {
+    let (exit_code, _, stderr) = run_sqlite_validator(
+        "SELECT id, status FROM orders;",
+        Some(ORDERS_SETUP),
+        Some("distinct \"status\" = 3"),
+        None,
+    )
+    .await;
+    assert_eq!(exit_code, 0, "3 distinct statuses should pass: {}", stderr);
+}
+
+/// Test: distinct "col" = N fails and reports the actual count
+#[tokio::test]
+async fn test_sqlite_distinct_assertion_fails() {
+    let (exit_code, _, stderr) = run_sqlite_validator(
+        "SELECT id, status FROM orders;",
+        Some(ORDERS_SETUP),
+        Some("distinct \"status\" = 5"),
+        None,
+    )
+    .await;
+    assert_ne!(exit_code, 0, "distinct = 5 should fail");
+    assert!(
+        stderr.contains("distinct") && stderr.contains("got 3"),
+        "stderr should show actual distinct count: {}",
+        stderr
+    );
+}
+
+/// Test: distinct supports comparison operators
+#[tokio::test]
+async fn test_sqlite_distinct_assertion_operators() {
+    let (exit_code, _, stderr) = run_sqlite_validator(
+        "SELECT id, status FROM orders;",
+        Some(ORDERS_SETUP),
+        Some("distinct \"status\" >= 2\ndistinct \"status\" < 4\ndistinct \"id\" != 3"),
+        None,
+    )
+    .await;
+    assert_eq!(exit_code, 0, "operators should pass: {}", stderr);
+}
+
+/// Test: sorted_by passes when the query orders by that column
+#[tokio::test]
+async fn test_sqlite_sorted_by_passes_for_ordered_output() {
+    let (exit_code, _, stderr) = run_sqlite_validator(
+        "SELECT id, status FROM orders ORDER BY status, id DESC;",
+        Some(ORDERS_SETUP),
+        Some("sorted_by \"status\" asc"),
+        None,
+    )
+    .await;
+    assert_eq!(exit_code, 0, "ordered output should pass: {}", stderr);
+
+    let (exit_code, _, stderr) = run_sqlite_validator(
+        "SELECT id FROM orders ORDER BY id DESC;",
+        Some(ORDERS_SETUP),
+        Some("sorted_by \"id\" desc"),
+        None,
+    )
+    .await;
+    assert_eq!(exit_code, 0, "descending output should pass: {}", stderr);
+}
+
+/// Test: sorted_by fails on unsorted output and names the first out-of-order pair
+#[tokio::test]
+async fn test_sqlite_sorted_by_fails_for_unsorted_output() {
+    let (exit_code, _, stderr) = run_sqlite_validator(
+        "SELECT id, status FROM orders ORDER BY id;",
+        Some(ORDERS_SETUP),
+        Some("sorted_by \"status\" asc"),
+        None,
+    )
+    .await;
+    assert_ne!(exit_code, 0, "statuses by id are not sorted");
+    assert!(
+        stderr.contains("rows 2 and 3 out of order") && stderr.contains("\"shipped\" then \"new\""),
+        "stderr should name the first out-of-order pair: {}",
+        stderr
+    );
+}
+
+/// Test: contains_count "str" = N passes when the string occurs exactly N times
+#[tokio::test]
+async fn test_sqlite_contains_count_exact_passes() {
+    let (exit_code, _, stderr) = run_sqlite_validator(
+        "SELECT status FROM orders ORDER BY id;",
+        Some(ORDERS_SETUP),
+        Some("contains_count \"new\" = 2\ncontains_count \"shipped\" >= 1"),
+        None,
+    )
+    .await;
+    assert_eq!(exit_code, 0, "'new' appears twice: {}", stderr);
+}
+
+/// Test: contains_count fails with expected and actual counts when the count differs
+#[tokio::test]
+async fn test_sqlite_contains_count_exact_fails() {
+    let (exit_code, _, stderr) = run_sqlite_validator(
+        "SELECT status FROM orders ORDER BY id;",
+        Some(ORDERS_SETUP),
+        Some("contains_count \"new\" = 3"),
+        None,
+    )
+    .await;
+    assert_ne!(exit_code, 0, "'new' appears twice, not three times");
+    assert!(
+        stderr.contains("contains_count \"new\" = 3: got 2 occurrences"),
+        "stderr should show expected and actual counts: {}",
+        stderr
+    );
+}
+
 /// Test: `VALIDATOR_EXPECT` passes when output matches exactly
 #[tokio::test]
 async fn test_sqlite_expected_output_passes() {
@@ -336,6 +510,49 @@ async fn test_sqlite_expected_output_fails() {
     );
 }
 
+/// Test: a passing ASSERT does not mask a failing EXPECT
+#[tokio::test]
+async fn test_sqlite_assert_passes_but_expect_fails() {
+    let setup = "sqlite3 /tmp/test.db 'CREATE TABLE t(id INTEGER); INSERT INTO t VALUES(1), (2);'";
+    let (exit_code, _, stderr) = run_sqlite_validator(
+        "SELECT id FROM t ORDER BY id;",
+        Some(setup),
+        Some("rows = 2"),
+        Some("[{\"id\":1},{\"id\":3}]"),
+    )
+    .await;
+    assert_ne!(exit_code, 0, "block should fail on the EXPECT mismatch");
+    assert!(
+        stderr.contains("Output mismatch"),
+        "stderr should mention the EXPECT mismatch: {}",
+        stderr
+    );
+    assert!(
+        !stderr.contains("Assertion failed"),
+        "the passing assertion should not be reported: {}",
+        stderr
+    );
+}
+
+/// Test: when ASSERT and EXPECT both fail, both failures are reported
+#[tokio::test]
+async fn test_sqlite_assert_and_expect_failures_both_reported() {
+    let setup = "sqlite3 /tmp/test.db 'CREATE TABLE t(id INTEGER); INSERT INTO t VALUES(1), (2);'";
+    let (exit_code, _, stderr) = run_sqlite_validator(
+        "SELECT id FROM t ORDER BY id;",
+        Some(setup),
+        Some("rows = 5"),
+        Some("[{\"id\":99}]"),
+    )
+    .await;
+    assert_ne!(exit_code, 0, "block should fail");
+    assert!(
+        stderr.contains("rows = 5") && stderr.contains("Output mismatch"),
+        "stderr should list both failures: {}",
+        stderr
+    );
+}
+
 /// Test: Multi-line SETUP with heredoc syntax works
 #[tokio::test]
 async fn test_sqlite_multiline_setup_heredoc() {
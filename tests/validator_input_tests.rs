@@ -175,3 +175,58 @@ fn test_multiple_assertions_first_fails() {
         "stderr should show first assertion failure: {stderr}"
     );
 }
+
+// =============================================================================
+// Negation prefix tests (5 tests)
+// =============================================================================
+
+#[test]
+fn test_negated_contains_passes_when_absent() {
+    let (exit_code, _stdout, stderr) =
+        run_validator_with_input(r#"[{"name": "alice"}]"#, Some(r#"!contains "bob""#));
+
+    assert_eq!(exit_code, 0, "!contains should pass when absent: {stderr}");
+}
+
+#[test]
+fn test_negated_contains_fails_when_present() {
+    let (exit_code, _stdout, stderr) =
+        run_validator_with_input(r#"[{"name": "alice"}]"#, Some(r#"!contains "alice""#));
+
+    assert_eq!(exit_code, 1, "!contains should fail when present");
+    assert!(
+        stderr.contains("!contains") && stderr.contains("negated"),
+        "stderr should indicate the negation: {stderr}"
+    );
+}
+
+#[test]
+fn test_negated_rows_passes_when_count_differs() {
+    let (exit_code, _stdout, stderr) =
+        run_validator_with_input(r#"[{"id": 1}, {"id": 2}]"#, Some("!rows = 0"));
+
+    assert_eq!(exit_code, 0, "!rows = 0 should pass with 2 rows: {stderr}");
+}
+
+#[test]
+fn test_negated_rows_fails_when_count_matches() {
+    let (exit_code, _stdout, stderr) = run_validator_with_input("[]", Some("!rows = 0"));
+
+    assert_eq!(exit_code, 1, "!rows = 0 should fail with 0 rows");
+    assert!(
+        stderr.contains("!rows = 0") && stderr.contains("negated"),
+        "stderr should indicate the negation: {stderr}"
+    );
+}
+
+#[test]
+fn test_negated_malformed_assertion_still_fails() {
+    // Negation inverts the outcome, not the error: a malformed assertion never passes
+    let (exit_code, _stdout, stderr) = run_validator_with_input("[]", Some("!rows = abc"));
+
+    assert_eq!(exit_code, 1, "malformed negated assertion should fail");
+    assert!(
+        stderr.contains("invalid integer"),
+        "stderr should report the malformed assertion: {stderr}"
+    );
+}
@@ -0,0 +1,92 @@
+//! Tests for watch mode
+//!
+//! Tests are allowed to panic for assertions and test failure.
+#![allow(
+    clippy::panic,
+    clippy::expect_used,
+    clippy::unwrap_used,
+    clippy::str_to_string
+)]
+
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use mdbook_validator::watch::{self, WatchSession};
+
+const PASSING: &str = r"# Chapter
+
+```sql validator=sqlite
+SELECT 1;
+<!--ASSERT
+rows = 1
+-->
+```
+";
+
+const FAILING: &str = r"# Chapter
+
+```sql validator=sqlite
+SELECT 1;
+<!--ASSERT
+rows = 5
+-->
+```
+";
+
+/// Writes a book using the repo's sqlite validator script
+fn create_book(chapter: &str) -> tempfile::TempDir {
+    let dir = tempfile::TempDir::new().unwrap();
+    let script = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("validators/validate-sqlite.sh");
+    std::fs::write(
+        dir.path().join("book.toml"),
+        format!(
+            "[book]\ntitle = \"Watch\"\n\n\
+             [preprocessor.validator]\ncommand = \"mdbook-validator\"\n\n\
+             [preprocessor.validator.validators.sqlite]\n\
+             container = \"keinos/sqlite3:3.47.2\"\n\
+             script = \"{}\"\n\
+             exec_command = \"sqlite3 -json /tmp/test.db\"\n",
+            script.display()
+        ),
+    )
+    .unwrap();
+    std::fs::create_dir(dir.path().join("src")).unwrap();
+    std::fs::write(dir.path().join("src/chapter.md"), chapter).unwrap();
+    dir
+}
+
+#[test]
+fn watch_session_requires_book_toml() {
+    let dir = tempfile::TempDir::new().unwrap();
+    assert!(WatchSession::load(dir.path()).is_err());
+}
+
+#[test]
+#[ignore = "requires Docker and a filesystem watcher"]
+fn watch_revalidates_changed_chapter() {
+    let book = create_book(PASSING);
+    let book_root = book.path().to_owned();
+    let (tx, rx) = mpsc::channel();
+
+    // watch() never returns, so run it on a detached thread
+    std::thread::spawn(move || {
+        let mut session = WatchSession::load(&book_root).unwrap();
+        let _ = watch::watch(&mut session, |path, result| {
+            let _ = tx.send((path.to_owned(), result.is_ok()));
+        });
+    });
+
+    let chapter = book.path().join("src/chapter.md");
+    let (path, ok) = rx.recv_timeout(Duration::from_secs(120)).unwrap();
+    assert_eq!(path, chapter);
+    assert!(ok, "initial validation should pass");
+
+    // Give the watcher a moment to register before changing the file
+    std::thread::sleep(Duration::from_millis(500));
+    std::fs::write(&chapter, FAILING).unwrap();
+
+    let (path, ok) = rx.recv_timeout(Duration::from_secs(60)).unwrap();
+    assert_eq!(path, chapter);
+    assert!(!ok, "re-validation should see the failing assertion");
+}
@@ -0,0 +1,45 @@
+#!/bin/sh
+#
+# python-format-exec.sh - Container execution script for a formatting check.
+#
+# Reads Python source from stdin and checks that formatting it changes
+# nothing: the source must already equal ast.unparse(ast.parse(source)).
+# Outputs the same JSON as bash-exec.sh, so validate-bash-exec.sh checks it:
+# exit_code 1 with a unified diff on stderr when the source would be reformatted.
+#
+# Output format: {"exit_code": N, "stdout": "...", "stderr": "..."}
+#
+# Any formatter with a check mode works the same way; replace the python
+# below with e.g. `black --check --diff` or `rustfmt --check`.
+#
+
+# Read source from stdin
+cat > /tmp/snippet.py
+
+python3 - /tmp/snippet.py <<'PY'
+import ast
+import difflib
+import json
+import sys
+
+with open(sys.argv[1]) as f:
+    source = f.read().rstrip("\n") + "\n"
+
+try:
+    formatted = ast.unparse(ast.parse(source)) + "\n"
+except SyntaxError as e:
+    result = {"exit_code": 1, "stdout": "", "stderr": f"SyntaxError: {e}"}
+else:
+    if formatted == source:
+        result = {"exit_code": 0, "stdout": "", "stderr": ""}
+    else:
+        diff = difflib.unified_diff(
+            source.splitlines(keepends=True),
+            formatted.splitlines(keepends=True),
+            "original",
+            "formatted",
+        )
+        result = {"exit_code": 1, "stdout": "", "stderr": "would reformat\n" + "".join(diff)}
+
+print(json.dumps(result))
+PY
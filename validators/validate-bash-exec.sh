@@ -11,9 +11,11 @@
 # - VALIDATOR_ASSERTIONS: Assertion rules, newline-separated (optional)
 #   - exit_code = N: Script must exit with code N
 #   - stdout_contains "string": Stdout must contain string
+#   - stderr_contains "string": Stderr must contain string
 #   - file_exists /path: File must exist (requires files in JSON)
 #   - dir_exists /path: Directory must exist (requires files in JSON)
 #   - file_contains /path "string": File must contain string (requires files in JSON)
+#   - stderr_empty: Script stderr must be empty or whitespace-only
 #
 # Exits 0 on success, 1 on failure with details to stderr.
 #
@@ -49,6 +51,98 @@ STDERR=$(echo "$JSON_INPUT" | jq -r '.stderr')
 # Track if we have an exit_code assertion
 HAS_EXIT_CODE_ASSERTION=false
 
+# Evaluate a single assertion, printing the failure reason to stderr.
+# Returns 0 if it holds, 1 if it does not, and 2 if it cannot be evaluated
+# (malformed or unknown syntax), which fails even when negated.
+check_assertion() {
+    local assertion=$1
+
+    case "$assertion" in
+        exit_code\ =\ *)
+            expected=${assertion#exit_code = }
+            if ! is_integer "$expected"; then
+                echo "Assertion failed: exit_code = $expected: invalid integer" >&2
+                return 2
+            fi
+            if [ "$EXIT_CODE" -ne "$expected" ]; then
+                echo "Assertion failed: exit_code = $expected: got $EXIT_CODE" >&2
+                if [ -n "$STDERR" ]; then
+                    echo "stderr: $STDERR" >&2
+                fi
+                return 1
+            fi
+            ;;
+        stdout_contains\ *)
+            needle=${assertion#stdout_contains }
+            # Remove surrounding quotes if present
+            needle=${needle#\"}
+            needle=${needle%\"}
+            if ! echo "$STDOUT" | grep -qF "$needle"; then
+                echo "Assertion failed: stdout_contains \"$needle\": not found" >&2
+                echo "stdout: $STDOUT" >&2
+                return 1
+            fi
+            ;;
+        stderr_contains\ *)
+            needle=${assertion#stderr_contains }
+            # Remove surrounding quotes if present
+            needle=${needle#\"}
+            needle=${needle%\"}
+            if ! echo "$STDERR" | grep -qF "$needle"; then
+                echo "Assertion failed: stderr_contains \"$needle\": not found" >&2
+                echo "stderr: $STDERR" >&2
+                return 1
+            fi
+            ;;
+        file_exists\ *)
+            filepath=${assertion#file_exists }
+            filepath=$(echo "$filepath" | xargs)
+            # Check files JSON object for this path
+            exists=$(echo "$JSON_INPUT" | jq -r --arg p "$filepath" '.files[$p].exists // false')
+            if [ "$exists" != "true" ]; then
+                echo "Assertion failed: file_exists $filepath: file not found" >&2
+                return 1
+            fi
+            ;;
+        dir_exists\ *)
+            dirpath=${assertion#dir_exists }
+            dirpath=$(echo "$dirpath" | xargs)
+            # Check files JSON object for this path
+            is_dir=$(echo "$JSON_INPUT" | jq -r --arg p "$dirpath" '.files[$p].is_dir // false')
+            if [ "$is_dir" != "true" ]; then
+                echo "Assertion failed: dir_exists $dirpath: directory not found" >&2
+                return 1
+            fi
+            ;;
+        file_contains\ *)
+            # Format: file_contains /path "string"
+            rest=${assertion#file_contains }
+            # Extract path (everything before the first quote)
+            filepath=$(echo "$rest" | sed 's/ *".*$//')
+            # Extract needle (content between quotes)
+            needle=$(echo "$rest" | sed 's/^[^"]*"//' | sed 's/"$//')
+            # Get file content from JSON
+            content=$(echo "$JSON_INPUT" | jq -r --arg p "$filepath" '.files[$p].content // ""')
+            if [ -z "$content" ] || ! echo "$content" | grep -qF "$needle"; then
+                echo "Assertion failed: file_contains $filepath \"$needle\": not found" >&2
+                return 1
+            fi
+            ;;
+        stderr_empty)
+            if [ -n "$(printf '%s' "$STDERR" | tr -d '[:space:]')" ]; then
+                echo "Assertion failed: stderr_empty: stderr is not empty" >&2
+                echo "stderr: $STDERR" >&2
+                return 1
+            fi
+            ;;
+        *)
+            echo "Assertion failed: Unknown assertion syntax: $assertion" >&2
+            echo "Supported: exit_code = N, stdout_contains \"str\", stderr_contains \"str\", file_exists /path, dir_exists /path, file_contains /path \"str\", stderr_empty" >&2
+            return 2
+            ;;
+    esac
+}
+
 # Evaluate assertions if provided
 if [ -n "${VALIDATOR_ASSERTIONS:-}" ]; then
     while IFS= read -r assertion || [ -n "$assertion" ]; do
@@ -57,73 +151,33 @@ if [ -n "${VALIDATOR_ASSERTIONS:-}" ]; then
         assertion="${assertion%"${assertion##*[![:space:]]}"}"  # trim trailing
         [ -z "$assertion" ] && continue
 
-        case "$assertion" in
-            exit_code\ =\ *)
-                HAS_EXIT_CODE_ASSERTION=true
-                expected=${assertion#exit_code = }
-                if ! is_integer "$expected"; then
-                    echo "Assertion failed: exit_code = $expected: invalid integer" >&2
-                    exit 1
-                fi
-                if [ "$EXIT_CODE" -ne "$expected" ]; then
-                    echo "Assertion failed: exit_code = $expected: got $EXIT_CODE" >&2
-                    if [ -n "$STDERR" ]; then
-                        echo "stderr: $STDERR" >&2
-                    fi
-                    exit 1
-                fi
-                ;;
-            stdout_contains\ *)
-                needle=${assertion#stdout_contains }
-                # Remove surrounding quotes if present
-                needle=${needle#\"}
-                needle=${needle%\"}
-                if ! echo "$STDOUT" | grep -qF "$needle"; then
-                    echo "Assertion failed: stdout_contains \"$needle\": not found" >&2
-                    echo "stdout: $STDOUT" >&2
-                    exit 1
-                fi
-                ;;
-            file_exists\ *)
-                filepath=${assertion#file_exists }
-                filepath=$(echo "$filepath" | xargs)
-                # Check files JSON object for this path
-                exists=$(echo "$JSON_INPUT" | jq -r --arg p "$filepath" '.files[$p].exists // false')
-                if [ "$exists" != "true" ]; then
-                    echo "Assertion failed: file_exists $filepath: file not found" >&2
-                    exit 1
-                fi
-                ;;
-            dir_exists\ *)
-                dirpath=${assertion#dir_exists }
-                dirpath=$(echo "$dirpath" | xargs)
-                # Check files JSON object for this path
-                is_dir=$(echo "$JSON_INPUT" | jq -r --arg p "$dirpath" '.files[$p].is_dir // false')
-                if [ "$is_dir" != "true" ]; then
-                    echo "Assertion failed: dir_exists $dirpath: directory not found" >&2
+        # A leading "!" negates any assertion: !contains "x", !rows = 0
+        negate=false
+        if [[ "$assertion" == '!'* ]]; then
+            negate=true
+            read -r assertion <<< "${assertion#!}"
+        fi
+        if [[ "$assertion" == exit_code\ =\ * ]]; then
+            HAS_EXIT_CODE_ASSERTION=true
+        fi
+        status=0
+        reason=$(check_assertion "$assertion" 2>&1) || status=$?
+        if [ "$negate" = true ]; then
+            case "$status" in
+                0)
+                    echo "Assertion failed: !$assertion: assertion holds but is negated" >&2
                     exit 1
-                fi
-                ;;
-            file_contains\ *)
-                # Format: file_contains /path "string"
-                rest=${assertion#file_contains }
-                # Extract path (everything before the first quote)
-                filepath=$(echo "$rest" | sed 's/ *".*$//')
-                # Extract needle (content between quotes)
-                needle=$(echo "$rest" | sed 's/^[^"]*"//' | sed 's/"$//')
-                # Get file content from JSON
-                content=$(echo "$JSON_INPUT" | jq -r --arg p "$filepath" '.files[$p].content // ""')
-                if [ -z "$content" ] || ! echo "$content" | grep -qF "$needle"; then
-                    echo "Assertion failed: file_contains $filepath \"$needle\": not found" >&2
+                    ;;
+                1) ;;
+                *)
+                    echo "$reason" >&2
                     exit 1
-                fi
-                ;;
-            *)
-                echo "Assertion failed: Unknown assertion syntax: $assertion" >&2
-                echo "Supported: exit_code = N, stdout_contains \"str\", file_exists /path, dir_exists /path, file_contains /path \"str\"" >&2
-                exit 1
-                ;;
-        esac
+                    ;;
+            esac
+        elif [ "$status" -ne 0 ]; then
+            echo "$reason" >&2
+            exit 1
+        fi
     done <<< "$VALIDATOR_ASSERTIONS"
 fi
 
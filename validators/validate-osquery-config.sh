@@ -47,6 +47,47 @@ if [ -z "${VALIDATOR_ASSERTIONS:-}" ] && [ -z "${VALIDATOR_EXPECT:-}" ]; then
     exit 0
 fi
 
+# Evaluate a single assertion, printing the failure reason to stderr.
+# Returns 0 if it holds, 1 if it does not, and 2 if it cannot be evaluated
+# (malformed or unknown syntax), which fails even when negated.
+check_assertion() {
+    local assertion=$1
+
+    case "$assertion" in
+        contains\ *)
+            needle=${assertion#contains }
+            # Remove surrounding quotes if present
+            needle=${needle#\"}
+            needle=${needle%\"}
+            # Check if the string appears anywhere in the JSON (keys or values)
+            if ! echo "$JSON_INPUT" | jq -e --arg s "$needle" 'any(.. | strings; contains($s))' >/dev/null 2>&1; then
+                # Also check if it appears as a key name
+                if ! echo "$JSON_INPUT" | jq -e --arg s "$needle" '[.. | objects | keys[]] | any(contains($s))' >/dev/null 2>&1; then
+                    echo "Assertion failed: contains \"$needle\": not found in config" >&2
+                    return 1
+                fi
+            fi
+            ;;
+        stderr_empty)
+            if [ -n "$(printf '%s' "${VALIDATOR_CONTAINER_STDERR:-}" | tr -d '[:space:]')" ]; then
+                echo "Assertion failed: stderr_empty: container stderr is not empty" >&2
+                echo "stderr: $VALIDATOR_CONTAINER_STDERR" >&2
+                return 1
+            fi
+            ;;
+        *)
+            echo "Assertion failed: Unknown assertion syntax: $assertion" >&2
+            echo "Supported assertions for config validation: contains \"string\", stderr_empty" >&2
+            return 2
+            ;;
+    esac
+}
+
+# ASSERT and EXPECT are evaluated independently: both are checked, each
+# failure is reported, and the block fails if either did. Assertions stop at
+# the first one that fails.
+failed=0
+
 # Evaluate assertions if provided
 if [ -n "${VALIDATOR_ASSERTIONS:-}" ]; then
     while IFS= read -r assertion || [ -n "$assertion" ]; do
@@ -54,27 +95,33 @@ if [ -n "${VALIDATOR_ASSERTIONS:-}" ]; then
         assertion=$(echo "$assertion" | xargs 2>/dev/null || echo "$assertion")
         [ -z "$assertion" ] && continue
 
-        case "$assertion" in
-            contains\ *)
-                needle=${assertion#contains }
-                # Remove surrounding quotes if present
-                needle=${needle#\"}
-                needle=${needle%\"}
-                # Check if the string appears anywhere in the JSON (keys or values)
-                if ! echo "$JSON_INPUT" | jq -e --arg s "$needle" 'any(.. | strings; contains($s))' >/dev/null 2>&1; then
-                    # Also check if it appears as a key name
-                    if ! echo "$JSON_INPUT" | jq -e --arg s "$needle" '[.. | objects | keys[]] | any(contains($s))' >/dev/null 2>&1; then
-                        echo "Assertion failed: contains \"$needle\": not found in config" >&2
-                        exit 1
-                    fi
-                fi
-                ;;
-            *)
-                echo "Assertion failed: Unknown assertion syntax: $assertion" >&2
-                echo "Supported assertions for config validation: contains \"string\"" >&2
-                exit 1
-                ;;
-        esac
+        # A leading "!" negates any assertion: !contains "x", !rows = 0
+        negate=false
+        if [[ "$assertion" == '!'* ]]; then
+            negate=true
+            read -r assertion <<< "${assertion#!}"
+        fi
+        status=0
+        reason=$(check_assertion "$assertion" 2>&1) || status=$?
+        if [ "$negate" = true ]; then
+            case "$status" in
+                0)
+                    echo "Assertion failed: !$assertion: assertion holds but is negated" >&2
+                    failed=1
+                    break
+                    ;;
+                1) ;;
+                *)
+                    echo "$reason" >&2
+                    failed=1
+                    break
+                    ;;
+            esac
+        elif [ "$status" -ne 0 ]; then
+            echo "$reason" >&2
+            failed=1
+            break
+        fi
     done <<< "$VALIDATOR_ASSERTIONS"
 fi
 
@@ -88,8 +135,8 @@ if [ -n "${VALIDATOR_EXPECT:-}" ]; then
         echo "Output mismatch:" >&2
         echo "  Expected: $VALIDATOR_EXPECT" >&2
         echo "  Actual:   $JSON_INPUT" >&2
-        exit 1
+        failed=1
     fi
 fi
 
-exit 0
+exit "$failed"
@@ -37,11 +37,11 @@ if [ -z "${VALIDATOR_ASSERTIONS:-}" ]; then
     exit 0
 fi
 
-# Evaluate assertions if provided
-while IFS= read -r assertion || [ -n "$assertion" ]; do
-    # Skip empty lines and trim whitespace
-    assertion=$(echo "$assertion" | xargs 2>/dev/null || echo "$assertion")
-    [ -z "$assertion" ] && continue
+# Evaluate a single assertion, printing the failure reason to stderr.
+# Returns 0 if it holds, 1 if it does not, and 2 if it cannot be evaluated
+# (malformed or unknown syntax), which fails even when negated.
+check_assertion() {
+    local assertion=$1
 
     case "$assertion" in
         contains\ *)
@@ -54,16 +54,55 @@ while IFS= read -r assertion || [ -n "$assertion" ]; do
                 # Also check stderr
                 if ! echo "${VALIDATOR_CONTAINER_STDERR:-}" | grep -qF "$needle"; then
                     echo "Assertion failed: contains \"$needle\": not found in output" >&2
-                    exit 1
+                    return 1
                 fi
             fi
             ;;
+        stderr_empty)
+            if [ -n "$(printf '%s' "${VALIDATOR_CONTAINER_STDERR:-}" | tr -d '[:space:]')" ]; then
+                echo "Assertion failed: stderr_empty: container stderr is not empty" >&2
+                echo "stderr: $VALIDATOR_CONTAINER_STDERR" >&2
+                return 1
+            fi
+            ;;
         *)
             echo "Assertion failed: Unknown assertion syntax: $assertion" >&2
-            echo "Supported assertions for python: contains \"string\"" >&2
-            exit 1
+            echo "Supported assertions for python: contains \"string\", stderr_empty" >&2
+            return 2
             ;;
     esac
+}
+
+# Evaluate assertions if provided
+while IFS= read -r assertion || [ -n "$assertion" ]; do
+    # Skip empty lines and trim whitespace
+    assertion=$(echo "$assertion" | xargs 2>/dev/null || echo "$assertion")
+    [ -z "$assertion" ] && continue
+
+    # A leading "!" negates any assertion: !contains "x", !rows = 0
+    negate=false
+    if [[ "$assertion" == '!'* ]]; then
+        negate=true
+        read -r assertion <<< "${assertion#!}"
+    fi
+    status=0
+    reason=$(check_assertion "$assertion" 2>&1) || status=$?
+    if [ "$negate" = true ]; then
+        case "$status" in
+            0)
+                echo "Assertion failed: !$assertion: assertion holds but is negated" >&2
+                exit 1
+                ;;
+            1) ;;
+            *)
+                echo "$reason" >&2
+                exit 1
+                ;;
+        esac
+    elif [ "$status" -ne 0 ]; then
+        echo "$reason" >&2
+        exit 1
+    fi
 done <<< "$VALIDATOR_ASSERTIONS"
 
 exit 0
@@ -9,6 +9,7 @@
 # Environment:
 # - VALIDATOR_ASSERTIONS: Assertion rules, newline-separated (optional)
 # - VALIDATOR_EXPECT: Expected JSON output for exact match (optional)
+# - VALIDATOR_CONTAINER_STDERR: Container stderr, checked by stderr_empty (optional)
 #
 # Exits 0 on success, 1 on failure with details to stderr.
 #
@@ -20,6 +21,19 @@ is_integer() {
     [[ "$1" =~ ^-?[0-9]+$ ]]
 }
 
+# Compare two integers with an operator: =, !=, >=, >, <=, <
+compare_int() {
+    case "$2" in
+        "=") [ "$1" -eq "$3" ] ;;
+        "!=") [ "$1" -ne "$3" ] ;;
+        ">=") [ "$1" -ge "$3" ] ;;
+        ">") [ "$1" -gt "$3" ] ;;
+        "<=") [ "$1" -le "$3" ] ;;
+        "<") [ "$1" -lt "$3" ] ;;
+        *) return 2 ;;
+    esac
+}
+
 # Check jq is available
 command -v jq >/dev/null 2>&1 || {
     echo "ERROR: jq is required but not installed" >&2
@@ -38,6 +52,196 @@ if [ -z "${VALIDATOR_ASSERTIONS:-}" ] && [ -z "${VALIDATOR_EXPECT:-}" ]; then
     exit 0
 fi
 
+# Evaluate a single assertion, printing the failure reason to stderr.
+# Returns 0 if it holds, 1 if it does not, and 2 if it cannot be evaluated
+# (malformed or unknown syntax), which fails even when negated.
+check_assertion() {
+    local assertion=$1
+
+    case "$assertion" in
+        rows\ =\ *)
+            spec=${assertion#rows = }
+            # Optional tolerance for varying counts: rows = N ± M, rows = N tolerance M
+            expected=$spec
+            tolerance=0
+            case "$spec" in
+                *" ± "*)
+                    expected=${spec%% ± *}
+                    tolerance=${spec#* ± }
+                    ;;
+                *" tolerance "*)
+                    expected=${spec%% tolerance *}
+                    tolerance=${spec#* tolerance }
+                    ;;
+            esac
+            if ! is_integer "$expected" || ! is_integer "$tolerance" || [ "$tolerance" -lt 0 ]; then
+                echo "Assertion failed: rows = $spec: invalid integer" >&2
+                return 2
+            fi
+            actual=$(echo "$JSON_INPUT" | jq 'length')
+            if [ "$actual" -lt $((expected - tolerance)) ] || [ "$actual" -gt $((expected + tolerance)) ]; then
+                echo "Assertion failed: rows = $spec: got $actual" >&2
+                return 1
+            fi
+            ;;
+        rows\ \>=\ *)
+            expected=${assertion#rows >= }
+            if ! is_integer "$expected"; then
+                echo "Assertion failed: rows >= $expected: invalid integer" >&2
+                return 2
+            fi
+            actual=$(echo "$JSON_INPUT" | jq 'length')
+            if [ "$actual" -lt "$expected" ]; then
+                echo "Assertion failed: rows >= $expected: got $actual" >&2
+                return 1
+            fi
+            ;;
+        rows\ \>\ *)
+            expected=${assertion#rows > }
+            if ! is_integer "$expected"; then
+                echo "Assertion failed: rows > $expected: invalid integer" >&2
+                return 2
+            fi
+            actual=$(echo "$JSON_INPUT" | jq 'length')
+            if [ "$actual" -le "$expected" ]; then
+                echo "Assertion failed: rows > $expected: got $actual" >&2
+                return 1
+            fi
+            ;;
+        rows\ \<=\ *)
+            expected=${assertion#rows <= }
+            if ! is_integer "$expected"; then
+                echo "Assertion failed: rows <= $expected: invalid integer" >&2
+                return 2
+            fi
+            actual=$(echo "$JSON_INPUT" | jq 'length')
+            if [ "$actual" -gt "$expected" ]; then
+                echo "Assertion failed: rows <= $expected: got $actual" >&2
+                return 1
+            fi
+            ;;
+        rows\ \<\ *)
+            expected=${assertion#rows < }
+            if ! is_integer "$expected"; then
+                echo "Assertion failed: rows < $expected: invalid integer" >&2
+                return 2
+            fi
+            actual=$(echo "$JSON_INPUT" | jq 'length')
+            if [ "$actual" -ge "$expected" ]; then
+                echo "Assertion failed: rows < $expected: got $actual" >&2
+                return 1
+            fi
+            ;;
+        columns\ =\ *)
+            expected=${assertion#columns = }
+            if ! is_integer "$expected"; then
+                echo "Assertion failed: columns = $expected: invalid integer" >&2
+                return 2
+            fi
+            # Handle empty array case - columns = 0 for empty results
+            actual=$(echo "$JSON_INPUT" | jq 'if length == 0 then 0 else (.[0] | keys | length) end')
+            if [ "$actual" -ne "$expected" ]; then
+                echo "Assertion failed: columns = $expected: got $actual" >&2
+                return 1
+            fi
+            ;;
+        distinct\ *)
+            # Format: distinct "col" <op> N (quotes around col are optional)
+            rest=${assertion#distinct }
+            if ! [[ "$rest" =~ ^\"?([^\"]+)\"?\ (=|!=|\>=|\>|\<=|\<)\ (.*)$ ]]; then
+                echo "Assertion failed: distinct $rest: expected distinct \"col\" <op> N" >&2
+                return 2
+            fi
+            column=${BASH_REMATCH[1]}
+            op=${BASH_REMATCH[2]}
+            expected=${BASH_REMATCH[3]}
+            if ! is_integer "$expected"; then
+                echo "Assertion failed: distinct \"$column\" $op $expected: invalid integer" >&2
+                return 2
+            fi
+            actual=$(echo "$JSON_INPUT" | jq --arg c "$column" '[.[] | select(has($c)) | .[$c]] | unique | length')
+            if ! compare_int "$actual" "$op" "$expected"; then
+                echo "Assertion failed: distinct \"$column\" $op $expected: got $actual" >&2
+                return 1
+            fi
+            ;;
+        sorted_by\ *)
+            # Format: sorted_by "col" asc|desc (quotes around col are optional)
+            rest=${assertion#sorted_by }
+            if ! [[ "$rest" =~ ^\"?([^\"]+)\"?\ (asc|desc)$ ]]; then
+                echo "Assertion failed: sorted_by $rest: expected sorted_by \"col\" asc|desc" >&2
+                return 2
+            fi
+            column=${BASH_REMATCH[1]}
+            direction=${BASH_REMATCH[2]}
+            if ! echo "$JSON_INPUT" | jq -e --arg c "$column" 'all(.[]; has($c))' >/dev/null 2>&1; then
+                echo "Assertion failed: sorted_by \"$column\" $direction: column missing from some rows" >&2
+                return 1
+            fi
+            # First adjacent pair out of order, as "<row of next>\t<prev value>\t<next value>"
+            pair=$(echo "$JSON_INPUT" | jq -r --arg c "$column" --arg d "$direction" '
+                [.[] | .[$c]] as $v
+                | first(range(1; $v | length)
+                    | select(if $d == "asc" then $v[. - 1] > $v[.] else $v[. - 1] < $v[.] end)
+                    | "\(. + 1)\t\($v[. - 1] | tojson)\t\($v[.] | tojson)")')
+            if [ -n "$pair" ]; then
+                IFS=$'\t' read -r row prev next <<< "$pair"
+                echo "Assertion failed: sorted_by \"$column\" $direction: rows $((row - 1)) and $row out of order ($prev then $next)" >&2
+                return 1
+            fi
+            ;;
+        contains_count\ *)
+            # Format: contains_count "str" <op> N, counting occurrences in string values
+            rest=${assertion#contains_count }
+            if ! [[ "$rest" =~ ^(.+)\ (=|!=|\>=|\>|\<=|\<)\ ([^\ ]+)$ ]]; then
+                echo "Assertion failed: contains_count $rest: expected contains_count \"str\" <op> N" >&2
+                return 2
+            fi
+            needle=${BASH_REMATCH[1]}
+            op=${BASH_REMATCH[2]}
+            expected=${BASH_REMATCH[3]}
+            # Remove surrounding quotes if present
+            needle=${needle#\"}
+            needle=${needle%\"}
+            if ! is_integer "$expected"; then
+                echo "Assertion failed: contains_count \"$needle\" $op $expected: invalid integer" >&2
+                return 2
+            fi
+            actual=$(echo "$JSON_INPUT" | jq --arg s "$needle" '[.. | strings | (split($s) | length) - 1] | add // 0')
+            if ! compare_int "$actual" "$op" "$expected"; then
+                echo "Assertion failed: contains_count \"$needle\" $op $expected: got $actual occurrences" >&2
+                return 1
+            fi
+            ;;
+        contains\ *)
+            needle=${assertion#contains }
+            # Remove surrounding quotes if present
+            needle=${needle#\"}
+            needle=${needle%\"}
+            if ! echo "$JSON_INPUT" | jq -e --arg s "$needle" 'any(.. | strings; contains($s))' >/dev/null 2>&1; then
+                echo "Assertion failed: contains \"$needle\": not found in output" >&2
+                return 1
+            fi
+            ;;
+        stderr_empty)
+            if [ -n "$(printf '%s' "${VALIDATOR_CONTAINER_STDERR:-}" | tr -d '[:space:]')" ]; then
+                echo "Assertion failed: stderr_empty: container stderr is not empty" >&2
+                echo "stderr: $VALIDATOR_CONTAINER_STDERR" >&2
+                return 1
+            fi
+            ;;
+        *)
+            echo "Assertion failed: Unknown assertion syntax: $assertion" >&2
+            return 2
+            ;;
+    esac
+}
+
+# ASSERT and EXPECT are evaluated independently: both are checked, each
+# failure is reported, and the block fails if either did. Assertions stop at
+# the first one that fails.
+failed=0
+
 # Evaluate assertions if provided
 if [ -n "${VALIDATOR_ASSERTIONS:-}" ]; then
     while IFS= read -r assertion || [ -n "$assertion" ]; do
@@ -45,71 +249,33 @@ if [ -n "${VALIDATOR_ASSERTIONS:-}" ]; then
         assertion=$(echo "$assertion" | xargs 2>/dev/null || echo "$assertion")
         [ -z "$assertion" ] && continue
 
-        case "$assertion" in
-            rows\ =\ *)
-                expected=${assertion#rows = }
-                if ! is_integer "$expected"; then
-                    echo "Assertion failed: rows = $expected: invalid integer" >&2
-                    exit 1
-                fi
-                actual=$(echo "$JSON_INPUT" | jq 'length')
-                if [ "$actual" -ne "$expected" ]; then
-                    echo "Assertion failed: rows = $expected: got $actual" >&2
-                    exit 1
-                fi
-                ;;
-            rows\ \>=\ *)
-                expected=${assertion#rows >= }
-                if ! is_integer "$expected"; then
-                    echo "Assertion failed: rows >= $expected: invalid integer" >&2
-                    exit 1
-                fi
-                actual=$(echo "$JSON_INPUT" | jq 'length')
-                if [ "$actual" -lt "$expected" ]; then
-                    echo "Assertion failed: rows >= $expected: got $actual" >&2
-                    exit 1
-                fi
-                ;;
-            rows\ \>\ *)
-                expected=${assertion#rows > }
-                if ! is_integer "$expected"; then
-                    echo "Assertion failed: rows > $expected: invalid integer" >&2
-                    exit 1
-                fi
-                actual=$(echo "$JSON_INPUT" | jq 'length')
-                if [ "$actual" -le "$expected" ]; then
-                    echo "Assertion failed: rows > $expected: got $actual" >&2
-                    exit 1
-                fi
-                ;;
-            columns\ =\ *)
-                expected=${assertion#columns = }
-                if ! is_integer "$expected"; then
-                    echo "Assertion failed: columns = $expected: invalid integer" >&2
-                    exit 1
-                fi
-                # Handle empty array case - columns = 0 for empty results
-                actual=$(echo "$JSON_INPUT" | jq 'if length == 0 then 0 else (.[0] | keys | length) end')
-                if [ "$actual" -ne "$expected" ]; then
-                    echo "Assertion failed: columns = $expected: got $actual" >&2
-                    exit 1
-                fi
-                ;;
-            contains\ *)
-                needle=${assertion#contains }
-                # Remove surrounding quotes if present
-                needle=${needle#\"}
-                needle=${needle%\"}
-                if ! echo "$JSON_INPUT" | jq -e --arg s "$needle" 'any(.. | strings; contains($s))' >/dev/null 2>&1; then
-                    echo "Assertion failed: contains \"$needle\": not found in output" >&2
-                    exit 1
-                fi
-                ;;
-            *)
-                echo "Assertion failed: Unknown assertion syntax: $assertion" >&2
-                exit 1
-                ;;
-        esac
+        # A leading "!" negates any assertion: !contains "x", !rows = 0
+        negate=false
+        if [[ "$assertion" == '!'* ]]; then
+            negate=true
+            read -r assertion <<< "${assertion#!}"
+        fi
+        status=0
+        reason=$(check_assertion "$assertion" 2>&1) || status=$?
+        if [ "$negate" = true ]; then
+            case "$status" in
+                0)
+                    echo "Assertion failed: !$assertion: assertion holds but is negated" >&2
+                    failed=1
+                    break
+                    ;;
+                1) ;;
+                *)
+                    echo "$reason" >&2
+                    failed=1
+                    break
+                    ;;
+            esac
+        elif [ "$status" -ne 0 ]; then
+            echo "$reason" >&2
+            failed=1
+            break
+        fi
     done <<< "$VALIDATOR_ASSERTIONS"
 fi
 
@@ -123,8 +289,8 @@ if [ -n "${VALIDATOR_EXPECT:-}" ]; then
         echo "Output mismatch:" >&2
         echo "  Expected: $VALIDATOR_EXPECT" >&2
         echo "  Actual:   $JSON_INPUT" >&2
-        exit 1
+        failed=1
     fi
 fi
 
-exit 0
+exit "$failed"
@@ -135,6 +135,80 @@ if [ -z "${VALIDATOR_ASSERTIONS:-}" ] && [ -z "${VALIDATOR_EXPECT:-}" ]; then
     exit 0
 fi
 
+# Evaluate a single assertion, printing the failure reason to stderr.
+# Returns 0 if it holds, 1 if it does not, and 2 if it cannot be evaluated
+# (malformed or unknown syntax), which fails even when negated.
+check_assertion() {
+    local assertion=$1
+
+    case "$assertion" in
+        # CUSTOMIZE: Add assertion types your validator supports
+
+        rows\ =\ *)
+            # Example: rows = 5
+            # shellcheck disable=SC2034  # expected is for user to implement
+            expected=${assertion#rows = }
+            # Uncomment and customize:
+            # actual=$(echo "$OUTPUT" | jq 'length')
+            # if [ "$actual" -ne "$expected" ]; then
+            #     echo "Assertion failed: rows = $expected: got $actual" >&2
+            #     return 1
+            # fi
+            echo "TODO: Implement rows = assertion for your validator" >&2
+            return 2
+            ;;
+
+        rows\ \>=\ *)
+            # Example: rows >= 1
+            # shellcheck disable=SC2034  # expected is for user to implement
+            expected=${assertion#rows >= }
+            # Uncomment and customize:
+            # actual=$(echo "$OUTPUT" | jq 'length')
+            # if [ "$actual" -lt "$expected" ]; then
+            #     echo "Assertion failed: rows >= $expected: got $actual" >&2
+            #     return 1
+            # fi
+            echo "TODO: Implement rows >= assertion for your validator" >&2
+            return 2
+            ;;
+
+        contains\ *)
+            # Example: contains "expected text"
+            needle=${assertion#contains }
+            # Remove surrounding quotes if present
+            needle=${needle#\"}
+            needle=${needle%\"}
+
+            # Check stdout first, then stderr
+            if ! echo "$OUTPUT" | grep -qF "$needle"; then
+                if ! echo "${VALIDATOR_CONTAINER_STDERR:-}" | grep -qF "$needle"; then
+                    echo "Assertion failed: contains \"$needle\": not found in output" >&2
+                    return 1
+                fi
+            fi
+            ;;
+
+        stderr_empty)
+            if [ -n "$(printf '%s' "${VALIDATOR_CONTAINER_STDERR:-}" | tr -d '[:space:]')" ]; then
+                echo "Assertion failed: stderr_empty: container stderr is not empty" >&2
+                echo "stderr: $VALIDATOR_CONTAINER_STDERR" >&2
+                return 1
+            fi
+            ;;
+
+        *)
+            echo "Assertion failed: Unknown assertion syntax: $assertion" >&2
+            echo "Supported assertions: rows = N, rows >= N, contains \"string\", stderr_empty" >&2
+            return 2
+            ;;
+    esac
+}
+
+# ASSERT and EXPECT are evaluated independently: both are checked, each
+# failure is reported, and the block fails if either did. Assertions stop at
+# the first one that fails.
+failed=0
+
 # Process assertions line by line
 if [ -n "${VALIDATOR_ASSERTIONS:-}" ]; then
     while IFS= read -r assertion || [ -n "$assertion" ]; do
@@ -142,59 +216,33 @@ if [ -n "${VALIDATOR_ASSERTIONS:-}" ]; then
         assertion=$(echo "$assertion" | xargs 2>/dev/null || echo "$assertion")
         [ -z "$assertion" ] && continue
 
-        case "$assertion" in
-            # CUSTOMIZE: Add assertion types your validator supports
-
-            rows\ =\ *)
-                # Example: rows = 5
-                # shellcheck disable=SC2034  # expected is for user to implement
-                expected=${assertion#rows = }
-                # Uncomment and customize:
-                # actual=$(echo "$OUTPUT" | jq 'length')
-                # if [ "$actual" -ne "$expected" ]; then
-                #     echo "Assertion failed: rows = $expected: got $actual" >&2
-                #     exit 1
-                # fi
-                echo "TODO: Implement rows = assertion for your validator" >&2
-                exit 1
-                ;;
-
-            rows\ \>=\ *)
-                # Example: rows >= 1
-                # shellcheck disable=SC2034  # expected is for user to implement
-                expected=${assertion#rows >= }
-                # Uncomment and customize:
-                # actual=$(echo "$OUTPUT" | jq 'length')
-                # if [ "$actual" -lt "$expected" ]; then
-                #     echo "Assertion failed: rows >= $expected: got $actual" >&2
-                #     exit 1
-                # fi
-                echo "TODO: Implement rows >= assertion for your validator" >&2
-                exit 1
-                ;;
-
-            contains\ *)
-                # Example: contains "expected text"
-                needle=${assertion#contains }
-                # Remove surrounding quotes if present
-                needle=${needle#\"}
-                needle=${needle%\"}
-
-                # Check stdout first, then stderr
-                if ! echo "$OUTPUT" | grep -qF "$needle"; then
-                    if ! echo "${VALIDATOR_CONTAINER_STDERR:-}" | grep -qF "$needle"; then
-                        echo "Assertion failed: contains \"$needle\": not found in output" >&2
-                        exit 1
-                    fi
-                fi
-                ;;
-
-            *)
-                echo "Assertion failed: Unknown assertion syntax: $assertion" >&2
-                echo "Supported assertions: rows = N, rows >= N, contains \"string\"" >&2
-                exit 1
-                ;;
-        esac
+        # A leading "!" negates any assertion: !contains "x", !rows = 0
+        negate=false
+        if [[ "$assertion" == '!'* ]]; then
+            negate=true
+            read -r assertion <<< "${assertion#!}"
+        fi
+        status=0
+        reason=$(check_assertion "$assertion" 2>&1) || status=$?
+        if [ "$negate" = true ]; then
+            case "$status" in
+                0)
+                    echo "Assertion failed: !$assertion: assertion holds but is negated" >&2
+                    failed=1
+                    break
+                    ;;
+                1) ;;
+                *)
+                    echo "$reason" >&2
+                    failed=1
+                    break
+                    ;;
+            esac
+        elif [ "$status" -ne 0 ]; then
+            echo "$reason" >&2
+            failed=1
+            break
+        fi
     done <<< "$VALIDATOR_ASSERTIONS"
 fi
 
@@ -218,11 +266,11 @@ if [ -n "${VALIDATOR_EXPECT:-}" ]; then
         echo "Output mismatch:" >&2
         echo "  Expected: $VALIDATOR_EXPECT" >&2
         echo "  Actual:   $OUTPUT" >&2
-        exit 1
+        failed=1
     fi
 fi
 
 # -----------------------------------------------------------------------------
 # All checks passed
 # -----------------------------------------------------------------------------
-exit 0
+exit "$failed"
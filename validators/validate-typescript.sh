@@ -13,6 +13,7 @@
 #   - contains "string": Stdout must contain string
 #   - stdout_contains "string": Stdout must contain string (alias for contains)
 #   - rows = N: If stdout is JSON array, must have N elements
+#   - stderr_empty: Script stderr must be empty or whitespace-only
 #
 # Exits 0 on success, 1 on failure with details to stderr.
 #
@@ -48,6 +49,75 @@ STDERR=$(echo "$JSON_INPUT" | jq -r '.stderr // ""')
 # Track if we have an exit_code assertion
 HAS_EXIT_CODE_ASSERTION=false
 
+# Evaluate a single assertion, printing the failure reason to stderr.
+# Returns 0 if it holds, 1 if it does not, and 2 if it cannot be evaluated
+# (malformed or unknown syntax), which fails even when negated.
+check_assertion() {
+    local assertion=$1
+
+    case "$assertion" in
+        exit_code\ =\ *)
+            expected=${assertion#exit_code = }
+            if ! is_integer "$expected"; then
+                echo "Assertion failed: exit_code = $expected: invalid integer" >&2
+                return 2
+            fi
+            if [ "$EXIT_CODE" -ne "$expected" ]; then
+                echo "Assertion failed: exit_code = $expected: got $EXIT_CODE" >&2
+                if [ -n "$STDERR" ]; then
+                    echo "stderr: $STDERR" >&2
+                fi
+                return 1
+            fi
+            ;;
+        contains\ *|stdout_contains\ *)
+            # Handle both contains and stdout_contains (alias)
+            if [[ "$assertion" == stdout_contains\ * ]]; then
+                needle=${assertion#stdout_contains }
+            else
+                needle=${assertion#contains }
+            fi
+            # Remove surrounding quotes if present
+            needle=${needle#\"}
+            needle=${needle%\"}
+            if ! echo "$STDOUT" | grep -qF "$needle"; then
+                echo "Assertion failed: contains \"$needle\": not found" >&2
+                echo "stdout: $STDOUT" >&2
+                return 1
+            fi
+            ;;
+        rows\ =\ *)
+            expected=${assertion#rows = }
+            if ! is_integer "$expected"; then
+                echo "Assertion failed: rows = $expected: invalid integer" >&2
+                return 2
+            fi
+            # Parse stdout as JSON array and count elements
+            if ! actual=$(echo "$STDOUT" | jq 'length' 2>/dev/null); then
+                echo "Assertion failed: rows = $expected: stdout is not valid JSON array" >&2
+                echo "stdout: $STDOUT" >&2
+                return 2
+            fi
+            if [ "$actual" -ne "$expected" ]; then
+                echo "Assertion failed: rows = $expected: got $actual" >&2
+                return 1
+            fi
+            ;;
+        stderr_empty)
+            if [ -n "$(printf '%s' "$STDERR" | tr -d '[:space:]')" ]; then
+                echo "Assertion failed: stderr_empty: stderr is not empty" >&2
+                echo "stderr: $STDERR" >&2
+                return 1
+            fi
+            ;;
+        *)
+            echo "Assertion failed: Unknown assertion syntax: $assertion" >&2
+            echo "Supported: exit_code = N, contains \"str\", stdout_contains \"str\", rows = N, stderr_empty" >&2
+            return 2
+            ;;
+    esac
+}
+
 # Evaluate assertions if provided
 if [ -n "${VALIDATOR_ASSERTIONS:-}" ]; then
     while IFS= read -r assertion || [ -n "$assertion" ]; do
@@ -56,61 +126,33 @@ if [ -n "${VALIDATOR_ASSERTIONS:-}" ]; then
         assertion="${assertion%"${assertion##*[![:space:]]}"}"  # trim trailing
         [ -z "$assertion" ] && continue
 
-        case "$assertion" in
-            exit_code\ =\ *)
-                HAS_EXIT_CODE_ASSERTION=true
-                expected=${assertion#exit_code = }
-                if ! is_integer "$expected"; then
-                    echo "Assertion failed: exit_code = $expected: invalid integer" >&2
-                    exit 1
-                fi
-                if [ "$EXIT_CODE" -ne "$expected" ]; then
-                    echo "Assertion failed: exit_code = $expected: got $EXIT_CODE" >&2
-                    if [ -n "$STDERR" ]; then
-                        echo "stderr: $STDERR" >&2
-                    fi
-                    exit 1
-                fi
-                ;;
-            contains\ *|stdout_contains\ *)
-                # Handle both contains and stdout_contains (alias)
-                if [[ "$assertion" == stdout_contains\ * ]]; then
-                    needle=${assertion#stdout_contains }
-                else
-                    needle=${assertion#contains }
-                fi
-                # Remove surrounding quotes if present
-                needle=${needle#\"}
-                needle=${needle%\"}
-                if ! echo "$STDOUT" | grep -qF "$needle"; then
-                    echo "Assertion failed: contains \"$needle\": not found" >&2
-                    echo "stdout: $STDOUT" >&2
-                    exit 1
-                fi
-                ;;
-            rows\ =\ *)
-                expected=${assertion#rows = }
-                if ! is_integer "$expected"; then
-                    echo "Assertion failed: rows = $expected: invalid integer" >&2
-                    exit 1
-                fi
-                # Parse stdout as JSON array and count elements
-                if ! actual=$(echo "$STDOUT" | jq 'length' 2>/dev/null); then
-                    echo "Assertion failed: rows = $expected: stdout is not valid JSON array" >&2
-                    echo "stdout: $STDOUT" >&2
+        # A leading "!" negates any assertion: !contains "x", !rows = 0
+        negate=false
+        if [[ "$assertion" == '!'* ]]; then
+            negate=true
+            read -r assertion <<< "${assertion#!}"
+        fi
+        if [[ "$assertion" == exit_code\ =\ * ]]; then
+            HAS_EXIT_CODE_ASSERTION=true
+        fi
+        status=0
+        reason=$(check_assertion "$assertion" 2>&1) || status=$?
+        if [ "$negate" = true ]; then
+            case "$status" in
+                0)
+                    echo "Assertion failed: !$assertion: assertion holds but is negated" >&2
                     exit 1
-                fi
-                if [ "$actual" -ne "$expected" ]; then
-                    echo "Assertion failed: rows = $expected: got $actual" >&2
+                    ;;
+                1) ;;
+                *)
+                    echo "$reason" >&2
                     exit 1
-                fi
-                ;;
-            *)
-                echo "Assertion failed: Unknown assertion syntax: $assertion" >&2
-                echo "Supported: exit_code = N, contains \"str\", stdout_contains \"str\", rows = N" >&2
-                exit 1
-                ;;
-        esac
+                    ;;
+            esac
+        elif [ "$status" -ne 0 ]; then
+            echo "$reason" >&2
+            exit 1
+        fi
     done <<< "$VALIDATOR_ASSERTIONS"
 fi
 